@@ -0,0 +1,229 @@
+use super::compare::locate;
+use crate::utils::{Command, Worker, extract_backup};
+use anyhow::Result;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Workloads run against each tool from the scratch directory, described for the table.
+const WORKLOADS: &[(&str, &str, &[&str])] = &[
+    ("sort", "sort 200,000 lines", &["lines.txt"]),
+    ("sort", "numeric sort 200,000 lines", &["-n", "numbers.txt"]),
+    ("find", "find in 4,000 files", &["tree", "-name", "*7.txt"]),
+    ("diff", "diff 200,000 lines", &["lines.txt", "changed.txt"]),
+    ("wc", "count 200,000 lines", &["lines.txt"]),
+];
+
+const LINES: usize = 200_000;
+
+/// The timings of one workload against both implementations.
+#[derive(Debug, PartialEq)]
+struct Timing {
+    tool: String,
+    workload: String,
+    original: Duration,
+    replacement: Duration,
+}
+
+/// Run representative workloads against both the backed up original binaries and their Rust
+/// replacements, `runs` times each, and print a table comparing the fastest run of each.
+/// Tools that aren't replaced by an enabled experiment are skipped.
+pub fn benchmark(system: &impl Worker, runs: usize) -> Result<()> {
+    let scratch = std::env::temp_dir().join(format!("oxidizr-benchmark-{}", std::process::id()));
+    info!("Generating workloads in {}", scratch.display());
+    generate(system, &scratch)?;
+
+    let timings = time_workloads(system, &scratch, runs.max(1));
+    std::fs::remove_dir_all(&scratch)?;
+    print!("{}", table(&timings?));
+    Ok(())
+}
+
+/// Write the files the workloads operate on. The content is pseudo-random but the same on
+/// every run, so results are comparable between machines.
+fn generate(system: &impl Worker, scratch: &Path) -> Result<()> {
+    let mut seed: u64 = 0x0123_4567_89ab_cdef;
+    let mut next = move || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        seed >> 33
+    };
+
+    let (mut lines, mut numbers, mut changed) = (String::new(), String::new(), String::new());
+    for i in 0..LINES {
+        let line = format!("{:08x} {:08x} line {i}\n", next(), next());
+        // Change roughly one line in a hundred, so diff has hunks to report.
+        match next() % 100 {
+            0 => changed.push_str(&format!("changed {i}\n")),
+            _ => changed.push_str(&line),
+        }
+        lines.push_str(&line);
+        numbers.push_str(&format!("{}\n", next() as i64 - (1 << 30)));
+    }
+    system.write_file(scratch.join("lines.txt"), &lines, 0o644)?;
+    system.write_file(scratch.join("numbers.txt"), &numbers, 0o644)?;
+    system.write_file(scratch.join("changed.txt"), &changed, 0o644)?;
+
+    for dir in 0..40 {
+        for file in 0..100 {
+            let path = scratch.join(format!("tree/{dir}/{file}.txt"));
+            system.write_file(path, "", 0o644)?;
+        }
+    }
+    Ok(())
+}
+
+fn time_workloads(system: &impl Worker, scratch: &Path, runs: usize) -> Result<Vec<Timing>> {
+    let mut timings = Vec::new();
+    for tool in ["sort", "find", "diff", "wc"] {
+        let (mut original, replacement) = match locate(system, tool) {
+            Ok(paths) => paths,
+            Err(err) => {
+                warn!("Skipping {tool}: {err}");
+                continue;
+            }
+        };
+
+        // Compressed backups are decompressed outside the scratch directory.
+        let extracted = !system.file_exists(original.clone());
+        if extracted {
+            original = std::env::temp_dir()
+                .join(format!("oxidizr-benchmark-{}-{tool}", std::process::id()));
+            extract_backup(system, &replacement, &original)?;
+        }
+
+        for (_, workload, args) in WORKLOADS.iter().filter(|(t, _, _)| *t == tool) {
+            info!("Benchmarking {tool}: {workload}");
+            timings.push(Timing {
+                tool: tool.to_string(),
+                workload: workload.to_string(),
+                original: fastest(system, &original, args, scratch, runs)?,
+                replacement: fastest(system, &replacement, args, scratch, runs)?,
+            });
+        }
+
+        if extracted {
+            system.remove_file(original)?;
+        }
+    }
+    Ok(timings)
+}
+
+/// Time `runs` runs of `binary` with `args`, returning the fastest.
+fn fastest(
+    system: &impl Worker,
+    binary: &Path,
+    args: &[&str],
+    scratch: &Path,
+    runs: usize,
+) -> Result<Duration> {
+    let cmd = Command::build(&binary.to_string_lossy(), args);
+    let mut best = Duration::MAX;
+    for _ in 0..runs {
+        let start = Instant::now();
+        system.capture(&cmd, scratch)?;
+        best = best.min(start.elapsed());
+    }
+    Ok(best)
+}
+
+/// Render timings as a table with aligned columns. The speedup is how many times faster the
+/// Rust replacement is than the original.
+fn table(timings: &[Timing]) -> String {
+    let header = ["TOOL", "WORKLOAD", "GNU", "RUST", "SPEEDUP"].map(str::to_string);
+    let rows: Vec<[String; 5]> = timings
+        .iter()
+        .map(|t| {
+            [
+                t.tool.clone(),
+                t.workload.clone(),
+                format!("{:.1}ms", t.original.as_secs_f64() * 1000.0),
+                format!("{:.1}ms", t.replacement.as_secs_f64() * 1000.0),
+                format!(
+                    "{:.2}x",
+                    t.original.as_secs_f64() / t.replacement.as_secs_f64().max(f64::EPSILON)
+                ),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    std::iter::once(header)
+        .chain(rows)
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect();
+            format!("{}\n", line.join("  ").trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_benchmark_workloads() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/coreutils/sort", "", false),
+            ("/usr/bin/.sort.oxidizr.bak", "", false),
+        ]);
+        runner.mock_symlinks(vec![("/usr/bin/sort", "/usr/bin/coreutils")]);
+
+        let timings = time_workloads(&runner, Path::new("/tmp/scratch"), 2).unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[1].workload, "numeric sort 200,000 lines");
+
+        // Each workload is run twice against each implementation; other tools are skipped.
+        let commands = runner.commands.borrow().clone();
+        assert_eq!(commands.len(), 8);
+        assert_eq!(commands[0], "/usr/bin/.sort.oxidizr.bak lines.txt");
+        assert_eq!(commands[7], "/usr/bin/sort -n numbers.txt");
+    }
+
+    #[test]
+    fn test_benchmark_generate() {
+        let runner = MockSystem::default();
+        generate(&runner, Path::new("/tmp/scratch")).unwrap();
+
+        let lines = runner
+            .read_file(PathBuf::from("/tmp/scratch/lines.txt"))
+            .unwrap();
+        let changed = runner
+            .read_file(PathBuf::from("/tmp/scratch/changed.txt"))
+            .unwrap();
+        assert_eq!(lines.lines().count(), LINES);
+        assert_eq!(changed.lines().count(), LINES);
+        assert_ne!(lines, changed);
+        assert!(runner.file_exists(PathBuf::from("/tmp/scratch/tree/39/99.txt")));
+    }
+
+    #[test]
+    fn test_benchmark_table() {
+        let timings = vec![Timing {
+            tool: "sort".to_string(),
+            workload: "sort 200,000 lines".to_string(),
+            original: Duration::from_millis(120),
+            replacement: Duration::from_millis(40),
+        }];
+        assert_eq!(
+            table(&timings),
+            "TOOL  WORKLOAD            GNU      RUST    SPEEDUP\n\
+             sort  sort 200,000 lines  120.0ms  40.0ms  3.00x\n"
+        );
+    }
+}
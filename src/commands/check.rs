@@ -0,0 +1,221 @@
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+use clap::ValueEnum;
+use std::path::PathBuf;
+
+/// Output formats supported by `oxidizr check`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum CheckFormat {
+    /// A human readable summary.
+    Text,
+    /// A single status line with perfdata, as expected from a Nagios/Icinga plugin.
+    Nagios,
+}
+
+/// The overall health of the experiments, following the Nagios plugin conventions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Ok,
+    Warning,
+    Critical,
+    Unknown,
+}
+
+impl Status {
+    /// The exit code a monitoring plugin reports for the status.
+    pub fn code(&self) -> i32 {
+        match self {
+            Status::Ok => 0,
+            Status::Warning => 1,
+            Status::Critical => 2,
+            Status::Unknown => 3,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Warning => "WARNING",
+            Status::Critical => "CRITICAL",
+            Status::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// The number of drifted binaries at which `oxidizr check` reports a warning or critical status.
+#[derive(Clone, Copy, Debug)]
+pub struct Thresholds {
+    pub warning: usize,
+    pub critical: usize,
+}
+
+/// The state of the experiments enabled on the system.
+#[derive(Debug, Default)]
+struct Report {
+    /// Names of the experiments that are enabled.
+    enabled: Vec<String>,
+    /// Symlinks managed by enabled experiments that are missing or point elsewhere, for example
+    /// because a package upgrade replaced them with the original binary.
+    drifted: Vec<PathBuf>,
+    /// Experiments recorded as enabled whose package is no longer installed.
+    missing: Vec<String>,
+}
+
+/// Check that the enabled experiments are still in place, print a report and return the exit
+/// code to terminate with.
+pub fn check(system: &impl Worker, format: CheckFormat, thresholds: Thresholds) -> i32 {
+    let (status, output) = match gather(system) {
+        Ok(report) => {
+            let status = evaluate(&report, thresholds);
+            let output = match format {
+                CheckFormat::Nagios => nagios(status, &report, thresholds),
+                CheckFormat::Text => text(status, &report),
+            };
+            (status, output)
+        }
+        Err(e) => (Status::Unknown, format!("OXIDIZR UNKNOWN - {e}\n")),
+    };
+    print!("{output}");
+    status.code()
+}
+
+fn gather(system: &impl Worker) -> Result<Report> {
+    let state = State::load(system)?;
+    let mut report = Report::default();
+
+    for e in all_experiments(system) {
+        if !e.check_installed() {
+            if state.experiments.contains_key(&e.name()) {
+                report.missing.push(e.name());
+            }
+            continue;
+        }
+
+        report.enabled.push(e.name());
+        for link in e.expected_links(&state)? {
+            if link.drifted(system) {
+                report.drifted.push(link.target);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn evaluate(report: &Report, thresholds: Thresholds) -> Status {
+    let drifted = report.drifted.len();
+    if !report.missing.is_empty() || drifted >= thresholds.critical {
+        Status::Critical
+    } else if drifted >= thresholds.warning {
+        Status::Warning
+    } else {
+        Status::Ok
+    }
+}
+
+fn nagios(status: Status, report: &Report, thresholds: Thresholds) -> String {
+    let mut summary = format!(
+        "{} experiments enabled, {} drifted binaries",
+        report.enabled.len(),
+        report.drifted.len()
+    );
+    if !report.missing.is_empty() {
+        summary.push_str(&format!(
+            ", packages removed for {}",
+            report.missing.join(", ")
+        ));
+    }
+
+    format!(
+        "OXIDIZR {} - {summary} | enabled={};;;0 drifted={};{};{};0\n",
+        status.label(),
+        report.enabled.len(),
+        report.drifted.len(),
+        thresholds.warning,
+        thresholds.critical,
+    )
+}
+
+fn text(status: Status, report: &Report) -> String {
+    let mut out = format!("Status: {}\n", status.label());
+    let enabled = match report.enabled.is_empty() {
+        true => "none".to_string(),
+        false => report.enabled.join(", "),
+    };
+    out.push_str(&format!("Enabled experiments: {enabled}\n"));
+
+    for name in &report.missing {
+        out.push_str(&format!(
+            "Experiment '{name}' is recorded as enabled, but its package is not installed\n"
+        ));
+    }
+    for path in &report.drifted {
+        out.push_str(&format!(
+            "Drifted: {} is no longer managed by oxidizr\n",
+            path.display()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    const THRESHOLDS: Thresholds = Thresholds {
+        warning: 1,
+        critical: 3,
+    };
+
+    #[test]
+    fn test_check_evaluate() {
+        let mut report = Report {
+            enabled: vec!["coreutils".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(evaluate(&report, THRESHOLDS), Status::Ok);
+
+        report.drifted = vec![PathBuf::from("/usr/bin/date")];
+        assert_eq!(evaluate(&report, THRESHOLDS), Status::Warning);
+
+        report.drifted = vec![PathBuf::from("/usr/bin/date"); 3];
+        assert_eq!(evaluate(&report, THRESHOLDS), Status::Critical);
+
+        report.drifted.clear();
+        report.missing = vec!["sudo-rs".to_string()];
+        assert_eq!(evaluate(&report, THRESHOLDS), Status::Critical);
+    }
+
+    #[test]
+    fn test_check_nagios_output() {
+        let report = Report {
+            enabled: vec!["coreutils".to_string(), "sudo-rs".to_string()],
+            drifted: vec![PathBuf::from("/usr/bin/sudo")],
+            missing: vec![],
+        };
+        assert_eq!(
+            nagios(Status::Warning, &report, THRESHOLDS),
+            "OXIDIZR WARNING - 2 experiments enabled, 1 drifted binaries | enabled=2;;;0 drifted=1;1;3;0\n"
+        );
+    }
+
+    #[test]
+    fn test_check_gather() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_symlinks(vec![("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo")]);
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"coreutils": {}, "sudo-rs": {}}}"#,
+            false,
+        )]);
+
+        let report = gather(&runner).unwrap();
+        assert_eq!(report.enabled, vec!["sudo-rs"]);
+        assert_eq!(report.drifted, vec![PathBuf::from("/usr/bin/visudo")]);
+        assert_eq!(report.missing, vec!["coreutils"]);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::experiments::all_experiments;
+use crate::utils::{Command, Worker, extract_backup, has_compressed_backup};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+/// Files created in the sandbox directory for the corpus to operate on.
+const SANDBOX_FILES: &[(&str, &str)] = &[
+    ("a.txt", "banana\napple\ncherry\napple\n"),
+    ("b.txt", "banana\napricot\ncherry\n"),
+    ("numbers.txt", "10\n9\n100\n-1\n3.5\n"),
+    ("table.csv", "name,qty,price\npear,4,0.5\nfig,12,1.25\n"),
+    ("dir/nested/c.txt", "nested\n"),
+    ("dir/d.log", ""),
+];
+
+/// Representative invocations of each command, run from the sandbox directory.
+const CORPUS: &[(&str, &[&[&str]])] = &[
+    (
+        "cat",
+        &[
+            &["a.txt"],
+            &["-n", "a.txt"],
+            &["-A", "table.csv"],
+            &["missing.txt"],
+        ],
+    ),
+    ("cut", &[&["-d,", "-f2", "table.csv"], &["-c1-3", "a.txt"]]),
+    (
+        "date",
+        &[
+            &["-u", "-d", "@0"],
+            &["-u", "-d", "@0", "+%Y-%m-%d %H:%M:%S"],
+            &["-d", "invalid"],
+        ],
+    ),
+    (
+        "head",
+        &[
+            &["-n", "2", "a.txt"],
+            &["-c", "5", "a.txt"],
+            &["a.txt", "b.txt"],
+        ],
+    ),
+    ("ls", &[&[], &["-1"], &["-a"], &["-R", "dir"], &["missing"]]),
+    (
+        "sort",
+        &[
+            &["a.txt"],
+            &["-u", "a.txt"],
+            &["-n", "numbers.txt"],
+            &["-t,", "-k2", "-n", "table.csv"],
+        ],
+    ),
+    ("tail", &[&["-n", "2", "a.txt"], &["-n", "+2", "a.txt"]]),
+    ("uniq", &[&["a.txt"], &["-c", "a.txt"]]),
+    (
+        "wc",
+        &[&["a.txt"], &["-l", "a.txt", "b.txt"], &["-c", "table.csv"]],
+    ),
+    (
+        "diff",
+        &[
+            &["a.txt", "b.txt"],
+            &["-u", "a.txt", "b.txt"],
+            &["-q", "a.txt", "a.txt"],
+            &["-r", "dir", "dir"],
+        ],
+    ),
+    ("cmp", &[&["a.txt", "b.txt"], &["a.txt", "a.txt"]]),
+    (
+        "find",
+        &[
+            &["."],
+            &[".", "-name", "*.txt"],
+            &[".", "-type", "d"],
+            &[".", "-maxdepth", "1", "-type", "f"],
+            &["dir", "-empty"],
+        ],
+    ),
+];
+
+/// The result of running one invocation against both implementations.
+#[derive(Debug, PartialEq)]
+struct Comparison {
+    invocation: String,
+    /// The aspects of the result that differ, e.g. `stdout`.
+    differences: Vec<String>,
+}
+
+/// Run a corpus of representative invocations of `tool` against both the backed up original
+/// binary and its Rust replacement, and print a report of any differences in behaviour.
+pub fn compare(system: &impl Worker, tool: &str) -> Result<()> {
+    let corpus = corpus(tool)?;
+    let (mut original, replacement) = locate(system, tool)?;
+
+    // Compressed backups are decompressed outside the sandbox, so they don't appear in listings.
+    let extracted = !system.file_exists(original.clone());
+    if extracted {
+        original =
+            std::env::temp_dir().join(format!("oxidizr-compare-{}-{tool}", std::process::id()));
+        extract_backup(system, &replacement, &original)?;
+    }
+
+    let sandbox = std::env::temp_dir().join(format!("oxidizr-compare-{}", std::process::id()));
+    for (file, contents) in SANDBOX_FILES {
+        system.write_file(sandbox.join(file), contents, 0o644)?;
+    }
+
+    let comparisons = run_corpus(system, corpus, &original, &replacement, &sandbox);
+    std::fs::remove_dir_all(&sandbox)?;
+    if extracted {
+        system.remove_file(original)?;
+    }
+    let comparisons = comparisons?;
+
+    print!("{}", report(tool, &comparisons));
+
+    let differing = comparisons
+        .iter()
+        .filter(|c| !c.differences.is_empty())
+        .count();
+    if differing > 0 {
+        anyhow::bail!("{differing} invocation(s) of '{tool}' behaved differently");
+    }
+    Ok(())
+}
+
+fn corpus(tool: &str) -> Result<&'static [&'static [&'static str]]> {
+    match CORPUS.iter().find(|(name, _)| *name == tool) {
+        Some((_, invocations)) => Ok(invocations),
+        None => anyhow::bail!(
+            "No invocations are known for '{tool}'. Supported tools are: {}",
+            CORPUS
+                .iter()
+                .map(|(n, _)| *n)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Find the backed up original binary, and the symlink to the Rust replacement, for `tool`.
+pub(super) fn locate(system: &impl Worker, tool: &str) -> Result<(PathBuf, PathBuf)> {
+    for e in all_experiments(system) {
+        if !e.check_installed() {
+            continue;
+        }
+        for link in e.managed_links()? {
+            if link.target.file_name().is_none_or(|n| n != tool) {
+                continue;
+            }
+            let original = system.backup_location(&link.target);
+            anyhow::ensure!(
+                system.file_exists(original.clone()) || has_compressed_backup(system, &link.target),
+                "No backup of the original '{tool}' was found at {}",
+                original.display()
+            );
+            anyhow::ensure!(
+                !link.drifted(system),
+                "{} no longer points at the Rust replacement",
+                link.target.display()
+            );
+            return Ok((original, link.target));
+        }
+    }
+    anyhow::bail!("'{tool}' is not replaced by an enabled experiment")
+}
+
+fn run_corpus(
+    system: &impl Worker,
+    corpus: &[&[&str]],
+    original: &Path,
+    replacement: &Path,
+    sandbox: &Path,
+) -> Result<Vec<Comparison>> {
+    corpus
+        .iter()
+        .map(|args| {
+            let mut expected =
+                system.capture(&Command::build(&original.to_string_lossy(), args), sandbox)?;
+            // The original reports errors using the name of the backup file it was run as.
+            expected.stderr = String::from_utf8_lossy(&expected.stderr)
+                .replace(
+                    &*original.file_name().unwrap().to_string_lossy(),
+                    &replacement.file_name().unwrap().to_string_lossy(),
+                )
+                .into_bytes();
+            let actual = system.capture(
+                &Command::build(&replacement.to_string_lossy(), args),
+                sandbox,
+            )?;
+            Ok(Comparison {
+                invocation: Command::build(
+                    &replacement.file_name().unwrap().to_string_lossy(),
+                    args,
+                )
+                .command(),
+                differences: differences(&expected, &actual),
+            })
+        })
+        .collect()
+}
+
+fn differences(expected: &Output, actual: &Output) -> Vec<String> {
+    let mut differences = Vec::new();
+    if expected.status.code() != actual.status.code() {
+        differences.push(format!(
+            "exit code ({} vs {})",
+            code(expected),
+            code(actual)
+        ));
+    }
+    if expected.stdout != actual.stdout {
+        differences.push("stdout".to_string());
+    }
+    if expected.stderr != actual.stderr {
+        differences.push("stderr".to_string());
+    }
+    differences
+}
+
+fn code(output: &Output) -> String {
+    match output.status.code() {
+        Some(code) => code.to_string(),
+        None => "signal".to_string(),
+    }
+}
+
+fn report(tool: &str, comparisons: &[Comparison]) -> String {
+    let mut out = String::new();
+    for c in comparisons {
+        match c.differences.is_empty() {
+            true => out.push_str(&format!("SAME  {}\n", c.invocation)),
+            false => out.push_str(&format!(
+                "DIFF  {}: {} differ\n",
+                c.invocation,
+                c.differences.join(", ")
+            )),
+        }
+    }
+    let same = comparisons
+        .iter()
+        .filter(|c| c.differences.is_empty())
+        .count();
+    out.push_str(&format!(
+        "\n{same} of {} invocations of '{tool}' behaved identically\n",
+        comparisons.len()
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_compare_locate() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![("/usr/bin/.sudo.oxidizr.bak", "", false)]);
+        runner.mock_symlinks(vec![("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo")]);
+
+        let (original, replacement) = locate(&runner, "sudo").unwrap();
+        assert_eq!(original, PathBuf::from("/usr/bin/.sudo.oxidizr.bak"));
+        assert_eq!(replacement, PathBuf::from("/usr/bin/sudo"));
+
+        assert!(locate(&runner, "visudo").is_err());
+        assert!(locate(&runner, "ls").is_err());
+    }
+
+    #[test]
+    fn test_compare_run_corpus() {
+        let runner = MockSystem::default();
+        runner.mock_command("/usr/bin/.wc.oxidizr.bak a.txt", "4 4 26 a.txt");
+        runner.mock_command("/usr/bin/wc a.txt", "4 4 26 a.txt");
+        runner.mock_command("/usr/bin/.wc.oxidizr.bak -c a.txt", "26 a.txt");
+        runner.mock_command("/usr/bin/wc -c a.txt", "26 a.txt");
+        runner.mock_command("/usr/bin/wc -l a.txt", "4 a.txt");
+        runner.mock_command_failure("/usr/bin/wc -c a.txt");
+
+        let comparisons = run_corpus(
+            &runner,
+            &[&["a.txt"], &["-l", "a.txt"], &["-c", "a.txt"]],
+            Path::new("/usr/bin/.wc.oxidizr.bak"),
+            Path::new("/usr/bin/wc"),
+            Path::new("/tmp/sandbox"),
+        )
+        .unwrap();
+
+        assert!(comparisons[0].differences.is_empty());
+        assert_eq!(comparisons[1].invocation, "wc -l a.txt");
+        assert_eq!(comparisons[1].differences, vec!["stdout"]);
+        assert_eq!(comparisons[2].differences, vec!["exit code (0 vs 1)"]);
+
+        let report = report("wc", &comparisons);
+        assert!(report.contains("DIFF  wc -l a.txt: stdout differ\n"));
+        assert!(report.ends_with("1 of 3 invocations of 'wc' behaved identically\n"));
+    }
+
+    #[test]
+    fn test_compare_unknown_tool() {
+        assert!(corpus("ls").is_ok());
+        assert!(corpus("not-a-tool").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::experiments::compat::{
+    Incompatibility, KERNEL_HOOK_DIRECTORIES, SCRIPT_DIRECTORIES, known_commands, scan_scripts,
+};
+use crate::utils::Worker;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Scan scripts for uses of flags and features known to behave differently in the Rust
+/// replacements, and print the scripts that may break. `paths` may be scripts or directories of
+/// scripts; by default the cron, local bin and kernel hook directories are scanned. Returns an
+/// error if any script uses a known incompatibility.
+pub fn compat_scan(system: &impl Worker, paths: &[PathBuf]) -> Result<()> {
+    let findings = scan(system, paths);
+    print!("{}", describe(&findings));
+
+    let scripts = scripts(&findings).len();
+    match scripts {
+        0 => println!("No scripts use known incompatibilities"),
+        _ => anyhow::bail!("{scripts} scripts may break when their utilities are replaced"),
+    }
+    Ok(())
+}
+
+fn scan(system: &impl Worker, paths: &[PathBuf]) -> Vec<(PathBuf, &'static Incompatibility)> {
+    let paths: Vec<String> = match paths.is_empty() {
+        true => SCRIPT_DIRECTORIES
+            .iter()
+            .chain(KERNEL_HOOK_DIRECTORIES)
+            .map(|d| d.to_string())
+            .collect(),
+        false => paths.iter().map(|p| p.display().to_string()).collect(),
+    };
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+
+    let mut findings = scan_scripts(system, &paths, &known_commands());
+    findings.sort_by(|a, b| a.0.cmp(&b.0));
+    findings
+}
+
+/// The scripts with findings, in order.
+fn scripts<'a>(findings: &'a [(PathBuf, &'static Incompatibility)]) -> Vec<&'a Path> {
+    let mut scripts: Vec<&Path> = findings.iter().map(|(f, _)| f.as_path()).collect();
+    scripts.dedup();
+    scripts
+}
+
+/// Describe the findings grouped by script, e.g. `  cp -Z: setting the SELinux ...`.
+fn describe(findings: &[(PathBuf, &'static Incompatibility)]) -> String {
+    let mut output = String::new();
+    for script in scripts(findings) {
+        output.push_str(&format!("{}:\n", script.display()));
+        for (_, i) in findings.iter().filter(|(f, _)| f == script) {
+            output.push_str(&format!("  {} {}: {}\n", i.command, i.feature, i.note));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_compat_scan() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            (
+                "/etc/kernel/postinst.d/selinux",
+                "cp -Z \"$src\" /boot\nfind /boot -fls /tmp/boot.txt",
+                false,
+            ),
+            ("/etc/cron.daily/fetch", "curl --retry 3 $URL", false),
+            ("/home/ubuntu/deploy.sh", "du -s -h /srv", false),
+        ]);
+
+        assert_eq!(
+            describe(&scan(&runner, &[])),
+            "/etc/cron.daily/fetch:\n  \
+             curl --retry: automatic retries have no xh equivalent; the original curl is used\n\
+             /etc/kernel/postinst.d/selinux:\n  \
+             cp -Z: setting the SELinux security context is not supported by uutils cp\n  \
+             find -fls: writing -ls output to a file is not implemented by uutils findutils\n"
+        );
+
+        // Scripts can be given explicitly, instead of the default directories.
+        let findings = scan(&runner, &[PathBuf::from("/home/ubuntu/deploy.sh")]);
+        assert_eq!(
+            scripts(&findings),
+            vec![Path::new("/home/ubuntu/deploy.sh")]
+        );
+        assert!(compat_scan(&runner, &[PathBuf::from("/home/ubuntu/deploy.sh")]).is_err());
+    }
+}
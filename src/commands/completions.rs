@@ -0,0 +1,54 @@
+use anyhow::Result;
+use clap::Command;
+use clap::builder::PossibleValuesParser;
+use clap_complete::{Shell, generate};
+use std::io::Write;
+
+/// Write a completion script for `shell` to `out`, completing the names of `experiments`
+/// wherever an experiment is expected.
+pub fn completions(
+    cmd: Command,
+    shell: Shell,
+    experiments: Vec<String>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let names = || PossibleValuesParser::new(experiments.clone());
+    let mut cmd = cmd
+        .mut_arg("experiments", |a| a.value_parser(names()))
+        .mut_subcommand("enable", |c| {
+            c.mut_arg("accept_risk", |a| a.value_parser(names()))
+        })
+        .mut_subcommand("import-state", |c| {
+            c.mut_arg("accept_risk", |a| a.value_parser(names()))
+        })
+        .mut_subcommand("promote", |c| {
+            c.mut_arg("experiments", |a| a.value_parser(names()))
+        });
+
+    let name = cmd.get_name().to_string();
+    generate(shell, &mut cmd, name, out);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    #[test]
+    fn test_completions_experiments() {
+        let accept_risk = Arg::new("accept_risk").long("accept-risk");
+        let cmd = Command::new("oxidizr")
+            .arg(Arg::new("experiments").long("experiments"))
+            .subcommand(Command::new("enable").arg(accept_risk.clone()))
+            .subcommand(Command::new("import-state").arg(accept_risk))
+            .subcommand(Command::new("promote").arg(Arg::new("experiments")));
+        let experiments = vec!["coreutils".to_string(), "sudo-rs".to_string()];
+
+        let mut out = Vec::new();
+        completions(cmd, Shell::Fish, experiments, &mut out).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("-l experiments -r -f -a \"coreutils"));
+        assert!(script.contains("-l accept-risk -r -f -a \"coreutils"));
+    }
+}
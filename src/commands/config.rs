@@ -0,0 +1,431 @@
+use crate::config::Config;
+use crate::experiments::{DiffFrontend, Experiment, all_experiments, is_bundle};
+use crate::state::State;
+use crate::utils::{Ppa, Worker, validate_apt_option, validate_proxy};
+use anyhow::Result;
+use clap::ValueEnum;
+use std::path::PathBuf;
+
+/// Settings in `/etc/oxidizr/config.toml` that can be managed with `oxidizr config`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ConfigKey {
+    /// Directory in which backups of replaced binaries are stored.
+    #[value(name = "backup_dir")]
+    BackupDir,
+    /// Experiments selected when `--experiments` isn't given.
+    Experiments,
+    /// Utilities left unreplaced whenever experiments are enabled.
+    Exclude,
+    /// Skip confirmation prompts.
+    Yes,
+    /// Compress backups into the backup store whenever experiments are enabled.
+    #[value(name = "compress_backups")]
+    CompressBackups,
+    /// Set bat as the pager when the `bat` experiment is enabled.
+    #[value(name = "bat_pager")]
+    BatPager,
+    /// Install dust without exposing it as `du`.
+    #[value(name = "dust_only")]
+    DustOnly,
+    /// Tool that `diff` is pointed at on terminals, either `delta` or `difftastic`.
+    #[value(name = "diff_frontend")]
+    DiffFrontend,
+    /// Install xh alongside curl and wget, rather than replacing them.
+    #[value(name = "xh_alongside")]
+    XhAlongside,
+    /// Show tldr pages from `man` for commands without a manual page.
+    #[value(name = "tldr_man")]
+    TldrMan,
+    /// Dispatch read-only git subcommands to gix in interactive login shells.
+    #[value(name = "gix_git")]
+    GixGit,
+    /// PPA from which a uutils experiment's package is installed, given as the experiment, the
+    /// PPA and the fingerprint of its signing key.
+    Ppa,
+    /// Proxy through which apt and other downloads are sent.
+    Proxy,
+    /// Options passed to apt with `-o`, as `Key=Value`.
+    #[value(name = "apt_options")]
+    AptOptions,
+}
+
+impl ConfigKey {
+    fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The experiment whose installed files depend on this setting, if any. Changing such a
+    /// setting while the experiment is enabled would leave the files it installed out of step
+    /// with the ones `disable` expects to find.
+    fn experiment(&self) -> Option<&'static str> {
+        match self {
+            ConfigKey::BatPager => Some("bat"),
+            ConfigKey::DustOnly => Some("dust"),
+            ConfigKey::DiffFrontend => Some("diffutils"),
+            ConfigKey::XhAlongside => Some("http"),
+            ConfigKey::TldrMan => Some("tldr"),
+            ConfigKey::GixGit => Some("gitoxide"),
+            _ => None,
+        }
+    }
+
+    /// Refuse to change the setting while the experiment it affects is enabled. Backups already
+    /// taken are looked up in the backup directory, so it can't be moved while any are recorded.
+    fn ensure_changeable(&self, system: &impl Worker) -> Result<()> {
+        if *self == ConfigKey::BackupDir {
+            anyhow::ensure!(
+                State::load(system)?.experiments.is_empty(),
+                "backup_dir can't be changed while experiments are enabled, as their backups \
+                 would no longer be found; disable them first"
+            );
+        }
+        if let Some(experiment) = self.experiment() {
+            anyhow::ensure!(
+                !State::load(system)?.experiments.contains_key(experiment),
+                "{} can't be changed while the '{experiment}' experiment is enabled; disable it \
+                 first, then enable it again once the setting is changed",
+                self.name()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Print every setting that is set, as `key = value` lines.
+pub fn config_list(system: &impl Worker) -> Result<()> {
+    let config = Config::load(system)?;
+    for key in ConfigKey::value_variants() {
+        if let Some(value) = value(&config, *key) {
+            println!("{} = {value}", key.name());
+        }
+    }
+    Ok(())
+}
+
+/// Print the value of a setting. Lists are printed space-separated.
+pub fn config_get(system: &impl Worker, key: ConfigKey) -> Result<()> {
+    match value(&Config::load(system)?, key) {
+        Some(value) => println!("{value}"),
+        None => anyhow::bail!("{} is not set", key.name()),
+    }
+    Ok(())
+}
+
+/// Change a setting. List settings take each item as a separate value.
+pub fn config_set(system: &impl Worker, key: ConfigKey, values: &[String]) -> Result<()> {
+    key.ensure_changeable(system)?;
+    let mut config = Config::load(system)?;
+    let single = || match values {
+        [value] => Ok(value.clone()),
+        _ => Err(anyhow::anyhow!("{} takes a single value", key.name())),
+    };
+
+    match key {
+        ConfigKey::BackupDir => config.backup_dir = Some(PathBuf::from(single()?)),
+        ConfigKey::Experiments => {
+            let known: Vec<String> = all_experiments(system).iter().map(|e| e.name()).collect();
+            if let Some(unknown) = values.iter().find(|v| !known.contains(v) && !is_bundle(v)) {
+                anyhow::bail!("Unknown experiment '{unknown}'");
+            }
+            config.experiments = Some(values.to_vec());
+        }
+        ConfigKey::Exclude => config.exclude = values.to_vec(),
+        ConfigKey::Yes => {
+            config.yes = single()?
+                .parse()
+                .map_err(|_| anyhow::anyhow!("yes must be 'true' or 'false'"))?
+        }
+        ConfigKey::CompressBackups => {
+            config.compress_backups = single()?
+                .parse()
+                .map_err(|_| anyhow::anyhow!("compress_backups must be 'true' or 'false'"))?
+        }
+        ConfigKey::BatPager => {
+            config.bat_pager = single()?
+                .parse()
+                .map_err(|_| anyhow::anyhow!("bat_pager must be 'true' or 'false'"))?
+        }
+        ConfigKey::DustOnly => {
+            config.dust_only = single()?
+                .parse()
+                .map_err(|_| anyhow::anyhow!("dust_only must be 'true' or 'false'"))?
+        }
+        ConfigKey::DiffFrontend => {
+            config.diff_frontend =
+                Some(DiffFrontend::from_str(&single()?, false).map_err(|_| {
+                    anyhow::anyhow!("diff_frontend must be 'delta' or 'difftastic'")
+                })?)
+        }
+        ConfigKey::XhAlongside => {
+            config.xh_alongside = single()?
+                .parse()
+                .map_err(|_| anyhow::anyhow!("xh_alongside must be 'true' or 'false'"))?
+        }
+        ConfigKey::TldrMan => {
+            config.tldr_man = single()?
+                .parse()
+                .map_err(|_| anyhow::anyhow!("tldr_man must be 'true' or 'false'"))?
+        }
+        ConfigKey::GixGit => {
+            config.gix_git = single()?
+                .parse()
+                .map_err(|_| anyhow::anyhow!("gix_git must be 'true' or 'false'"))?
+        }
+        ConfigKey::Ppa => {
+            let [experiment, name, fingerprint] = values else {
+                anyhow::bail!("ppa takes an experiment, a PPA and a key fingerprint");
+            };
+            let uutils = all_experiments(system)
+                .iter()
+                .any(|e| matches!(e, Experiment::Uutils(_)) && e.name() == *experiment);
+            anyhow::ensure!(
+                uutils,
+                "PPAs can only be configured for the uutils experiments, not '{experiment}'"
+            );
+            let ppa = Ppa {
+                name: name.clone(),
+                fingerprint: fingerprint.clone(),
+            };
+            ppa.validate()?;
+            config.ppa.insert(experiment.clone(), ppa);
+        }
+        ConfigKey::Proxy => {
+            let proxy = single()?;
+            validate_proxy(&proxy)?;
+            config.proxy = Some(proxy);
+        }
+        ConfigKey::AptOptions => {
+            for option in values {
+                validate_apt_option(option)?;
+            }
+            config.apt_options = values.to_vec();
+        }
+    }
+    config.save(system)
+}
+
+/// Reset a setting to its default.
+pub fn config_unset(system: &impl Worker, key: ConfigKey) -> Result<()> {
+    key.ensure_changeable(system)?;
+    let mut config = Config::load(system)?;
+    match key {
+        ConfigKey::BackupDir => config.backup_dir = None,
+        ConfigKey::Experiments => config.experiments = None,
+        ConfigKey::Exclude => config.exclude.clear(),
+        ConfigKey::Yes => config.yes = false,
+        ConfigKey::CompressBackups => config.compress_backups = false,
+        ConfigKey::BatPager => config.bat_pager = false,
+        ConfigKey::DustOnly => config.dust_only = false,
+        ConfigKey::DiffFrontend => config.diff_frontend = None,
+        ConfigKey::XhAlongside => config.xh_alongside = false,
+        ConfigKey::TldrMan => config.tldr_man = false,
+        ConfigKey::GixGit => config.gix_git = false,
+        ConfigKey::Ppa => config.ppa.clear(),
+        ConfigKey::Proxy => config.proxy = None,
+        ConfigKey::AptOptions => config.apt_options.clear(),
+    }
+    config.save(system)
+}
+
+fn value(config: &Config, key: ConfigKey) -> Option<String> {
+    match key {
+        ConfigKey::BackupDir => config.backup_dir.as_ref().map(|d| d.display().to_string()),
+        ConfigKey::Experiments => config.experiments.as_ref().map(|e| e.join(" ")),
+        ConfigKey::Exclude => (!config.exclude.is_empty()).then(|| config.exclude.join(" ")),
+        ConfigKey::Yes => config.yes.then(|| "true".to_string()),
+        ConfigKey::CompressBackups => config.compress_backups.then(|| "true".to_string()),
+        ConfigKey::BatPager => config.bat_pager.then(|| "true".to_string()),
+        ConfigKey::DustOnly => config.dust_only.then(|| "true".to_string()),
+        ConfigKey::DiffFrontend => config.diff_frontend.map(|f| f.name()),
+        ConfigKey::XhAlongside => config.xh_alongside.then(|| "true".to_string()),
+        ConfigKey::TldrMan => config.tldr_man.then(|| "true".to_string()),
+        ConfigKey::GixGit => config.gix_git.then(|| "true".to_string()),
+        ConfigKey::Ppa => (!config.ppa.is_empty()).then(|| {
+            config
+                .ppa
+                .iter()
+                .map(|(experiment, ppa)| format!("{experiment} {} {}", ppa.name, ppa.fingerprint))
+                .collect::<Vec<String>>()
+                .join(", ")
+        }),
+        ConfigKey::Proxy => config.proxy.clone(),
+        ConfigKey::AptOptions => {
+            (!config.apt_options.is_empty()).then(|| config.apt_options.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG_FILE;
+    use crate::utils::MockSystem;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_config_set_unset() {
+        let runner = MockSystem::default();
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        config_set(
+            &runner,
+            ConfigKey::Experiments,
+            &strings(&["coreutils", "findutils"]),
+        )
+        .unwrap();
+        config_set(&runner, ConfigKey::Yes, &strings(&["true"])).unwrap();
+        config_set(&runner, ConfigKey::BackupDir, &strings(&["/srv/backups"])).unwrap();
+        assert_eq!(
+            runner.read_file(PathBuf::from(CONFIG_FILE)).unwrap(),
+            "backup_dir = \"/srv/backups\"\nexperiments = [\"coreutils\", \"findutils\"]\nyes = true\n"
+        );
+
+        let config = Config::load(&runner).unwrap();
+        assert_eq!(
+            value(&config, ConfigKey::Experiments),
+            Some("coreutils findutils".to_string())
+        );
+        assert_eq!(value(&config, ConfigKey::Exclude), None);
+
+        assert!(config_set(&runner, ConfigKey::Experiments, &strings(&["nope"])).is_err());
+        assert!(config_set(&runner, ConfigKey::Yes, &strings(&["maybe"])).is_err());
+        assert!(config_set(&runner, ConfigKey::BackupDir, &strings(&["backups"])).is_err());
+
+        config_set(&runner, ConfigKey::CompressBackups, &strings(&["true"])).unwrap();
+        assert!(Config::load(&runner).unwrap().compress_backups);
+
+        config_unset(&runner, ConfigKey::Yes).unwrap();
+        assert!(!Config::load(&runner).unwrap().yes);
+    }
+
+    #[test]
+    fn test_config_set_network() {
+        let runner = MockSystem::default();
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        config_set(&runner, ConfigKey::Proxy, &strings(&["http://proxy:3128"])).unwrap();
+        config_set(
+            &runner,
+            ConfigKey::AptOptions,
+            &strings(&["Acquire::Retries=3", "Acquire::ForceIPv4=true"]),
+        )
+        .unwrap();
+        let config = Config::load(&runner).unwrap();
+        assert_eq!(
+            value(&config, ConfigKey::Proxy),
+            Some("http://proxy:3128".to_string())
+        );
+        assert_eq!(
+            value(&config, ConfigKey::AptOptions),
+            Some("Acquire::Retries=3 Acquire::ForceIPv4=true".to_string())
+        );
+
+        assert!(config_set(&runner, ConfigKey::Proxy, &strings(&["proxy:3128"])).is_err());
+        assert!(config_set(&runner, ConfigKey::AptOptions, &strings(&["Acquire"])).is_err());
+
+        config_unset(&runner, ConfigKey::Proxy).unwrap();
+        assert_eq!(Config::load(&runner).unwrap().proxy, None);
+    }
+
+    #[test]
+    fn test_config_refuses_while_enabled() {
+        let runner = MockSystem::default();
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        let mut state = State::load(&runner).unwrap();
+        state.record("dust", Vec::new());
+        state.save(&runner).unwrap();
+
+        assert!(config_set(&runner, ConfigKey::DustOnly, &strings(&["true"])).is_err());
+        assert!(config_unset(&runner, ConfigKey::DustOnly).is_err());
+        assert!(!Config::load(&runner).unwrap().dust_only);
+        // Settings no enabled experiment depends on can still be changed.
+        config_set(&runner, ConfigKey::BatPager, &strings(&["true"])).unwrap();
+
+        let mut state = State::load(&runner).unwrap();
+        state.remove("dust");
+        state.save(&runner).unwrap();
+        config_set(&runner, ConfigKey::DustOnly, &strings(&["true"])).unwrap();
+        assert!(Config::load(&runner).unwrap().dust_only);
+
+        let mut state = State::load(&runner).unwrap();
+        state.record("http", Vec::new());
+        state.save(&runner).unwrap();
+        assert!(config_set(&runner, ConfigKey::XhAlongside, &strings(&["true"])).is_err());
+        assert!(config_unset(&runner, ConfigKey::XhAlongside).is_err());
+
+        let mut state = State::load(&runner).unwrap();
+        state.record("gitoxide", Vec::new());
+        state.save(&runner).unwrap();
+        assert!(config_set(&runner, ConfigKey::GixGit, &strings(&["true"])).is_err());
+
+        for (experiment, key, value) in [
+            ("diffutils", ConfigKey::DiffFrontend, "delta"),
+            ("bat", ConfigKey::BatPager, "false"),
+            ("tldr", ConfigKey::TldrMan, "true"),
+        ] {
+            let mut state = State::load(&runner).unwrap();
+            state.record(experiment, Vec::new());
+            state.save(&runner).unwrap();
+            assert!(config_set(&runner, key, &strings(&[value])).is_err());
+            assert!(config_unset(&runner, key).is_err());
+        }
+        assert!(Config::load(&runner).unwrap().bat_pager);
+        assert_eq!(Config::load(&runner).unwrap().diff_frontend, None);
+    }
+
+    #[test]
+    fn test_config_refuses_backup_dir() {
+        let runner = MockSystem::default();
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        // Any recorded experiment may have backups in the current directory.
+        let mut state = State::load(&runner).unwrap();
+        state.record("coreutils", Vec::new());
+        state.record_backups(
+            "coreutils",
+            BTreeMap::from([(PathBuf::from("/usr/bin/date"), "abc".to_string())]),
+        );
+        state.save(&runner).unwrap();
+        assert!(config_set(&runner, ConfigKey::BackupDir, &strings(&["/srv/backups"])).is_err());
+        assert!(config_unset(&runner, ConfigKey::BackupDir).is_err());
+
+        let mut state = State::load(&runner).unwrap();
+        state.remove("coreutils");
+        state.save(&runner).unwrap();
+        config_set(&runner, ConfigKey::BackupDir, &strings(&["/srv/backups"])).unwrap();
+    }
+
+    #[test]
+    fn test_config_set_ppa() {
+        let runner = MockSystem::default();
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let fingerprint = "0123456789ABCDEF0123456789ABCDEF01234567";
+
+        config_set(
+            &runner,
+            ConfigKey::Ppa,
+            &strings(&["coreutils", "ppa:uutils/rust-coreutils", fingerprint]),
+        )
+        .unwrap();
+        assert_eq!(
+            value(&Config::load(&runner).unwrap(), ConfigKey::Ppa),
+            Some(format!("coreutils ppa:uutils/rust-coreutils {fingerprint}"))
+        );
+
+        let ppa = |experiment, fingerprint| {
+            config_set(
+                &runner,
+                ConfigKey::Ppa,
+                &strings(&[experiment, "ppa:uutils/rust-coreutils", fingerprint]),
+            )
+        };
+        assert!(ppa("sudo-rs", fingerprint).is_err());
+        assert!(ppa("coreutils", "01234567").is_err());
+
+        config_unset(&runner, ConfigKey::Ppa).unwrap();
+        assert!(Config::load(&runner).unwrap().ppa.is_empty());
+    }
+}
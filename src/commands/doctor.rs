@@ -0,0 +1,261 @@
+use crate::experiments::{Experiment, all_experiments};
+use crate::state::State;
+use crate::utils::{Command, Worker, has_backup};
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// A problem found by `oxidizr doctor`, along with the steps to fix it.
+#[derive(Debug, PartialEq)]
+struct Finding {
+    problem: String,
+    remedy: String,
+}
+
+impl Finding {
+    fn new(problem: String, remedy: String) -> Self {
+        Self { problem, remedy }
+    }
+}
+
+/// Inspect the system for problems left behind by interrupted or partial runs, such as broken
+/// symlinks, missing backups and half-installed packages, and print how to fix each of them.
+pub fn doctor(system: &impl Worker) -> Result<()> {
+    let findings = diagnose(system)?;
+    print!("{}", report(&findings));
+
+    if !findings.is_empty() {
+        anyhow::bail!("{} problem(s) found", findings.len());
+    }
+    Ok(())
+}
+
+fn diagnose(system: &impl Worker) -> Result<Vec<Finding>> {
+    let state = State::load(system)?;
+    let mut findings = Vec::new();
+    // Experiments can share a package, which only needs to be reported once.
+    let mut half_installed_packages = BTreeSet::new();
+
+    for e in all_experiments(system) {
+        let (name, package) = (e.name(), e.package());
+        let recorded = state.experiments.get(&name);
+
+        if let Some(status) = half_installed(system, &package) {
+            if !half_installed_packages.insert(package.clone()) {
+                continue;
+            }
+            findings.push(Finding::new(
+                format!("{package} is half-installed (dpkg status '{status}')"),
+                format!(
+                    "Run 'sudo dpkg --configure -a', then 'sudo oxidizr enable -e {name}' or 'sudo oxidizr disable -e {name}'"
+                ),
+            ));
+            continue;
+        }
+
+        if !e.check_installed() {
+            match recorded {
+                Some(_) => findings.push(Finding::new(
+                    format!("'{name}' is recorded as enabled, but {package} is not installed"),
+                    format!(
+                        "Run 'sudo oxidizr disable -e {name}' to restore the originals, or 'sudo oxidizr enable -e {name}' to reinstall it"
+                    ),
+                )),
+                None => findings.extend(leftover_backups(system, &e)),
+            }
+            continue;
+        }
+
+        // Experiments provided by a system extension image don't replace any files.
+        if recorded.is_some_and(|s| s.sysext) {
+            continue;
+        }
+
+        let links = e.expected_links(&state)?;
+        let mut unlinked = Vec::new();
+        for link in &links {
+            if system.read_link(link.target.clone()).ok().as_ref() != Some(&link.source) {
+                unlinked.push(link.target.display().to_string());
+                continue;
+            }
+            if !system.file_exists(link.source.clone()) {
+                findings.push(Finding::new(
+                    format!(
+                        "{} is a broken symlink to {}",
+                        link.target.display(),
+                        link.source.display()
+                    ),
+                    format!("Run 'sudo apt-get install --reinstall {package}'"),
+                ));
+            } else if owned_by_package(system, &link.target) && !has_backup(system, &link.target) {
+                findings.push(Finding::new(
+                    format!("No backup of the original {} was found", link.target.display()),
+                    format!(
+                        "After disabling '{name}', reinstall the package providing it (see 'dpkg -S {}')",
+                        link.target.display()
+                    ),
+                ));
+            }
+        }
+
+        let linked = links.len() - unlinked.len();
+        match recorded {
+            Some(_) if !unlinked.is_empty() => findings.push(Finding::new(
+                format!(
+                    "{} binaries managed by '{name}' are not linked: {}",
+                    unlinked.len(),
+                    unlinked.join(", ")
+                ),
+                format!("Run 'sudo oxidizr enable -e {name}' again to relink them"),
+            )),
+            None if linked > 0 => findings.push(Finding::new(
+                format!(
+                    "'{name}' is partially enabled ({linked} of {} binaries linked), but not recorded as enabled",
+                    links.len()
+                ),
+                format!(
+                    "Run 'sudo oxidizr enable -e {name}' to finish enabling it, or 'sudo oxidizr disable -e {name}' to restore the originals"
+                ),
+            )),
+            _ => {}
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Report backups left behind by an experiment whose package has since been removed.
+fn leftover_backups(system: &impl Worker, experiment: &Experiment) -> Vec<Finding> {
+    // The binaries of some experiments can't be listed without their package installed.
+    let links = experiment.managed_links().unwrap_or_default();
+    links
+        .into_iter()
+        .filter(|link| has_backup(system, &link.target))
+        .map(|link| {
+            Finding::new(
+                format!(
+                    "A backup of {} is left over, but '{}' is not enabled",
+                    link.target.display(),
+                    experiment.name()
+                ),
+                format!(
+                    "Check {} matches the original, then remove {}",
+                    link.target.display(),
+                    system.backup_location(&link.target).display()
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Return the dpkg status of a package that is only partially installed or configured.
+fn half_installed(system: &impl Worker, package: &str) -> Option<String> {
+    let cmd = Command::build("dpkg-query", &["-W", "-f=${db:Status-Abbrev}", package]);
+    let output = system.run(&cmd).ok()?;
+    let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    // The second letter is the package state, and a third letter flags an error.
+    let mut chars = status.chars().skip(1);
+    match (chars.next(), chars.next()) {
+        (Some('U' | 'F' | 'H' | 'W' | 't'), _) | (_, Some('R')) => Some(status),
+        _ => None,
+    }
+}
+
+/// Report whether a file is shipped by a package, such that it was backed up when replaced.
+fn owned_by_package(system: &impl Worker, file: &Path) -> bool {
+    let cmd = Command::build("dpkg-query", &["-S", &file.to_string_lossy()]);
+    system.run(&cmd).is_ok()
+}
+
+fn report(findings: &[Finding]) -> String {
+    if findings.is_empty() {
+        return "No problems found\n".to_string();
+    }
+
+    let mut out = format!("Found {} problem(s):\n", findings.len());
+    for f in findings {
+        out.push_str(&format!("\n- {}\n  Fix: {}\n", f.problem, f.remedy));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    fn sudors_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/sudo", "", false),
+            ("/usr/bin/sudo", "", true),
+            ("/usr/bin/visudo", "", true),
+            ("/usr/bin/.sudo.oxidizr.bak", "", false),
+        ]);
+        runner
+    }
+
+    #[test]
+    fn test_doctor_partially_enabled() {
+        let runner = sudors_runner();
+        runner.mock_symlinks(vec![("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo")]);
+
+        let findings = diagnose(&runner).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].problem.contains("1 of 2 binaries linked"));
+        assert!(findings[0].remedy.contains("oxidizr enable -e sudo-rs"));
+    }
+
+    #[test]
+    fn test_doctor_broken_links_and_backups() {
+        let runner = sudors_runner();
+        runner.mock_symlinks(vec![
+            ("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo"),
+            ("/usr/bin/visudo", "/usr/lib/cargo/bin/visudo"),
+        ]);
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}}}"#,
+            false,
+        )]);
+
+        let problems: Vec<String> = diagnose(&runner)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.problem)
+            .collect();
+        assert_eq!(
+            problems,
+            vec!["/usr/bin/visudo is a broken symlink to /usr/lib/cargo/bin/visudo"]
+        );
+
+        runner.mock_files(vec![("/usr/lib/cargo/bin/visudo", "", false)]);
+        let problems: Vec<String> = diagnose(&runner)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.problem)
+            .collect();
+        assert_eq!(
+            problems,
+            vec!["No backup of the original /usr/bin/visudo was found"]
+        );
+    }
+
+    #[test]
+    fn test_doctor_half_installed() {
+        let runner = sudors_runner();
+        runner.mock_command("dpkg-query -W -f=${db:Status-Abbrev} sudo-rs", "iF ");
+        runner.mock_command("dpkg-query -W -f=${db:Status-Abbrev} rust-coreutils", "ii ");
+        runner.mock_command("dpkg-query -W -f=${db:Status-Abbrev} frawk", "rc ");
+
+        let findings = diagnose(&runner).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].problem,
+            "sudo-rs is half-installed (dpkg status 'iF')"
+        );
+        assert!(report(&findings).starts_with("Found 1 problem(s):\n"));
+    }
+}
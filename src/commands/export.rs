@@ -0,0 +1,176 @@
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// A portable description of the experiments enabled on a machine, written by
+/// `oxidizr export-state` and converged on by `oxidizr import-state`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedState {
+    /// The package installed by each enabled experiment, keyed by experiment name.
+    #[serde(default)]
+    pub experiments: BTreeMap<String, ExportedPackage>,
+    /// Utilities that are left unreplaced by any experiment, e.g. `cp`.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub excluded: BTreeSet<String>,
+}
+
+/// A package installed by an enabled experiment, and its installed version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedPackage {
+    pub name: String,
+    pub version: String,
+}
+
+impl ExportedState {
+    /// Capture the experiments enabled on the system, and the versions of their packages.
+    pub fn capture(system: &impl Worker) -> Result<Self> {
+        let state = State::load(system)?;
+        let mut exported = Self {
+            excluded: state.excluded.clone(),
+            ..Default::default()
+        };
+
+        for e in all_experiments(system) {
+            if !state.experiments.contains_key(&e.name()) || !e.check_installed() {
+                continue;
+            }
+            let package = e.package();
+            let version = system.package_version(&package)?;
+            exported.experiments.insert(
+                e.name(),
+                ExportedPackage {
+                    name: package,
+                    version,
+                },
+            );
+        }
+        Ok(exported)
+    }
+
+    /// Read an exported state from disk, as TOML if the file has a `.toml` extension and as
+    /// JSON otherwise.
+    pub fn load(system: &dyn Worker, file: &Path) -> Result<Self> {
+        let contents = system
+            .read_file(file.to_path_buf())
+            .map_err(|e| anyhow::anyhow!("Unable to read {}: {e}", file.display()))?;
+        match is_toml(file) {
+            true => Ok(toml::from_str(&contents)?),
+            false => Ok(serde_json::from_str(&contents)?),
+        }
+    }
+
+    /// Report the version of each exported package, keyed by package name.
+    pub fn versions(&self) -> BTreeMap<String, String> {
+        self.experiments
+            .values()
+            .map(|p| (p.name.clone(), p.version.clone()))
+            .collect()
+    }
+
+    /// Work out which experiments must be enabled and disabled to converge on this state.
+    /// Experiments that are already enabled are left in place, even at a different version.
+    pub fn plan(&self, system: &impl Worker) -> Result<(Vec<String>, Vec<String>)> {
+        let state = State::load(system)?;
+        let mut enable = Vec::new();
+        let mut disable = Vec::new();
+
+        for e in all_experiments(system) {
+            let enabled = state.experiments.contains_key(&e.name()) && e.check_installed();
+            match (self.experiments.get(&e.name()), enabled) {
+                (Some(_), false) => enable.push(e.name()),
+                (Some(exported), true) => {
+                    let installed = system.package_version(&exported.name)?;
+                    if installed != exported.version {
+                        warn!(
+                            "'{}' is enabled with {} {installed} rather than the exported {}",
+                            e.name(),
+                            exported.name,
+                            exported.version
+                        );
+                    }
+                }
+                (None, true) => disable.push(e.name()),
+                (None, false) => {}
+            }
+        }
+
+        for name in self.experiments.keys() {
+            if !all_experiments(system).iter().any(|e| e.name() == *name) {
+                warn!("Ignoring unknown experiment '{name}'");
+            }
+        }
+        Ok((enable, disable))
+    }
+}
+
+/// Write the experiments enabled on the system to `output`, as TOML if the file has a `.toml`
+/// extension and as JSON otherwise.
+pub fn export_state(system: &impl Worker, output: PathBuf) -> Result<()> {
+    let exported = ExportedState::capture(system)?;
+    let contents = match is_toml(&output) {
+        true => toml::to_string_pretty(&exported)?,
+        false => format!("{}\n", serde_json::to_string_pretty(&exported)?),
+    };
+    info!(
+        "Exporting {} experiments to {}",
+        exported.experiments.len(),
+        output.display()
+    );
+    system.write_file(output, &contents, 0o644)
+}
+
+fn is_toml(file: &Path) -> bool {
+    file.extension().is_some_and(|ext| ext == "toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    fn sudors_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_command("dpkg-query -W -f=${Version} sudo-rs", "0.2.2-1");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}}, "excluded": ["cp"]}"#,
+            false,
+        )]);
+        runner
+    }
+
+    #[test]
+    fn test_export_state_toml() {
+        let runner = sudors_runner();
+        export_state(&runner, PathBuf::from("fleet.toml")).unwrap();
+
+        let exported = ExportedState::load(&runner, Path::new("fleet.toml")).unwrap();
+        assert_eq!(exported.excluded, BTreeSet::from(["cp".to_string()]));
+        assert_eq!(
+            exported.experiments["sudo-rs"],
+            ExportedPackage {
+                name: "sudo-rs".to_string(),
+                version: "0.2.2-1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_import_state_plan() {
+        let runner = sudors_runner();
+        let exported: ExportedState = serde_json::from_str(
+            r#"{"experiments": {"coreutils": {"name": "rust-coreutils", "version": "0.0.30-1"}}}"#,
+        )
+        .unwrap();
+
+        let (enable, disable) = exported.plan(&runner).unwrap();
+        assert_eq!(enable, vec!["coreutils"]);
+        assert_eq!(disable, vec!["sudo-rs"]);
+    }
+}
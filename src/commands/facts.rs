@@ -0,0 +1,125 @@
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+/// Output formats supported by `oxidizr facts`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum FactsFormat {
+    /// `key=value` lines, as read by Puppet external facts from a `.txt` file.
+    KeyValue,
+    /// A JSON object, as read by Ansible local facts or Puppet external facts from a `.json` file.
+    Json,
+}
+
+/// Print facts about the experiments enabled on the system, suitable for writing to
+/// `/etc/ansible/facts.d/oxidizr.fact` or `/etc/facter/facts.d/oxidizr.txt`.
+pub fn facts(system: &impl Worker, format: FactsFormat) -> Result<()> {
+    let facts = gather(system)?;
+    let output = match format {
+        FactsFormat::KeyValue => key_value(&facts),
+        FactsFormat::Json => format!("{}\n", serde_json::to_string_pretty(&facts)?),
+    };
+    print!("{output}");
+    Ok(())
+}
+
+/// Gather a flat map of facts. Keys are prefixed with `oxidizr_`, and any characters that
+/// aren't valid in a fact name are replaced with underscores.
+fn gather(system: &impl Worker) -> Result<BTreeMap<String, Value>> {
+    let state = State::load(system)?;
+    let mut facts = BTreeMap::new();
+    let mut enabled = Vec::new();
+    let mut replaced = 0;
+
+    for e in all_experiments(system) {
+        if !e.check_installed() {
+            continue;
+        }
+        let version = system.package_version(&e.package())?;
+        facts.insert(
+            format!("oxidizr_{}_version", fact_name(&e.name())),
+            json!(version),
+        );
+        replaced += e.managed_links()?.len();
+        enabled.push(e.name());
+    }
+
+    facts.insert(
+        "oxidizr_version".to_string(),
+        json!(env!("CARGO_PKG_VERSION")),
+    );
+    facts.insert("oxidizr_enabled_experiments".to_string(), json!(enabled));
+    facts.insert("oxidizr_replaced_binaries".to_string(), json!(replaced));
+    if let Some(last_run) = state.last_run {
+        facts.insert("oxidizr_last_run".to_string(), json!(last_run));
+    }
+
+    Ok(facts)
+}
+
+/// Render facts as `key=value` lines. Lists are joined with commas.
+fn key_value(facts: &BTreeMap<String, Value>) -> String {
+    facts
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Array(items) => items
+                    .iter()
+                    .map(|i| i.as_str().map(str::to_string).unwrap_or(i.to_string()))
+                    .collect::<Vec<String>>()
+                    .join(","),
+                other => other.to_string(),
+            };
+            format!("{key}={value}\n")
+        })
+        .collect()
+}
+
+fn fact_name(name: &str) -> String {
+    name.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_facts_gather() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_command("dpkg-query -W -f=${Version} sudo-rs", "0.2.2-1");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}}, "last_run": 1700000000}"#,
+            false,
+        )]);
+
+        let facts = gather(&runner).unwrap();
+        assert_eq!(facts["oxidizr_sudo_rs_version"], json!("0.2.2-1"));
+        assert_eq!(facts["oxidizr_enabled_experiments"], json!(["sudo-rs"]));
+        assert_eq!(facts["oxidizr_replaced_binaries"], json!(2));
+        assert_eq!(facts["oxidizr_last_run"], json!(1700000000));
+    }
+
+    #[test]
+    fn test_facts_key_value() {
+        let facts = BTreeMap::from([
+            (
+                "oxidizr_enabled_experiments".to_string(),
+                json!(["coreutils", "sudo-rs"]),
+            ),
+            ("oxidizr_replaced_binaries".to_string(), json!(42)),
+            ("oxidizr_sudo_rs_version".to_string(), json!("0.2.2-1")),
+        ]);
+        assert_eq!(
+            key_value(&facts),
+            "oxidizr_enabled_experiments=coreutils,sudo-rs\noxidizr_replaced_binaries=42\noxidizr_sudo_rs_version=0.2.2-1\n"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::experiments::all_experiments;
+use crate::utils::Worker;
+use anyhow::Result;
+use clap::ValueEnum;
+
+/// Configuration management tools that `oxidizr generate` can produce configuration for.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum GenerateFormat {
+    /// A Puppet manifest.
+    Puppet,
+    /// A Chef recipe.
+    Chef,
+}
+
+/// An experiment that is enabled on the system, and the package it installs.
+struct Enabled {
+    name: String,
+    package: String,
+    high_risk: bool,
+}
+
+/// Print configuration for a configuration management tool that reproduces the experiments
+/// currently enabled on the system.
+pub fn generate(system: &impl Worker, format: GenerateFormat) -> Result<()> {
+    let enabled: Vec<Enabled> = all_experiments(system)
+        .into_iter()
+        .filter(|e| e.check_installed())
+        .map(|e| Enabled {
+            name: e.name(),
+            package: e.package(),
+            high_risk: e.high_risk(),
+        })
+        .collect();
+
+    let output = match format {
+        GenerateFormat::Puppet => puppet(&enabled),
+        GenerateFormat::Chef => chef(&enabled),
+    };
+    print!("{output}");
+    Ok(())
+}
+
+/// Render a Puppet manifest. The packages are managed natively, and oxidizr is run whenever
+/// they're installed or changed to (re)create the symlinks.
+fn puppet(enabled: &[Enabled]) -> String {
+    let mut out = header("#");
+    if enabled.is_empty() {
+        out.push_str("# No experiments are currently enabled.\n");
+        return out;
+    }
+
+    let packages = quoted(enabled.iter().map(|e| e.package.as_str()));
+    let command = command(enabled);
+
+    out.push_str(&format!(
+        r#"package {{ [{packages}]:
+  ensure => installed,
+}}
+
+exec {{ 'oxidizr enable':
+  command     => '{command}',
+  path        => ['/usr/local/sbin', '/usr/local/bin', '/usr/sbin', '/usr/bin', '/sbin', '/bin'],
+  refreshonly => true,
+  subscribe   => Package[{packages}],
+}}
+"#
+    ));
+    out
+}
+
+/// Render a Chef recipe. The packages are managed natively, and oxidizr is run whenever they're
+/// installed or changed to (re)create the symlinks.
+fn chef(enabled: &[Enabled]) -> String {
+    let mut out = header("#");
+    if enabled.is_empty() {
+        out.push_str("# No experiments are currently enabled.\n");
+        return out;
+    }
+
+    let packages = joined(enabled.iter().map(|e| e.package.as_str()));
+    let command = command(enabled);
+
+    out.push_str(&format!(
+        r#"package %w({packages}) do
+  action :install
+  notifies :run, 'execute[oxidizr enable]', :immediately
+end
+
+execute 'oxidizr enable' do
+  command '{command}'
+  action :nothing
+end
+"#
+    ));
+    out
+}
+
+/// The command enabling the experiments non-interactively. High-risk experiments must be
+/// explicitly accepted, since the configuration management tool can't type their names.
+fn command(enabled: &[Enabled]) -> String {
+    let mut command = format!(
+        "oxidizr enable --yes --experiments {}",
+        joined(enabled.iter().map(|e| e.name.as_str()))
+    );
+    let high_risk: Vec<&str> = enabled
+        .iter()
+        .filter(|e| e.high_risk)
+        .map(|e| e.name.as_str())
+        .collect();
+    if !high_risk.is_empty() {
+        command.push_str(&format!(" --accept-risk {}", high_risk.join(" ")));
+    }
+    command
+}
+
+fn header(comment: &str) -> String {
+    format!(
+        "{comment} Generated by oxidizr {}. Reproduces the currently enabled experiments.\n\n",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+fn quoted<'a>(items: impl Iterator<Item = &'a str>) -> String {
+    items
+        .map(|i| format!("'{i}'"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn joined<'a>(items: impl Iterator<Item = &'a str>) -> String {
+    items.collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_puppet() {
+        let output = puppet(&enabled_fixture());
+        assert!(output.contains("package { ['rust-coreutils', 'sudo-rs']:"));
+        assert!(
+            output
+                .contains("command     => 'oxidizr enable --yes --experiments coreutils sudo-rs --accept-risk sudo-rs',")
+        );
+        assert!(output.contains("subscribe   => Package['rust-coreutils', 'sudo-rs'],"));
+    }
+
+    #[test]
+    fn test_generate_chef() {
+        let output = chef(&enabled_fixture());
+        assert!(output.contains("package %w(rust-coreutils sudo-rs) do"));
+        assert!(output.contains(
+            "command 'oxidizr enable --yes --experiments coreutils sudo-rs --accept-risk sudo-rs'"
+        ));
+    }
+
+    #[test]
+    fn test_generate_nothing_enabled() {
+        assert!(puppet(&[]).ends_with("# No experiments are currently enabled.\n"));
+        assert!(chef(&[]).ends_with("# No experiments are currently enabled.\n"));
+    }
+
+    fn enabled_fixture() -> Vec<Enabled> {
+        vec![
+            Enabled {
+                name: "coreutils".to_string(),
+                package: "rust-coreutils".to_string(),
+                high_risk: false,
+            },
+            Enabled {
+                name: "sudo-rs".to_string(),
+                package: "sudo-rs".to_string(),
+                high_risk: true,
+            },
+        ]
+    }
+}
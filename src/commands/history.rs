@@ -0,0 +1,93 @@
+use crate::state::{Operation, State};
+use crate::utils::Worker;
+use anyhow::Result;
+
+/// Print the runs of `enable`, `disable` and `repair` recorded on the system, oldest first, with
+/// the package versions involved. With `files`, the files each run changed are listed too.
+pub fn history(system: &impl Worker, files: bool) -> Result<()> {
+    let state = State::load(system)?;
+    if state.history.is_empty() {
+        println!("No operations recorded");
+    }
+    for operation in &state.history {
+        print!("{}", describe(operation, files));
+    }
+    Ok(())
+}
+
+/// Describe an operation, e.g. `2025-01-31 12:00:00 UTC  enable coreutils (12 files)`, followed
+/// by its package versions.
+fn describe(operation: &Operation, files: bool) -> String {
+    let undone = match operation.undone {
+        true => ", undone",
+        false => "",
+    };
+    let changed = match operation.files.len() {
+        1 => "1 file".to_string(),
+        n => format!("{n} files"),
+    };
+    let mut output = format!(
+        "{}  {} {} ({changed}{undone})\n",
+        utc(operation.time),
+        operation.kind,
+        operation.experiments.join(" "),
+    );
+    for (package, version) in &operation.packages {
+        output.push_str(&format!("    {package} {version}\n"));
+    }
+    if files {
+        for file in &operation.files {
+            output.push_str(&format!("    {}\n", file.display()));
+        }
+    }
+    output
+}
+
+/// Format seconds since the Unix epoch as a UTC date and time, e.g. `2025-01-31 12:00:00 UTC`.
+fn utc(time: u64) -> String {
+    let (days, seconds) = ((time / 86400) as i64, time % 86400);
+
+    // Convert days since the epoch to a civil date, per Howard Hinnant's `civil_from_days`.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::OperationKind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_history_describe() {
+        let mut operation = Operation::new(OperationKind::Enable, None);
+        operation.time = 1738324800;
+        operation.experiments = vec!["sudo-rs".to_string()];
+        operation
+            .packages
+            .insert("sudo-rs".to_string(), "0.2.2-1".to_string());
+        operation.files.insert(PathBuf::from("/usr/bin/sudo"));
+        operation.undone = true;
+
+        assert_eq!(
+            describe(&operation, false),
+            "2025-01-31 12:00:00 UTC  enable sudo-rs (1 file, undone)\n    sudo-rs 0.2.2-1\n"
+        );
+        assert!(describe(&operation, true).ends_with("\n    /usr/bin/sudo\n"));
+        assert_eq!(utc(951782400), "2000-02-29 00:00:00 UTC");
+    }
+}
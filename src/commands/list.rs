@@ -0,0 +1,121 @@
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+
+/// A row of the table printed by `oxidizr list`.
+#[derive(Debug, PartialEq)]
+struct Row {
+    name: String,
+    package: String,
+    releases: String,
+    compatible: &'static str,
+    enabled: &'static str,
+}
+
+/// Print every known experiment, with its package, the releases it supports, and whether
+/// it's compatible with and enabled on this system. Experiments are enabled if they're recorded
+/// in the state, rather than whenever their package happens to be installed.
+pub fn list(system: &impl Worker) -> Result<()> {
+    print!("{}", table(&rows(system, &State::load(system)?)));
+    Ok(())
+}
+
+fn rows(system: &impl Worker, state: &State) -> Vec<Row> {
+    all_experiments(system)
+        .into_iter()
+        .map(|e| {
+            let name = match e.opt_in() {
+                true => format!("{} (opt-in)", e.name()),
+                false => e.name(),
+            };
+            Row {
+                name,
+                package: e.package(),
+                releases: e.supported_releases().join(", "),
+                compatible: yes_no(e.check_compatible()),
+                enabled: yes_no(state.experiments.contains_key(&e.name())),
+            }
+        })
+        .collect()
+}
+
+fn yes_no(value: bool) -> &'static str {
+    match value {
+        true => "yes",
+        false => "no",
+    }
+}
+
+/// Render rows as a table with aligned columns.
+fn table(rows: &[Row]) -> String {
+    let header = ["EXPERIMENT", "PACKAGE", "RELEASES", "COMPATIBLE", "ENABLED"];
+    let cells: Vec<[&str; 5]> = rows
+        .iter()
+        .map(|r| [&*r.name, &*r.package, &*r.releases, r.compatible, r.enabled])
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    std::iter::once(header)
+        .chain(cells)
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect();
+            format!("{}\n", line.join("  ").trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_list_rows() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_install_package("rust-coreutils");
+        let mut state = State::default();
+        state.record("sudo-rs", Vec::new());
+
+        let rows = rows(&runner, &state);
+        let sudors = rows.iter().find(|r| r.name == "sudo-rs").unwrap();
+        assert_eq!(sudors.package, "sudo-rs");
+        assert_eq!(sudors.compatible, "yes");
+        assert_eq!(sudors.enabled, "yes");
+
+        let frawk = rows.iter().find(|r| r.package == "frawk").unwrap();
+        assert_eq!(frawk.name, "frawk (opt-in)");
+        assert_eq!(frawk.enabled, "no");
+
+        // Installed, but not enabled by oxidizr.
+        let coreutils = rows.iter().find(|r| r.name == "coreutils").unwrap();
+        assert_eq!(coreutils.enabled, "no");
+    }
+
+    #[test]
+    fn test_list_table() {
+        let rows = vec![Row {
+            name: "sudo-rs".to_string(),
+            package: "sudo-rs".to_string(),
+            releases: "24.04, 24.10".to_string(),
+            compatible: "yes",
+            enabled: "no",
+        }];
+        assert_eq!(
+            table(&rows),
+            "EXPERIMENT  PACKAGE  RELEASES      COMPATIBLE  ENABLED\n\
+             sudo-rs     sudo-rs  24.04, 24.10  yes         no\n"
+        );
+    }
+}
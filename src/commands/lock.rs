@@ -0,0 +1,228 @@
+use crate::experiments::Experiment;
+use crate::utils::Worker;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Default location of the lockfile, relative to the working directory.
+pub const DEFAULT_LOCKFILE: &str = "oxidizr.lock";
+
+/// Exact package versions and checksums for a set of experiments, so that every machine
+/// enabling them from the lockfile installs identical binaries.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Locked packages, keyed by experiment name.
+    #[serde(default)]
+    pub experiments: BTreeMap<String, LockedPackage>,
+}
+
+/// A package pinned to an exact version and `.deb` checksum.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub sha256: String,
+}
+
+impl Lockfile {
+    /// Read a lockfile from disk.
+    pub fn load(system: &dyn Worker, file: PathBuf) -> Result<Self> {
+        let contents = system
+            .read_file(file.clone())
+            .map_err(|e| anyhow::anyhow!("Unable to read lockfile {}: {e}", file.display()))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Report the locked version of each package, keyed by package name.
+    pub fn versions(&self) -> BTreeMap<String, String> {
+        self.experiments
+            .values()
+            .map(|p| (p.name.clone(), p.version.clone()))
+            .collect()
+    }
+
+    /// Check that each experiment is locked, and that the exact version of its package is
+    /// available with the locked checksum.
+    pub fn verify(&self, system: &dyn Worker, experiments: &[Experiment]) -> Result<()> {
+        for e in experiments {
+            let Some(locked) = self.experiments.get(&e.name()) else {
+                anyhow::bail!("'{}' is not in the lockfile", e.name());
+            };
+
+            let sha256 = match system.local_package_dir() {
+                Some(_) => system.sha256(system.local_package(&locked.name)?)?,
+                None => {
+                    let spec = format!("{}={}", locked.name, locked.version);
+                    let record = system.package_record(&spec).map_err(|_| {
+                        anyhow::anyhow!("Locked version {spec} is not available from the archive")
+                    })?;
+                    field(&record, "SHA256").unwrap_or_default()
+                }
+            };
+            anyhow::ensure!(
+                sha256 == locked.sha256,
+                "Checksum of {} {} does not match the lockfile (expected {}, found {sha256})",
+                locked.name,
+                locked.version,
+                locked.sha256
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Write a lockfile capturing the package version and checksum for each of `experiments`.
+/// Installed packages are locked at their installed version, otherwise the candidate version
+/// from the archive is used.
+pub fn lock(system: &impl Worker, experiments: &[Experiment], output: PathBuf) -> Result<()> {
+    let mut lockfile = Lockfile::default();
+    for e in experiments {
+        let package = e.package();
+        let spec = match e.check_installed() {
+            true => format!("{package}={}", system.package_version(&package)?),
+            false => package.clone(),
+        };
+        let record = system.package_record(&spec)?;
+
+        let (Some(version), Some(sha256)) = (field(&record, "Version"), field(&record, "SHA256"))
+        else {
+            anyhow::bail!("Unable to determine the version and checksum of {spec}");
+        };
+        info!("Locking {} at {package} {version}", e.name());
+        lockfile.experiments.insert(
+            e.name(),
+            LockedPackage {
+                name: package,
+                version,
+                sha256,
+            },
+        );
+    }
+
+    let contents = serde_json::to_string_pretty(&lockfile)?;
+    system.write_file(output, &format!("{contents}\n"), 0o644)
+}
+
+/// Read a field from an apt package record.
+fn field(record: &str, name: &str) -> Option<String> {
+    record.lines().find_map(|line| {
+        line.strip_prefix(&format!("{name}:"))
+            .map(|value| value.trim().to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::all_experiments;
+    use crate::utils::MockSystem;
+
+    const SUDO_RS_RECORD: &str =
+        "Package: sudo-rs\nVersion: 0.2.2-1\nSHA256: abc123\nSHA512: def456\n";
+
+    fn sudors(runner: &MockSystem) -> Vec<Experiment<'_>> {
+        all_experiments(runner)
+            .into_iter()
+            .filter(|e| e.name() == "sudo-rs")
+            .collect()
+    }
+
+    #[test]
+    fn test_lock() {
+        let runner = MockSystem::default();
+        runner.mock_command("apt-cache show --no-all-versions sudo-rs", SUDO_RS_RECORD);
+
+        lock(
+            &runner,
+            &sudors(&runner),
+            PathBuf::from("/tmp/oxidizr.lock"),
+        )
+        .unwrap();
+
+        let lockfile = Lockfile::load(&runner, PathBuf::from("/tmp/oxidizr.lock")).unwrap();
+        assert_eq!(
+            lockfile.experiments["sudo-rs"],
+            LockedPackage {
+                name: "sudo-rs".to_string(),
+                version: "0.2.2-1".to_string(),
+                sha256: "abc123".to_string(),
+            }
+        );
+        assert_eq!(
+            lockfile.versions(),
+            BTreeMap::from([("sudo-rs".to_string(), "0.2.2-1".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_lock_installed_version() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_command("dpkg-query -W -f=${Version} sudo-rs", "0.2.2-1");
+        runner.mock_command(
+            "apt-cache show --no-all-versions sudo-rs=0.2.2-1",
+            SUDO_RS_RECORD,
+        );
+
+        lock(
+            &runner,
+            &sudors(&runner),
+            PathBuf::from("/tmp/oxidizr.lock"),
+        )
+        .unwrap();
+        assert!(
+            runner
+                .commands
+                .borrow()
+                .contains(&"apt-cache show --no-all-versions sudo-rs=0.2.2-1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lockfile_verify() {
+        let runner = MockSystem::default();
+        let mut lockfile = Lockfile::default();
+        lockfile.experiments.insert(
+            "sudo-rs".to_string(),
+            LockedPackage {
+                name: "sudo-rs".to_string(),
+                version: "0.2.2-1".to_string(),
+                sha256: "abc123".to_string(),
+            },
+        );
+
+        // The locked version isn't available from the archive.
+        runner.mock_command_failure("apt-cache show --no-all-versions sudo-rs=0.2.2-1");
+        assert!(lockfile.verify(&runner, &sudors(&runner)).is_err());
+
+        let runner = MockSystem::default();
+        runner.mock_command(
+            "apt-cache show --no-all-versions sudo-rs=0.2.2-1",
+            SUDO_RS_RECORD,
+        );
+        assert!(lockfile.verify(&runner, &sudors(&runner)).is_ok());
+
+        lockfile.experiments.get_mut("sudo-rs").unwrap().sha256 = "other".to_string();
+        assert!(lockfile.verify(&runner, &sudors(&runner)).is_err());
+
+        // Experiments missing from the lockfile can't be enabled from it.
+        let coreutils: Vec<Experiment> = all_experiments(&runner)
+            .into_iter()
+            .filter(|e| e.name() == "coreutils")
+            .collect();
+        assert!(lockfile.verify(&runner, &coreutils).is_err());
+    }
+
+    #[test]
+    fn test_install_pinned_version() {
+        let runner = MockSystem::default();
+        runner.mock_pinned_versions(vec![("sudo-rs", "0.2.2-1")]);
+        runner.install_package("sudo-rs").unwrap();
+        assert_eq!(
+            runner.commands.borrow().clone(),
+            vec!["apt-get install -y --allow-downgrades sudo-rs=0.2.2-1"]
+        );
+    }
+}
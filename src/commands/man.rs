@@ -0,0 +1,30 @@
+use anyhow::Result;
+use clap::Command;
+use clap_mangen::Man;
+use std::io::Write;
+
+/// Write the `oxidizr(8)` manual page to `out`, documenting every subcommand and option.
+pub fn man(cmd: Command, out: &mut impl Write) -> Result<()> {
+    // oxidizr administers the system, so belongs in section 8 rather than the default 1.
+    Man::new(cmd).section("8").render(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_man() {
+        let cmd = Command::new("oxidizr")
+            .about("Rust replacements")
+            .subcommand(Command::new("enable").about("Enable experiments"));
+
+        let mut out = Vec::new();
+        man(cmd, &mut out).unwrap();
+        let page = String::from_utf8(out).unwrap();
+        assert!(page.starts_with(".ie \\n(.g .ds Aq \\(aq"));
+        assert!(page.contains(".TH oxidizr 8"));
+        assert!(page.contains("Enable experiments"));
+    }
+}
@@ -0,0 +1,68 @@
+//! Implementations of the subcommands that inspect or report on the experiments managed by
+//! oxidizr, as opposed to enabling or disabling them.
+mod benchmark;
+mod check;
+mod compare;
+mod compat_scan;
+mod completions;
+mod config;
+mod doctor;
+mod export;
+mod facts;
+mod generate;
+mod history;
+mod list;
+mod lock;
+mod man;
+mod pin;
+mod plan;
+mod promote;
+mod purge_backups;
+mod recover;
+mod refresh;
+mod repair;
+mod report;
+mod rollback;
+mod self_test;
+mod snapshot;
+mod switch;
+pub mod sysext;
+mod trial;
+mod try_shell;
+mod tui;
+mod undo;
+mod upgrade;
+mod verify;
+
+pub use benchmark::benchmark;
+pub use check::{CheckFormat, Thresholds, check};
+pub use compare::compare;
+pub use compat_scan::compat_scan;
+pub use completions::completions;
+pub use config::{ConfigKey, config_get, config_list, config_set, config_unset};
+pub use doctor::doctor;
+pub use export::{ExportedState, export_state};
+pub use facts::{FactsFormat, facts};
+pub use generate::{GenerateFormat, generate};
+pub use history::history;
+pub use list::list;
+pub use lock::{DEFAULT_LOCKFILE, Lockfile, lock};
+pub use man::man;
+pub use pin::{pin, unpin};
+pub use plan::{PlanAction, plan};
+pub use promote::{parse_duration, promote};
+pub use purge_backups::{purge_backups, purge_summary, purgeable_backups};
+pub use recover::{recover, restore_originals, restore_recorded};
+pub use refresh::refresh;
+pub use repair::repair;
+pub use report::{ReportFormat, report};
+pub use rollback::rollback;
+pub use self_test::self_test;
+pub use snapshot::{restore_snapshot, snapshot};
+pub use switch::{Implementation, switch};
+pub use trial::trial;
+pub use try_shell::try_shell;
+pub use tui::{Selection, choose, utility};
+pub use undo::undo_enable;
+pub use upgrade::upgrade;
+pub use verify::{backup_checksums, verify};
@@ -0,0 +1,112 @@
+use crate::experiments::{Experiment, all_experiments};
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+use tracing::info;
+
+/// Hold the packages of the enabled experiments with `apt-mark`, or `dnf versionlock` on Fedora
+/// and `zypper addlock` on openSUSE, so that upgrades and autoremoval can't replace or remove them from underneath the managed
+/// symlinks.
+pub fn pin(system: &impl Worker) -> Result<()> {
+    let held = system.held_packages()?;
+    for e in enabled_experiments(system)? {
+        if held.contains(&e.package()) {
+            info!("{} is already held", e.package());
+            continue;
+        }
+        info!("Holding {}", e.package());
+        system.hold_package(&e.package())?;
+    }
+    Ok(())
+}
+
+/// Release the holds placed on the packages of the enabled experiments by `pin`.
+pub fn unpin(system: &impl Worker) -> Result<()> {
+    let held = system.held_packages()?;
+    for e in enabled_experiments(system)? {
+        if !held.contains(&e.package()) {
+            continue;
+        }
+        info!("Releasing the hold on {}", e.package());
+        system.unhold_package(&e.package())?;
+    }
+    Ok(())
+}
+
+fn enabled_experiments(system: &impl Worker) -> Result<Vec<Experiment<'_>>> {
+    let state = State::load(system)?;
+    Ok(all_experiments(system)
+        .into_iter()
+        .filter(|e| state.experiments.contains_key(&e.name()) && e.check_installed())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Distribution, MockSystem};
+
+    fn enabled_sudo_rs() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}}}"#,
+            false,
+        )]);
+        runner
+    }
+
+    #[test]
+    fn test_pin() {
+        let runner = enabled_sudo_rs();
+        pin(&runner).unwrap();
+
+        // rust-coreutils is installed, but coreutils isn't enabled.
+        let commands = runner.commands.borrow().clone();
+        assert!(commands.contains(&"apt-mark hold sudo-rs".to_string()));
+        assert!(!commands.contains(&"apt-mark hold rust-coreutils".to_string()));
+    }
+
+    #[test]
+    fn test_pin_pacman() {
+        let runner = MockSystem::new(Distribution {
+            id: "Arch".to_string(),
+            release: "rolling".to_string(),
+        });
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}}}"#,
+            false,
+        )]);
+
+        assert!(pin(&runner).is_err());
+        // Nothing can have been held, so there's nothing to release.
+        assert!(unpin(&runner).is_ok());
+        assert!(runner.commands.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_unpin() {
+        let runner = enabled_sudo_rs();
+        unpin(&runner).unwrap();
+        assert!(
+            !runner
+                .commands
+                .borrow()
+                .iter()
+                .any(|c| c.contains("unhold"))
+        );
+
+        runner.mock_command("apt-mark showhold", "sudo-rs\n");
+        unpin(&runner).unwrap();
+        assert!(
+            runner
+                .commands
+                .borrow()
+                .contains(&"apt-mark unhold sudo-rs".to_string())
+        );
+    }
+}
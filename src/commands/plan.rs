@@ -0,0 +1,123 @@
+use crate::experiments::Experiment;
+use crate::utils::{DryRun, Worker};
+use anyhow::Result;
+use clap::ValueEnum;
+
+/// The runs that `plan` can preview.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum PlanAction {
+    Enable,
+    Disable,
+}
+
+/// Print the commands that enabling or disabling `experiments` would run, and the files it would
+/// back up, link, restore or remove, without changing anything. The experiments must be built
+/// with `system`, so that their changes are recorded rather than made.
+pub fn plan(
+    system: &DryRun,
+    experiments: &[Experiment],
+    action: PlanAction,
+    no_compatibility_check: bool,
+) -> Result<()> {
+    print!(
+        "{}",
+        describe(system, experiments, action, no_compatibility_check)?
+    );
+    Ok(())
+}
+
+fn describe(
+    system: &DryRun,
+    experiments: &[Experiment],
+    action: PlanAction,
+    no_compatibility_check: bool,
+) -> Result<String> {
+    let mut out = String::new();
+    let mut section = |heading: String, actions: Vec<String>, note: Option<String>| {
+        out.push_str(&format!("{heading}:\n"));
+        for action in actions {
+            out.push_str(&format!("  {action}\n"));
+        }
+        if let Some(note) = note {
+            out.push_str(&format!("  ({note})\n"));
+        }
+    };
+
+    if action == PlanAction::Enable && system.local_package_dir().is_none() {
+        system.update_package_lists()?;
+        section(
+            "Updating the package lists".to_string(),
+            system.take_actions(),
+            None,
+        );
+    }
+
+    for e in experiments {
+        let installed = e.check_installed();
+        let note = match action {
+            PlanAction::Enable => match e.enable(no_compatibility_check) {
+                Ok(true) => None,
+                Ok(false) => Some("skipped, as this release isn't supported".to_string()),
+                // The utilities an experiment replaces are found in its package's files, so
+                // they can't be listed until the package is installed.
+                Err(_) if !installed => Some(format!(
+                    "the files {} replaces are listed once it's installed",
+                    e.package()
+                )),
+                Err(err) => Some(format!("fails: {err}")),
+            },
+            PlanAction::Disable => {
+                e.disable()?;
+                (!installed).then(|| "not enabled, nothing to do".to_string())
+            }
+        };
+        let verb = match action {
+            PlanAction::Enable => "Enabling",
+            PlanAction::Disable => "Disabling",
+        };
+        section(format!("{verb} {}", e.name()), system.take_actions(), note);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::all_experiments;
+    use crate::utils::MockSystem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_plan_describe() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/findutils/find", "", false),
+            ("/usr/bin/find", "", false),
+        ]);
+        runner.mock_install_package("rust-findutils");
+        let dry_run = DryRun::new(&runner);
+        let experiments: Vec<Experiment> = all_experiments(&dry_run)
+            .into_iter()
+            .filter(|e| e.name() == "findutils")
+            .collect();
+
+        assert_eq!(
+            describe(&dry_run, &experiments, PlanAction::Enable, true).unwrap(),
+            "Updating the package lists:\n  \
+             run 'apt-get update'\n\
+             Enabling findutils:\n  \
+             run 'apt-get install -y rust-findutils'\n  \
+             back up /usr/bin/find to /usr/bin/.find.oxidizr.bak\n  \
+             link /usr/bin/find -> /usr/lib/cargo/bin/findutils/find\n"
+        );
+        assert!(runner.created_symlinks.borrow().is_empty());
+
+        assert_eq!(
+            describe(&dry_run, &experiments, PlanAction::Disable, true).unwrap(),
+            "Disabling findutils:\n  \
+             restore /usr/bin/find from its backup\n  \
+             run 'apt-get remove -y rust-findutils'\n"
+        );
+        assert!(runner.file_exists(PathBuf::from("/usr/bin/find")));
+    }
+}
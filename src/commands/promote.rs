@@ -0,0 +1,165 @@
+use crate::commands::backup_checksums;
+use crate::experiments::{Experiment, all_experiments};
+use crate::state::State;
+use crate::utils::{Command, Worker, original_packages};
+use anyhow::Result;
+use tracing::info;
+
+/// Promote experiments enabled as canaries, linking their remaining binaries once the canary
+/// binaries have been validated. If `names` is empty, every canary awaiting promotion is
+/// considered. With `if_soaked`, canaries are only promoted once their soak period has elapsed,
+/// which makes it suitable for running from a timer.
+pub fn promote(system: &impl Worker, names: &[String], if_soaked: bool) -> Result<()> {
+    let mut state = State::load(system)?;
+    let mut promoted = 0;
+
+    for e in all_experiments(system) {
+        if !names.is_empty() && !names.contains(&e.name()) {
+            continue;
+        }
+        let Some(canary) = state
+            .experiments
+            .get(&e.name())
+            .and_then(|s| s.canary.clone())
+        else {
+            continue;
+        };
+
+        if if_soaked && !canary.soaked() {
+            info!("'{}' is still soaking, skipping", e.name());
+            continue;
+        }
+
+        validate(system, &e)?;
+        info!("Promoting '{}'", e.name());
+        e.promote()?;
+        state.finish_canary(&e.name());
+        let links = e.managed_links()?;
+        state.record_links(&e.name(), links.into_iter().map(|l| l.target));
+        let backups = backup_checksums(system, &e, &state)?;
+        state.record_backups(&e.name(), backups);
+        let links = &state.experiments[&e.name()].links;
+        let originals = original_packages(system, links, &e.package());
+        state.record_originals(&e.name(), originals);
+        state.save(system)?;
+        promoted += 1;
+    }
+
+    if promoted == 0 {
+        info!("No canaries were promoted");
+    }
+    Ok(())
+}
+
+/// Check that each canary binary is still linked, and still runs.
+fn validate(system: &impl Worker, experiment: &Experiment) -> Result<()> {
+    for link in experiment.canary_links()? {
+        anyhow::ensure!(
+            !link.drifted(system),
+            "Canary validation of '{}' failed: {} is no longer linked",
+            experiment.name(),
+            link.target.display()
+        );
+        let cmd = Command::build(&link.target.to_string_lossy(), &["--version"]);
+        system.run(&cmd).map_err(|e| {
+            anyhow::anyhow!("Canary validation of '{}' failed: {e}", experiment.name())
+        })?;
+    }
+    Ok(())
+}
+
+/// Parse a duration such as `90`, `30m`, `24h` or `7d` into seconds.
+pub fn parse_duration(value: &str) -> Result<u64, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "unknown unit '{unit}', expected one of s, m, h or d"
+            ));
+        }
+    };
+    number
+        .parse::<u64>()
+        .map(|n| n * multiplier)
+        .map_err(|_| format!("invalid duration '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    fn canary_runner(started: u64) -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/coreutils/date", "", false),
+            ("/usr/lib/cargo/bin/coreutils/seq", "", false),
+            ("/usr/bin/date", "", true),
+            ("/usr/bin/seq", "", true),
+            (
+                "/var/lib/oxidizr/state.json",
+                &format!(
+                    r#"{{"experiments": {{"coreutils": {{"canary": {{"started": {started}, "soak": 3600}}}}}}}}"#
+                ),
+                false,
+            ),
+        ]);
+        runner.mock_symlinks(vec![("/usr/bin/seq", "/usr/bin/coreutils")]);
+        runner
+    }
+
+    #[test]
+    fn test_promote() {
+        let runner = canary_runner(u64::MAX / 2);
+
+        // The canary hasn't soaked yet, so isn't promoted automatically.
+        promote(&runner, &[], true).unwrap();
+        assert!(runner.created_symlinks.borrow().is_empty());
+
+        promote(&runner, &[], false).unwrap();
+        assert!(
+            runner
+                .commands
+                .borrow()
+                .contains(&"/usr/bin/seq --version".to_string())
+        );
+        assert_eq!(
+            runner.created_symlinks.borrow().clone(),
+            vec![(
+                "/usr/bin/coreutils".to_string(),
+                "/usr/bin/date".to_string()
+            )]
+        );
+        assert_eq!(
+            State::load(&runner).unwrap().experiments["coreutils"].canary,
+            None
+        );
+    }
+
+    #[test]
+    fn test_promote_validation_failure() {
+        let runner = canary_runner(0);
+        runner.mock_command_failure("/usr/bin/seq --version");
+
+        assert!(promote(&runner, &[], true).is_err());
+        assert!(runner.created_symlinks.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("30m"), Ok(1800));
+        assert_eq!(parse_duration("24h"), Ok(86400));
+        assert_eq!(parse_duration("7d"), Ok(604800));
+        assert!(parse_duration("1w").is_err());
+        assert!(parse_duration("h").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::experiments::Experiment;
+use crate::state::State;
+use crate::utils::{
+    Worker, compressed_backup_filename, find_backup, forget_purged_backups, has_compressed_backup,
+    remove_compressed_backup,
+};
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::info;
+
+/// A backup of a file replaced by an experiment, which can be purged.
+#[derive(Debug, PartialEq)]
+pub struct Backup {
+    /// The file that was backed up, e.g. `/usr/bin/date`.
+    pub file: PathBuf,
+    /// The path of the backup itself.
+    pub path: PathBuf,
+    compressed: bool,
+}
+
+/// Find the backups of the files replaced by `experiments`. Backups of originals run by wrapper
+/// scripts are still in use, so aren't included. Nor are backups of symlinks, such as busybox
+/// applets or links managed by the alternatives system, which take no space and often can't be
+/// restored by reinstalling a package, as no package ships the links.
+pub fn purgeable_backups(system: &impl Worker, experiments: &[Experiment]) -> Result<Vec<Backup>> {
+    let mut backups = Vec::new();
+    for e in experiments {
+        for link in e.managed_links()? {
+            if link.wraps_original() {
+                continue;
+            }
+            if let Some(path) = find_backup(system, &link.target) {
+                if system.read_link(path.clone()).is_ok() {
+                    continue;
+                }
+                backups.push(Backup {
+                    file: link.target,
+                    path,
+                    compressed: false,
+                });
+            } else if has_compressed_backup(system, &link.target) {
+                backups.push(Backup {
+                    path: compressed_backup_filename(system, &link.target),
+                    file: link.target,
+                    compressed: true,
+                });
+            }
+        }
+    }
+    Ok(backups)
+}
+
+/// Describe the backups that will be purged, and the consequences for `disable`.
+pub fn purge_summary(backups: &[Backup]) -> String {
+    let mut summary = String::from("The following backups will be removed:\n");
+    for backup in backups {
+        summary.push_str(&format!("  {}\n", backup.path.display()));
+    }
+    summary.push_str(&format!(
+        "{} backups in total. Once removed, 'oxidizr disable' restores these files by \
+         reinstalling the packages that ship them.\n",
+        backups.len()
+    ));
+    summary
+}
+
+/// Remove `backups`, and forget their checksums and their entries in the backup manifest so
+/// `oxidizr verify` doesn't report them.
+pub fn purge_backups(system: &impl Worker, backups: &[Backup]) -> Result<()> {
+    let mut state = State::load(system)?;
+    for backup in backups {
+        info!("Removing {}", backup.path.display());
+        match backup.compressed {
+            true => remove_compressed_backup(system, &backup.file)?,
+            false => system.remove_file(backup.path.clone())?,
+        }
+        state.forget_backup(&backup.file);
+    }
+    let files: Vec<PathBuf> = backups.iter().map(|b| b.file.clone()).collect();
+    forget_purged_backups(system, &files)?;
+    state.save(system)?;
+    info!("Purged {} backups", backups.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::all_experiments;
+    use crate::utils::{BACKUP_MANIFEST, Distribution, MockSystem};
+
+    #[test]
+    fn test_purge_backups() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![
+            ("/usr/bin/.sudo.oxidizr.bak", "", false),
+            ("/usr/bin/.visudo.oxidizr.bak", "", false),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"sudo-rs": {"backups": {"/usr/bin/sudo": "abc", "/usr/bin/visudo": "def"}}}}"#,
+                false,
+            ),
+            (
+                BACKUP_MANIFEST,
+                r#"{
+                    "/usr/bin/sudo": {"sha256": "abc", "mode": 493, "uid": 0, "gid": 0},
+                    "/usr/bin/visudo": {"sha256": "def", "mode": 493, "uid": 0, "gid": 0},
+                    "/usr/bin/date": {"sha256": "123", "mode": 493, "uid": 0, "gid": 0}
+                }"#,
+                false,
+            ),
+        ]);
+        let experiments: Vec<Experiment> = all_experiments(&runner)
+            .into_iter()
+            .filter(|e| e.name() == "sudo-rs")
+            .collect();
+
+        let backups = purgeable_backups(&runner, &experiments).unwrap();
+        assert_eq!(
+            backups.iter().map(|b| &b.path).collect::<Vec<_>>(),
+            vec![
+                &PathBuf::from("/usr/bin/.sudo.oxidizr.bak"),
+                &PathBuf::from("/usr/bin/.visudo.oxidizr.bak"),
+            ]
+        );
+        assert!(purge_summary(&backups).contains("2 backups in total"));
+
+        purge_backups(&runner, &backups).unwrap();
+        let removed = runner.removed_files.borrow().clone();
+        assert_eq!(
+            removed,
+            vec!["/usr/bin/.sudo.oxidizr.bak", "/usr/bin/.visudo.oxidizr.bak"]
+        );
+        assert!(
+            State::load(&runner).unwrap().experiments["sudo-rs"]
+                .backups
+                .is_empty()
+        );
+        // Only the purged backups are dropped from the manifest.
+        let manifest: serde_json::Value =
+            serde_json::from_str(&runner.read_file(PathBuf::from(BACKUP_MANIFEST)).unwrap())
+                .unwrap();
+        assert_eq!(
+            manifest.as_object().unwrap().keys().collect::<Vec<_>>(),
+            &["/usr/bin/date"]
+        );
+    }
+
+    #[test]
+    fn test_purge_backups_keeps_busybox_applets() {
+        let runner = MockSystem::new(Distribution {
+            id: "Alpine".to_string(),
+            release: "3.21".to_string(),
+        });
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![
+            ("/usr/bin/.su.oxidizr.bak", "", false),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"su": {}}}"#,
+                false,
+            ),
+        ]);
+        runner.mock_symlinks(vec![("/usr/bin/.su.oxidizr.bak", "/bin/busybox")]);
+        let experiments: Vec<Experiment> = all_experiments(&runner)
+            .into_iter()
+            .filter(|e| e.name() == "su")
+            .collect();
+
+        assert!(purgeable_backups(&runner, &experiments).unwrap().is_empty());
+    }
+}
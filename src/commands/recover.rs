@@ -0,0 +1,190 @@
+use crate::state::State;
+use crate::utils::{JOURNAL_FILE, Worker, has_backup};
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// Restore every file replaced by the enabled experiments from its backup, and remove the
+/// symlinks that have no backup, using only filesystem operations. Neither apt nor dpkg is run,
+/// so this works when the package manager is broken; the packages are left installed, and are
+/// listed so they can be removed once apt works again. Files are found from the recorded state.
+pub fn recover(system: &impl Worker) -> Result<()> {
+    let mut state = State::load(system)?;
+    let mut packages = BTreeSet::new();
+
+    for (name, recorded) in state.experiments.clone() {
+        if recorded.sysext {
+            warn!("Skipping '{name}', as it's provided by the system extension image");
+            continue;
+        }
+        info!("Recovering '{name}'");
+        for file in &recorded.links {
+            if has_backup(system, file) {
+                info!("Restoring {}", file.display());
+                system.restore_file(file.clone())?;
+            } else if system.read_link(file.clone()).is_ok() {
+                info!("Removing {}", file.display());
+                system.remove_file(file.clone())?;
+            }
+        }
+        packages.extend(recorded.packages.iter().map(|p| p.name.clone()));
+        state.remove(&name);
+        state.save(system)?;
+    }
+
+    // The journal records actions on files that have now been restored.
+    system.remove_file(PathBuf::from(JOURNAL_FILE))?;
+    state.discard_journal();
+    state.save(system)?;
+
+    if !packages.is_empty() {
+        let packages: Vec<String> = packages.into_iter().collect();
+        info!(
+            "Once apt works again, remove the packages left installed with 'apt-get remove {}'",
+            packages.join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Restore the recorded files of a disabled experiment whose backups remain, because the
+/// experiment didn't find them to restore, e.g. as its package no longer ships a binary it
+/// replaced or was removed by hand. Files without a backup are left alone.
+pub fn restore_recorded(system: &impl Worker, links: &BTreeSet<PathBuf>) -> Result<()> {
+    for file in links {
+        if has_backup(system, file) {
+            info!("Restoring {}", file.display());
+            system.restore_file(file.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that the packages that shipped the files a disabled experiment replaced are still
+/// installed at the versions recorded when it was enabled, warning otherwise. With `exact`,
+/// packages that were removed or changed version are installed at the recorded version.
+pub fn restore_originals(
+    system: &impl Worker,
+    originals: &BTreeMap<String, String>,
+    exact: bool,
+) -> Result<()> {
+    for (package, recorded) in originals {
+        let installed = match system.check_installed(package)? {
+            true => Some(system.package_version(package)?),
+            false => None,
+        };
+        if installed.as_ref() == Some(recorded) {
+            continue;
+        }
+        if exact {
+            info!("Installing {package} {recorded}, as installed when the experiment was enabled");
+            let cmd = system.package_manager().install(package, Some(recorded));
+            if let Err(e) = system.run(&cmd) {
+                warn!("Unable to install {package} {recorded}: {e}");
+            }
+            continue;
+        }
+        match installed {
+            Some(installed) => warn!(
+                "{package} is at version {installed}, not {recorded} as when the experiment was \
+                 enabled. Pass '--restore-versions' to install {recorded}"
+            ),
+            None => warn!(
+                "{package}, which shipped the replaced files, is no longer installed. Pass \
+                 '--restore-versions' to install {recorded}"
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_recover() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/bin/.date.oxidizr.bak", "", false),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"coreutils": {
+                    "packages": [{"name": "rust-coreutils"}],
+                    "links": ["/usr/bin/date", "/usr/bin/b2sum", "/usr/bin/sort"]
+                }}}"#,
+                false,
+            ),
+        ]);
+        runner.mock_symlinks(vec![
+            ("/usr/bin/date", "/usr/bin/coreutils"),
+            ("/usr/bin/b2sum", "/usr/bin/coreutils"),
+        ]);
+
+        recover(&runner).unwrap();
+
+        // /usr/bin/sort has neither a backup nor a symlink, so is left alone.
+        assert_eq!(*runner.restored_files.borrow(), vec!["/usr/bin/date"]);
+        assert_eq!(
+            *runner.removed_files.borrow(),
+            vec!["/usr/bin/b2sum", JOURNAL_FILE]
+        );
+        assert!(runner.commands.borrow().is_empty());
+        assert!(State::load(&runner).unwrap().experiments.is_empty());
+    }
+
+    #[test]
+    fn test_restore_recorded() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/bin/.date.oxidizr.bak", "", false)]);
+        runner.mock_symlinks(vec![("/usr/bin/b2sum", "/usr/bin/coreutils")]);
+
+        let links = BTreeSet::from([
+            PathBuf::from("/usr/bin/date"),
+            PathBuf::from("/usr/bin/b2sum"),
+        ]);
+        restore_recorded(&runner, &links).unwrap();
+
+        assert_eq!(*runner.restored_files.borrow(), vec!["/usr/bin/date"]);
+        assert!(runner.removed_files.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_restore_originals() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("coreutils");
+        runner.mock_install_package("findutils");
+        runner.mock_command("dpkg-query -W -f=${Version} coreutils", "9.4-3ubuntu6");
+        runner.mock_command("dpkg-query -W -f=${Version} findutils", "4.9.0-6");
+        let originals = BTreeMap::from([
+            ("coreutils".to_string(), "9.4-3ubuntu6".to_string()),
+            ("diffutils".to_string(), "1:3.10-1".to_string()),
+            ("findutils".to_string(), "4.9.0-5".to_string()),
+        ]);
+
+        restore_originals(&runner, &originals, false).unwrap();
+        assert!(
+            !runner
+                .commands
+                .borrow()
+                .iter()
+                .any(|c| c.starts_with("apt-get"))
+        );
+
+        restore_originals(&runner, &originals, true).unwrap();
+        let commands = runner.commands.borrow();
+        let installs: Vec<_> = commands
+            .iter()
+            .filter(|c| c.starts_with("apt-get"))
+            .collect();
+        assert_eq!(
+            installs,
+            vec![
+                "apt-get install -y --allow-downgrades diffutils=1:3.10-1",
+                "apt-get install -y --allow-downgrades findutils=4.9.0-5",
+            ]
+        );
+    }
+}
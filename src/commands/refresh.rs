@@ -0,0 +1,108 @@
+use crate::commands::{backup_checksums, sysext};
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+use tracing::{info, warn};
+
+/// Re-apply enabled experiments after their packages are upgraded, linking any utilities the
+/// new packages provide that aren't linked yet. Utilities that are already linked are left
+/// untouched, and experiments provided by the system extension image are rebuilt into it.
+pub fn refresh(system: &impl Worker) -> Result<()> {
+    let mut state = State::load(system)?;
+    let mut added = 0;
+
+    for e in all_experiments(system) {
+        let Some(recorded) = state.experiments.get(&e.name()) else {
+            continue;
+        };
+        if !e.check_installed() || recorded.sysext {
+            continue;
+        }
+
+        // Experiments enabled before links were recorded treat their intact links as existing.
+        let mut existing = recorded.links.clone();
+        let links = e.expected_links(&state)?;
+        if existing.is_empty() {
+            existing.extend(
+                links
+                    .iter()
+                    .filter(|l| !l.drifted(system))
+                    .map(|l| l.target.clone()),
+            );
+        }
+
+        for target in &existing {
+            if !links.iter().any(|l| l.target == *target) {
+                warn!(
+                    "{} is no longer provided by '{}'; run 'oxidizr doctor' for advice",
+                    target.display(),
+                    e.name()
+                );
+            }
+        }
+
+        for link in links {
+            if existing.contains(&link.target) {
+                continue;
+            }
+            info!("Linking new utility {}", link.target.display());
+            system.replace_file_with_symlink(link.source, link.target.clone())?;
+            existing.insert(link.target);
+            added += 1;
+        }
+
+        state.record_links(&e.name(), existing);
+        let backups = backup_checksums(system, &e, &state)?;
+        state.record_backups(&e.name(), backups);
+    }
+
+    if !state.sysext_experiments().is_empty() {
+        sysext::rebuild(system, &state)?;
+    }
+
+    state.save(system)?;
+    info!("Linked {added} new utilities");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_refresh() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/coreutils/date", "", false),
+            ("/usr/lib/cargo/bin/coreutils/seq", "", false),
+            ("/usr/lib/cargo/bin/coreutils/shuf", "", false),
+            ("/usr/bin/date", "", true),
+            ("/usr/bin/seq", "", true),
+            ("/usr/bin/shuf", "", true),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"coreutils": {"links": ["/usr/bin/date", "/usr/bin/seq"]}}}"#,
+                false,
+            ),
+        ]);
+
+        refresh(&runner).unwrap();
+
+        // Only the newly provided utility is linked, even though date isn't linked.
+        assert_eq!(
+            runner.created_symlinks.borrow().clone(),
+            vec![(
+                "/usr/bin/coreutils".to_string(),
+                "/usr/bin/shuf".to_string()
+            )]
+        );
+        assert!(
+            State::load(&runner).unwrap().experiments["coreutils"]
+                .links
+                .contains(&std::path::PathBuf::from("/usr/bin/shuf"))
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::commands::backup_checksums;
+use crate::experiments::all_experiments;
+use crate::state::{Operation, OperationKind, State};
+use crate::utils::Worker;
+use anyhow::Result;
+use tracing::info;
+
+/// Recreate the symlinks of enabled experiments that are missing or point elsewhere, for example
+/// because a package upgrade restored the original binary. Packages aren't reinstalled, and
+/// symlinks that are still in place are left untouched.
+pub fn repair(system: &impl Worker) -> Result<()> {
+    let mut state = State::load(system)?;
+    let mut operation = Operation::new(OperationKind::Repair, None);
+    let mut repaired = 0;
+
+    for e in all_experiments(system) {
+        let Some(recorded) = state.experiments.get(&e.name()) else {
+            continue;
+        };
+        if !e.check_installed() || recorded.sysext {
+            continue;
+        }
+
+        let mut relinked = Vec::new();
+        for link in e.expected_links(&state)? {
+            if !link.drifted(system) {
+                continue;
+            }
+            info!("Relinking {}", link.target.display());
+            relinked.push(link.target.clone());
+            match system.read_link(link.target.clone()) {
+                // A file restored by a package upgrade is newer than the backup, so replaces it.
+                Err(_) if system.file_exists(link.target.clone()) => {
+                    system.replace_file_with_symlink(link.source, link.target)?
+                }
+                _ => system.create_symlink(link.source, link.target)?,
+            }
+            repaired += 1;
+        }
+        if !relinked.is_empty() {
+            operation.add(system, &e.name(), &e.package(), relinked);
+        }
+
+        let backups = backup_checksums(system, &e, &state)?;
+        state.record_backups(&e.name(), backups);
+    }
+
+    state.record_operation(operation);
+    state.save(system)?;
+    info!("Repaired {repaired} symlinks");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+    use std::path::Path;
+
+    #[test]
+    fn test_repair() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![
+            ("/usr/bin/sudo", "", true),
+            ("/usr/bin/su", "", true),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"sudo-rs": {}, "su": {}}}"#,
+                false,
+            ),
+        ]);
+        runner.mock_symlinks(vec![
+            ("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo"),
+            ("/usr/bin/visudo", "/usr/sbin/visudo.real"),
+        ]);
+
+        repair(&runner).unwrap();
+
+        // /usr/bin/su was restored by a package upgrade, so it's backed up again.
+        assert_eq!(runner.backed_up_files.borrow().clone(), vec!["/usr/bin/su"]);
+        assert_eq!(
+            runner.created_symlinks.borrow().clone(),
+            vec![
+                (
+                    "/usr/lib/cargo/bin/visudo".to_string(),
+                    "/usr/bin/visudo".to_string()
+                ),
+                (
+                    "/usr/lib/cargo/bin/su".to_string(),
+                    "/usr/bin/su".to_string()
+                ),
+            ]
+        );
+
+        let history = State::load(&runner).unwrap().history;
+        assert_eq!(history[0].kind, OperationKind::Repair);
+        assert_eq!(
+            history[0].files.iter().collect::<Vec<_>>(),
+            vec![Path::new("/usr/bin/su"), Path::new("/usr/bin/visudo")]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::{Worker, compressed_backup_filename, find_backup, has_compressed_backup};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Output formats supported by `oxidizr report`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ReportFormat {
+    /// One line per managed file.
+    Text,
+    /// A JSON document, for consumption by configuration management and audit tooling.
+    Json,
+}
+
+/// A description of every file managed by oxidizr on the system.
+#[derive(Debug, PartialEq, Serialize)]
+struct Report {
+    oxidizr_version: &'static str,
+    files: Vec<ManagedFile>,
+}
+
+/// A file replaced with a symlink by an enabled experiment.
+#[derive(Debug, PartialEq, Serialize)]
+struct ManagedFile {
+    /// The path of the original file, e.g. `/usr/bin/date`.
+    path: PathBuf,
+    /// The replacement the symlink should point at.
+    target: PathBuf,
+    /// Whether the symlink is in place and points at `target`.
+    linked: bool,
+    /// Where the original is backed up, if it was backed up.
+    backup: Option<PathBuf>,
+    experiment: String,
+    package: String,
+    version: String,
+}
+
+/// Print a description of every file managed by the enabled experiments: its backup, the
+/// replacement it's linked to, and the package and version providing the replacement.
+pub fn report(system: &impl Worker, format: ReportFormat) -> Result<()> {
+    let report = gather(system)?;
+    let output = match format {
+        ReportFormat::Text => text(&report),
+        ReportFormat::Json => format!("{}\n", serde_json::to_string_pretty(&report)?),
+    };
+    print!("{output}");
+    Ok(())
+}
+
+fn gather(system: &impl Worker) -> Result<Report> {
+    let state = State::load(system)?;
+    let mut files = Vec::new();
+
+    for e in all_experiments(system) {
+        let Some(recorded) = state.experiments.get(&e.name()) else {
+            continue;
+        };
+        // Experiments provided by the system extension image don't replace any files.
+        if !e.check_installed() || recorded.sysext {
+            continue;
+        }
+
+        let version = system.package_version(&e.package())?;
+        for link in e.expected_links(&state)? {
+            let backup = find_backup(system, &link.target).or_else(|| {
+                has_compressed_backup(system, &link.target)
+                    .then(|| compressed_backup_filename(system, &link.target))
+            });
+            files.push(ManagedFile {
+                linked: !link.drifted(system),
+                path: link.target,
+                target: link.source,
+                backup,
+                experiment: e.name(),
+                package: e.package(),
+                version: version.clone(),
+            });
+        }
+    }
+
+    Ok(Report {
+        oxidizr_version: env!("CARGO_PKG_VERSION"),
+        files,
+    })
+}
+
+fn text(report: &Report) -> String {
+    report
+        .files
+        .iter()
+        .map(|f| {
+            let backup = match &f.backup {
+                Some(backup) => backup.display().to_string(),
+                None => "no backup".to_string(),
+            };
+            let drifted = match f.linked {
+                true => "",
+                false => " (not linked)",
+            };
+            format!(
+                "{} -> {}{drifted} [{} {}, {backup}]\n",
+                f.path.display(),
+                f.target.display(),
+                f.package,
+                f.version,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+    use std::path::Path;
+
+    #[test]
+    fn test_report() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_command("dpkg-query -W -f=${Version} sudo-rs", "0.2.2-1");
+        runner.mock_files(vec![
+            ("/usr/bin/.sudo.oxidizr.bak", "", false),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"sudo-rs": {}}}"#,
+                false,
+            ),
+        ]);
+        runner.mock_symlinks(vec![("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo")]);
+
+        let report = gather(&runner).unwrap();
+        let sudo = report
+            .files
+            .iter()
+            .find(|f| f.path == Path::new("/usr/bin/sudo"))
+            .unwrap();
+        assert_eq!(
+            sudo,
+            &ManagedFile {
+                path: PathBuf::from("/usr/bin/sudo"),
+                target: PathBuf::from("/usr/lib/cargo/bin/sudo"),
+                linked: true,
+                backup: Some(PathBuf::from("/usr/bin/.sudo.oxidizr.bak")),
+                experiment: "sudo-rs".to_string(),
+                package: "sudo-rs".to_string(),
+                version: "0.2.2-1".to_string(),
+            }
+        );
+
+        assert!(text(&report).contains(
+            "/usr/bin/sudo -> /usr/lib/cargo/bin/sudo [sudo-rs 0.2.2-1, /usr/bin/.sudo.oxidizr.bak]\n"
+        ));
+        assert!(text(&report).contains(
+            "/usr/bin/visudo -> /usr/lib/cargo/bin/visudo (not linked) [sudo-rs 0.2.2-1, no backup]\n"
+        ));
+    }
+}
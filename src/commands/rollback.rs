@@ -0,0 +1,255 @@
+use crate::state::{STATE_FILE, State};
+use crate::utils::{JOURNAL_FILE, Journal, JournalEntry, Worker};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Undo every action recorded in the journal by runs of `enable` whose experiments are still
+/// enabled, most recent first, restoring the system to its state before `enable` was run. This
+/// also works if `enable` was interrupted halfway through.
+pub fn rollback(system: &impl Worker) -> Result<()> {
+    let entries = Journal::load(system)?;
+    if entries.is_empty() {
+        info!("Nothing to roll back");
+        return Ok(());
+    }
+
+    let mut state = State::load(system)?;
+    let rolled_back = journaled_experiments(&state, &entries);
+
+    info!("Rolling back {} recorded actions", entries.len());
+    for entry in entries.iter().rev() {
+        // Snapshots of the state taken by earlier runs still list experiments disabled since, so
+        // the state is updated below instead.
+        if matches!(entry, JournalEntry::WriteFile { file, .. } if file == Path::new(STATE_FILE)) {
+            continue;
+        }
+        undo(system, entry)?;
+    }
+
+    for experiment in &rolled_back {
+        state.remove(experiment);
+    }
+    for operation in state.history.iter_mut().filter(|o| o.journal.is_some()) {
+        operation.undone = true;
+    }
+    state.discard_journal();
+    state.save(system)?;
+    system.remove_file(PathBuf::from(JOURNAL_FILE))
+}
+
+/// The experiments enabled by the actions in the journal: those of the runs of `enable` whose
+/// actions are kept in it, and those recorded by a run interrupted before it finished, whose
+/// packages it installed.
+fn journaled_experiments(state: &State, entries: &[JournalEntry]) -> Vec<String> {
+    let installed: Vec<&str> = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            JournalEntry::InstallPackage { package, .. } => Some(package.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut experiments: Vec<String> = state
+        .history
+        .iter()
+        .filter(|o| o.journal.is_some())
+        .flat_map(|o| o.experiments.iter().cloned())
+        .collect();
+    for (name, recorded) in &state.experiments {
+        let unfinished = !state.history.iter().any(|o| o.experiments.contains(name));
+        if unfinished
+            && recorded
+                .packages
+                .iter()
+                .any(|p| installed.contains(&p.name.as_str()))
+        {
+            experiments.push(name.clone());
+        }
+    }
+    experiments
+}
+
+pub(super) fn undo(system: &impl Worker, entry: &JournalEntry) -> Result<()> {
+    match entry {
+        JournalEntry::InstallPackage {
+            package,
+            installed: false,
+        } => {
+            if system.check_installed(package)? {
+                info!("Removing {package}");
+                system.remove_package(package)?;
+            }
+        }
+        JournalEntry::InstallPackage { .. } => {}
+        JournalEntry::ReplaceFile { file } => system.restore_file(file.clone())?,
+        JournalEntry::CreateSymlink {
+            link,
+            previous: Some(previous),
+        } => system.create_symlink(previous.clone(), link.clone())?,
+        JournalEntry::CreateSymlink {
+            link,
+            previous: None,
+        } => {
+            // Only remove the link if it hasn't since been replaced by something else.
+            if system.read_link(link.clone()).is_ok() {
+                system.remove_file(link.clone())?;
+            }
+        }
+        JournalEntry::WriteFile {
+            file,
+            previous: Some((contents, mode)),
+        } => system.write_file(file.clone(), contents, *mode)?,
+        JournalEntry::WriteFile {
+            file,
+            previous: None,
+        } => system.remove_file(file.clone())?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::all_experiments;
+    use crate::state::{Operation, OperationKind, PackageRecord};
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_enable_journal() {
+        let runner = MockSystem::default();
+        runner.journal.replace(Some(Vec::new()));
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/sudo", "", false),
+            ("/usr/bin/sudo", "", true),
+        ]);
+        runner.mock_command("stat -c %u %a /etc/sudoers", "0 440\n");
+        let sudors = all_experiments(&runner)
+            .into_iter()
+            .find(|e| e.name() == "sudo-rs")
+            .unwrap();
+        sudors.enable(false).unwrap();
+
+        let journal = runner.journal.borrow().clone().unwrap();
+        assert_eq!(
+            journal[0],
+            JournalEntry::InstallPackage {
+                package: "sudo-rs".to_string(),
+                installed: false
+            }
+        );
+        assert!(journal.contains(&JournalEntry::ReplaceFile {
+            file: PathBuf::from("/usr/bin/sudo")
+        }));
+    }
+
+    #[test]
+    fn test_rollback() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_symlinks(vec![("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo")]);
+        runner.mock_files(vec![
+            ("/usr/bin/sudo", "", true),
+            ("/etc/profile.d/oxidizr-shadow.sh", "", false),
+            (
+                JOURNAL_FILE,
+                concat!(
+                    "{\"action\":\"install-package\",\"package\":\"rust-coreutils\",\"installed\":true}\n",
+                    "{\"action\":\"install-package\",\"package\":\"sudo-rs\",\"installed\":false}\n",
+                    "{\"action\":\"replace-file\",\"file\":\"/usr/bin/sudo\"}\n",
+                    "{\"action\":\"create-symlink\",\"link\":\"/usr/bin/sudo\",\"previous\":null}\n",
+                    "{\"action\":\"write-file\",\"file\":\"/etc/profile.d/oxidizr-shadow.sh\",\"previous\":null}\n",
+                ),
+                false,
+            ),
+        ]);
+
+        rollback(&runner).unwrap();
+
+        assert_eq!(
+            runner.removed_files.borrow().clone(),
+            vec![
+                "/etc/profile.d/oxidizr-shadow.sh",
+                "/usr/bin/sudo",
+                JOURNAL_FILE
+            ]
+        );
+        assert_eq!(
+            runner.restored_files.borrow().clone(),
+            vec!["/usr/bin/sudo"]
+        );
+        assert_eq!(
+            runner.commands.borrow().clone(),
+            vec!["apt-get remove -y sudo-rs"]
+        );
+    }
+
+    #[test]
+    fn test_rollback_after_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_files(vec![("/usr/bin/date", "", true)]);
+        let package = |name: &str| PackageRecord {
+            name: name.to_string(),
+            version: None,
+            deb: None,
+            sha256: None,
+        };
+
+        // sudo-rs is enabled, then coreutils, whose run snapshots the state listing sudo-rs.
+        let mut state = State::default();
+        state.record("sudo-rs", vec![package("sudo-rs")]);
+        let mut sudo_rs = Operation::new(OperationKind::Enable, Some(0));
+        sudo_rs.experiments = vec!["sudo-rs".to_string()];
+        state.record_operation(sudo_rs);
+        let snapshot = serde_json::to_string(&state).unwrap();
+        state.record("coreutils", vec![package("rust-coreutils")]);
+        let mut coreutils = Operation::new(OperationKind::Enable, Some(2));
+        coreutils.experiments = vec!["coreutils".to_string()];
+        state.record_operation(coreutils);
+        Journal::replace(
+            &runner,
+            &[
+                JournalEntry::InstallPackage {
+                    package: "sudo-rs".to_string(),
+                    installed: false,
+                },
+                JournalEntry::ReplaceFile {
+                    file: PathBuf::from("/usr/bin/sudo"),
+                },
+                JournalEntry::InstallPackage {
+                    package: "rust-coreutils".to_string(),
+                    installed: false,
+                },
+                JournalEntry::ReplaceFile {
+                    file: PathBuf::from("/usr/bin/date"),
+                },
+                JournalEntry::WriteFile {
+                    file: PathBuf::from(STATE_FILE),
+                    previous: Some((snapshot, 0o644)),
+                },
+            ],
+        )
+        .unwrap();
+
+        // Disabling sudo-rs drops its actions from the journal.
+        state.remove("sudo-rs");
+        state.trim_journal(&runner).unwrap();
+        state.save(&runner).unwrap();
+
+        rollback(&runner).unwrap();
+
+        let state = State::load(&runner).unwrap();
+        assert!(state.experiments.is_empty());
+        assert!(state.history.iter().all(|o| o.journal.is_none()));
+        assert!(state.history[1].undone);
+        assert_eq!(
+            runner.restored_files.borrow().clone(),
+            vec!["/usr/bin/date"]
+        );
+        assert_eq!(
+            runner.commands.borrow().clone(),
+            vec!["apt-get remove -y rust-coreutils"]
+        );
+        assert!(!runner.file_exists(PathBuf::from(JOURNAL_FILE)));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Files created in the scratch directory for the checks to operate on.
+const SCRATCH_FILES: &[(&str, &str)] = &[
+    ("a.txt", "banana\napple\ncherry\napple\n"),
+    ("b.txt", "banana\napricot\ncherry\n"),
+    ("numbers.txt", "10\n9\n100\n-1\n"),
+    ("table.csv", "name,qty\npear,4\nfig,12\n"),
+    ("dir/nested/c.txt", "nested\n"),
+];
+
+/// The arguments to run a utility with from the scratch directory, and its expected output.
+/// Checks without an expected output only need to succeed.
+type Check = (&'static [&'static str], Option<&'static str>);
+
+/// Sanity checks for each utility.
+const CHECKS: &[(&str, &[Check])] = &[
+    ("basename", &[(&["/usr/bin/date"], Some("date\n"))]),
+    ("cat", &[(&["b.txt"], Some("banana\napricot\ncherry\n"))]),
+    (
+        "cut",
+        &[(&["-d,", "-f2", "table.csv"], Some("qty\n4\n12\n"))],
+    ),
+    (
+        "date",
+        &[(&["-u", "-d", "@0", "+%Y-%m-%d"], Some("1970-01-01\n"))],
+    ),
+    ("head", &[(&["-n", "2", "a.txt"], Some("banana\napple\n"))]),
+    ("ls", &[(&["dir"], Some("nested\n"))]),
+    ("seq", &[(&["3"], Some("1\n2\n3\n"))]),
+    (
+        "sort",
+        &[
+            (&["a.txt"], Some("apple\napple\nbanana\ncherry\n")),
+            (&["-n", "numbers.txt"], Some("-1\n9\n10\n100\n")),
+        ],
+    ),
+    ("tail", &[(&["-n", "1", "a.txt"], Some("apple\n"))]),
+    ("rev", &[(&["b.txt"], Some("ananab\ntocirpa\nyrrehc\n"))]),
+    ("uuidgen", &[(&[], None)]),
+    ("wc", &[(&["-l", "a.txt"], Some("4 a.txt\n"))]),
+    ("cmp", &[(&["a.txt", "a.txt"], Some(""))]),
+    ("diff", &[(&["a.txt", "a.txt"], Some(""))]),
+    (
+        "find",
+        &[(&["dir", "-name", "c.txt"], Some("dir/nested/c.txt\n"))],
+    ),
+    (
+        "awk",
+        &[(
+            &["-F,", "{print $1}", "table.csv"],
+            Some("name\npear\nfig\n"),
+        )],
+    ),
+    ("sudo", &[(&["-V"], None)]),
+    ("curl", &[(&["--version"], None)]),
+    ("wget", &[(&["--version"], None)]),
+    (
+        "sed",
+        &[(&["s/an/AN/g", "b.txt"], Some("bANANa\napricot\ncherry\n"))],
+    ),
+    (
+        "grep",
+        &[
+            (&["-n", "apple", "a.txt"], Some("2:apple\n4:apple\n")),
+            (&["-c", "^b", "a.txt", "b.txt"], Some("a.txt:1\nb.txt:1\n")),
+        ],
+    ),
+];
+
+/// The result of checking one replaced utility.
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    utility: String,
+    /// The reason the utility failed its checks, if it did.
+    failure: Option<String>,
+}
+
+/// Run a battery of sanity checks against the utilities replaced by the enabled experiments,
+/// in a scratch directory, and print whether each passed. Returns an error if any failed.
+pub fn self_test(system: &impl Worker) -> Result<()> {
+    let scratch = std::env::temp_dir().join(format!("oxidizr-self-test-{}", std::process::id()));
+    for (file, contents) in SCRATCH_FILES {
+        system.write_file(scratch.join(file), contents, 0o644)?;
+    }
+
+    let outcomes = replaced_utilities(system).and_then(|u| run_checks(system, &u, &scratch));
+    std::fs::remove_dir_all(&scratch)?;
+    let outcomes = outcomes?;
+
+    for o in &outcomes {
+        match &o.failure {
+            None => println!("PASS {}", o.utility),
+            Some(reason) => println!("FAIL {}: {reason}", o.utility),
+        }
+    }
+
+    let failed = outcomes.iter().filter(|o| o.failure.is_some()).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} utilities failed", outcomes.len());
+    }
+    if outcomes.is_empty() {
+        println!("No replaced utilities have checks");
+    }
+    Ok(())
+}
+
+/// Find the paths of the utilities replaced by the enabled experiments that have checks.
+fn replaced_utilities(system: &impl Worker) -> Result<Vec<PathBuf>> {
+    let state = State::load(system)?;
+    let mut utilities = Vec::new();
+    for e in all_experiments(system) {
+        if !state.experiments.contains_key(&e.name()) || !e.check_installed() {
+            continue;
+        }
+        for link in e.expected_links(&state)? {
+            if link
+                .target
+                .file_name()
+                .is_some_and(|n| CHECKS.iter().any(|(name, _)| n == *name))
+            {
+                utilities.push(link.target);
+            }
+        }
+    }
+    Ok(utilities)
+}
+
+fn run_checks(system: &impl Worker, utilities: &[PathBuf], scratch: &Path) -> Result<Vec<Outcome>> {
+    let mut outcomes = Vec::new();
+    for path in utilities {
+        let utility = path.file_name().unwrap().to_string_lossy().to_string();
+        let checks = CHECKS
+            .iter()
+            .find(|(name, _)| *name == utility)
+            .map(|(_, checks)| *checks)
+            .unwrap_or_default();
+
+        let mut failure = None;
+        for (args, expected) in checks {
+            let output = system.capture(&Command::build(&path.to_string_lossy(), args), scratch)?;
+            let invocation = Command::build(&utility, args).command();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if !output.status.success() {
+                failure = Some(format!("'{invocation}' exited with {}", output.status));
+            } else if expected.is_some_and(|e| e != stdout) {
+                failure = Some(format!("'{invocation}' printed {stdout:?}"));
+            }
+            if failure.is_some() {
+                break;
+            }
+        }
+        outcomes.push(Outcome { utility, failure });
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_self_test_checks() {
+        let runner = MockSystem::default();
+        runner.mock_command("/usr/bin/seq 3", "1\n2\n3\n");
+        runner.mock_command("/usr/bin/sort a.txt", "apple\napple\nbanana\ncherry\n");
+        runner.mock_command("/usr/bin/sort -n numbers.txt", "-1\n10\n100\n9\n");
+        runner.mock_command_failure("/usr/bin/sudo -V");
+
+        let utilities = ["/usr/bin/seq", "/usr/bin/sort", "/usr/bin/sudo"].map(PathBuf::from);
+        let outcomes = run_checks(&runner, &utilities, Path::new("/tmp")).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome {
+                    utility: "seq".to_string(),
+                    failure: None
+                },
+                Outcome {
+                    utility: "sort".to_string(),
+                    failure: Some(
+                        "'sort -n numbers.txt' printed \"-1\\n10\\n100\\n9\\n\"".to_string()
+                    )
+                },
+                Outcome {
+                    utility: "sudo".to_string(),
+                    failure: Some("'sudo -V' exited with exit status: 1".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_self_test_replaced_utilities() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}}}"#,
+            false,
+        )]);
+
+        // su and visudo are replaced too, but have no checks.
+        assert_eq!(
+            replaced_utilities(&runner).unwrap(),
+            vec![PathBuf::from("/usr/bin/sudo")]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use crate::state::State;
+use crate::utils::{Snapshotter, Worker};
+use anyhow::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Snapshot the root filesystem with the first supported tool found, and record the snapshot
+/// so that `oxidizr snapshot --restore` can revert to it.
+pub fn snapshot(system: &impl Worker) -> Result<()> {
+    let Some(tool) = Snapshotter::detect(system) else {
+        anyhow::bail!(
+            "Unable to snapshot the root filesystem: install and configure snapper or Timeshift, or use a ZFS or LVM root filesystem"
+        );
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let snapshot = tool.create(system, &format!("oxidizr-{now}"))?;
+    info!("Took {snapshot}");
+
+    let mut state = State::load(system)?;
+    state.snapshot = Some(snapshot);
+    state.save(system)
+}
+
+/// Revert the root filesystem to the snapshot most recently taken by oxidizr.
+pub fn restore_snapshot(system: &impl Worker) -> Result<()> {
+    let Some(snapshot) = State::load(system)?.snapshot else {
+        anyhow::bail!("No snapshot has been taken by oxidizr");
+    };
+
+    if snapshot.restore(system)? {
+        info!("Reboot to finish reverting to {snapshot}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_snapshot() {
+        let runner = MockSystem::default();
+        assert!(snapshot(&runner).is_err());
+        assert!(restore_snapshot(&runner).is_err());
+
+        runner.mock_command("findmnt -n -o FSTYPE,SOURCE /", "zfs rpool/ROOT/ubuntu\n");
+        snapshot(&runner).unwrap();
+        let name = State::load(&runner).unwrap().snapshot.unwrap().name;
+        assert!(name.starts_with("oxidizr-"));
+
+        restore_snapshot(&runner).unwrap();
+        let rollback = format!("zfs rollback -r rpool/ROOT/ubuntu@{name}");
+        assert!(runner.commands.borrow().contains(&rollback));
+    }
+}
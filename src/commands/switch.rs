@@ -0,0 +1,131 @@
+use super::backup_checksums;
+use crate::experiments::{Experiment, ManagedLink, all_experiments};
+use crate::state::State;
+use crate::utils::{Worker, has_backup};
+use anyhow::Result;
+use clap::ValueEnum;
+use tracing::info;
+
+/// The implementations a single utility can be switched between.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Implementation {
+    /// The Rust replacement installed by the experiment.
+    Rust,
+    /// The original utility, restored from its backup.
+    Gnu,
+}
+
+/// Switch a single utility of an enabled experiment, e.g. `date`, between its Rust replacement
+/// and the original, leaving the experiment's other utilities untouched. Utilities switched to
+/// the original are excluded, so stay that way when experiments are enabled again.
+pub fn switch(system: &impl Worker, utility: &str, to: Implementation) -> Result<()> {
+    let mut state = State::load(system)?;
+    let Some((experiment, link)) = find(system, &state, utility)? else {
+        anyhow::bail!(
+            "'{utility}' isn't replaced by an enabled experiment that supports switching"
+        );
+    };
+    let excluded = state.excluded.contains(utility);
+
+    match to {
+        Implementation::Gnu if excluded => info!("'{utility}' already uses the original"),
+        Implementation::Gnu => {
+            anyhow::ensure!(
+                has_backup(system, &link.target),
+                "No backup of {} to restore",
+                link.target.display()
+            );
+            info!("Restoring {}", link.target.display());
+            system.restore_file(link.target.clone())?;
+            state.exclude(&[utility.to_string()]);
+            state.forget_link(&link.target);
+            state.forget_backup(&link.target);
+        }
+        Implementation::Rust if !excluded => info!("'{utility}' already uses the Rust replacement"),
+        Implementation::Rust => {
+            // The experiment's expected links are found from the saved exclusions.
+            state.include(utility);
+            state.save(system)?;
+            info!("Linking {}", link.target.display());
+            system.replace_file_with_symlink(link.source, link.target.clone())?;
+            state.record_links(&experiment.name(), [link.target]);
+            let backups = backup_checksums(system, &experiment, &state)?;
+            state.record_backups(&experiment.name(), backups);
+        }
+    }
+    state.save(system)
+}
+
+/// Find the enabled experiment that replaces `utility`, and the symlink it manages for it.
+fn find<'a>(
+    system: &'a impl Worker,
+    state: &State,
+    utility: &str,
+) -> Result<Option<(Experiment<'a>, ManagedLink)>> {
+    for e in all_experiments(system) {
+        let enabled = state
+            .experiments
+            .get(&e.name())
+            .is_some_and(|recorded| !recorded.sysext);
+        if !enabled || !e.check_installed() {
+            continue;
+        }
+        if let Some(link) = e.utility_link(utility)? {
+            return Ok(Some((e, link)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_switch() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/coreutils/date", "", false),
+            ("/usr/lib/cargo/bin/coreutils/sort", "", false),
+            ("/usr/bin/.date.oxidizr.bak", "", false),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"coreutils": {"links": ["/usr/bin/date", "/usr/bin/sort"]}}}"#,
+                false,
+            ),
+        ]);
+        runner.mock_symlinks(vec![("/usr/bin/date", "/usr/bin/coreutils")]);
+        runner.mock_command("sha256sum /usr/bin/.date.oxidizr.bak", "abc123  -\n");
+
+        switch(&runner, "date", Implementation::Gnu).unwrap();
+        assert_eq!(*runner.restored_files.borrow(), vec!["/usr/bin/date"]);
+        let state = State::load(&runner).unwrap();
+        assert!(state.excluded.contains("date"));
+        assert_eq!(
+            state.experiments["coreutils"].links,
+            [PathBuf::from("/usr/bin/sort")].into()
+        );
+
+        switch(&runner, "date", Implementation::Rust).unwrap();
+        assert!(runner.created_symlinks.borrow().contains(&(
+            "/usr/bin/coreutils".to_string(),
+            "/usr/bin/date".to_string()
+        )));
+        let state = State::load(&runner).unwrap();
+        assert!(state.excluded.is_empty());
+        assert!(
+            state.experiments["coreutils"]
+                .links
+                .contains(Path::new("/usr/bin/date"))
+        );
+        assert_eq!(
+            state.experiments["coreutils"].backups[Path::new("/usr/bin/date")],
+            "abc123"
+        );
+
+        assert!(switch(&runner, "sudo", Implementation::Gnu).is_err());
+    }
+}
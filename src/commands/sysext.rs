@@ -0,0 +1,285 @@
+use crate::experiments::{Experiment, all_experiments};
+use crate::state::{PackageRecord, State};
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Location from which `systemd-sysext` merges the image built by `enable --via-sysext`.
+pub const SYSEXT_IMAGE: &str = "/var/lib/extensions/oxidizr.raw";
+
+/// Location of the extension release file inside the image, which must match the image name.
+const EXTENSION_RELEASE: &str = "usr/lib/extension-release.d/extension-release.oxidizr";
+
+/// Build a systemd system extension image which overlays the Rust replacements provided by
+/// `experiments` onto `/usr`. The replacements are copied into the image, so the image works
+/// without the packages installed.
+pub fn build(system: &impl Worker, experiments: &[Experiment], output: PathBuf) -> Result<()> {
+    let tree = std::env::temp_dir().join(format!("oxidizr-sysext-{}", std::process::id()));
+    let result = stage(system, experiments, &tree).and_then(|_| squash(system, &tree, &output));
+    std::fs::remove_dir_all(&tree).ok();
+    result?;
+
+    info!("Wrote system extension image {}", output.display());
+    Ok(())
+}
+
+/// Enable `experiments` by installing their packages and merging an image providing them, and
+/// any experiments already provided by the image. Returns the names of experiments skipped as
+/// incompatible with the system.
+pub fn enable(
+    system: &impl Worker,
+    experiments: &[Experiment],
+    state: &mut State,
+    no_compatibility_check: bool,
+) -> Result<Vec<String>> {
+    let mut skipped = Vec::new();
+    let mut selected = Vec::new();
+    for e in experiments {
+        anyhow::ensure!(
+            e.supports_sysext(),
+            "'{}' can't be provided by a system extension image",
+            e.name()
+        );
+        if !no_compatibility_check && !e.check_compatible() {
+            warn!(
+                "Skipping '{}'. Minimum supported releases are {}.",
+                e.name(),
+                e.supported_releases().join(", ")
+            );
+            skipped.push(e.name());
+            continue;
+        }
+        selected.push(e);
+    }
+
+    // /usr is read-only while extensions are merged, so unmerge before installing packages.
+    unmerge(system)?;
+    for e in selected {
+        if !e.check_installed() {
+            info!("Installing {}", e.package());
+            system.install_package(&e.package())?;
+        }
+        state.record(&e.name(), vec![PackageRecord::new(system, &e.package())?]);
+        state.mark_sysext(&e.name());
+    }
+    merge(system, state)?;
+    state.save(system)?;
+    Ok(skipped)
+}
+
+/// Disable experiments provided by the image, rebuilding it without them. Experiments in
+/// `experiments` that aren't provided by the image are returned, to be disabled as usual.
+pub fn disable<'a>(
+    system: &impl Worker,
+    experiments: Vec<Experiment<'a>>,
+    state: &mut State,
+) -> Result<Vec<Experiment<'a>>> {
+    let provided = state.sysext_experiments();
+    let (disabled, remaining): (Vec<Experiment>, Vec<Experiment>) = experiments
+        .into_iter()
+        .partition(|e| provided.contains(&e.name()));
+    if disabled.is_empty() {
+        return Ok(remaining);
+    }
+
+    unmerge(system)?;
+    for e in disabled {
+        info!("Removing {}", e.package());
+        system.remove_package(&e.package())?;
+        state.remove(&e.name());
+    }
+    merge(system, state)?;
+    state.save(system)?;
+    Ok(remaining)
+}
+
+/// Rebuild the image from the current contents of the packages it provides, for example after
+/// they were upgraded. The image is unmerged first, so it isn't copied from itself.
+pub fn rebuild(system: &impl Worker, state: &State) -> Result<()> {
+    unmerge(system)?;
+    merge(system, state)
+}
+
+/// Rebuild the image from the experiments recorded as provided by it, and merge it. If there
+/// are none, the image is removed instead.
+fn merge(system: &impl Worker, state: &State) -> Result<()> {
+    let provided = state.sysext_experiments();
+    let experiments: Vec<Experiment> = all_experiments(system)
+        .into_iter()
+        .filter(|e| provided.contains(&e.name()))
+        .collect();
+
+    system.remove_file(PathBuf::from(SYSEXT_IMAGE))?;
+    if !experiments.is_empty() {
+        build(system, &experiments, PathBuf::from(SYSEXT_IMAGE))?;
+    }
+
+    info!("Merging system extensions");
+    system.run(&Command::build("systemd-sysext", &["refresh"]))?;
+    Ok(())
+}
+
+fn unmerge(system: &impl Worker) -> Result<()> {
+    if system.file_exists(PathBuf::from(SYSEXT_IMAGE)) {
+        info!("Unmerging system extensions");
+        system.run(&Command::build("systemd-sysext", &["unmerge"]))?;
+    }
+    Ok(())
+}
+
+/// Lay out the contents of the image in `tree`: a copy of each replacement, symlinked from the
+/// path of the utility it replaces.
+fn stage(system: &impl Worker, experiments: &[Experiment], tree: &Path) -> Result<()> {
+    for e in experiments {
+        anyhow::ensure!(
+            e.supports_sysext(),
+            "'{}' can't be provided by a system extension image",
+            e.name()
+        );
+        anyhow::ensure!(
+            e.check_installed(),
+            "The {} package must be installed to build an image providing '{}'",
+            e.package(),
+            e.name()
+        );
+
+        for link in e.managed_links()? {
+            if !link.target.starts_with("/usr") {
+                warn!(
+                    "{} is outside /usr, so can't be replaced by a system extension",
+                    link.target.display()
+                );
+                continue;
+            }
+            let source = staged(tree, &link.source);
+            if !system.file_exists(source.clone()) {
+                system.copy_file(link.source.clone(), source)?;
+            }
+            system.create_symlink(link.source, staged(tree, &link.target))?;
+        }
+    }
+
+    // systemd-sysext only merges images built for the running OS release.
+    let distribution = system.distribution()?;
+    system.write_file(
+        tree.join(EXTENSION_RELEASE),
+        &format!(
+            "ID={}\nVERSION_ID={}\n",
+            distribution.id.to_lowercase(),
+            distribution.release
+        ),
+        0o644,
+    )
+}
+
+fn squash(system: &impl Worker, tree: &Path, output: &Path) -> Result<()> {
+    let cmd = Command::build(
+        "mksquashfs",
+        &[
+            &tree.to_string_lossy(),
+            &output.to_string_lossy(),
+            "-noappend",
+            "-all-root",
+            "-quiet",
+        ],
+    );
+    system.run(&cmd)?;
+    Ok(())
+}
+
+/// Get the path of `path` inside the image tree.
+fn staged(tree: &Path, path: &Path) -> PathBuf {
+    tree.join(path.strip_prefix("/").unwrap_or(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    fn sudors(runner: &MockSystem) -> Vec<Experiment<'_>> {
+        all_experiments(runner)
+            .into_iter()
+            .filter(|e| e.name() == "sudo-rs")
+            .collect()
+    }
+
+    #[test]
+    fn test_sysext_stage() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/sudo", "", false),
+            ("/usr/lib/cargo/bin/visudo", "", false),
+            ("/usr/bin/sudo", "", true),
+            ("/usr/sbin/visudo", "", true),
+        ]);
+
+        stage(&runner, &sudors(&runner), Path::new("/tmp/tree")).unwrap();
+
+        let mut copied: Vec<String> = runner
+            .copied_files
+            .borrow()
+            .iter()
+            .map(|(_, dest)| dest.clone())
+            .collect();
+        copied.sort();
+        assert_eq!(
+            copied,
+            vec![
+                "/tmp/tree/usr/lib/cargo/bin/sudo",
+                "/tmp/tree/usr/lib/cargo/bin/visudo",
+            ]
+        );
+        assert!(runner.created_symlinks.borrow().contains(&(
+            "/usr/lib/cargo/bin/sudo".to_string(),
+            "/tmp/tree/usr/bin/sudo".to_string()
+        )));
+        assert_eq!(
+            runner
+                .read_file(PathBuf::from("/tmp/tree").join(EXTENSION_RELEASE))
+                .unwrap(),
+            "ID=ubuntu\nVERSION_ID=24.04\n"
+        );
+    }
+
+    #[test]
+    fn test_sysext_stage_unsupported() {
+        let runner = MockSystem::default();
+        let experiments: Vec<Experiment> = all_experiments(&runner)
+            .into_iter()
+            .filter(|e| e.name() == "delta")
+            .collect();
+        assert!(stage(&runner, &experiments, Path::new("/tmp/tree")).is_err());
+
+        // Packages must be installed to take the replacements from.
+        assert!(stage(&runner, &sudors(&runner), Path::new("/tmp/tree")).is_err());
+    }
+
+    #[test]
+    fn test_sysext_disable() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(SYSEXT_IMAGE, "", false)]);
+        let mut state = State::default();
+        state.record("sudo-rs", vec![]);
+        state.mark_sysext("sudo-rs");
+        state.record("coreutils", vec![]);
+
+        let remaining = disable(&runner, all_experiments(&runner), &mut state).unwrap();
+        assert!(remaining.iter().all(|e| e.name() != "sudo-rs"));
+        assert!(state.experiments.contains_key("coreutils"));
+        assert!(!state.experiments.contains_key("sudo-rs"));
+
+        // The image is removed, as it no longer provides any experiments.
+        assert_eq!(
+            runner.commands.borrow().clone(),
+            vec![
+                "systemd-sysext unmerge",
+                "apt-get remove -y sudo-rs",
+                "systemd-sysext refresh"
+            ]
+        );
+        assert!(!runner.file_exists(PathBuf::from(SYSEXT_IMAGE)));
+    }
+}
@@ -0,0 +1,283 @@
+use crate::experiments::Experiment;
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Where the oxidizr binary is made available inside the trial container.
+const CONTAINER_BINARY: &str = "/usr/local/sbin/oxidizr";
+
+/// Commands run inside the container after enabling the experiments, to check that the basic
+/// tools (and privilege escalation) still work.
+const SMOKE_TESTS: &[&[&str]] = &[
+    &["ls", "/"],
+    &["cat", "/etc/os-release"],
+    &["date", "-u"],
+    &["find", "/etc", "-maxdepth", "1", "-name", "os-release"],
+    &["diff", "/etc/os-release", "/etc/os-release"],
+    &["sudo", "-n", "true"],
+];
+
+/// How the root filesystem of the trial container is provided.
+enum Root<'a> {
+    /// A copy-on-write overlay of the host's root filesystem, mounted at the given directory.
+    Host(&'a Path),
+    /// A disk image, e.g. an Ubuntu cloud image matching the host's release.
+    Image(&'a Path),
+}
+
+/// The outcome of a step run inside the trial container.
+struct Step {
+    description: String,
+    passed: bool,
+    output: String,
+}
+
+/// Rehearse enabling `experiments` inside a throwaway `systemd-nspawn` container, cloned from
+/// the host's root filesystem or booted from `image`, and report the results. The host is not
+/// modified, and the container's changes are discarded.
+pub fn trial(
+    system: &impl Worker,
+    experiments: &[Experiment],
+    image: Option<PathBuf>,
+    no_compatibility_check: bool,
+) -> Result<()> {
+    anyhow::ensure!(
+        system.which("systemd-nspawn").is_ok(),
+        "systemd-nspawn is required for a trial run; install the systemd-container package"
+    );
+    let binary = std::env::current_exe()?;
+
+    let steps = match &image {
+        Some(image) => run_steps(
+            system,
+            &Root::Image(image),
+            &binary,
+            experiments,
+            no_compatibility_check,
+        ),
+        None => {
+            let work = std::env::temp_dir().join(format!("oxidizr-trial-{}", std::process::id()));
+            let (upper, overlay, root) = (work.join("upper"), work.join("work"), work.join("root"));
+            for dir in [&upper, &overlay, &root] {
+                std::fs::create_dir_all(dir)?;
+            }
+
+            info!("Cloning the host's root filesystem into {}", root.display());
+            let options = format!(
+                "lowerdir=/,upperdir={},workdir={}",
+                upper.display(),
+                overlay.display()
+            );
+            system
+                .run(&Command::build(
+                    "mount",
+                    &[
+                        "-t",
+                        "overlay",
+                        "overlay",
+                        "-o",
+                        &options,
+                        &root.to_string_lossy(),
+                    ],
+                ))
+                .inspect_err(|_| {
+                    let _ = std::fs::remove_dir_all(&work);
+                })?;
+
+            let steps = run_steps(
+                system,
+                &Root::Host(&root),
+                &binary,
+                experiments,
+                no_compatibility_check,
+            );
+            system.run(&Command::build("umount", &[&root.to_string_lossy()]))?;
+            std::fs::remove_dir_all(&work)?;
+            steps
+        }
+    }?;
+
+    print!("{}", report(&steps));
+
+    let failed = steps.iter().filter(|s| !s.passed).count();
+    anyhow::ensure!(failed == 0, "{failed} step(s) of the trial run failed");
+    Ok(())
+}
+
+/// Run the trial steps in the container: enable the experiments, check the result for drift,
+/// then run the smoke tests.
+fn run_steps(
+    system: &impl Worker,
+    root: &Root,
+    binary: &Path,
+    experiments: &[Experiment],
+    no_compatibility_check: bool,
+) -> Result<Vec<Step>> {
+    let names: Vec<String> = experiments.iter().map(|e| e.name()).collect();
+
+    let high_risk: Vec<String> = experiments
+        .iter()
+        .filter(|e| e.high_risk())
+        .map(|e| e.name())
+        .collect();
+
+    let mut enable = vec!["enable", "--yes", "--experiments"];
+    enable.extend(names.iter().map(String::as_str));
+    if !high_risk.is_empty() {
+        enable.push("--accept-risk");
+        enable.extend(high_risk.iter().map(String::as_str));
+    }
+    if no_compatibility_check {
+        enable.push("--no-compatibility-check");
+    }
+    let mut check = vec!["check"];
+    if no_compatibility_check {
+        check.push("--no-compatibility-check");
+    }
+
+    let mut steps = vec![
+        (format!("oxidizr {}", enable.join(" ")), oxidizr(&enable)),
+        (format!("oxidizr {}", check.join(" ")), oxidizr(&check)),
+    ];
+    steps.extend(
+        SMOKE_TESTS
+            .iter()
+            .map(|args| (args.join(" "), args.iter().map(|a| a.to_string()).collect())),
+    );
+
+    let mut results = Vec::new();
+    for (description, args) in steps {
+        info!("Running '{}' in the trial container", description);
+        let cmd = nspawn(root, binary, &args);
+        let output = system.capture(&cmd, Path::new("/"))?;
+        results.push(Step {
+            description,
+            passed: output.status.success(),
+            output: format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        });
+
+        // There's nothing to test if the experiments couldn't be enabled.
+        if !results[0].passed {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+fn oxidizr(args: &[&str]) -> Vec<String> {
+    std::iter::once(CONTAINER_BINARY)
+        .chain(args.iter().copied())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Build the `systemd-nspawn` command running `args` in the container.
+fn nspawn(root: &Root, binary: &Path, args: &[String]) -> Command {
+    let bind = format!("--bind-ro={}:{CONTAINER_BINARY}", binary.display());
+    let mut nspawn = vec!["--quiet", "--pipe", "--register=no", &bind];
+    let root_arg = match root {
+        Root::Host(dir) => format!("--directory={}", dir.display()),
+        Root::Image(image) => format!("--image={}", image.display()),
+    };
+    if let Root::Image(_) = root {
+        nspawn.push("--ephemeral");
+    }
+    nspawn.push(&root_arg);
+    nspawn.push("--");
+    nspawn.extend(args.iter().map(String::as_str));
+    Command::build("systemd-nspawn", &nspawn)
+}
+
+fn report(steps: &[Step]) -> String {
+    let mut out = String::new();
+    for step in steps {
+        match step.passed {
+            true => out.push_str(&format!("PASS  {}\n", step.description)),
+            false => {
+                out.push_str(&format!("FAIL  {}\n", step.description));
+                for line in step.output.lines() {
+                    out.push_str(&format!("      {line}\n"));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::all_experiments;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_trial_nspawn_command() {
+        let args = oxidizr(&["check"]);
+        let binary = Path::new("/usr/bin/oxidizr");
+
+        let cmd = nspawn(&Root::Host(Path::new("/tmp/root")), binary, &args);
+        assert_eq!(
+            cmd.command(),
+            "systemd-nspawn --quiet --pipe --register=no --bind-ro=/usr/bin/oxidizr:/usr/local/sbin/oxidizr --directory=/tmp/root -- /usr/local/sbin/oxidizr check"
+        );
+
+        let cmd = nspawn(&Root::Image(Path::new("/srv/noble.img")), binary, &args);
+        assert!(
+            cmd.command()
+                .contains("--ephemeral --image=/srv/noble.img -- /usr/local/sbin/oxidizr check")
+        );
+    }
+
+    #[test]
+    fn test_trial_run_steps() {
+        let runner = MockSystem::default();
+        let experiments: Vec<Experiment> = all_experiments(&runner)
+            .into_iter()
+            .filter(|e| e.name() == "sudo-rs")
+            .collect();
+        let prefix = "systemd-nspawn --quiet --pipe --register=no --bind-ro=/usr/bin/oxidizr:/usr/local/sbin/oxidizr --directory=/tmp/root --";
+        runner.mock_command_failure(&format!("{prefix} sudo -n true"));
+
+        let steps = run_steps(
+            &runner,
+            &Root::Host(Path::new("/tmp/root")),
+            Path::new("/usr/bin/oxidizr"),
+            &experiments,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(steps.len(), 2 + SMOKE_TESTS.len());
+        assert_eq!(
+            runner.commands.borrow()[0],
+            format!(
+                "{prefix} /usr/local/sbin/oxidizr enable --yes --experiments sudo-rs --accept-risk sudo-rs"
+            )
+        );
+        assert!(steps.iter().take(steps.len() - 1).all(|s| s.passed));
+        assert!(!steps.last().unwrap().passed);
+        assert!(report(&steps).contains("FAIL  sudo -n true\n"));
+    }
+
+    #[test]
+    fn test_trial_stops_when_enable_fails() {
+        let runner = MockSystem::default();
+        runner.mock_command_failure("systemd-nspawn --quiet --pipe --register=no --bind-ro=/usr/bin/oxidizr:/usr/local/sbin/oxidizr --directory=/tmp/root -- /usr/local/sbin/oxidizr enable --yes --experiments");
+
+        let steps = run_steps(
+            &runner,
+            &Root::Host(Path::new("/tmp/root")),
+            Path::new("/usr/bin/oxidizr"),
+            &[],
+            false,
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 1);
+        assert!(!steps[0].passed);
+    }
+}
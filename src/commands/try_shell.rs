@@ -0,0 +1,132 @@
+use crate::experiments::{Experiment, ManagedLink};
+use crate::utils::Worker;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Start an interactive shell in which the Rust replacements provided by `experiments` take
+/// precedence in `PATH`, without replacing any files in the system directories. Packages that
+/// need to be installed for the session are removed again when the shell exits.
+pub fn try_shell(system: &impl Worker, experiments: &[Experiment]) -> Result<()> {
+    let missing: Vec<&Experiment> = experiments
+        .iter()
+        .filter(|e| !e.check_installed())
+        .collect();
+
+    if !missing.is_empty() {
+        anyhow::ensure!(
+            uzers::get_current_uid() == 0,
+            "Packages must be installed to try {}; run as root",
+            missing
+                .iter()
+                .map(|e| e.name())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        info!("Updating apt package cache");
+        system.update_package_lists()?;
+        for e in &missing {
+            info!("Installing {} for this session", e.package());
+            system.install_package(&e.package())?;
+        }
+    }
+
+    let dir = std::env::temp_dir().join(format!("oxidizr-try-{}", std::process::id()));
+    let result = populate(system, experiments, &dir).and_then(|_| spawn(&dir));
+    std::fs::remove_dir_all(&dir).ok();
+
+    for e in &missing {
+        info!("Removing {}", e.package());
+        system.remove_package(&e.package())?;
+    }
+    result
+}
+
+/// Create symlinks to each Rust replacement in `dir`, named after the utility it replaces.
+fn populate(system: &impl Worker, experiments: &[Experiment], dir: &Path) -> Result<()> {
+    for link in links(system, experiments)? {
+        let name = link.target.file_name().unwrap();
+        system.create_symlink(link.source.clone(), dir.join(name))?;
+    }
+    Ok(())
+}
+
+/// List the replacements for the session. Replacements that only exist once an experiment has
+/// been enabled, such as generated compatibility wrappers, are skipped.
+fn links(system: &impl Worker, experiments: &[Experiment]) -> Result<Vec<ManagedLink>> {
+    let mut links = Vec::new();
+    for e in experiments {
+        for link in e.managed_links()? {
+            match system.file_exists(link.source.clone()) {
+                true => links.push(link),
+                false => warn!(
+                    "'{}' is not available until '{}' is enabled",
+                    link.target.file_name().unwrap().to_string_lossy(),
+                    e.name()
+                ),
+            }
+        }
+    }
+    Ok(links)
+}
+
+fn spawn(dir: &Path) -> Result<()> {
+    let shell = std::env::var("SHELL").unwrap_or("/bin/sh".to_string());
+    let path = match std::env::var("PATH") {
+        Ok(path) => format!("{}:{path}", dir.display()),
+        Err(_) => dir.display().to_string(),
+    };
+
+    println!(
+        "Starting {shell} with the Rust replacements first in PATH. Exit the shell to return; no files in the system directories have been changed."
+    );
+    let status = std::process::Command::new(&shell)
+        .env("PATH", path)
+        .env("OXIDIZR_TRY", PathBuf::from(dir))
+        .status()?;
+    if !status.success() {
+        warn!("{shell} exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::all_experiments;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_try_links() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/findutils/find", "", false),
+            ("/usr/lib/cargo/bin/findutils/xargs", "", false),
+            ("/usr/bin/find", "", true),
+            ("/usr/bin/xargs", "", true),
+        ]);
+        let experiments: Vec<Experiment> = all_experiments(&runner)
+            .into_iter()
+            .filter(|e| e.name() == "findutils" || e.name() == "http")
+            .collect();
+
+        populate(&runner, &experiments, Path::new("/tmp/try")).unwrap();
+
+        // The curl and wget wrappers are only generated when the http experiment is enabled.
+        let mut created = runner.created_symlinks.borrow().clone();
+        created.sort();
+        assert_eq!(
+            created,
+            vec![
+                (
+                    "/usr/lib/cargo/bin/findutils/find".to_string(),
+                    "/tmp/try/find".to_string()
+                ),
+                (
+                    "/usr/lib/cargo/bin/findutils/xargs".to_string(),
+                    "/tmp/try/xargs".to_string()
+                ),
+            ]
+        );
+    }
+}
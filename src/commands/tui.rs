@@ -0,0 +1,123 @@
+use crate::experiments::{Experiment, ManagedLink, all_experiments};
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+use inquire::MultiSelect;
+
+/// The changes chosen in `oxidizr tui`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Selection {
+    /// Experiments to enable, by name.
+    pub enable: Vec<String>,
+    /// Enabled experiments to disable, by name.
+    pub disable: Vec<String>,
+    /// Utilities to leave unreplaced, by the experiment replacing them.
+    pub exclude: Vec<(String, ManagedLink)>,
+}
+
+/// Present a checklist of experiments, showing whether each is compatible and enabled, and
+/// then a checklist of the utilities replaced by each chosen experiment whose package is
+/// installed. Returns the changes needed to apply the selection.
+pub fn choose(system: &impl Worker) -> Result<Selection> {
+    let state = State::load(system)?;
+    let experiments = all_experiments(system);
+    let enabled: Vec<bool> = experiments.iter().map(|e| enabled(e, &state)).collect();
+
+    let labels: Vec<String> = experiments.iter().map(|e| label(e, &state)).collect();
+    let defaults: Vec<usize> = (0..experiments.len()).filter(|&i| enabled[i]).collect();
+    let chosen = MultiSelect::new("Experiments to enable:", labels.clone())
+        .with_default(&defaults)
+        .with_page_size(labels.len())
+        .raw_prompt()?;
+    let chosen: Vec<usize> = chosen.into_iter().map(|o| o.index).collect();
+
+    let mut selection = Selection::default();
+    for (i, e) in experiments.iter().enumerate() {
+        match (chosen.contains(&i), enabled[i]) {
+            (true, false) => selection.enable.push(e.name()),
+            (false, true) => selection.disable.push(e.name()),
+            _ => {}
+        }
+    }
+
+    // Utilities are only known once the experiment's package is installed.
+    for &i in &chosen {
+        let links = experiments[i].managed_links().unwrap_or_default();
+        if links.len() < 2 {
+            continue;
+        }
+        let names: Vec<String> = links.iter().map(utility).collect();
+        let all: Vec<usize> = (0..names.len()).collect();
+        let kept = MultiSelect::new(
+            &format!("Utilities to replace with '{}':", experiments[i].name()),
+            names,
+        )
+        .with_default(&all)
+        .raw_prompt()?;
+        let kept: Vec<usize> = kept.into_iter().map(|o| o.index).collect();
+
+        for (j, link) in links.into_iter().enumerate() {
+            if !kept.contains(&j) {
+                selection.exclude.push((experiments[i].name(), link));
+            }
+        }
+    }
+
+    Ok(selection)
+}
+
+fn enabled(experiment: &Experiment, state: &State) -> bool {
+    state.experiments.contains_key(&experiment.name()) && experiment.check_installed()
+}
+
+/// Describe an experiment in the checklist, e.g. `coreutils (rust-coreutils, enabled)`.
+fn label(experiment: &Experiment, state: &State) -> String {
+    let status = match (enabled(experiment, state), experiment.check_compatible()) {
+        (true, _) => "enabled",
+        (false, true) => "compatible",
+        (false, false) => "incompatible",
+    };
+    let opt_in = match experiment.opt_in() {
+        true => ", opt-in",
+        false => "",
+    };
+    format!(
+        "{} ({}, {status}{opt_in})",
+        experiment.name(),
+        experiment.package()
+    )
+}
+
+/// The name of the utility replaced by a link, e.g. `date` for `/usr/bin/date`.
+pub fn utility(link: &ManagedLink) -> String {
+    link.target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_tui_labels() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}}}"#,
+            false,
+        )]);
+        let state = State::load(&runner).unwrap();
+        let experiments = all_experiments(&runner);
+        let find = |name: &str| experiments.iter().find(|e| e.name() == name).unwrap();
+
+        assert_eq!(label(find("sudo-rs"), &state), "sudo-rs (sudo-rs, enabled)");
+        assert_eq!(
+            label(find("coreutils"), &state),
+            "coreutils (rust-coreutils, compatible)"
+        );
+    }
+}
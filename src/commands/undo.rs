@@ -0,0 +1,126 @@
+use super::rollback::undo;
+use crate::state::{Operation, State};
+use crate::utils::{Journal, Worker};
+use anyhow::Result;
+use tracing::info;
+
+/// Undo a run of `enable` by replaying the actions it recorded in the journal in reverse. Unlike
+/// `rollback`, actions recorded by earlier runs are kept, so experiments they enabled are left
+/// untouched. The state file is restored by the journal along with the replaced files.
+pub fn undo_enable(system: &impl Worker, operation: &Operation) -> Result<()> {
+    let Some(start) = operation.journal else {
+        anyhow::bail!(
+            "The actions taken by enabling {} were discarded when they were disabled, so it can't be undone. Use 'oxidizr disable' instead",
+            operation.experiments.join(", ")
+        );
+    };
+
+    let state = State::load(system)?;
+    if let Some(e) = operation
+        .experiments
+        .iter()
+        .find(|e| state.experiments.get(*e).is_some_and(|e| e.sysext))
+    {
+        anyhow::bail!("'{e}' is provided by the system extension image; use 'oxidizr disable'");
+    }
+
+    let history = state.history;
+    let entries = Journal::load(system)?;
+    anyhow::ensure!(
+        start <= entries.len(),
+        "The journal is missing actions taken by enabling {}",
+        operation.experiments.join(", ")
+    );
+
+    info!("Undoing {} recorded actions", entries.len() - start);
+    for entry in entries[start..].iter().rev() {
+        undo(system, entry)?;
+    }
+
+    Journal::replace(system, &entries[..start])?;
+
+    // The state file is restored as it was before the run, but the history is kept for auditing.
+    let mut state = State::load(system)?;
+    state.history = history;
+    state.mark_undone(operation);
+    state.save(system)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::OperationKind;
+    use crate::utils::{JOURNAL_FILE, JournalEntry, MockSystem};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_undo_enable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_symlinks(vec![("/usr/bin/date", "/usr/bin/coreutils")]);
+
+        let mut state = State::default();
+        state.record("sudo-rs", Vec::new());
+        let before = serde_json::to_string(&state).unwrap();
+        let mut sudo_rs = Operation::new(OperationKind::Enable, Some(0));
+        sudo_rs.add(&runner, "sudo-rs", "sudo-rs", []);
+        state.record_operation(sudo_rs);
+
+        let mut coreutils = Operation::new(OperationKind::Enable, Some(2));
+        coreutils.add(&runner, "coreutils", "rust-coreutils", []);
+        state.record("coreutils", Vec::new());
+        state.record_operation(coreutils.clone());
+        state.save(&runner).unwrap();
+
+        let entries = [
+            JournalEntry::InstallPackage {
+                package: "sudo-rs".to_string(),
+                installed: false,
+            },
+            JournalEntry::ReplaceFile {
+                file: PathBuf::from("/usr/bin/sudo"),
+            },
+            JournalEntry::InstallPackage {
+                package: "rust-coreutils".to_string(),
+                installed: false,
+            },
+            JournalEntry::ReplaceFile {
+                file: PathBuf::from("/usr/bin/date"),
+            },
+            JournalEntry::WriteFile {
+                file: PathBuf::from(crate::state::STATE_FILE),
+                previous: Some((before, 0o644)),
+            },
+        ];
+        let journal: String = entries
+            .iter()
+            .map(|e| format!("{}\n", serde_json::to_string(e).unwrap()))
+            .collect();
+        runner.mock_files(vec![(JOURNAL_FILE, &journal, false)]);
+        runner.commands.borrow_mut().clear();
+
+        undo_enable(&runner, &coreutils).unwrap();
+
+        // Only the actions taken by enabling coreutils are undone.
+        assert_eq!(
+            runner.restored_files.borrow().clone(),
+            vec!["/usr/bin/date"]
+        );
+        assert_eq!(
+            runner.commands.borrow().clone(),
+            vec!["apt-get remove -y rust-coreutils"]
+        );
+        assert_eq!(Journal::load(&runner).unwrap().len(), 2);
+
+        let state = State::load(&runner).unwrap();
+        assert!(state.experiments.contains_key("sudo-rs"));
+        assert!(!state.experiments.contains_key("coreutils"));
+        assert_eq!(state.history.len(), 2);
+        assert!(state.history[1].undone);
+        assert_eq!(state.last_undoable().unwrap().experiments, vec!["sudo-rs"]);
+
+        coreutils.journal = None;
+        assert!(undo_enable(&runner, &coreutils).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::experiments::all_experiments;
+use crate::state::State;
+use crate::utils::Worker;
+use anyhow::Result;
+
+/// How the experiments supported on this system have changed since oxidizr last ran, for
+/// example because of a release upgrade from 24.04 to 24.10.
+#[derive(Debug, PartialEq)]
+struct ReleaseChanges {
+    /// The release oxidizr last ran on, if it's been recorded.
+    previous: Option<String>,
+    current: String,
+    /// The distribution, e.g. `Ubuntu`.
+    distribution: String,
+    /// Experiments that aren't enabled, and have become supported.
+    available: Vec<String>,
+    /// Enabled experiments that are no longer supported.
+    unsupported: Vec<String>,
+}
+
+/// Re-evaluate which experiments are supported on the running release, and print those that
+/// have become available since oxidizr last ran, and any enabled experiments that are no longer
+/// supported. Returns the names of the newly available experiments. If the release oxidizr last
+/// ran on isn't recorded, every supported experiment that isn't enabled is reported.
+pub fn upgrade(system: &impl Worker) -> Result<Vec<String>> {
+    let changes = release_changes(system)?;
+    print!("{}", describe(&changes));
+    Ok(changes.available)
+}
+
+fn release_changes(system: &impl Worker) -> Result<ReleaseChanges> {
+    let state = State::load(system)?;
+    let distribution = system.distribution()?;
+    let current = distribution.release;
+    let previous = state.release.clone();
+    let mut available = Vec::new();
+    let mut unsupported = Vec::new();
+
+    for e in all_experiments(system) {
+        let supported = e.supported_releases().contains(&current);
+        let enabled = state.experiments.contains_key(&e.name()) && e.check_installed();
+        let supported_before = previous
+            .as_ref()
+            .is_some_and(|p| e.supported_releases().contains(p));
+
+        if enabled && !supported {
+            unsupported.push(e.name());
+        } else if !enabled && supported && !supported_before {
+            available.push(e.name());
+        }
+    }
+
+    Ok(ReleaseChanges {
+        previous,
+        current,
+        distribution: distribution.id,
+        available,
+        unsupported,
+    })
+}
+
+fn describe(changes: &ReleaseChanges) -> String {
+    let current = &changes.current;
+    let mut out = match &changes.previous {
+        Some(previous) if previous != current => {
+            format!(
+                "{} has been upgraded from {previous} to {current}\n",
+                changes.distribution
+            )
+        }
+        _ => String::new(),
+    };
+
+    match changes.available.is_empty() {
+        true => out.push_str(&format!("No newly supported experiments on {current}\n")),
+        false => out.push_str(&format!(
+            "Now supported on {current}: {}\n",
+            changes.available.join(", ")
+        )),
+    }
+    if !changes.unsupported.is_empty() {
+        out.push_str(&format!(
+            "Enabled but no longer supported on {current}: {}\n",
+            changes.unsupported.join(", ")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Distribution, MockSystem};
+
+    #[test]
+    fn test_release_changes() {
+        let runner = MockSystem::new(Distribution {
+            id: "Ubuntu".to_string(),
+            release: "24.10".to_string(),
+        });
+        runner.mock_install_package("rust-coreutils");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"coreutils": {}}, "release": "24.04"}"#,
+            false,
+        )]);
+
+        let changes = release_changes(&runner).unwrap();
+        assert_eq!(changes.previous, Some("24.04".to_string()));
+        assert_eq!(changes.available, vec!["diffutils"]);
+        assert!(changes.unsupported.is_empty());
+        assert_eq!(
+            describe(&changes),
+            "Ubuntu has been upgraded from 24.04 to 24.10\nNow supported on 24.10: diffutils\n"
+        );
+    }
+}
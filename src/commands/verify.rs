@@ -0,0 +1,170 @@
+use crate::experiments::{Experiment, all_experiments};
+use crate::state::State;
+use crate::utils::{Worker, backup_checksum, extract_backup, find_backup, has_compressed_backup};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Calculate the checksums of the backups of the files replaced by an experiment, to be
+/// recorded in the state and checked by `oxidizr verify`.
+pub fn backup_checksums(
+    system: &impl Worker,
+    experiment: &Experiment,
+    state: &State,
+) -> Result<BTreeMap<PathBuf, String>> {
+    let mut checksums = BTreeMap::new();
+    for link in experiment.expected_links(state)? {
+        if let Some(sha256) = backup_checksum(system, &link.target)? {
+            checksums.insert(link.target, sha256);
+        }
+    }
+    Ok(checksums)
+}
+
+/// Check that every symlink managed by the enabled experiments points at the expected binary,
+/// and that every recorded backup exists and matches its checksum. Returns an error if any
+/// discrepancies are found.
+pub fn verify(system: &impl Worker) -> Result<()> {
+    let discrepancies = discrepancies(system)?;
+    for d in &discrepancies {
+        println!("{d}");
+    }
+
+    if !discrepancies.is_empty() {
+        anyhow::bail!("{} discrepancies found", discrepancies.len());
+    }
+    println!("All managed files verified");
+    Ok(())
+}
+
+fn discrepancies(system: &impl Worker) -> Result<Vec<String>> {
+    let state = State::load(system)?;
+    let mut found = Vec::new();
+
+    for e in all_experiments(system) {
+        let Some(recorded) = state.experiments.get(&e.name()) else {
+            continue;
+        };
+        if !e.check_installed() || recorded.sysext {
+            continue;
+        }
+
+        for link in e.expected_links(&state)? {
+            let target = link.target.display();
+            match system.read_link(link.target.clone()) {
+                Ok(actual) if actual != link.source => found.push(format!(
+                    "{target}: points at {} instead of {}",
+                    actual.display(),
+                    link.source.display()
+                )),
+                Err(_) => found.push(format!(
+                    "{target}: is not a symlink to {}",
+                    link.source.display()
+                )),
+                Ok(_) if !system.file_exists(link.source.clone()) => {
+                    found.push(format!("{target}: {} is missing", link.source.display()))
+                }
+                Ok(_) => {}
+            }
+
+            if let Some(expected) = recorded.backups.get(&link.target) {
+                match checksum(system, &link.target) {
+                    Ok(Some(actual)) if actual == *expected => {}
+                    Ok(Some(actual)) => found.push(format!(
+                        "{target}: backup checksum is {actual}, expected {expected}"
+                    )),
+                    Ok(None) => found.push(format!("{target}: backup of the original is missing")),
+                    Err(err) => found.push(format!("{target}: backup is unreadable: {err}")),
+                }
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Calculate the checksum of the content of the backup of `file`. Compressed backups are
+/// decompressed to check their content, rather than trusting the recorded checksum.
+fn checksum(system: &impl Worker, file: &Path) -> Result<Option<String>> {
+    if let Some(backup) = find_backup(system, file) {
+        return Ok(Some(system.sha256(backup)?));
+    }
+    if !has_compressed_backup(system, file) {
+        return Ok(None);
+    }
+
+    let dest = std::env::temp_dir().join(format!("oxidizr-verify-{}", std::process::id()));
+    let result = extract_backup(system, file, &dest).and_then(|_| system.sha256(dest.clone()));
+    system.remove_file(dest)?;
+    Ok(Some(result?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    fn sudors_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/su", "", false),
+            ("/usr/lib/cargo/bin/sudo", "", false),
+            ("/usr/lib/cargo/bin/visudo", "", false),
+            ("/usr/bin/sudo", "", true),
+            ("/usr/bin/su", "", true),
+            ("/usr/bin/visudo", "", true),
+            ("/usr/bin/.sudo.oxidizr.bak", "", false),
+            ("/usr/bin/.su.oxidizr.bak", "", false),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"sudo-rs": {"backups": {"/usr/bin/sudo": "abc", "/usr/bin/visudo": "123"}}, "su": {"backups": {"/usr/bin/su": "def"}}}}"#,
+                false,
+            ),
+        ]);
+        runner.mock_command(
+            "sha256sum /usr/bin/.sudo.oxidizr.bak",
+            "abc  /usr/bin/.sudo.oxidizr.bak",
+        );
+        runner.mock_command(
+            "sha256sum /usr/bin/.su.oxidizr.bak",
+            "xyz  /usr/bin/.su.oxidizr.bak",
+        );
+        runner
+    }
+
+    #[test]
+    fn test_verify_discrepancies() {
+        let runner = sudors_runner();
+        runner.mock_symlinks(vec![
+            ("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo"),
+            ("/usr/bin/su", "/usr/lib/cargo/bin/su"),
+            ("/usr/bin/visudo", "/usr/sbin/visudo.real"),
+        ]);
+
+        assert_eq!(
+            discrepancies(&runner).unwrap(),
+            vec![
+                "/usr/bin/visudo: points at /usr/sbin/visudo.real instead of /usr/lib/cargo/bin/visudo",
+                "/usr/bin/visudo: backup of the original is missing",
+                "/usr/bin/su: backup checksum is xyz, expected def",
+            ]
+        );
+        assert!(verify(&runner).is_err());
+    }
+
+    #[test]
+    fn test_backup_checksums() {
+        let runner = sudors_runner();
+        let sudors = all_experiments(&runner)
+            .into_iter()
+            .find(|e| e.name() == "sudo-rs")
+            .unwrap();
+
+        let checksums = backup_checksums(&runner, &sudors, &State::default()).unwrap();
+        assert_eq!(
+            checksums,
+            BTreeMap::from([(PathBuf::from("/usr/bin/sudo"), "abc".to_string()),])
+        );
+    }
+}
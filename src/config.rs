@@ -0,0 +1,206 @@
+use crate::experiments::DiffFrontend;
+use crate::utils::{Ppa, Worker, validate_apt_option, validate_proxy};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Location of the file holding persistent settings for oxidizr.
+pub const CONFIG_FILE: &str = "/etc/oxidizr/config.toml";
+
+/// Environment variable overriding the `proxy` setting.
+pub const PROXY_ENV: &str = "OXIDIZR_PROXY";
+
+/// Environment variable overriding the `apt_options` setting, with the options separated by
+/// spaces.
+pub const APT_OPTIONS_ENV: &str = "OXIDIZR_APT_OPTIONS";
+
+/// Persistent settings for oxidizr, read from `/etc/oxidizr/config.toml`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// Directory in which backups of the replaced binaries are stored, rather than alongside the
+    /// originals. This may be on a different filesystem, e.g. a persistent volume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_dir: Option<PathBuf>,
+    /// Experiments selected when `--experiments` isn't given, instead of coreutils and sudo-rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiments: Option<Vec<String>>,
+    /// Utilities left unreplaced whenever experiments are enabled, as with `--exclude`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Compress backups into the backup store whenever experiments are enabled, as with
+    /// `--compress-backups`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compress_backups: bool,
+    /// Skip confirmation prompts, as with `--yes`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub yes: bool,
+    /// Set bat as the pager for login shells when the `bat` experiment is enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bat_pager: bool,
+    /// Install dust without exposing it as `du` when the `dust` experiment is enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dust_only: bool,
+    /// Tool that `diff` is pointed at on terminals when the `diffutils` experiment is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_frontend: Option<DiffFrontend>,
+    /// Install xh alongside curl and wget, rather than replacing them, when the `http`
+    /// experiment is enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub xh_alongside: bool,
+    /// Show tldr pages from `man` for commands without a manual page when the `tldr`
+    /// experiment is enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tldr_man: bool,
+    /// Dispatch read-only git subcommands to gix in interactive login shells when the
+    /// `gitoxide` experiment is enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub gix_git: bool,
+    /// PPAs from which the packages of uutils experiments are installed, by experiment, e.g. for
+    /// newer builds of rust-coreutils on an LTS release.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ppa: BTreeMap<String, Ppa>,
+    /// Proxy through which apt and the other commands that download packages send their
+    /// requests, e.g. `http://proxy.example.com:3128`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Options passed to apt with `-o` for every package operation, e.g. `Acquire::Retries=3`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apt_options: Vec<String>,
+}
+
+impl Config {
+    /// Read the configuration, returning the defaults if no configuration file exists.
+    pub fn load(system: &dyn Worker) -> Result<Self> {
+        let file = PathBuf::from(CONFIG_FILE);
+        if !system.file_exists(file.clone()) {
+            return Ok(Self::default());
+        }
+
+        let config: Self = toml::from_str(&system.read_file(file)?)
+            .map_err(|e| anyhow::anyhow!("Invalid configuration in {CONFIG_FILE}: {e}"))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Write the configuration to disk.
+    pub fn save(&self, system: &dyn Worker) -> Result<()> {
+        self.validate()?;
+        system.write_file(PathBuf::from(CONFIG_FILE), &toml::to_string(self)?, 0o644)
+    }
+
+    /// Apply the network settings given in the environment, as the values of `OXIDIZR_PROXY`
+    /// and `OXIDIZR_APT_OPTIONS`, which take precedence over those in the configuration file.
+    pub fn override_network(
+        &mut self,
+        proxy: Option<String>,
+        apt_options: Option<String>,
+    ) -> Result<()> {
+        if let Some(proxy) = proxy.filter(|p| !p.is_empty()) {
+            validate_proxy(&proxy).map_err(|e| anyhow::anyhow!("Invalid {PROXY_ENV}: {e}"))?;
+            self.proxy = Some(proxy);
+        }
+        if let Some(options) = apt_options {
+            let options: Vec<String> = options.split_whitespace().map(str::to_string).collect();
+            for option in &options {
+                validate_apt_option(option)
+                    .map_err(|e| anyhow::anyhow!("Invalid {APT_OPTIONS_ENV}: {e}"))?;
+            }
+            self.apt_options = options;
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(dir) = &self.backup_dir {
+            anyhow::ensure!(
+                dir.is_absolute(),
+                "backup_dir in {CONFIG_FILE} must be an absolute path"
+            );
+        }
+        if let Some(proxy) = &self.proxy {
+            validate_proxy(proxy)
+                .map_err(|e| anyhow::anyhow!("Invalid proxy in {CONFIG_FILE}: {e}"))?;
+        }
+        for option in &self.apt_options {
+            validate_apt_option(option)
+                .map_err(|e| anyhow::anyhow!("Invalid apt_options in {CONFIG_FILE}: {e}"))?;
+        }
+        for (experiment, ppa) in &self.ppa {
+            ppa.validate().map_err(|e| {
+                anyhow::anyhow!("Invalid ppa for '{experiment}' in {CONFIG_FILE}: {e}")
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_config_load() {
+        let runner = MockSystem::default();
+        assert_eq!(Config::load(&runner).unwrap(), Config::default());
+
+        runner.mock_files(vec![(
+            CONFIG_FILE,
+            "backup_dir = \"/srv/backups\"\n",
+            false,
+        )]);
+        assert_eq!(
+            Config::load(&runner).unwrap().backup_dir,
+            Some(PathBuf::from("/srv/backups"))
+        );
+
+        runner.mock_files(vec![(CONFIG_FILE, "backup_dir = \"backups\"\n", false)]);
+        assert!(Config::load(&runner).is_err());
+
+        runner.mock_files(vec![(
+            CONFIG_FILE,
+            "[ppa.coreutils]\nname = \"ppa:uutils/rust-coreutils\"\nfingerprint = \"ABCD\"\n",
+            false,
+        )]);
+        assert!(Config::load(&runner).is_err());
+
+        runner.mock_files(vec![(CONFIG_FILE, "proxy = \"proxy:3128\"\n", false)]);
+        assert!(Config::load(&runner).is_err());
+    }
+
+    #[test]
+    fn test_config_override_network() {
+        let mut config = Config {
+            proxy: Some("http://proxy:3128".to_string()),
+            apt_options: vec!["Acquire::Retries=3".to_string()],
+            ..Default::default()
+        };
+        config.override_network(None, None).unwrap();
+        assert_eq!(config.proxy, Some("http://proxy:3128".to_string()));
+        assert_eq!(config.apt_options, ["Acquire::Retries=3"]);
+
+        config
+            .override_network(
+                Some("http://corporate:8080".to_string()),
+                Some("Acquire::Retries=5 Acquire::ForceIPv4=true".to_string()),
+            )
+            .unwrap();
+        assert_eq!(config.proxy, Some("http://corporate:8080".to_string()));
+        assert_eq!(
+            config.apt_options,
+            ["Acquire::Retries=5", "Acquire::ForceIPv4=true"]
+        );
+
+        assert!(
+            config
+                .override_network(Some("corporate".to_string()), None)
+                .is_err()
+        );
+        assert!(
+            config
+                .override_network(None, Some("Acquire".to_string()))
+                .is_err()
+        );
+    }
+}
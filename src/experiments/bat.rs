@@ -0,0 +1,170 @@
+use crate::config::Config;
+use crate::experiments::{Exposure, ManagedLink, Shim, WrapperExperiment};
+use crate::utils::Worker;
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::info;
+
+const PACKAGE: &str = "bat";
+
+/// Profile script setting bat as the pager for login shells, written only if `bat_pager` is set
+/// in the configuration.
+const PAGER_PROFILE: &str = "/etc/profile.d/oxidizr-bat-pager.sh";
+
+// bat itself pages with less, so BAT_PAGER stops it from trying to page through itself.
+const PAGER_PROFILE_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+export PAGER="batcat --plain"
+export BAT_PAGER="less -RF"
+"#;
+
+/// An experiment to install bat, show files with it when `cat` writes to a terminal, and
+/// optionally configure it as the pager for login shells.
+pub struct BatExperiment<'a> {
+    system: &'a dyn Worker,
+    wrapper: WrapperExperiment<'a>,
+}
+
+impl<'a> BatExperiment<'a> {
+    /// Create a new BatExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self {
+            system,
+            wrapper: WrapperExperiment::new(
+                "bat",
+                system,
+                PACKAGE,
+                &["24.04", "24.10", "25.04"],
+                Exposure::Replace,
+                vec![Shim::script("cat", include_str!("wrappers/cat-bat.sh"))],
+            ),
+        }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.wrapper.check_compatible()
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        self.wrapper.supported_releases()
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.wrapper.check_installed()
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        self.wrapper.package()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        self.wrapper.name()
+    }
+
+    /// Enable the experiment by installing the package and replacing `cat` with the wrapper.
+    /// If `bat_pager` is set in the configuration, bat is also set as the pager.
+    pub fn enable(&self) -> Result<()> {
+        self.wrapper.enable()?;
+
+        if Config::load(self.system)?.bat_pager {
+            info!("Configuring bat as the pager for login shells");
+            self.system
+                .write_file(PathBuf::from(PAGER_PROFILE), PAGER_PROFILE_CONTENTS, 0o644)?;
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by removing the pager configuration, restoring the original
+    /// `cat` and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        self.system.remove_file(PathBuf::from(PAGER_PROFILE))?;
+        self.wrapper.disable()
+    }
+
+    /// List the symlinks the experiment manages.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        self.wrapper.managed_links()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG_FILE;
+    use crate::experiments::wrapper::testing::WrapperRun;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_cat_bat_wrapper() {
+        let cat = WrapperRun::new(include_str!("wrappers/cat-bat.sh"), &["batcat"]);
+        assert_eq!(
+            cat.run_terminal(&["a", "b"]),
+            "batcat --plain --paging=never a b"
+        );
+        // Options, and output that is piped or redirected, are left to the original cat.
+        assert_eq!(cat.run_terminal(&["-A", "a"]), "original -A a");
+        assert_eq!(cat.run(&["a", "b"]), "original a b");
+    }
+
+    #[test]
+    fn test_bat_enable() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/bin/cat", "", false)]);
+        let bat = BatExperiment::new(&runner);
+
+        assert!(bat.enable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["apt-get install -y bat"]
+        );
+        assert_eq!(
+            runner.backed_up_files.clone().into_inner(),
+            &["/usr/bin/cat"]
+        );
+        let wrapper = runner
+            .read_file(PathBuf::from("/usr/lib/oxidizr/wrappers/cat"))
+            .unwrap();
+        assert!(wrapper.contains("original=\"/usr/bin/.cat.oxidizr.bak\""));
+        assert!(!runner.file_exists(PathBuf::from(PAGER_PROFILE)));
+    }
+
+    #[test]
+    fn test_bat_enable_pager() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/bin/cat", "", false),
+            (CONFIG_FILE, "bat_pager = true\n", false),
+        ]);
+        let bat = BatExperiment::new(&runner);
+
+        assert!(bat.enable().is_ok());
+        assert_eq!(
+            runner.read_file(PathBuf::from(PAGER_PROFILE)).unwrap(),
+            PAGER_PROFILE_CONTENTS
+        );
+    }
+
+    #[test]
+    fn test_bat_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("bat");
+        let bat = BatExperiment::new(&runner);
+
+        assert!(bat.disable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["apt-get remove -y bat"]
+        );
+        assert!(
+            runner
+                .removed_files
+                .clone()
+                .into_inner()
+                .contains(&PAGER_PROFILE.to_string())
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crate::experiments::ManagedLink;
+use crate::utils::{Command, Worker, has_backup};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const PACKAGE: &str = "brush";
+
+/// The shell installed by the package.
+const BINARY: &str = "/usr/bin/brush";
+
+/// The system shell, which Ubuntu links to dash.
+const SYSTEM_SHELL: &str = "/usr/bin/sh";
+
+/// Directories containing scripts run with `/bin/sh` that must parse before the system shell is
+/// replaced: init scripts, and package maintainer scripts, which break apt if they can't run.
+const SCRIPT_DIRECTORIES: &[&str] = &["/etc/init.d", "/var/lib/dpkg/info"];
+
+/// File extensions of the package maintainer scripts in `/var/lib/dpkg/info`.
+const MAINTAINER_SCRIPTS: &[&str] = &["preinst", "postinst", "prerm", "postrm", "config"];
+
+/// An experiment to install brush and make it the system shell, `/bin/sh`, in place of dash.
+pub struct BrushExperiment<'a> {
+    system: &'a dyn Worker,
+}
+
+impl<'a> BrushExperiment<'a> {
+    /// Create a new BrushExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self { system }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.supported_releases().contains(
+            &self
+                .system
+                .distribution()
+                .expect("unable to determine distribution information")
+                .release,
+        )
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        vec![
+            "24.04".to_string(),
+            "24.10".to_string(),
+            "25.04".to_string(),
+        ]
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.system.check_installed(PACKAGE).unwrap_or(false)
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        PACKAGE.to_string()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        String::from("brush")
+    }
+
+    /// Enable the experiment by installing the package and pointing `/bin/sh` at brush. The
+    /// system shell must be dash, and every init and maintainer script must parse with brush,
+    /// otherwise the package is removed and `/bin/sh` is left alone.
+    pub fn enable(&self) -> Result<()> {
+        let current = self.system.read_link(PathBuf::from(SYSTEM_SHELL)).ok();
+        anyhow::ensure!(
+            current.as_deref() == Some(Path::new("dash")),
+            "{SYSTEM_SHELL} doesn't link to dash, so can't be restored on disable"
+        );
+
+        warn!("Replacing the system shell: scripts run by the system will be run by brush");
+        info!("Installing and configuring {}", PACKAGE);
+        self.system.install_package(PACKAGE)?;
+
+        let unparsable = self.unparsable_scripts();
+        if !unparsable.is_empty() {
+            self.system.remove_package(PACKAGE)?;
+            let files: Vec<String> = unparsable.iter().map(|f| f.display().to_string()).collect();
+            anyhow::bail!(
+                "brush is unable to parse scripts run with {SYSTEM_SHELL}: {}",
+                files.join(", ")
+            );
+        }
+
+        // The link to dash is backed up as a link, so the system shell is restored with the
+        // other backups, including by 'oxidizr recover'.
+        for link in self.managed_links()? {
+            self.system.backup_file(link.target.clone())?;
+            self.system.create_symlink(link.source, link.target)?;
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by pointing `/bin/sh` back at dash and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        info!("Restoring dash as the system shell");
+        let shell = PathBuf::from(SYSTEM_SHELL);
+        match has_backup(self.system, &shell) {
+            true => self.system.restore_file(shell)?,
+            false => self.system.create_symlink(PathBuf::from("dash"), shell)?,
+        }
+
+        info!("Removing {}", PACKAGE);
+        self.system.remove_package(PACKAGE)?;
+
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(vec![ManagedLink::new(
+            PathBuf::from(BINARY),
+            PathBuf::from(SYSTEM_SHELL),
+        )])
+    }
+
+    /// Syntax check the scripts run with `/bin/sh` using brush, returning those it can't parse.
+    fn unparsable_scripts(&self) -> Vec<PathBuf> {
+        let mut unparsable = Vec::new();
+        for directory in SCRIPT_DIRECTORIES {
+            let Ok(files) = self.system.list_files(PathBuf::from(directory)) else {
+                continue;
+            };
+            for file in files {
+                let maintainer_script = file
+                    .extension()
+                    .is_some_and(|e| MAINTAINER_SCRIPTS.contains(&&*e.to_string_lossy()));
+                if *directory == "/var/lib/dpkg/info" && !maintainer_script {
+                    continue;
+                }
+                let uses_sh = self
+                    .system
+                    .read_file(file.clone())
+                    .is_ok_and(|contents| contents.lines().next().is_some_and(runs_with_sh));
+                if !uses_sh {
+                    continue;
+                }
+
+                let file_arg = file.to_string_lossy();
+                let cmd = Command::build(BINARY, &["-n", &file_arg]);
+                let parsed = self
+                    .system
+                    .capture(&cmd, Path::new("/"))
+                    .is_ok_and(|output| output.status.success());
+                if !parsed {
+                    unparsable.push(file);
+                }
+            }
+        }
+        unparsable.sort();
+        unparsable
+    }
+}
+
+/// Report whether a script's first line runs it with `sh`, directly as `/bin/sh` or
+/// `/usr/bin/sh`, or through `/usr/bin/env sh`.
+fn runs_with_sh(line: &str) -> bool {
+    let Some(shebang) = line.strip_prefix("#!") else {
+        return false;
+    };
+    let mut words = shebang.split_whitespace();
+    match words.next() {
+        Some("/bin/sh" | "/usr/bin/sh") => true,
+        Some("/usr/bin/env" | "/bin/env") => words.next() == Some("sh"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{MockSystem, vecs_eq};
+
+    #[test]
+    fn test_brush_enable() {
+        let runner = brush_compatible_runner();
+        let brush = BrushExperiment::new(&runner);
+
+        assert!(brush.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert!(vecs_eq(
+            commands,
+            vec![
+                "apt-get install -y brush".to_string(),
+                "/usr/bin/brush -n /etc/init.d/kmod".to_string(),
+                "/usr/bin/brush -n /etc/init.d/procps".to_string(),
+                "/usr/bin/brush -n /etc/init.d/udev".to_string(),
+                "/usr/bin/brush -n /var/lib/dpkg/info/procps.postinst".to_string(),
+            ]
+        ));
+        assert_eq!(
+            runner.read_link(PathBuf::from(SYSTEM_SHELL)).unwrap(),
+            PathBuf::from(BINARY)
+        );
+        assert_eq!(runner.backed_up_files.clone().into_inner(), &[SYSTEM_SHELL]);
+    }
+
+    #[test]
+    fn test_runs_with_sh() {
+        assert!(runs_with_sh("#!/bin/sh"));
+        assert!(runs_with_sh("#! /bin/sh -e"));
+        assert!(runs_with_sh("#!/usr/bin/sh"));
+        assert!(runs_with_sh("#!/usr/bin/env sh"));
+        assert!(!runs_with_sh("#!/bin/bash"));
+        assert!(!runs_with_sh("#!/bin/shell"));
+        assert!(!runs_with_sh("#!/usr/bin/env bash"));
+        assert!(!runs_with_sh("# /bin/sh"));
+    }
+
+    #[test]
+    fn test_brush_enable_unparsable_script() {
+        let runner = brush_compatible_runner();
+        runner.mock_command_failure("/usr/bin/brush -n /etc/init.d/procps");
+        let brush = BrushExperiment::new(&runner);
+
+        let err = brush.enable().unwrap_err();
+        assert!(err.to_string().contains("/etc/init.d/procps"));
+
+        // The system shell is left alone, and the package removed.
+        assert_eq!(
+            runner.read_link(PathBuf::from(SYSTEM_SHELL)).unwrap(),
+            PathBuf::from("dash")
+        );
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands.last().unwrap(), "apt-get remove -y brush");
+    }
+
+    #[test]
+    fn test_brush_enable_requires_dash() {
+        let runner = MockSystem::default();
+        runner.mock_symlinks(vec![(SYSTEM_SHELL, "bash")]);
+        let brush = BrushExperiment::new(&runner);
+
+        assert!(brush.enable().is_err());
+        assert!(runner.commands.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_brush_disable_from_backup() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/bin/.sh.oxidizr.bak", "", false)]);
+        runner.mock_install_package("brush");
+        let brush = BrushExperiment::new(&runner);
+
+        assert!(brush.disable().is_ok());
+        assert_eq!(runner.restored_files.clone().into_inner(), &[SYSTEM_SHELL]);
+    }
+
+    #[test]
+    fn test_brush_disable() {
+        let runner = MockSystem::default();
+        runner.mock_symlinks(vec![(SYSTEM_SHELL, BINARY)]);
+        runner.mock_install_package("brush");
+        let brush = BrushExperiment::new(&runner);
+
+        assert!(brush.disable().is_ok());
+
+        assert_eq!(
+            runner.read_link(PathBuf::from(SYSTEM_SHELL)).unwrap(),
+            PathBuf::from("dash")
+        );
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands, &["apt-get remove -y brush"]);
+    }
+
+    fn brush_compatible_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_symlinks(vec![(SYSTEM_SHELL, "dash")]);
+        runner.mock_files(vec![
+            ("/etc/init.d/procps", "#! /bin/sh\n", false),
+            ("/etc/init.d/apparmor", "#!/bin/bash\n", false),
+            ("/etc/init.d/kmod", "#!/usr/bin/sh -e\n", false),
+            ("/etc/init.d/udev", "#!/usr/bin/env sh\n", false),
+            ("/etc/init.d/ufw", "#!/usr/bin/env bash\n", false),
+            ("/var/lib/dpkg/info/procps.postinst", "#!/bin/sh\n", false),
+            (
+                "/var/lib/dpkg/info/procps.list",
+                "/etc/init.d/procps\n",
+                false,
+            ),
+        ]);
+        runner
+    }
+}
@@ -0,0 +1,82 @@
+use crate::experiments::Experiment;
+
+/// Bundle selecting every experiment, including opt-in experiments.
+pub const EVERYTHING: &str = "everything";
+
+/// Named groups of experiments, which can be selected with `--experiments` in place of their
+/// members.
+pub const BUNDLES: &[(&str, &[&str])] = &[
+    ("shell-tools", &["coreutils", "findutils", "diffutils"]),
+    ("security", &["sudo-rs"]),
+];
+
+/// Report whether `name` is the name of a bundle.
+pub fn is_bundle(name: &str) -> bool {
+    name == EVERYTHING || BUNDLES.iter().any(|(bundle, _)| *bundle == name)
+}
+
+/// Replace the names of bundles in a selection with the names of their members, keeping the
+/// order of the selection and dropping duplicates.
+pub fn expand_bundles(selected: Vec<String>, experiments: &[Experiment]) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::new();
+    for name in selected {
+        let members = match name.as_str() {
+            EVERYTHING => experiments.iter().map(|e| e.name()).collect(),
+            _ => match BUNDLES.iter().find(|(bundle, _)| *bundle == name) {
+                Some((_, members)) => members.iter().map(|m| m.to_string()).collect(),
+                None => vec![name],
+            },
+        };
+        for member in members {
+            if !expanded.contains(&member) {
+                expanded.push(member);
+            }
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::all_experiments;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_expand_bundles() {
+        let runner = MockSystem::default();
+        let experiments = all_experiments(&runner);
+
+        let selected = vec!["sudo-rs".to_string(), "shell-tools".to_string()];
+        assert_eq!(
+            expand_bundles(selected, &experiments),
+            &["sudo-rs", "coreutils", "findutils", "diffutils"]
+        );
+
+        let selected = vec!["security".to_string(), "bat".to_string()];
+        assert_eq!(expand_bundles(selected, &experiments), &["sudo-rs", "bat"]);
+    }
+
+    #[test]
+    fn test_expand_everything() {
+        let runner = MockSystem::default();
+        let experiments = all_experiments(&runner);
+
+        let expanded = expand_bundles(vec![EVERYTHING.to_string()], &experiments);
+        assert_eq!(expanded.len(), experiments.len());
+        assert!(expanded.contains(&"zram".to_string()));
+    }
+
+    #[test]
+    fn test_bundle_members_exist() {
+        let runner = MockSystem::default();
+        let names: Vec<String> = all_experiments(&runner).iter().map(|e| e.name()).collect();
+
+        for (bundle, members) in BUNDLES {
+            assert!(!names.contains(&bundle.to_string()));
+            for member in *members {
+                assert!(names.contains(&member.to_string()), "{bundle}: {member}");
+            }
+        }
+    }
+}
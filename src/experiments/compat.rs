@@ -0,0 +1,522 @@
+use crate::utils::Worker;
+use std::path::PathBuf;
+
+/// Directories commonly containing scripts run unattended, scanned before replacing a utility.
+pub const SCRIPT_DIRECTORIES: &[&str] = &[
+    "/etc/cron.d",
+    "/etc/cron.daily",
+    "/etc/cron.hourly",
+    "/etc/cron.weekly",
+    "/etc/cron.monthly",
+    "/usr/local/bin",
+    "/usr/local/sbin",
+];
+
+/// Directories containing initramfs and kernel hook scripts, which run as part of package upgrades
+/// and break apt if a utility they use fails.
+pub const KERNEL_HOOK_DIRECTORIES: &[&str] = &[
+    "/usr/share/initramfs-tools/hooks",
+    "/usr/share/initramfs-tools/scripts",
+    "/etc/initramfs-tools/hooks",
+    "/etc/initramfs-tools/scripts",
+    "/etc/kernel/preinst.d",
+    "/etc/kernel/postinst.d",
+    "/etc/kernel/prerm.d",
+    "/etc/kernel/postrm.d",
+    "/etc/kernel.d",
+];
+
+/// A known behavioural difference between a traditional utility and its Rust replacement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incompatibility {
+    /// The command affected, e.g. `awk`.
+    pub command: &'static str,
+    /// The flag, option or language feature that behaves differently.
+    pub feature: &'static str,
+    /// A short explanation of the difference.
+    pub note: &'static str,
+}
+
+/// Known incompatibilities between the traditional utilities and the Rust replacements that
+/// oxidizr can install.
+const KNOWN_INCOMPATIBILITIES: &[Incompatibility] = &[
+    Incompatibility {
+        command: "awk",
+        feature: "-i inplace",
+        note: "in-place editing is a gawk extension and is not supported by frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "@include",
+        note: "source file inclusion is a gawk extension and is not supported by frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "strftime",
+        note: "gawk time functions (strftime, systime, mktime) are not available in frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "PROCINFO",
+        note: "the gawk PROCINFO array is not available in frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "-W",
+        note: "mawk/gawk implementation-specific -W options are not recognised by frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "gensub",
+        note: "the gawk gensub function is not available in frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "asort",
+        note: "gawk array sorting functions (asort, asorti) are not available in frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "BEGINFILE",
+        note: "gawk BEGINFILE and ENDFILE patterns are not supported by frawk",
+    },
+    Incompatibility {
+        command: "awk",
+        feature: "|&",
+        note: "gawk coprocesses are not supported by frawk",
+    },
+    Incompatibility {
+        command: "mawk",
+        feature: "-W",
+        note: "mawk-specific -W options are not recognised by frawk",
+    },
+    Incompatibility {
+        command: "cp",
+        feature: "-Z",
+        note: "setting the SELinux security context is not supported by uutils cp",
+    },
+    Incompatibility {
+        command: "mv",
+        feature: "-Z",
+        note: "setting the SELinux security context is not supported by uutils mv",
+    },
+    Incompatibility {
+        command: "install",
+        feature: "-Z",
+        note: "setting the SELinux security context is not supported by uutils install",
+    },
+    Incompatibility {
+        command: "find",
+        feature: "-fls",
+        note: "writing -ls output to a file is not implemented by uutils findutils",
+    },
+    Incompatibility {
+        command: "find",
+        feature: "-fprintf",
+        note: "writing -printf output to a file is not implemented by uutils findutils",
+    },
+    Incompatibility {
+        command: "du",
+        feature: "-s",
+        note: "dust uses -s for apparent size rather than summarising each argument",
+    },
+    Incompatibility {
+        command: "du",
+        feature: "-c",
+        note: "dust uses -c to disable colours rather than printing a grand total",
+    },
+    Incompatibility {
+        command: "du",
+        feature: "-h",
+        note: "dust always prints human readable sizes and uses -h for help",
+    },
+    Incompatibility {
+        command: "du",
+        feature: "--max-depth",
+        note: "dust uses -d/--depth to limit depth rather than --max-depth",
+    },
+    Incompatibility {
+        command: "curl",
+        feature: "-F",
+        note: "multipart form uploads are not translated for xh; the original curl is used",
+    },
+    Incompatibility {
+        command: "curl",
+        feature: "--data-binary",
+        note: "binary request bodies are not translated for xh; the original curl is used",
+    },
+    Incompatibility {
+        command: "curl",
+        feature: "--compressed",
+        note: "compressed transfer negotiation is not translated for xh; the original curl is used",
+    },
+    Incompatibility {
+        command: "curl",
+        feature: "-w",
+        note: "--write-out formatting has no xh equivalent; the original curl is used",
+    },
+    Incompatibility {
+        command: "curl",
+        feature: "--retry",
+        note: "automatic retries have no xh equivalent; the original curl is used",
+    },
+    Incompatibility {
+        command: "curl",
+        feature: "--cacert",
+        note: "custom CA bundles are not translated for xh; the original curl is used",
+    },
+    Incompatibility {
+        command: "wget",
+        feature: "-r",
+        note: "recursive retrieval has no xh equivalent; the original wget is used",
+    },
+    Incompatibility {
+        command: "wget",
+        feature: "--mirror",
+        note: "mirroring has no xh equivalent; the original wget is used",
+    },
+    Incompatibility {
+        command: "wget",
+        feature: "-N",
+        note: "timestamping has no xh equivalent; the original wget is used",
+    },
+    Incompatibility {
+        command: "wget",
+        feature: "-P",
+        note: "--directory-prefix is not translated for xh; the original wget is used",
+    },
+    Incompatibility {
+        command: "grep",
+        feature: "-P",
+        note: "Perl-compatible regular expressions are not translated for ripgrep; the original grep is used",
+    },
+    Incompatibility {
+        command: "grep",
+        feature: "--include",
+        note: "file name filters are not translated for ripgrep; the original grep is used",
+    },
+    Incompatibility {
+        command: "grep",
+        feature: "-z",
+        note: "NUL-separated input is not translated for ripgrep; the original grep is used",
+    },
+    Incompatibility {
+        command: "grep",
+        feature: "\\(",
+        note: "basic regular expression groups have no ripgrep equivalent; the original grep is used",
+    },
+    Incompatibility {
+        command: "sed",
+        feature: "-n",
+        note: "printing only selected lines has no sd equivalent; the original sed is used",
+    },
+    Incompatibility {
+        command: "sed",
+        feature: "-f",
+        note: "sed script files are not translated for sd; the original sed is used",
+    },
+    Incompatibility {
+        command: "sed",
+        feature: "-z",
+        note: "NUL-separated input is not translated for sd; the original sed is used",
+    },
+    Incompatibility {
+        command: "tar",
+        feature: "-c",
+        note: "creating archives is not translated for ouch; the original tar is used",
+    },
+    Incompatibility {
+        command: "tar",
+        feature: "--exclude",
+        note: "excluding members is not translated for ouch; the original tar is used",
+    },
+    Incompatibility {
+        command: "unzip",
+        feature: "-l",
+        note: "listing archives is not translated for ouch; the original unzip is used",
+    },
+    Incompatibility {
+        command: "gzip",
+        feature: "-r",
+        note: "compressing files in place is not translated for crabz; the original gzip is used",
+    },
+    Incompatibility {
+        command: "gzip",
+        feature: "-t",
+        note: "testing archives has no crabz equivalent; the original gzip is used",
+    },
+    Incompatibility {
+        command: "bc",
+        feature: "-l",
+        note: "fend names the math library functions differently, e.g. sin rather than s",
+    },
+    Incompatibility {
+        command: "bc",
+        feature: "scale",
+        note: "fend has no fixed scale; input read from files or pipes uses the original bc",
+    },
+    Incompatibility {
+        command: "file",
+        feature: "-b",
+        note: "magika describes file types differently to libmagic, so matching on the output may fail",
+    },
+    Incompatibility {
+        command: "file",
+        feature: "-i",
+        note: "magika reports the MIME type without a charset",
+    },
+    Incompatibility {
+        command: "file",
+        feature: "-z",
+        note: "magika doesn't look inside compressed files; the original file is used",
+    },
+    Incompatibility {
+        command: "file",
+        feature: "--mime-encoding",
+        note: "magika doesn't detect character encodings; the original file is used",
+    },
+    Incompatibility {
+        command: "parallel",
+        feature: "--will-cite",
+        note: "rust-parallel shows no citation notice, so the flag is accepted and ignored",
+    },
+    Incompatibility {
+        command: "parallel",
+        feature: "--citation",
+        note: "the citation notice is specific to GNU parallel; the original parallel is used",
+    },
+    Incompatibility {
+        command: "parallel",
+        feature: "{}",
+        note: "replacement strings are not translated for rust-parallel; the original parallel is used",
+    },
+    Incompatibility {
+        command: "parallel",
+        feature: "-k",
+        note: "rust-parallel doesn't keep output in input order; the original parallel is used",
+    },
+];
+
+/// Return the known incompatibilities for a given command.
+pub fn known_incompatibilities(command: &str) -> Vec<&'static Incompatibility> {
+    KNOWN_INCOMPATIBILITIES
+        .iter()
+        .filter(|i| i.command == command)
+        .collect()
+}
+
+/// Return the commands with known incompatibilities, e.g. `awk`.
+pub fn known_commands() -> Vec<&'static str> {
+    let mut commands: Vec<&'static str> =
+        KNOWN_INCOMPATIBILITIES.iter().map(|i| i.command).collect();
+    commands.sort();
+    commands.dedup();
+    commands
+}
+
+/// Return the known incompatibilities for `command` that are used by a shell script.
+///
+/// This is a heuristic: a line is considered to invoke `command` if any word on it is the
+/// command name or a path ending in the command name. Flags are matched as whole words (or
+/// `--flag=value`), other features are matched anywhere on the line.
+pub fn scan_script(contents: &str, command: &str) -> Vec<&'static Incompatibility> {
+    let incompatibilities = known_incompatibilities(command);
+    let mut found: Vec<&'static Incompatibility> = Vec::new();
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let invoked = words
+            .iter()
+            .any(|w| *w == command || w.ends_with(&format!("/{command}")));
+        if !invoked {
+            continue;
+        }
+
+        for i in &incompatibilities {
+            let used = match i.feature.starts_with('-') {
+                true => words
+                    .iter()
+                    .any(|w| *w == i.feature || w.starts_with(&format!("{}=", i.feature))),
+                false => line.contains(i.feature),
+            };
+            if used && !found.contains(i) {
+                found.push(i);
+            }
+        }
+    }
+
+    found
+}
+
+/// Scan the scripts in `directories` for uses of known incompatibilities of `commands`. A path
+/// that is a file rather than a directory is scanned itself. Paths that don't exist, and files
+/// that can't be read as text, are skipped.
+pub fn scan_scripts(
+    system: &dyn Worker,
+    directories: &[&str],
+    commands: &[&str],
+) -> Vec<(PathBuf, &'static Incompatibility)> {
+    let mut findings = Vec::new();
+
+    for dir in directories {
+        let files = system
+            .list_files(PathBuf::from(dir))
+            .unwrap_or_else(|_| vec![PathBuf::from(dir)]);
+
+        for file in files {
+            let Ok(contents) = system.read_file(file.clone()) else {
+                continue;
+            };
+            for command in commands {
+                for i in scan_script(&contents, command) {
+                    findings.push((file.clone(), i));
+                }
+            }
+        }
+    }
+
+    findings
+}
+
+/// Scripts that run during kernel or initramfs updates use features of utilities that an
+/// experiment would replace.
+#[derive(Debug)]
+pub struct HookConflicts {
+    pub experiment: String,
+    pub conflicts: Vec<(PathBuf, &'static Incompatibility)>,
+}
+
+impl HookConflicts {
+    /// The commands that must be left unreplaced for the hooks to keep working.
+    pub fn commands(&self) -> Vec<String> {
+        let mut commands: Vec<String> = self
+            .conflicts
+            .iter()
+            .map(|(_, i)| i.command.to_string())
+            .collect();
+        commands.sort();
+        commands.dedup();
+        commands
+    }
+}
+
+impl std::fmt::Display for HookConflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Enabling '{}' would break kernel and initramfs updates:",
+            self.experiment
+        )?;
+        for (file, i) in &self.conflicts {
+            writeln!(
+                f,
+                "  {} uses '{} {}': {}",
+                file.display(),
+                i.command,
+                i.feature,
+                i.note
+            )?;
+        }
+        write!(
+            f,
+            "Re-run with '--exclude {}' to leave these utilities unreplaced",
+            self.commands().join(" ")
+        )
+    }
+}
+
+impl std::error::Error for HookConflicts {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_known_incompatibilities() {
+        let awk = known_incompatibilities("awk");
+        assert!(!awk.is_empty());
+        assert!(awk.iter().all(|i| i.command == "awk"));
+
+        assert!(known_incompatibilities("not-a-command").is_empty());
+    }
+
+    #[test]
+    fn test_known_commands() {
+        let commands = known_commands();
+        assert!(commands.contains(&"cp"));
+        assert_eq!(commands.iter().filter(|c| **c == "awk").count(), 1);
+    }
+
+    #[test]
+    fn test_scan_script() {
+        let script = r#"#!/bin/sh
+# curl --compressed is mentioned in a comment, which should be ignored
+curl -fsSL https://example.com -o /tmp/file
+/usr/bin/curl --retry 3 https://example.com
+wget -r https://example.com
+"#;
+
+        let found = scan_script(script, "curl");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].feature, "--retry");
+
+        let found = scan_script(script, "wget");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].feature, "-r");
+
+        assert!(scan_script("awk '{ print strftime() }'", "awk")[0].feature == "strftime");
+        assert!(scan_script(r"grep '^\(a\|b\)$' list", "grep")[0].feature == r"\(");
+        assert!(scan_script("sed -n '/^x/p' list", "sed")[0].feature == "-n");
+    }
+
+    #[test]
+    fn test_hook_conflicts() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(
+            "/usr/share/initramfs-tools/hooks/selinux",
+            "cp -Z \"$src\" \"${DESTDIR}/etc\"",
+            false,
+        )]);
+
+        let conflicts = HookConflicts {
+            experiment: "coreutils".to_string(),
+            conflicts: scan_scripts(&runner, KERNEL_HOOK_DIRECTORIES, &["cp", "mv"]),
+        };
+        assert_eq!(conflicts.commands(), vec!["cp"]);
+        assert!(conflicts.to_string().contains(
+            "/usr/share/initramfs-tools/hooks/selinux uses 'cp -Z': setting the SELinux"
+        ));
+        assert!(
+            conflicts
+                .to_string()
+                .ends_with("Re-run with '--exclude cp' to leave these utilities unreplaced")
+        );
+    }
+
+    #[test]
+    fn test_scan_scripts() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            (
+                "/etc/cron.daily/backup",
+                "wget --mirror https://example.com",
+                false,
+            ),
+            (
+                "/usr/local/bin/fetch",
+                "curl -sL https://example.com",
+                false,
+            ),
+        ]);
+
+        let findings = scan_scripts(&runner, SCRIPT_DIRECTORIES, &["curl", "wget"]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].0, PathBuf::from("/etc/cron.daily/backup"));
+        assert_eq!(findings[0].1.feature, "--mirror");
+    }
+}
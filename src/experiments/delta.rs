@@ -0,0 +1,217 @@
+use crate::experiments::{Exposure, ManagedLink, Shim, WrapperExperiment};
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const PACKAGE: &str = "git-delta";
+
+/// Git configuration included from the system-wide gitconfig while the experiment is enabled.
+/// Using an include means the administrator's own settings are never modified.
+const GITCONFIG: &str = "/etc/oxidizr/delta.gitconfig";
+
+const GITCONFIG_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+[core]
+	pager = delta
+[interactive]
+	diffFilter = delta --color-only
+[delta]
+	navigate = true
+"#;
+
+/// An experiment to install git-delta and configure it as the pager for diff output.
+pub struct DeltaExperiment<'a> {
+    system: &'a dyn Worker,
+    wrapper: WrapperExperiment<'a>,
+}
+
+impl<'a> DeltaExperiment<'a> {
+    /// Create a new DeltaExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self {
+            system,
+            wrapper: WrapperExperiment::new(
+                "delta",
+                system,
+                PACKAGE,
+                &["24.04", "24.10", "25.04"],
+                Exposure::ShadowPath,
+                vec![Shim::script("diff", include_str!("wrappers/diff-delta.sh"))],
+            ),
+        }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.wrapper.check_compatible()
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        self.wrapper.supported_releases()
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.wrapper.check_installed()
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        self.wrapper.package()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        self.wrapper.name()
+    }
+
+    /// Enable the experiment by installing the package, exposing the `diff` wrapper and
+    /// configuring git to use delta as its pager.
+    pub fn enable(&self) -> Result<()> {
+        self.wrapper.enable()?;
+
+        if self.system.which("git").is_err() {
+            warn!("git is not installed, skipping system-wide git configuration for delta");
+            return Ok(());
+        }
+
+        info!("Configuring git to use delta as its pager");
+        self.system
+            .write_file(PathBuf::from(GITCONFIG), GITCONFIG_CONTENTS, 0o644)?;
+        // Remove any existing include first, so repeated enables don't stack duplicates.
+        self.unset_include();
+        let cmd = Command::build(
+            "git",
+            &["config", "--system", "--add", "include.path", GITCONFIG],
+        );
+        self.system.run(&cmd)?;
+
+        Ok(())
+    }
+
+    /// Disable the experiment by reverting the git configuration, removing the `diff` wrapper
+    /// and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        if self.system.which("git").is_ok() {
+            info!("Removing delta from the system git configuration");
+            self.unset_include();
+        }
+        self.system.remove_file(PathBuf::from(GITCONFIG))?;
+
+        self.wrapper.disable()
+    }
+
+    /// List the symlinks the experiment manages.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        self.wrapper.managed_links()
+    }
+
+    /// Remove the include of the delta gitconfig from the system gitconfig. Git reports an error
+    /// if the include isn't present, which is ignored.
+    fn unset_include(&self) {
+        let cmd = Command::build(
+            "git",
+            &[
+                "config",
+                "--system",
+                "--fixed-value",
+                "--unset-all",
+                "include.path",
+                GITCONFIG,
+            ],
+        );
+        let _ = self.system.run(&cmd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::wrapper::testing::WrapperRun;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_diff_delta_wrapper() {
+        let diff = WrapperRun::new(include_str!("wrappers/diff-delta.sh"), &["delta"]);
+        // On a terminal, the output of the original diff is piped through delta.
+        assert_eq!(
+            diff.run_terminal(&["-u", "a", "b"]),
+            "delta\noriginal -u a b"
+        );
+        // Output that is piped or redirected is left to the original diff.
+        assert_eq!(diff.run(&["-u", "a", "b"]), "original -u a b");
+    }
+
+    #[test]
+    fn test_delta_enable() {
+        let runner = delta_compatible_runner();
+        let delta = DeltaExperiment::new(&runner);
+
+        assert!(delta.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y git-delta",
+                "git config --system --fixed-value --unset-all include.path /etc/oxidizr/delta.gitconfig",
+                "git config --system --add include.path /etc/oxidizr/delta.gitconfig",
+            ]
+        );
+
+        let written_files = runner.written_files.clone().into_inner();
+        assert!(written_files.contains(&"/usr/lib/oxidizr/shadow/diff".to_string()));
+        assert!(written_files.contains(&GITCONFIG.to_string()));
+
+        // The diff wrapper calls the original diff, which is left in place.
+        let wrapper = runner
+            .read_file(PathBuf::from("/usr/lib/oxidizr/shadow/diff"))
+            .unwrap();
+        assert!(wrapper.contains("original=\"/usr/bin/diff\""));
+        assert_eq!(runner.backed_up_files.clone().into_inner().len(), 0);
+    }
+
+    #[test]
+    fn test_delta_enable_without_git() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/bin/diff", "", true)]);
+        let delta = DeltaExperiment::new(&runner);
+
+        assert!(delta.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands, &["apt-get install -y git-delta"]);
+    }
+
+    #[test]
+    fn test_delta_disable() {
+        let runner = delta_compatible_runner();
+        runner.mock_install_package("git-delta");
+        let delta = DeltaExperiment::new(&runner);
+
+        assert!(delta.disable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "git config --system --fixed-value --unset-all include.path /etc/oxidizr/delta.gitconfig",
+                "apt-get remove -y git-delta",
+            ]
+        );
+
+        let removed_files = runner.removed_files.clone().into_inner();
+        assert!(removed_files.contains(&GITCONFIG.to_string()));
+        assert!(removed_files.contains(&"/usr/lib/oxidizr/shadow/diff".to_string()));
+    }
+
+    fn delta_compatible_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/bin/diff", "", true),
+            ("/usr/bin/git", "", true),
+        ]);
+        runner
+    }
+}
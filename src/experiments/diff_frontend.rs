@@ -0,0 +1,57 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// A tool that `diff` can be pointed at for human-readable output on terminals, while the other
+/// diffutils binaries remain linked to their Rust replacements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffFrontend {
+    /// Pipe the output of diff through delta.
+    Delta,
+    /// Compare two files structurally with difftastic.
+    Difftastic,
+}
+
+impl DiffFrontend {
+    /// Report the package providing the frontend.
+    pub fn package(&self) -> &'static str {
+        match self {
+            DiffFrontend::Delta => "git-delta",
+            DiffFrontend::Difftastic => "difftastic",
+        }
+    }
+
+    /// The wrapper script exposed as `diff`, which runs the original diff when writing to a
+    /// pipe or file.
+    pub fn script(&self) -> &'static str {
+        match self {
+            DiffFrontend::Delta => include_str!("wrappers/diff-delta.sh"),
+            DiffFrontend::Difftastic => include_str!("wrappers/diff-difftastic.sh"),
+        }
+    }
+
+    /// Report the name of the frontend, as used in the configuration.
+    pub fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::wrapper::testing::WrapperRun;
+
+    #[test]
+    fn test_diff_difftastic_wrapper() {
+        let diff = WrapperRun::new(DiffFrontend::Difftastic.script(), &["difft"]);
+        assert_eq!(diff.run_terminal(&["a", "b"]), "difft --exit-code a b");
+        // Options, standard input and anything other than two files are left to the original
+        // diff, as is output that is piped or redirected.
+        assert_eq!(diff.run_terminal(&["-u", "a", "b"]), "original -u a b");
+        assert_eq!(diff.run_terminal(&["-", "b"]), "original - b");
+        assert_eq!(diff.run_terminal(&["a", "b", "c"]), "original a b c");
+        assert_eq!(diff.run(&["a", "b"]), "original a b");
+    }
+}
@@ -0,0 +1,442 @@
+use crate::experiments::ManagedLink;
+use crate::utils::{Worker, has_backup};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const PACKAGE: &str = "hickory-dns";
+
+/// The resolver configuration pointed at the local stub resolver while the experiment is
+/// enabled. On Ubuntu it's a symlink to the systemd-resolved stub, which is backed up as a link.
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// The upstream name servers and search domains systemd-resolved has learned, e.g. from DHCP.
+/// hickory-dns forwards queries to the name servers, and the search domains are kept in the
+/// resolver configuration.
+const RESOLVED_UPSTREAM: &str = "/run/systemd/resolve/resolv.conf";
+
+/// Drop-in disabling the systemd-resolved stub listener, so hickory-dns can listen on port 53.
+/// systemd-resolved keeps running to track the upstream name servers.
+const RESOLVED_DROP_IN: &str = "/etc/systemd/resolved.conf.d/oxidizr-dns.conf";
+
+const RESOLVED_DROP_IN_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+[Resolve]
+DNSStubListener=no
+"#;
+
+/// Configuration for hickory-dns, forwarding every query to the upstream name servers.
+const CONFIG: &str = "/etc/oxidizr/hickory-dns.toml";
+
+const SERVICE: &str = "oxidizr-dns.service";
+
+const UNIT: &str = "/etc/systemd/system/oxidizr-dns.service";
+
+const UNIT_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+[Unit]
+Description=hickory-dns local stub resolver
+After=systemd-resolved.service
+Before=nss-lookup.target
+Wants=nss-lookup.target
+
+[Service]
+ExecStart=/usr/bin/hickory-dns --config /etc/oxidizr/hickory-dns.toml
+Restart=on-failure
+
+[Install]
+WantedBy=multi-user.target
+"#;
+
+/// The name servers and search domains read from systemd-resolved when the experiment is
+/// enabled.
+struct Upstream {
+    servers: Vec<String>,
+    search: Vec<String>,
+}
+
+/// An experiment to install hickory-dns and use it as the local stub resolver in place of
+/// systemd-resolved's stub.
+pub struct DnsExperiment<'a> {
+    system: &'a dyn Worker,
+}
+
+impl<'a> DnsExperiment<'a> {
+    /// Create a new DnsExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self { system }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.supported_releases().contains(
+            &self
+                .system
+                .distribution()
+                .expect("unable to determine distribution information")
+                .release,
+        )
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        vec![
+            "24.04".to_string(),
+            "24.10".to_string(),
+            "25.04".to_string(),
+        ]
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.system.check_installed(PACKAGE).unwrap_or(false)
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        PACKAGE.to_string()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        String::from("dns")
+    }
+
+    /// Enable the experiment by installing hickory-dns, running it on 127.0.0.1 in place of the
+    /// systemd-resolved stub, and pointing the resolver configuration at it. If hickory-dns fails
+    /// to start, the systemd-resolved stub is restored.
+    ///
+    /// The upstream name servers are only read once, so hickory-dns keeps forwarding to them
+    /// until the experiment is enabled again.
+    pub fn enable(&self) -> Result<()> {
+        let upstream = self.upstream()?;
+
+        info!("Installing and configuring {}", PACKAGE);
+        info!(
+            "Forwarding queries to {}. If the upstream name servers change, e.g. on another \
+             network, disable and enable the experiment again to pick them up",
+            upstream.servers.join(", ")
+        );
+        self.system.install_package(PACKAGE)?;
+        self.system
+            .write_file(PathBuf::from(CONFIG), &config(&upstream.servers), 0o644)?;
+        self.system
+            .write_file(PathBuf::from(UNIT), UNIT_CONTENTS, 0o644)?;
+
+        info!("Disabling the systemd-resolved stub listener");
+        self.system.write_file(
+            PathBuf::from(RESOLVED_DROP_IN),
+            RESOLVED_DROP_IN_CONTENTS,
+            0o644,
+        )?;
+        // Without the stub listener, nothing resolves names until hickory-dns is running, so
+        // any failure from here restores it.
+        let started = self
+            .system
+            .reload_service_manager()
+            .and_then(|_| self.system.restart_service("systemd-resolved.service"))
+            .context("Failed to restart systemd-resolved")
+            .and_then(|_| {
+                self.system
+                    .enable_service(SERVICE)
+                    .context("Failed to start hickory-dns")
+            });
+        if let Err(e) = started {
+            warn!("Restoring the systemd-resolved stub resolver");
+            if let Err(restore) = self.unconfigure() {
+                warn!("Unable to restore the systemd-resolved stub resolver: {restore}");
+            }
+            self.system.remove_package(PACKAGE)?;
+            return Err(e);
+        }
+
+        info!("Pointing {} at hickory-dns", RESOLV_CONF);
+        let resolv_conf = PathBuf::from(RESOLV_CONF);
+        self.system.backup_file(resolv_conf.clone())?;
+        // Removed first, so a symlink is replaced rather than written through.
+        self.system.remove_file(resolv_conf.clone())?;
+        self.system
+            .write_file(resolv_conf, &resolv_conf_contents(&upstream.search), 0o644)?;
+        Ok(())
+    }
+
+    /// Disable the experiment by restoring the resolver configuration, stopping hickory-dns,
+    /// restoring the systemd-resolved stub listener and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        let resolv_conf = PathBuf::from(RESOLV_CONF);
+        if has_backup(self.system, &resolv_conf) {
+            info!("Restoring {}", RESOLV_CONF);
+            self.system.restore_file(resolv_conf)?;
+        } else {
+            warn!("No backup of {RESOLV_CONF}, linking it to the systemd-resolved stub");
+            self.system.remove_file(resolv_conf.clone())?;
+            self.system.create_symlink(
+                PathBuf::from("../run/systemd/resolve/stub-resolv.conf"),
+                resolv_conf,
+            )?;
+        }
+
+        info!("Restoring the systemd-resolved stub resolver");
+        self.unconfigure()?;
+
+        info!("Removing {}", PACKAGE);
+        self.system.remove_package(PACKAGE)?;
+
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages. The experiment manages services and
+    /// configuration files rather than symlinks.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(Vec::new())
+    }
+
+    /// Stop hickory-dns, remove its configuration, and restart systemd-resolved with its stub
+    /// listener.
+    fn unconfigure(&self) -> Result<()> {
+        if let Err(e) = self.system.disable_service(SERVICE) {
+            warn!("Unable to stop {SERVICE}: {e}");
+        }
+        self.system.remove_file(PathBuf::from(UNIT))?;
+        self.system.remove_file(PathBuf::from(CONFIG))?;
+        self.system.remove_file(PathBuf::from(RESOLVED_DROP_IN))?;
+        self.system.reload_service_manager()?;
+        self.system.restart_service("systemd-resolved.service")
+    }
+
+    /// Read the upstream name servers and search domains from systemd-resolved, ignoring
+    /// loopback addresses, which would forward queries back to the stub resolver. Link-local
+    /// addresses scoped to an interface, e.g. `fe80::1%eth0`, can't be given to hickory-dns, so
+    /// are skipped.
+    fn upstream(&self) -> Result<Upstream> {
+        let contents = self
+            .system
+            .read_file(PathBuf::from(RESOLVED_UPSTREAM))
+            .map_err(|_| {
+                anyhow::anyhow!("Unable to read the upstream name servers from {RESOLVED_UPSTREAM}")
+            })?;
+        let values = |keyword: &str| -> Vec<String> {
+            contents
+                .lines()
+                .filter_map(|l| l.trim().strip_prefix(keyword))
+                .filter(|rest| rest.starts_with(char::is_whitespace))
+                .flat_map(|rest| rest.split_whitespace().map(String::from))
+                .collect()
+        };
+
+        let mut servers = values("nameserver");
+        servers.retain(|s| !s.starts_with("127.") && s != "::1");
+        servers.retain(|s| {
+            let scoped = s.contains('%');
+            if scoped {
+                warn!("Skipping the upstream name server {s}, as hickory-dns can't forward to scoped addresses");
+            }
+            !scoped
+        });
+        anyhow::ensure!(
+            !servers.is_empty(),
+            "No upstream name servers are configured in {RESOLVED_UPSTREAM}"
+        );
+        Ok(Upstream {
+            servers,
+            search: values("search"),
+        })
+    }
+}
+
+/// Build the hickory-dns configuration, forwarding every zone to the `upstream` name servers.
+fn config(upstream: &[String]) -> String {
+    let name_servers: Vec<String> = upstream
+        .iter()
+        .map(|s| {
+            let addr = match s.contains(':') {
+                true => format!("[{s}]:53"),
+                false => format!("{s}:53"),
+            };
+            format!("{{ socket_addr = \"{addr}\", protocol = \"udp\" }}")
+        })
+        .collect();
+    format!(
+        r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+listen_addrs_ipv4 = ["127.0.0.1"]
+listen_port = 53
+
+[[zones]]
+zone = "."
+zone_type = "Forward"
+stores = {{ type = "forward", name_servers = [{}] }}
+"#,
+        name_servers.join(", ")
+    )
+}
+
+/// Build the resolver configuration pointing at hickory-dns, keeping the `search` domains.
+fn resolv_conf_contents(search: &[String]) -> String {
+    let mut contents = String::from(
+        "# Managed by oxidizr. Do not edit, this file is restored by 'oxidizr disable'.\nnameserver 127.0.0.1\n",
+    );
+    if !search.is_empty() {
+        contents.push_str(&format!("search {}\n", search.join(" ")));
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_dns_enable() {
+        let runner = dns_compatible_runner();
+        let dns = DnsExperiment::new(&runner);
+
+        assert!(dns.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y hickory-dns",
+                "systemctl daemon-reload",
+                "systemctl restart systemd-resolved.service",
+                "systemctl enable --now oxidizr-dns.service",
+            ]
+        );
+        let config = runner.read_file(PathBuf::from(CONFIG)).unwrap();
+        assert!(config.contains(
+            r#"name_servers = [{ socket_addr = "192.168.1.1:53", protocol = "udp" }, { socket_addr = "[fd00::1]:53", protocol = "udp" }]"#
+        ));
+        assert_eq!(runner.backed_up_files.clone().into_inner(), &[RESOLV_CONF]);
+        assert!(
+            runner
+                .read_file(PathBuf::from(RESOLV_CONF))
+                .unwrap()
+                .contains("nameserver 127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_dns_enable_search_domains() {
+        let runner = dns_compatible_runner();
+        runner.mock_files(vec![(
+            RESOLVED_UPSTREAM,
+            "nameserver 192.168.1.1\nsearch corp.example lab.example\n",
+            false,
+        )]);
+        let dns = DnsExperiment::new(&runner);
+
+        assert!(dns.enable().is_ok());
+        let resolv_conf = runner.read_file(PathBuf::from(RESOLV_CONF)).unwrap();
+        assert!(resolv_conf.ends_with("nameserver 127.0.0.1\nsearch corp.example lab.example\n"));
+    }
+
+    #[test]
+    fn test_dns_enable_scoped_ipv6() {
+        let runner = dns_compatible_runner();
+        runner.mock_files(vec![(
+            RESOLVED_UPSTREAM,
+            "nameserver fe80::1%eth0\nnameserver 2001:db8::53\n",
+            false,
+        )]);
+        let dns = DnsExperiment::new(&runner);
+
+        assert!(dns.enable().is_ok());
+        let config = runner.read_file(PathBuf::from(CONFIG)).unwrap();
+        assert!(config.contains(
+            r#"name_servers = [{ socket_addr = "[2001:db8::53]:53", protocol = "udp" }]"#
+        ));
+        assert!(!config.contains("fe80"));
+
+        // With only scoped name servers, there's nothing hickory-dns can forward to.
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(
+            RESOLVED_UPSTREAM,
+            "nameserver fe80::1%eth0\n",
+            false,
+        )]);
+        assert!(DnsExperiment::new(&runner).enable().is_err());
+        assert!(runner.commands.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_dns_enable_service_failure() {
+        let runner = dns_compatible_runner();
+        runner.mock_command_failure("systemctl enable --now oxidizr-dns.service");
+        let dns = DnsExperiment::new(&runner);
+
+        assert!(dns.enable().is_err());
+
+        // The stub listener is restored, and resolv.conf is never touched.
+        assert!(!runner.file_exists(PathBuf::from(RESOLVED_DROP_IN)));
+        assert!(runner.backed_up_files.clone().into_inner().is_empty());
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands.last().unwrap(), "apt-get remove -y hickory-dns");
+    }
+
+    #[test]
+    fn test_dns_enable_restart_failure() {
+        let runner = dns_compatible_runner();
+        runner.mock_command_failure("systemctl restart systemd-resolved.service");
+        let dns = DnsExperiment::new(&runner);
+
+        assert!(dns.enable().is_err());
+
+        // hickory-dns is never started, and the stub listener is restored even though
+        // systemd-resolved can't be restarted.
+        assert!(!runner.file_exists(PathBuf::from(RESOLVED_DROP_IN)));
+        assert!(!runner.file_exists(PathBuf::from(CONFIG)));
+        assert!(runner.backed_up_files.clone().into_inner().is_empty());
+        let commands = runner.commands.clone().into_inner();
+        assert!(!commands.contains(&"systemctl enable --now oxidizr-dns.service".to_string()));
+        assert_eq!(commands.last().unwrap(), "apt-get remove -y hickory-dns");
+    }
+
+    #[test]
+    fn test_dns_enable_without_upstream() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(RESOLVED_UPSTREAM, "nameserver 127.0.0.53\n", false)]);
+        let dns = DnsExperiment::new(&runner);
+
+        assert!(dns.enable().is_err());
+        assert!(runner.commands.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_dns_disable() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/etc/.resolv.conf.oxidizr.bak", "", false),
+            (RESOLVED_DROP_IN, RESOLVED_DROP_IN_CONTENTS, false),
+        ]);
+        runner.mock_install_package("hickory-dns");
+        let dns = DnsExperiment::new(&runner);
+
+        assert!(dns.disable().is_ok());
+
+        assert_eq!(runner.restored_files.clone().into_inner(), &[RESOLV_CONF]);
+        assert!(!runner.file_exists(PathBuf::from(RESOLVED_DROP_IN)));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "systemctl disable --now oxidizr-dns.service",
+                "systemctl daemon-reload",
+                "systemctl restart systemd-resolved.service",
+                "apt-get remove -y hickory-dns",
+            ]
+        );
+    }
+
+    fn dns_compatible_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(
+            RESOLVED_UPSTREAM,
+            "# This is systemd-resolved's upstream configuration\nnameserver 192.168.1.1\nnameserver fd00::1\nnameserver 127.0.0.53\n",
+            false,
+        )]);
+        runner.mock_symlinks(vec![(
+            RESOLV_CONF,
+            "../run/systemd/resolve/stub-resolv.conf",
+        )]);
+        runner
+    }
+}
@@ -0,0 +1,535 @@
+use crate::experiments::ManagedLink;
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const PACKAGE: &str = "vector";
+
+/// The syslog daemon replaced by Vector, which is stopped and disabled while the experiment is
+/// enabled. The package is left installed.
+const RSYSLOG_SERVICE: &str = "rsyslog.service";
+
+const RSYSLOG_CONF: &str = "/etc/rsyslog.conf";
+
+const RSYSLOG_DIR: &str = "/etc/rsyslog.d";
+
+/// The rules in Ubuntu's `/etc/rsyslog.d/50-default.conf`, used if no rules writing to
+/// `/var/log` are found in the rsyslog configuration.
+const DEFAULT_RULES: &str = r#"auth,authpriv.*                 /var/log/auth.log
+*.*;auth,authpriv.none          -/var/log/syslog
+kern.*                          -/var/log/kern.log
+mail.*                          -/var/log/mail.log
+mail.err                        /var/log/mail.err
+"#;
+
+/// Configuration for Vector, generated from the rsyslog rules.
+const CONFIG: &str = "/etc/oxidizr/vector.toml";
+
+const SERVICE: &str = "oxidizr-logging.service";
+
+const UNIT: &str = "/etc/systemd/system/oxidizr-logging.service";
+
+const UNIT_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+[Unit]
+Description=Vector, writing the system log files in place of rsyslog
+After=systemd-journald.service
+
+[Service]
+ExecStart=/usr/bin/vector --config /etc/oxidizr/vector.toml
+Restart=on-failure
+
+[Install]
+WantedBy=multi-user.target
+"#;
+
+/// Formats journal entries as lines in rsyslog's default file format, e.g.
+/// `2025-01-31T12:00:00.000000+00:00 host sshd[42]: message`, and extracts the facility and
+/// severity the rules match on.
+const REMAP: &str = r#"
+.facility = to_int(string(.SYSLOG_FACILITY) ?? "1") ?? 1
+.severity = to_int(string(.PRIORITY) ?? "6") ?? 6
+ident = string(.SYSLOG_IDENTIFIER) ?? string(._COMM) ?? "-"
+pid = string(.SYSLOG_PID) ?? string(._PID) ?? ""
+tag = if pid == "" { ident } else { ident + "[" + pid + "]" }
+timestamp = format_timestamp!(.timestamp, format: "%Y-%m-%dT%H:%M:%S%.6f%:z")
+.message = timestamp + " " + (string(.host) ?? "localhost") + " " + tag + ": " + (string(.message) ?? "")
+"#;
+
+const FACILITIES: &[(&str, u8)] = &[
+    ("kern", 0),
+    ("user", 1),
+    ("mail", 2),
+    ("daemon", 3),
+    ("auth", 4),
+    ("security", 4),
+    ("syslog", 5),
+    ("lpr", 6),
+    ("news", 7),
+    ("uucp", 8),
+    ("cron", 9),
+    ("authpriv", 10),
+    ("ftp", 11),
+    ("local0", 16),
+    ("local1", 17),
+    ("local2", 18),
+    ("local3", 19),
+    ("local4", 20),
+    ("local5", 21),
+    ("local6", 22),
+    ("local7", 23),
+];
+
+const SEVERITIES: &[(&str, u8)] = &[
+    ("emerg", 0),
+    ("panic", 0),
+    ("alert", 1),
+    ("crit", 2),
+    ("err", 3),
+    ("error", 3),
+    ("warning", 4),
+    ("warn", 4),
+    ("notice", 5),
+    ("info", 6),
+    ("debug", 7),
+];
+
+/// Which severities of a facility a selector matches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Severities {
+    /// Severities at least as severe as the given one, i.e. with a lower or equal value.
+    AtLeast(u8),
+    Exactly(u8),
+}
+
+/// An experiment to install Vector and use it to write the system log files in place of
+/// rsyslog.
+pub struct LoggingExperiment<'a> {
+    system: &'a dyn Worker,
+}
+
+impl<'a> LoggingExperiment<'a> {
+    /// Create a new LoggingExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self { system }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.supported_releases().contains(
+            &self
+                .system
+                .distribution()
+                .expect("unable to determine distribution information")
+                .release,
+        )
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        vec![
+            "24.04".to_string(),
+            "24.10".to_string(),
+            "25.04".to_string(),
+        ]
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.system.check_installed(PACKAGE).unwrap_or(false)
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        PACKAGE.to_string()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        String::from("logging")
+    }
+
+    /// Enable the experiment by installing Vector, configuring it to write the files in
+    /// `/var/log` written by the rsyslog rules, and running it in place of rsyslog. If Vector
+    /// rejects the configuration or fails to start, rsyslog is left running.
+    pub fn enable(&self) -> Result<()> {
+        anyhow::ensure!(
+            self.system.check_installed("rsyslog")?,
+            "rsyslog is not installed, so there are no log files for Vector to write"
+        );
+        let rules = self.rules()?;
+
+        info!("Installing and configuring {}", PACKAGE);
+        self.system.install_package(PACKAGE)?;
+        self.system
+            .write_file(PathBuf::from(CONFIG), &config(&rules), 0o644)?;
+
+        let validate = Command::build("vector", &["validate", "--no-environment", CONFIG]);
+        if let Err(e) = self.system.run(&validate) {
+            self.system.remove_file(PathBuf::from(CONFIG))?;
+            self.system.remove_package(PACKAGE)?;
+            return Err(e.context("Vector rejected the configuration generated from rsyslog"));
+        }
+
+        info!("Replacing rsyslog with Vector");
+        self.system
+            .write_file(PathBuf::from(UNIT), UNIT_CONTENTS, 0o644)?;
+        self.system.reload_service_manager()?;
+        self.system.disable_service(RSYSLOG_SERVICE)?;
+
+        if let Err(e) = self.system.enable_service(SERVICE) {
+            warn!("Restoring rsyslog");
+            self.unconfigure()?;
+            self.system.remove_package(PACKAGE)?;
+            return Err(e.context("Failed to start Vector"));
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by stopping Vector, removing its configuration, restarting rsyslog
+    /// and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        info!("Restoring rsyslog");
+        self.unconfigure()?;
+
+        info!("Removing {}", PACKAGE);
+        self.system.remove_package(PACKAGE)?;
+
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages. The experiment manages services and
+    /// configuration files rather than symlinks.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(Vec::new())
+    }
+
+    /// Stop Vector, remove its configuration, and start rsyslog again.
+    fn unconfigure(&self) -> Result<()> {
+        if let Err(e) = self.system.disable_service(SERVICE) {
+            warn!("Unable to stop {SERVICE}: {e}");
+        }
+        self.system.remove_file(PathBuf::from(UNIT))?;
+        self.system.remove_file(PathBuf::from(CONFIG))?;
+        self.system.reload_service_manager()?;
+        self.system.enable_service(RSYSLOG_SERVICE)
+    }
+
+    /// Read the rsyslog rules writing to files in `/var/log`, from the main configuration and
+    /// the `.conf` files in `/etc/rsyslog.d`, in the order rsyslog reads them.
+    fn rules(&self) -> Result<Vec<(String, String)>> {
+        let mut files = vec![PathBuf::from(RSYSLOG_CONF)];
+        let mut included = self
+            .system
+            .list_files(PathBuf::from(RSYSLOG_DIR))
+            .unwrap_or_default();
+        included.sort();
+        files.extend(
+            included
+                .into_iter()
+                .filter(|f| f.extension().is_some_and(|e| e == "conf")),
+        );
+
+        let mut rules = Vec::new();
+        for file in files {
+            let Ok(contents) = self.system.read_file(file.clone()) else {
+                continue;
+            };
+            for line in contents.lines() {
+                match parse_rule(line) {
+                    Some(Ok(rule)) => rules.push(rule),
+                    Some(Err(line)) => {
+                        warn!(
+                            "Unable to translate rsyslog rule in {}: {line}",
+                            file.display()
+                        )
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        if rules.is_empty() {
+            info!("No rsyslog rules writing to /var/log were found, using Ubuntu's defaults");
+            rules = DEFAULT_RULES
+                .lines()
+                .filter_map(parse_rule)
+                .collect::<Result<_, _>>()
+                .expect("default rsyslog rules must be valid");
+        }
+        Ok(rules)
+    }
+}
+
+/// Parse a line of rsyslog configuration as a rule writing to a file in `/var/log`, returning
+/// the file and a VRL condition matching the messages written to it. Lines that aren't rules,
+/// such as comments and directives, return `None`, and rules that can't be translated return the
+/// line as an error.
+fn parse_rule(line: &str) -> Option<Result<(String, String), String>> {
+    let line = line.trim();
+    let first = line.chars().next()?;
+    if !(first.is_ascii_lowercase() || first == '*') || line.contains('(') {
+        return None;
+    }
+    let (selectors, action) = line.split_once(char::is_whitespace)?;
+    let path = action.trim().trim_start_matches('-');
+    if !Path::new(path).starts_with("/var/log") || path.contains(char::is_whitespace) {
+        return Some(Err(line.to_string()));
+    }
+    match condition(selectors) {
+        Some(condition) => Some(Ok((path.to_string(), condition))),
+        None => Some(Err(line.to_string())),
+    }
+}
+
+/// Build a VRL condition matching the messages selected by rsyslog selectors such as
+/// `*.*;auth,authpriv.none`. Later selectors override earlier ones for the facilities they
+/// name.
+fn condition(selectors: &str) -> Option<String> {
+    let mut selected: Vec<Option<Severities>> = vec![None; 24];
+    for selector in selectors.split(';') {
+        let (facilities, priority) = selector.split_once('.')?;
+        let severities = match priority {
+            "none" => None,
+            "*" => Some(Severities::AtLeast(7)),
+            p => match p.strip_prefix('=') {
+                Some(p) => Some(Severities::Exactly(severity(p)?)),
+                None => Some(Severities::AtLeast(severity(p)?)),
+            },
+        };
+        for facility in facilities.split(',') {
+            match facility {
+                "*" => selected.iter_mut().for_each(|s| *s = severities),
+                f => {
+                    let (_, code) = FACILITIES.iter().find(|(name, _)| *name == f)?;
+                    selected[*code as usize] = severities;
+                }
+            }
+        }
+    }
+
+    // Facilities selecting the same severities are matched together.
+    let mut groups: Vec<(Severities, Vec<usize>)> = Vec::new();
+    for (code, severities) in selected.into_iter().enumerate() {
+        let Some(severities) = severities else {
+            continue;
+        };
+        match groups.iter_mut().find(|(s, _)| *s == severities) {
+            Some((_, codes)) => codes.push(code),
+            None => groups.push((severities, vec![code])),
+        }
+    }
+    if groups.is_empty() {
+        return None;
+    }
+
+    let conditions: Vec<String> = groups
+        .into_iter()
+        .map(|(severities, codes)| {
+            let codes: Vec<String> = codes.iter().map(|c| c.to_string()).collect();
+            let severity = match severities {
+                Severities::AtLeast(7) => String::new(),
+                Severities::AtLeast(s) => format!(" && .severity <= {s}"),
+                Severities::Exactly(s) => format!(" && .severity == {s}"),
+            };
+            format!("(includes([{}], .facility){severity})", codes.join(", "))
+        })
+        .collect();
+    Some(conditions.join(" || "))
+}
+
+fn severity(name: &str) -> Option<u8> {
+    SEVERITIES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, code)| *code)
+}
+
+/// Build the Vector configuration, reading the journal and writing each file with the messages
+/// matched by the rules writing to it.
+fn config(rules: &[(String, String)]) -> String {
+    let mut files: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (path, condition) in rules {
+        match files.iter_mut().find(|(p, _)| p == path) {
+            Some((_, conditions)) => conditions.push(condition),
+            None => files.push((path, vec![condition])),
+        }
+    }
+
+    let mut routes = String::new();
+    let mut sinks = String::new();
+    for (n, (path, conditions)) in files.iter().enumerate() {
+        let name = format!("file_{}", n + 1);
+        routes.push_str(&format!("{name} = '{}'\n", conditions.join(" || ")));
+        sinks.push_str(&format!(
+            "\n[sinks.{name}]\ntype = \"file\"\ninputs = [\"rules.{name}\"]\npath = \"{path}\"\nencoding.codec = \"text\"\n"
+        ));
+    }
+
+    format!(
+        r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+data_dir = "/var/lib/vector"
+
+[sources.journal]
+type = "journald"
+since_now = true
+
+[transforms.syslog]
+type = "remap"
+inputs = ["journal"]
+source = '''{REMAP}'''
+
+[transforms.rules]
+type = "route"
+inputs = ["syslog"]
+
+[transforms.rules.route]
+{routes}{sinks}"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_logging_rules() {
+        let rules: Vec<(String, String)> = DEFAULT_RULES
+            .lines()
+            .filter_map(parse_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rules[0],
+            (
+                "/var/log/auth.log".to_string(),
+                "(includes([4, 10], .facility))".to_string()
+            )
+        );
+        assert_eq!(
+            rules[1].1,
+            "(includes([0, 1, 2, 3, 5, 6, 7, 8, 9, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23], .facility))"
+        );
+        assert_eq!(
+            rules[4],
+            (
+                "/var/log/mail.err".to_string(),
+                "(includes([2], .facility) && .severity <= 3)".to_string()
+            )
+        );
+
+        assert!(parse_rule("# kern.* /var/log/kern.log").is_none());
+        assert!(parse_rule("module(load=\"imuxsock\")").is_none());
+        assert!(parse_rule("$FileOwner syslog").is_none());
+        assert_eq!(
+            parse_rule("*.emerg :omusrmsg:*"),
+            Some(Err("*.emerg :omusrmsg:*".to_string()))
+        );
+        assert_eq!(
+            parse_rule("cron.=info /var/log/cron.log"),
+            Some(Ok((
+                "/var/log/cron.log".to_string(),
+                "(includes([9], .facility) && .severity == 6)".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_logging_enable() {
+        let runner = logging_compatible_runner();
+        let logging = LoggingExperiment::new(&runner);
+
+        assert!(logging.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y vector",
+                "vector validate --no-environment /etc/oxidizr/vector.toml",
+                "systemctl daemon-reload",
+                "systemctl disable --now rsyslog.service",
+                "systemctl enable --now oxidizr-logging.service",
+            ]
+        );
+        let config = runner.read_file(PathBuf::from(CONFIG)).unwrap();
+        // The rule in the main configuration is read before those in rsyslog.d.
+        assert!(config.contains("file_1 = '(includes([16], .facility))'\n"));
+        assert!(config.contains("file_2 = '(includes([4, 10], .facility))'\n"));
+        assert!(config.contains(
+            "[sinks.file_2]\ntype = \"file\"\ninputs = [\"rules.file_2\"]\npath = \"/var/log/auth.log\"\n"
+        ));
+        assert!(!config.contains("/var/log/ignored.log"));
+    }
+
+    #[test]
+    fn test_logging_enable_rejected_config() {
+        let runner = logging_compatible_runner();
+        runner.mock_command_failure("vector validate --no-environment /etc/oxidizr/vector.toml");
+        let logging = LoggingExperiment::new(&runner);
+
+        assert!(logging.enable().is_err());
+
+        // rsyslog is never stopped.
+        assert!(!runner.file_exists(PathBuf::from(CONFIG)));
+        let commands = runner.commands.clone().into_inner();
+        assert!(!commands.iter().any(|c| c.contains("rsyslog")));
+        assert_eq!(commands.last().unwrap(), "apt-get remove -y vector");
+    }
+
+    #[test]
+    fn test_logging_enable_service_failure() {
+        let runner = logging_compatible_runner();
+        runner.mock_command_failure("systemctl enable --now oxidizr-logging.service");
+        let logging = LoggingExperiment::new(&runner);
+
+        assert!(logging.enable().is_err());
+
+        assert!(!runner.file_exists(PathBuf::from(UNIT)));
+        let commands = runner.commands.clone().into_inner();
+        assert!(commands.contains(&"systemctl enable --now rsyslog.service".to_string()));
+        assert_eq!(commands.last().unwrap(), "apt-get remove -y vector");
+    }
+
+    #[test]
+    fn test_logging_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("vector");
+        runner.mock_files(vec![(UNIT, UNIT_CONTENTS, false)]);
+        let logging = LoggingExperiment::new(&runner);
+
+        assert!(logging.disable().is_ok());
+
+        assert!(!runner.file_exists(PathBuf::from(UNIT)));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "systemctl disable --now oxidizr-logging.service",
+                "systemctl daemon-reload",
+                "systemctl enable --now rsyslog.service",
+                "apt-get remove -y vector",
+            ]
+        );
+    }
+
+    fn logging_compatible_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_install_package("rsyslog");
+        runner.mock_files(vec![
+            (
+                RSYSLOG_CONF,
+                "module(load=\"imuxsock\")\nlocal0.* /var/log/local.log\n",
+                false,
+            ),
+            ("/etc/rsyslog.d/50-default.conf", DEFAULT_RULES, false),
+            (
+                "/etc/rsyslog.d/60-ignored.conf.dpkg-old",
+                "*.* /var/log/ignored.log\n",
+                false,
+            ),
+        ]);
+        runner
+    }
+}
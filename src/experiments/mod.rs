@@ -1,15 +1,123 @@
+mod bat;
+mod brush;
+mod bundles;
+pub mod compat;
+mod delta;
+mod diff_frontend;
+mod dns;
+mod logging;
+mod pager;
+mod pleaser;
+mod rustybox;
 mod sudors;
+mod tldr;
 mod uutils;
-use crate::utils::Worker;
+mod wrapper;
+mod zram;
+use crate::config::Config;
+use crate::state::State;
+use crate::utils::{PackageManager, TUMBLEWEED, Worker};
 use anyhow::Result;
+pub use bat::BatExperiment;
+pub use brush::BrushExperiment;
+pub use bundles::{expand_bundles, is_bundle};
+pub use delta::DeltaExperiment;
+pub use diff_frontend::DiffFrontend;
+pub use dns::DnsExperiment;
+pub use logging::LoggingExperiment;
+pub use pager::PagerExperiment;
+pub use pleaser::PleaserExperiment;
+pub use rustybox::RustyboxExperiment;
 use std::path::PathBuf;
 pub use sudors::SudoRsExperiment;
+pub use tldr::TldrExperiment;
 use tracing::warn;
 pub use uutils::UutilsExperiment;
+pub use wrapper::{Exposure, Shim, WrapperExperiment};
+pub use zram::ZramExperiment;
+
+/// Releases supported by an experiment, by distribution, as distributions share release numbers.
+pub type SupportedReleases = &'static [(&'static str, &'static [&'static str])];
+
+/// Report the releases of the running distribution among `supported`.
+pub(crate) fn distribution_releases(
+    system: &dyn Worker,
+    supported: SupportedReleases,
+) -> Vec<String> {
+    system
+        .distribution()
+        .map(|d| releases_of(supported, &d.id))
+        .unwrap_or_default()
+}
+
+/// Check if the running distribution and release are among `supported`.
+pub(crate) fn release_supported(system: &dyn Worker, supported: SupportedReleases) -> bool {
+    let distribution = system
+        .distribution()
+        .expect("unable to determine distribution information");
+    releases_of(supported, &distribution.id).contains(&distribution.release)
+}
+
+fn releases_of(supported: SupportedReleases, distribution: &str) -> Vec<String> {
+    supported
+        .iter()
+        .find(|(id, _)| *id == distribution)
+        .map(|(_, releases)| releases.iter().map(|r| r.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// A symlink managed by an experiment, replacing or shadowing a system utility.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedLink {
+    /// The file the symlink points to, e.g. `/usr/bin/coreutils`.
+    pub source: PathBuf,
+    /// The path of the symlink itself, e.g. `/usr/bin/date`.
+    pub target: PathBuf,
+}
+
+impl ManagedLink {
+    /// Create a new `ManagedLink` from `target` to `source`.
+    pub fn new(source: PathBuf, target: PathBuf) -> Self {
+        Self { source, target }
+    }
+
+    /// Report whether the symlink is missing, or no longer points at its source.
+    pub fn drifted(&self, system: &dyn Worker) -> bool {
+        system
+            .read_link(self.target.clone())
+            .map(|source| source != self.source)
+            .unwrap_or(true)
+    }
+
+    /// Report whether the target has been replaced since the symlink was created, e.g. by an
+    /// administrator or a package upgrade, such that restoring its backup would overwrite it.
+    pub fn replaced(&self, system: &dyn Worker) -> bool {
+        let exists = system.read_link(self.target.clone()).is_ok()
+            || system.file_exists(self.target.clone());
+        exists && self.drifted(system)
+    }
+
+    /// Report whether the symlink points at a wrapper script which runs the original utility
+    /// from its backup, such that the backup must be kept.
+    pub fn wraps_original(&self) -> bool {
+        self.source.starts_with(wrapper::WRAPPER_DIR)
+    }
+}
 
 pub enum Experiment<'a> {
     Uutils(UutilsExperiment<'a>),
     SudoRs(SudoRsExperiment<'a>),
+    Wrapper(WrapperExperiment<'a>),
+    Delta(DeltaExperiment<'a>),
+    Bat(BatExperiment<'a>),
+    Rustybox(RustyboxExperiment<'a>),
+    Brush(BrushExperiment<'a>),
+    Dns(DnsExperiment<'a>),
+    Pager(PagerExperiment<'a>),
+    Pleaser(PleaserExperiment<'a>),
+    Logging(LoggingExperiment<'a>),
+    Zram(ZramExperiment<'a>),
+    Tldr(TldrExperiment<'a>),
 }
 
 impl Experiment<'_> {
@@ -17,22 +125,115 @@ impl Experiment<'_> {
         match self {
             Experiment::Uutils(uutils) => uutils.name(),
             Experiment::SudoRs(sudors) => sudors.name(),
+            Experiment::Wrapper(wrapper) => wrapper.name(),
+            Experiment::Delta(delta) => delta.name(),
+            Experiment::Bat(bat) => bat.name(),
+            Experiment::Rustybox(rustybox) => rustybox.name(),
+            Experiment::Brush(brush) => brush.name(),
+            Experiment::Dns(dns) => dns.name(),
+            Experiment::Pager(pager) => pager.name(),
+            Experiment::Pleaser(pleaser) => pleaser.name(),
+            Experiment::Logging(logging) => logging.name(),
+            Experiment::Zram(zram) => zram.name(),
+            Experiment::Tldr(tldr) => tldr.name(),
+        }
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        match self {
+            Experiment::Uutils(e) => e.package(),
+            Experiment::SudoRs(e) => e.package(),
+            Experiment::Wrapper(e) => e.package(),
+            Experiment::Delta(e) => e.package(),
+            Experiment::Bat(e) => e.package(),
+            Experiment::Rustybox(e) => e.package(),
+            Experiment::Brush(e) => e.package(),
+            Experiment::Dns(e) => e.package(),
+            Experiment::Pager(e) => e.package(),
+            Experiment::Pleaser(e) => e.package(),
+            Experiment::Logging(e) => e.package(),
+            Experiment::Zram(e) => e.package(),
+            Experiment::Tldr(e) => e.package(),
         }
     }
 
-    pub fn enable(&self, no_compatibility_check: bool) -> Result<()> {
+    /// Enable the experiment, returning `false` if it was skipped because the system is not
+    /// compatible.
+    pub fn enable(&self, no_compatibility_check: bool) -> Result<bool> {
         if !no_compatibility_check && !self.check_compatible() {
-            warn!(
-                "Skipping '{}'. Minimum supported releases are {}.",
-                self.name(),
-                self.supported_releases().join(", ")
-            );
-            return Ok(());
+            self.warn_incompatible();
+            return Ok(false);
         }
         match self {
             Experiment::Uutils(e) => e.enable(),
             Experiment::SudoRs(e) => e.enable(),
+            Experiment::Wrapper(e) => e.enable(),
+            Experiment::Delta(e) => e.enable(),
+            Experiment::Bat(e) => e.enable(),
+            Experiment::Rustybox(e) => e.enable(),
+            Experiment::Brush(e) => e.enable(),
+            Experiment::Dns(e) => e.enable(),
+            Experiment::Pager(e) => e.enable(),
+            Experiment::Pleaser(e) => e.enable(),
+            Experiment::Logging(e) => e.enable(),
+            Experiment::Zram(e) => e.enable(),
+            Experiment::Tldr(e) => e.enable(),
+        }?;
+        Ok(true)
+    }
+
+    /// Enable the experiment for a low-risk subset of its binaries, returning `false` if it was
+    /// skipped because the system is not compatible. Only uutils experiments support canary
+    /// rollouts.
+    pub fn enable_canary(&self, no_compatibility_check: bool) -> Result<bool> {
+        let Experiment::Uutils(e) = self else {
+            anyhow::bail!("Canary rollout is not supported for '{}'", self.name());
+        };
+        if !no_compatibility_check && !self.check_compatible() {
+            self.warn_incompatible();
+            return Ok(false);
         }
+        e.enable_canary()?;
+        Ok(true)
+    }
+
+    /// Warn that the experiment is skipped, as it doesn't support the system's release, or isn't
+    /// available on the distribution at all.
+    fn warn_incompatible(&self) {
+        match self.supported_releases().as_slice() {
+            [] => warn!(
+                "Skipping '{}'. It isn't available on this distribution.",
+                self.name()
+            ),
+            releases => warn!(
+                "Skipping '{}'. Minimum supported releases are {}.",
+                self.name(),
+                releases.join(", ")
+            ),
+        }
+    }
+
+    /// Link the remaining binaries of an experiment enabled as a canary.
+    pub fn promote(&self) -> Result<()> {
+        match self {
+            Experiment::Uutils(e) => e.promote(),
+            _ => anyhow::bail!("Canary rollout is not supported for '{}'", self.name()),
+        }
+    }
+
+    /// List the symlinks linked first when the experiment is enabled as a canary.
+    pub fn canary_links(&self) -> Result<Vec<ManagedLink>> {
+        match self {
+            Experiment::Uutils(e) => e.canary_links(),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Report whether the experiment can be provided by a system extension image, which can
+    /// only overlay `/usr`.
+    pub fn supports_sysext(&self) -> bool {
+        matches!(self, Experiment::Uutils(_) | Experiment::SudoRs(_))
     }
 
     pub fn disable(&self) -> Result<()> {
@@ -43,6 +244,17 @@ impl Experiment<'_> {
         match self {
             Experiment::Uutils(e) => e.disable(),
             Experiment::SudoRs(e) => e.disable(),
+            Experiment::Wrapper(e) => e.disable(),
+            Experiment::Delta(e) => e.disable(),
+            Experiment::Bat(e) => e.disable(),
+            Experiment::Rustybox(e) => e.disable(),
+            Experiment::Brush(e) => e.disable(),
+            Experiment::Dns(e) => e.disable(),
+            Experiment::Pager(e) => e.disable(),
+            Experiment::Pleaser(e) => e.disable(),
+            Experiment::Logging(e) => e.disable(),
+            Experiment::Zram(e) => e.disable(),
+            Experiment::Tldr(e) => e.disable(),
         }
     }
 
@@ -50,6 +262,17 @@ impl Experiment<'_> {
         match self {
             Experiment::Uutils(e) => e.check_compatible(),
             Experiment::SudoRs(e) => e.check_compatible(),
+            Experiment::Wrapper(e) => e.check_compatible(),
+            Experiment::Delta(e) => e.check_compatible(),
+            Experiment::Bat(e) => e.check_compatible(),
+            Experiment::Rustybox(e) => e.check_compatible(),
+            Experiment::Brush(e) => e.check_compatible(),
+            Experiment::Dns(e) => e.check_compatible(),
+            Experiment::Pager(e) => e.check_compatible(),
+            Experiment::Pleaser(e) => e.check_compatible(),
+            Experiment::Logging(e) => e.check_compatible(),
+            Experiment::Zram(e) => e.check_compatible(),
+            Experiment::Tldr(e) => e.check_compatible(),
         }
     }
 
@@ -57,43 +280,441 @@ impl Experiment<'_> {
         match self {
             Experiment::Uutils(e) => e.supported_releases(),
             Experiment::SudoRs(e) => e.supported_releases(),
+            Experiment::Wrapper(e) => e.supported_releases(),
+            Experiment::Delta(e) => e.supported_releases(),
+            Experiment::Bat(e) => e.supported_releases(),
+            Experiment::Rustybox(e) => e.supported_releases(),
+            Experiment::Brush(e) => e.supported_releases(),
+            Experiment::Dns(e) => e.supported_releases(),
+            Experiment::Pager(e) => e.supported_releases(),
+            Experiment::Pleaser(e) => e.supported_releases(),
+            Experiment::Logging(e) => e.supported_releases(),
+            Experiment::Zram(e) => e.supported_releases(),
+            Experiment::Tldr(e) => e.supported_releases(),
+        }
+    }
+
+    /// List the symlinks managed by the experiment.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        match self {
+            Experiment::Uutils(e) => e.managed_links(),
+            Experiment::SudoRs(e) => e.managed_links(),
+            Experiment::Wrapper(e) => e.managed_links(),
+            Experiment::Delta(e) => e.managed_links(),
+            Experiment::Bat(e) => e.managed_links(),
+            Experiment::Rustybox(e) => e.managed_links(),
+            Experiment::Brush(e) => e.managed_links(),
+            Experiment::Dns(e) => e.managed_links(),
+            Experiment::Pager(e) => e.managed_links(),
+            Experiment::Pleaser(e) => e.managed_links(),
+            Experiment::Logging(e) => e.managed_links(),
+            Experiment::Zram(e) => e.managed_links(),
+            Experiment::Tldr(e) => e.managed_links(),
+        }
+    }
+
+    /// Find the symlink the experiment manages for a single utility, e.g. `date`, even if it's
+    /// excluded. Only uutils experiments honour exclusions, so only they can switch single
+    /// utilities.
+    pub fn utility_link(&self, utility: &str) -> Result<Option<ManagedLink>> {
+        match self {
+            Experiment::Uutils(e) => e.utility_link(utility),
+            _ => Ok(None),
+        }
+    }
+
+    /// List the symlinks expected to be in place according to the recorded `state`. Only the
+    /// canary binaries are linked until a canary rollout is promoted.
+    pub fn expected_links(&self, state: &State) -> Result<Vec<ManagedLink>> {
+        match state
+            .experiments
+            .get(&self.name())
+            .and_then(|s| s.canary.as_ref())
+        {
+            Some(_) => self.canary_links(),
+            None => self.managed_links(),
+        }
+    }
+
+    /// Report whether the experiment replaces the tools used to gain privileges, such that a
+    /// failure could lock the operator out of a remote machine.
+    pub fn affects_privilege_escalation(&self) -> bool {
+        matches!(self, Experiment::SudoRs(_))
+    }
+
+    /// Report whether a failure of the experiment is likely to leave the system unusable or
+    /// unreachable, such that enabling it requires typing its name rather than a y/n
+    /// confirmation.
+    pub fn high_risk(&self) -> bool {
+        match self {
+            // util-linux replaces utilities used while booting and by logins.
+            Experiment::Uutils(_) => self.name() == "util-linux",
+            Experiment::SudoRs(_)
+            | Experiment::Rustybox(_)
+            | Experiment::Brush(_)
+            | Experiment::Dns(_) => true,
+            _ => false,
         }
     }
 
+    /// Report whether the experiment must be explicitly selected, rather than being included
+    /// when enabling all experiments.
+    pub fn opt_in(&self) -> bool {
+        matches!(
+            self,
+            Experiment::Wrapper(_)
+                | Experiment::Delta(_)
+                | Experiment::Bat(_)
+                | Experiment::Rustybox(_)
+                | Experiment::Brush(_)
+                | Experiment::Dns(_)
+                | Experiment::Pager(_)
+                | Experiment::Pleaser(_)
+                | Experiment::Logging(_)
+                | Experiment::Zram(_)
+                | Experiment::Tldr(_)
+        )
+    }
+
     pub fn check_installed(&self) -> bool {
         match self {
             Experiment::Uutils(e) => e.check_installed(),
             Experiment::SudoRs(e) => e.check_installed(),
+            Experiment::Wrapper(e) => e.check_installed(),
+            Experiment::Delta(e) => e.check_installed(),
+            Experiment::Bat(e) => e.check_installed(),
+            Experiment::Rustybox(e) => e.check_installed(),
+            Experiment::Brush(e) => e.check_installed(),
+            Experiment::Dns(e) => e.check_installed(),
+            Experiment::Pager(e) => e.check_installed(),
+            Experiment::Pleaser(e) => e.check_installed(),
+            Experiment::Logging(e) => e.check_installed(),
+            Experiment::Zram(e) => e.check_installed(),
+            Experiment::Tldr(e) => e.check_installed(),
         }
     }
 }
 
 pub fn all_experiments<'a>(system: &'a impl Worker) -> Vec<Experiment<'a>> {
-    vec![
-        Experiment::Uutils(UutilsExperiment::<'a>::new(
+    let config = Config::load(system).unwrap_or_default();
+    // dust is exposed as du in the shadow PATH, unless it's configured to be available as dust
+    // only.
+    let dust_shims = match config.dust_only {
+        true => vec![],
+        false => vec![Shim::link("du", "/usr/bin/dust")],
+    };
+    // xh replaces curl and wget, unless it's configured to be installed alongside them.
+    let http_shims = match config.xh_alongside {
+        true => vec![],
+        false => vec![
+            Shim::script("curl", include_str!("wrappers/curl.sh")),
+            Shim::script("wget", include_str!("wrappers/wget.sh")),
+        ],
+    };
+    // gix is on PATH once installed, and shadows git only when configured to.
+    let gitoxide_shims = match config.gix_git {
+        true => vec![Shim::script("git", include_str!("wrappers/git.sh"))],
+        false => vec![],
+    };
+    // Other distributions package uutils coreutils under a different name, installing prefixed
+    // binaries alongside the originals.
+    let coreutils = match system.package_manager() {
+        PackageManager::Apt => UutilsExperiment::<'a>::new(
             "coreutils",
             system,
             "rust-coreutils",
-            &["24.04", "24.10", "25.04"],
+            &[
+                ("Ubuntu", &["24.04", "24.10", "25.04"]),
+                ("Debian", &["12", "13"]),
+            ],
             Some(PathBuf::from("/usr/bin/coreutils")),
             PathBuf::from("/usr/lib/cargo/bin/coreutils"),
+        )
+        .with_ppa(config.ppa.get("coreutils").cloned()),
+        _ => UutilsExperiment::<'a>::new(
+            "coreutils",
+            system,
+            "uutils-coreutils",
+            &[
+                ("Fedora", &["41", "42"]),
+                ("Arch", &["rolling"]),
+                ("openSUSE", &[TUMBLEWEED, "16.0"]),
+                ("Alpine", &["3.21", "3.22"]),
+            ],
+            None,
+            PathBuf::from("/usr/bin"),
+        )
+        .with_prefix("uu-"),
+    };
+
+    // diffutils, findutils and util-linux are only packaged for Ubuntu, so are unavailable, rather
+    // than installed under their Ubuntu package names, elsewhere.
+    vec![
+        Experiment::Uutils(coreutils),
+        Experiment::Uutils(
+            UutilsExperiment::<'a>::new(
+                "diffutils",
+                system,
+                "rust-diffutils",
+                &[("Ubuntu", &["24.10", "25.04"])],
+                Some(PathBuf::from("/usr/lib/cargo/bin/diffutils/diffutils")),
+                PathBuf::from("/usr/lib/cargo/bin/diffutils"),
+            )
+            .with_diff_frontend(config.diff_frontend)
+            .with_ppa(config.ppa.get("diffutils").cloned()),
+        ),
+        Experiment::Uutils(
+            UutilsExperiment::<'a>::new(
+                "findutils",
+                system,
+                "rust-findutils",
+                &[("Ubuntu", &["24.04", "24.10", "25.04"])],
+                None,
+                PathBuf::from("/usr/lib/cargo/bin/findutils"),
+            )
+            .with_ppa(config.ppa.get("findutils").cloned()),
+        ),
+        Experiment::Uutils(
+            UutilsExperiment::<'a>::new(
+                "util-linux",
+                system,
+                "rust-util-linux",
+                &[("Ubuntu", &["25.04"])],
+                None,
+                PathBuf::from("/usr/lib/cargo/bin/util-linux"),
+            )
+            .with_ppa(config.ppa.get("util-linux").cloned()),
+        ),
+        Experiment::SudoRs(SudoRsExperiment::<'a>::new(system)),
+        Experiment::SudoRs(SudoRsExperiment::<'a>::su(system)),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "frawk",
+            system,
+            "frawk",
+            &["24.04", "24.10", "25.04"],
+            Exposure::ShadowPath,
+            vec![Shim::link("awk", "/usr/bin/frawk")],
         )),
-        Experiment::Uutils(UutilsExperiment::<'a>::new(
-            "diffutils",
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "awk",
             system,
-            "rust-diffutils",
-            &["24.10", "25.04"],
-            Some(PathBuf::from("/usr/lib/cargo/bin/diffutils/diffutils")),
-            PathBuf::from("/usr/lib/cargo/bin/diffutils"),
+            "frawk",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![
+                Shim::script("awk", include_str!("wrappers/awk.sh")),
+                Shim::script("mawk", include_str!("wrappers/awk.sh")),
+            ],
         )),
-        Experiment::Uutils(UutilsExperiment::<'a>::new(
-            "findutils",
+        Experiment::Wrapper(
+            WrapperExperiment::<'a>::new(
+                "dust",
+                system,
+                "du-dust",
+                &["24.04", "24.10", "25.04"],
+                Exposure::ShadowPath,
+                dust_shims,
+            )
+            .with_snap("dust"),
+        ),
+        Experiment::Delta(DeltaExperiment::<'a>::new(system)),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "http",
             system,
-            "rust-findutils",
+            "xh",
             &["24.04", "24.10", "25.04"],
-            None,
-            PathBuf::from("/usr/lib/cargo/bin/findutils"),
+            Exposure::Replace,
+            http_shims,
         )),
-        Experiment::SudoRs(SudoRsExperiment::<'a>::new(system)),
+        // wget alone, for systems keeping curl. xh is kept installed while either is enabled.
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "wget",
+            system,
+            "xh",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![Shim::script("wget", include_str!("wrappers/wget.sh"))],
+        )),
+        Experiment::Wrapper(
+            WrapperExperiment::<'a>::new(
+                "grep",
+                system,
+                "ripgrep",
+                &["24.04", "24.10", "25.04"],
+                Exposure::Replace,
+                vec![
+                    Shim::script("grep", include_str!("wrappers/grep.sh")),
+                    Shim::script("egrep", include_str!("wrappers/egrep.sh")),
+                    Shim::script("fgrep", include_str!("wrappers/fgrep.sh")),
+                ],
+            )
+            .with_release("ripgrep"),
+        ),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "sed",
+            system,
+            "sd",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![Shim::script("sed", include_str!("wrappers/sed.sh"))],
+        )),
+        Experiment::Bat(BatExperiment::<'a>::new(system)),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "eza",
+            system,
+            "eza",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![
+                Shim::script("ls", include_str!("wrappers/ls.sh")),
+                Shim::script("dir", include_str!("wrappers/dir.sh")),
+                Shim::script("vdir", include_str!("wrappers/vdir.sh")),
+            ],
+        )),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "hexyl",
+            system,
+            "hexyl",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![
+                Shim::script("hexdump", include_str!("wrappers/hexdump.sh")),
+                Shim::script("xxd", include_str!("wrappers/xxd.sh")),
+            ],
+        )),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "archives",
+            system,
+            "ouch",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![
+                Shim::script("tar", include_str!("wrappers/tar.sh")),
+                Shim::script("unzip", include_str!("wrappers/unzip.sh")),
+            ],
+        )),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "compression",
+            system,
+            "crabz",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![
+                Shim::script("gzip", include_str!("wrappers/gzip.sh")),
+                Shim::script("gunzip", include_str!("wrappers/gunzip.sh")),
+                Shim::script("zcat", include_str!("wrappers/zcat.sh")),
+            ],
+        )),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "fend",
+            system,
+            "fend",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![Shim::script("bc", include_str!("wrappers/bc.sh"))],
+        )),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "parallel",
+            system,
+            "rust-parallel",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![Shim::script(
+                "parallel",
+                include_str!("wrappers/parallel.sh"),
+            )],
+        )),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "file",
+            system,
+            "magika",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![Shim::script("file", include_str!("wrappers/file.sh"))],
+        )),
+        Experiment::Wrapper(WrapperExperiment::<'a>::new(
+            "gitoxide",
+            system,
+            "gitoxide",
+            &["24.04", "24.10", "25.04"],
+            Exposure::ShadowPath,
+            gitoxide_shims,
+        )),
+        Experiment::Rustybox(RustyboxExperiment::<'a>::new(system)),
+        Experiment::Brush(BrushExperiment::<'a>::new(system)),
+        Experiment::Dns(DnsExperiment::<'a>::new(system)),
+        Experiment::Pager(PagerExperiment::<'a>::new(system)),
+        Experiment::Pleaser(PleaserExperiment::<'a>::new(system)),
+        Experiment::Logging(LoggingExperiment::<'a>::new(system)),
+        Experiment::Zram(ZramExperiment::<'a>::new(system)),
+        Experiment::Tldr(TldrExperiment::<'a>::new(system, config.tldr_man)),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Distribution, MockSystem};
+
+    #[test]
+    fn test_high_risk() {
+        let runner = MockSystem::default();
+        let high_risk: Vec<String> = all_experiments(&runner)
+            .iter()
+            .filter(|e| e.high_risk())
+            .map(|e| e.name())
+            .collect();
+        assert_eq!(
+            high_risk,
+            vec!["util-linux", "sudo-rs", "su", "rustybox", "brush", "dns"]
+        );
+    }
+
+    #[test]
+    fn test_uutils_distributions() {
+        let uutils = |id: &str, release: &str| {
+            let runner = MockSystem::new(Distribution {
+                id: id.to_string(),
+                release: release.to_string(),
+            });
+            all_experiments(&runner)
+                .iter()
+                .filter(|e| matches!(e, Experiment::Uutils(_)))
+                .map(|e| (e.name(), e.package(), e.check_compatible()))
+                .collect::<Vec<_>>()
+        };
+        let only_coreutils = |package: &str| {
+            vec![
+                ("coreutils".to_string(), package.to_string(), true),
+                ("diffutils".to_string(), "rust-diffutils".to_string(), false),
+                ("findutils".to_string(), "rust-findutils".to_string(), false),
+                (
+                    "util-linux".to_string(),
+                    "rust-util-linux".to_string(),
+                    false,
+                ),
+            ]
+        };
+
+        assert!(
+            uutils("Ubuntu", "25.04")
+                .iter()
+                .all(|(_, _, compatible)| *compatible)
+        );
+        assert_eq!(uutils("Debian", "13"), only_coreutils("rust-coreutils"));
+        // Only uutils coreutils is packaged elsewhere, under another name.
+        assert_eq!(uutils("Fedora", "42"), only_coreutils("uutils-coreutils"));
+        assert_eq!(
+            uutils("Arch", "rolling"),
+            only_coreutils("uutils-coreutils")
+        );
+        assert_eq!(
+            uutils("openSUSE", TUMBLEWEED),
+            only_coreutils("uutils-coreutils")
+        );
+        assert_eq!(uutils("Alpine", "3.21"), only_coreutils("uutils-coreutils"));
+        // Releases of one distribution aren't taken for those of another.
+        assert!(!uutils("Ubuntu", "13")[0].2);
+        assert!(!uutils("Alpine", "42")[0].2);
+    }
+}
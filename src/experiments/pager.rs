@@ -0,0 +1,191 @@
+use crate::experiments::ManagedLink;
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use tracing::{info, warn};
+
+const PACKAGE: &str = "moar";
+
+/// The binary installed by the package.
+const BINARY: &str = "/usr/bin/moar";
+
+/// The alternative providing `/usr/bin/pager`, also used by tools such as `man` and `git` when
+/// `PAGER` isn't set.
+const ALTERNATIVE: &str = "pager";
+
+const ALTERNATIVE_LINK: &str = "/usr/bin/pager";
+
+/// Priority of moar in the alternatives system. It's lower than that of less, so less becomes
+/// the default again if moar is removed without oxidizr.
+const PRIORITY: &str = "10";
+
+/// An experiment to install moar and make it the system pager, through the alternatives system.
+pub struct PagerExperiment<'a> {
+    system: &'a dyn Worker,
+}
+
+impl<'a> PagerExperiment<'a> {
+    /// Create a new PagerExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self { system }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.supported_releases().contains(
+            &self
+                .system
+                .distribution()
+                .expect("unable to determine distribution information")
+                .release,
+        )
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        vec![
+            "24.04".to_string(),
+            "24.10".to_string(),
+            "25.04".to_string(),
+        ]
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.system.check_installed(PACKAGE).unwrap_or(false)
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        PACKAGE.to_string()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        String::from("pager")
+    }
+
+    /// Enable the experiment by installing the package, registering it as an alternative for
+    /// `pager` and selecting it. If it can't be selected, the package is removed again.
+    pub fn enable(&self) -> Result<()> {
+        info!("Installing and configuring {}", PACKAGE);
+        self.system.install_package(PACKAGE)?;
+
+        info!("Setting moar as the system pager");
+        let install = self.update_alternatives(&[
+            "--install",
+            ALTERNATIVE_LINK,
+            ALTERNATIVE,
+            BINARY,
+            PRIORITY,
+        ]);
+        if let Err(e) =
+            install.and_then(|_| self.update_alternatives(&["--set", ALTERNATIVE, BINARY]))
+        {
+            if self
+                .update_alternatives(&["--remove", ALTERNATIVE, BINARY])
+                .is_err()
+            {
+                warn!("Unable to remove moar from the '{ALTERNATIVE}' alternatives");
+            }
+            self.system.remove_package(PACKAGE)?;
+            return Err(e.context("Failed to set moar as the system pager"));
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by removing moar from the alternatives, selecting less as the
+    /// pager if it's installed, and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        self.update_alternatives(&["--remove", ALTERNATIVE, BINARY])?;
+
+        match self.system.which("less") {
+            Ok(less) => {
+                info!("Restoring less as the system pager");
+                self.update_alternatives(&["--set", ALTERNATIVE, &less.to_string_lossy()])?;
+            }
+            Err(_) => self.update_alternatives(&["--auto", ALTERNATIVE])?,
+        }
+
+        info!("Removing {}", PACKAGE);
+        self.system.remove_package(PACKAGE)?;
+
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages. `/usr/bin/pager` is managed by the alternatives
+    /// system, so none are.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(Vec::new())
+    }
+
+    fn update_alternatives(&self, args: &[&str]) -> Result<()> {
+        let cmd = Command::build("update-alternatives", args);
+        self.system.run(&cmd)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_pager_enable() {
+        let runner = MockSystem::default();
+        let pager = PagerExperiment::new(&runner);
+
+        assert!(pager.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y moar",
+                "update-alternatives --install /usr/bin/pager pager /usr/bin/moar 10",
+                "update-alternatives --set pager /usr/bin/moar",
+            ]
+        );
+        // The alternatives system manages the links, so no files are replaced.
+        assert!(runner.backed_up_files.clone().into_inner().is_empty());
+        assert!(runner.created_symlinks.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_pager_enable_reverts_on_failure() {
+        let runner = MockSystem::default();
+        runner.mock_command_failure("update-alternatives --set pager /usr/bin/moar");
+        let pager = PagerExperiment::new(&runner);
+
+        assert!(pager.enable().is_err());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            &commands[commands.len() - 2..],
+            &[
+                "update-alternatives --remove pager /usr/bin/moar",
+                "apt-get remove -y moar",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pager_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("moar");
+        runner.mock_files(vec![("/usr/bin/less", "", true)]);
+        let pager = PagerExperiment::new(&runner);
+
+        assert!(pager.disable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "update-alternatives --remove pager /usr/bin/moar",
+                "update-alternatives --set pager /usr/bin/less",
+                "apt-get remove -y moar",
+            ]
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use crate::experiments::{Exposure, ManagedLink, Shim, WrapperExperiment};
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const PACKAGE: &str = "pleaser";
+
+/// The policy read by please.
+const POLICY: &str = "/etc/please.ini";
+
+/// First line of a policy generated by oxidizr. Policies without it were written by the
+/// administrator, and are never modified or removed.
+const POLICY_MARKER: &str = "# Generated by oxidizr from the sudoers rules. Removed by 'oxidizr disable' unless this line is deleted.";
+
+const SUDOERS: &str = "/etc/sudoers";
+
+const SUDOERS_DIR: &str = "/etc/sudoers.d";
+
+/// An experiment to install pleaser, expose it as `please` and `doas`, and generate a starter
+/// policy from the sudoers rules. sudo is left in place.
+pub struct PleaserExperiment<'a> {
+    system: &'a dyn Worker,
+    wrapper: WrapperExperiment<'a>,
+}
+
+impl<'a> PleaserExperiment<'a> {
+    /// Create a new PleaserExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self {
+            system,
+            wrapper: WrapperExperiment::new(
+                "pleaser",
+                system,
+                PACKAGE,
+                &["24.04", "24.10", "25.04"],
+                Exposure::Replace,
+                vec![Shim::script("doas", include_str!("wrappers/doas.sh"))],
+            ),
+        }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.wrapper.check_compatible()
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        self.wrapper.supported_releases()
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.wrapper.check_installed()
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        self.wrapper.package()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        self.wrapper.name()
+    }
+
+    /// Enable the experiment by installing the package, exposing `doas` as a wrapper for please
+    /// and, unless a policy already exists, generating one from the sudoers rules. If please
+    /// rejects the generated policy, the experiment is disabled again.
+    pub fn enable(&self) -> Result<()> {
+        self.wrapper.enable()?;
+
+        let policy = PathBuf::from(POLICY);
+        if self.system.file_exists(policy.clone()) {
+            info!("Keeping the existing please policy in {POLICY}");
+            return Ok(());
+        }
+
+        info!("Generating a please policy from the sudoers rules");
+        let (contents, skipped) = policy_from_sudoers(&self.sudoers()?);
+        for rule in &skipped {
+            warn!("Unable to translate sudoers rule for please: {rule}");
+        }
+        self.system.write_file(policy.clone(), &contents, 0o600)?;
+
+        let check = Command::build("please", &["--check", POLICY]);
+        if let Err(e) = self.system.run(&check) {
+            self.system.remove_file(policy)?;
+            self.wrapper.disable()?;
+            return Err(e.context("please rejected the policy generated from the sudoers rules"));
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by removing the generated policy, unless it's been adopted by the
+    /// administrator, restoring `doas` and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        let policy = PathBuf::from(POLICY);
+        if let Ok(contents) = self.system.read_file(policy.clone()) {
+            match contents.lines().next() == Some(POLICY_MARKER) {
+                true => self.system.remove_file(policy)?,
+                false => info!("Keeping {POLICY}, as it's no longer managed by oxidizr"),
+            }
+        }
+
+        self.wrapper.disable()
+    }
+
+    /// List the symlinks the experiment manages.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        self.wrapper.managed_links()
+    }
+
+    /// Read the sudoers rules, including those in the files `sudo` reads from `/etc/sudoers.d`.
+    fn sudoers(&self) -> Result<String> {
+        let mut contents = self.system.read_file(PathBuf::from(SUDOERS))?;
+        let mut files = self
+            .system
+            .list_files(PathBuf::from(SUDOERS_DIR))
+            .unwrap_or_default();
+        files.sort();
+        for file in files.into_iter().filter(|f| included_by_sudo(f)) {
+            contents.push('\n');
+            contents.push_str(&self.system.read_file(file)?);
+        }
+        Ok(contents)
+    }
+}
+
+/// Report whether sudo reads a file in `/etc/sudoers.d`. Files whose names end in `~` or contain
+/// a `.` are skipped, so editor backups and package leftovers aren't used.
+fn included_by_sudo(file: &Path) -> bool {
+    file.file_name()
+        .map(|n| n.to_string_lossy())
+        .is_some_and(|n| !n.ends_with('~') && !n.contains('.'))
+}
+
+/// Translate the sudoers user specifications that can be expressed exactly in please's policy,
+/// returning the policy and the rules that were skipped. Rules must apply to all hosts, and
+/// aliases, wildcards and per-command run-as users are not translated.
+fn policy_from_sudoers(sudoers: &str) -> (String, Vec<String>) {
+    let mut policy = format!("{POLICY_MARKER}\n");
+    let mut skipped = Vec::new();
+
+    for (n, rule) in logical_lines(sudoers)
+        .into_iter()
+        .filter(|l| !is_directive(l))
+        .enumerate()
+    {
+        match translate(&rule) {
+            Some(section) => policy.push_str(&format!("\n[sudoers_{}]\n{section}", n + 1)),
+            None => skipped.push(rule),
+        }
+    }
+
+    if !skipped.is_empty() {
+        policy.push_str("\n# Rules that could not be translated:\n");
+        for rule in &skipped {
+            policy.push_str(&format!("#   {rule}\n"));
+        }
+    }
+    (policy, skipped)
+}
+
+/// Join lines continued with a trailing backslash, and drop comments and blank lines.
+fn logical_lines(sudoers: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in sudoers.lines() {
+        match line.strip_suffix('\\') {
+            Some(continued) => current.push_str(continued),
+            None => {
+                current.push_str(line);
+                let trimmed = current.trim();
+                if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                    lines.push(trimmed.to_string());
+                }
+                current.clear();
+            }
+        }
+    }
+    lines
+}
+
+/// Report whether a line is a sudoers directive, such as Defaults or an alias, rather than a
+/// user specification.
+fn is_directive(line: &str) -> bool {
+    let keyword = line.split_whitespace().next().unwrap_or_default();
+    keyword.starts_with("Defaults") || keyword.starts_with('@') || keyword.ends_with("_Alias")
+}
+
+/// Translate a single user specification, e.g. `%sudo ALL=(ALL:ALL) ALL`, to the body of a
+/// please policy section.
+fn translate(rule: &str) -> Option<String> {
+    let (who, rest) = rule.split_once(char::is_whitespace)?;
+    let (hosts, spec) = rest.split_once('=')?;
+    if hosts.trim() != "ALL" {
+        return None;
+    }
+
+    let name = match who.strip_prefix('%') {
+        Some(group) => format!("name = ^{}$\ngroup = true\n", simple_name(group)?),
+        None => format!("name = ^{}$\n", simple_name(who)?),
+    };
+
+    let mut spec = spec.trim();
+    let mut target = "root".to_string();
+    if let Some(runas) = spec.strip_prefix('(') {
+        let (runas, commands) = runas.split_once(')')?;
+        let user = runas.split(':').next()?.trim();
+        target = match user {
+            "ALL" => ".*".to_string(),
+            "" => "root".to_string(),
+            user => simple_name(user)?.to_string(),
+        };
+        spec = commands.trim();
+    }
+    if target != ".*" {
+        target = format!("^{target}$");
+    }
+
+    let mut require_pass = true;
+    while let Some((tag, commands)) = spec.split_once(':') {
+        if tag.is_empty() || !tag.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+            break;
+        }
+        match tag {
+            "NOPASSWD" => require_pass = false,
+            "PASSWD" => require_pass = true,
+            // Other tags, such as NOEXEC, restrict commands in ways please can't.
+            "SETENV" | "NOSETENV" => {}
+            _ => return None,
+        }
+        spec = commands.trim();
+    }
+
+    let rule = match spec {
+        "ALL" => ".*".to_string(),
+        commands => {
+            let patterns = commands
+                .split(',')
+                .map(|c| command_pattern(c.trim()))
+                .collect::<Option<Vec<_>>>()?;
+            format!("^({})$", patterns.join("|"))
+        }
+    };
+
+    Some(format!(
+        "{name}target = {target}\nrule = {rule}\nrequire_pass = {require_pass}\n"
+    ))
+}
+
+/// Return a user or group name if it can be matched literally, i.e. isn't an alias, uid or
+/// netgroup.
+fn simple_name(name: &str) -> Option<&str> {
+    let simple = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-".contains(c))
+        && !name.starts_with('-');
+    simple.then_some(name)
+}
+
+/// Translate a sudoers command, e.g. `/usr/bin/apt update`, to a regular expression. A command
+/// without arguments may be run with any arguments.
+fn command_pattern(command: &str) -> Option<String> {
+    if !command.starts_with('/') || command.contains(['*', '?', '[', '"', '\\', '(', ')']) {
+        return None;
+    }
+    let escaped = regex_escape(command);
+    match command.contains(char::is_whitespace) {
+        true => Some(escaped),
+        false => Some(format!("{escaped}( .*)?")),
+    }
+}
+
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if r"\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::wrapper::testing::WrapperRun;
+    use crate::utils::MockSystem;
+
+    const SUDOERS_CONTENTS: &str = r#"Defaults	env_reset
+Defaults	secure_path="/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin"
+User_Alias ADMINS = alice, bob
+
+# User privilege specification
+root	ALL=(ALL:ALL) ALL
+%sudo	ALL=(ALL:ALL) ALL
+ADMINS	ALL=(ALL) ALL
+
+@includedir /etc/sudoers.d
+"#;
+
+    #[test]
+    fn test_policy_from_sudoers() {
+        let sudoers = format!(
+            "{SUDOERS_CONTENTS}deploy web1=(ALL) ALL\nbackup ALL=(root) NOPASSWD: /usr/bin/rsync, \\\n    /usr/bin/tar -czf /srv/backup.tgz /srv\n"
+        );
+        let (policy, skipped) = policy_from_sudoers(&sudoers);
+
+        assert!(policy.starts_with(POLICY_MARKER));
+        assert!(
+            policy.contains(
+                "[sudoers_1]\nname = ^root$\ntarget = .*\nrule = .*\nrequire_pass = true\n"
+            )
+        );
+        assert!(policy.contains(
+            "[sudoers_2]\nname = ^sudo$\ngroup = true\ntarget = .*\nrule = .*\nrequire_pass = true\n"
+        ));
+        assert!(policy.contains(
+            "name = ^backup$\ntarget = ^root$\nrule = ^(/usr/bin/rsync( .*)?|/usr/bin/tar -czf /srv/backup\\.tgz /srv)$\nrequire_pass = false\n"
+        ));
+        assert_eq!(
+            skipped,
+            vec!["ADMINS\tALL=(ALL) ALL", "deploy web1=(ALL) ALL"]
+        );
+        assert!(policy.contains("#   deploy web1=(ALL) ALL\n"));
+    }
+
+    #[test]
+    fn test_doas_wrapper() {
+        let doas = WrapperRun::new(include_str!("wrappers/doas.sh"), &["please"]);
+        assert_eq!(doas.run(&["apt", "update"]), "please -- apt update");
+        assert_eq!(
+            doas.run(&["-u", "www-data", "--", "ls", "-l"]),
+            "please --target www-data -- ls -l"
+        );
+        // Options of the command aren't mistaken for doas options.
+        assert_eq!(doas.run(&["ls", "-n"]), "please -- ls -n");
+        assert!(
+            doas.run(&["-u", "root", "-s"])
+                .starts_with("please --target root -- /")
+        );
+
+        // Untranslated options, and a shell given a command, are left to the original.
+        assert_eq!(doas.run(&["-n", "true"]), "original -n true");
+        assert_eq!(doas.run(&["-s", "ls"]), "original -s ls");
+        assert_eq!(doas.run(&["-u"]), "original -u");
+    }
+
+    #[test]
+    fn test_pleaser_enable() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            (SUDOERS, SUDOERS_CONTENTS, false),
+            ("/etc/sudoers.d/README", "", false),
+            (
+                "/etc/sudoers.d/deploy",
+                "deploy ALL=(ALL) NOPASSWD: ALL\n",
+                false,
+            ),
+            ("/etc/sudoers.d/deploy~", "deploy ALL=(ALL) ALL\n", false),
+        ]);
+        let pleaser = PleaserExperiment::new(&runner);
+
+        assert!(pleaser.enable().is_ok());
+
+        let policy = runner.read_file(PathBuf::from(POLICY)).unwrap();
+        assert!(policy.contains("name = ^deploy$\ntarget = .*\nrule = .*\nrequire_pass = false\n"));
+        // Editor backups in /etc/sudoers.d are ignored by sudo.
+        assert_eq!(policy.matches("name = ^deploy$").count(), 1);
+        assert!(runner.created_symlinks.borrow().contains(&(
+            "/usr/lib/oxidizr/wrappers/doas".to_string(),
+            "/usr/bin/doas".to_string()
+        )));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y pleaser",
+                "please --check /etc/please.ini"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pleaser_enable_keeps_existing_policy() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(POLICY, "[admins]\nname = ^alice$\n", false)]);
+        let pleaser = PleaserExperiment::new(&runner);
+
+        assert!(pleaser.enable().is_ok());
+        assert_eq!(
+            runner.read_file(PathBuf::from(POLICY)).unwrap(),
+            "[admins]\nname = ^alice$\n"
+        );
+
+        runner.mock_install_package("pleaser");
+        assert!(pleaser.disable().is_ok());
+        assert!(runner.file_exists(PathBuf::from(POLICY)));
+    }
+
+    #[test]
+    fn test_pleaser_enable_rejected_policy() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(SUDOERS, SUDOERS_CONTENTS, false)]);
+        runner.mock_command_failure("please --check /etc/please.ini");
+        let pleaser = PleaserExperiment::new(&runner);
+
+        assert!(pleaser.enable().is_err());
+        assert!(!runner.file_exists(PathBuf::from(POLICY)));
+    }
+
+    #[test]
+    fn test_pleaser_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("pleaser");
+        runner.mock_files(vec![(POLICY, &format!("{POLICY_MARKER}\n"), false)]);
+        let pleaser = PleaserExperiment::new(&runner);
+
+        assert!(pleaser.disable().is_ok());
+
+        assert!(!runner.file_exists(PathBuf::from(POLICY)));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands, &["apt-get remove -y pleaser"]);
+    }
+}
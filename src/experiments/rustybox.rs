@@ -0,0 +1,211 @@
+use crate::experiments::ManagedLink;
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const PACKAGE: &str = "rustybox";
+
+/// The multi-call binary installed by the package.
+const BINARY: &str = "/usr/bin/rustybox";
+
+/// Directory searched for the busybox copied into the initramfs, while the experiment is
+/// enabled. The system's busybox is never modified.
+const BUSYBOX_DIR: &str = "/usr/lib/oxidizr/rustybox";
+
+/// initramfs-tools configuration pointing the initramfs at rustybox rather than busybox.
+const INITRAMFS_CONF: &str = "/etc/initramfs-tools/conf.d/oxidizr-rustybox";
+
+const INITRAMFS_CONF_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+BUSYBOXDIR=/usr/lib/oxidizr/rustybox
+"#;
+
+/// An experiment to install rustybox and use it in place of busybox in the initramfs.
+pub struct RustyboxExperiment<'a> {
+    system: &'a dyn Worker,
+}
+
+impl<'a> RustyboxExperiment<'a> {
+    /// Create a new RustyboxExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self { system }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.supported_releases().contains(
+            &self
+                .system
+                .distribution()
+                .expect("unable to determine distribution information")
+                .release,
+        )
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        vec![
+            "24.04".to_string(),
+            "24.10".to_string(),
+            "25.04".to_string(),
+        ]
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.system.check_installed(PACKAGE).unwrap_or(false)
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        PACKAGE.to_string()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        String::from("rustybox")
+    }
+
+    /// Enable the experiment by installing the package, configuring initramfs-tools to use it as
+    /// busybox, and regenerating the initramfs. If rustybox can't run a shell, or the initramfs
+    /// can't be regenerated, the configuration is reverted.
+    pub fn enable(&self) -> Result<()> {
+        info!("Installing and configuring {}", PACKAGE);
+        self.system.install_package(PACKAGE)?;
+
+        // The initramfs runs its scripts with the busybox shell.
+        let check = Command::build(BINARY, &["sh", "-c", "exit 0"]);
+        if let Err(e) = self.system.run(&check) {
+            self.system.remove_package(PACKAGE)?;
+            anyhow::bail!("rustybox is unable to run a shell, so can't replace busybox: {e}");
+        }
+
+        for link in self.managed_links()? {
+            self.system.create_symlink(link.source, link.target)?;
+        }
+        self.system.write_file(
+            PathBuf::from(INITRAMFS_CONF),
+            INITRAMFS_CONF_CONTENTS,
+            0o644,
+        )?;
+
+        info!("Regenerating the initramfs with rustybox");
+        if let Err(e) = self.update_initramfs() {
+            warn!("Reverting the initramfs configuration for rustybox");
+            self.unconfigure()?;
+            if self.update_initramfs().is_err() {
+                warn!(
+                    "Unable to regenerate the initramfs, run 'update-initramfs -u' before rebooting"
+                );
+            }
+            self.system.remove_package(PACKAGE)?;
+            return Err(e.context("Failed to regenerate the initramfs with rustybox"));
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by reverting the initramfs configuration, regenerating the
+    /// initramfs with busybox and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        self.unconfigure()?;
+        info!("Regenerating the initramfs with busybox");
+        self.update_initramfs()?;
+
+        info!("Removing {}", PACKAGE);
+        self.system.remove_package(PACKAGE)?;
+
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(vec![ManagedLink::new(
+            PathBuf::from(BINARY),
+            PathBuf::from(BUSYBOX_DIR).join("busybox"),
+        )])
+    }
+
+    /// Remove the initramfs configuration and the symlink it points at.
+    fn unconfigure(&self) -> Result<()> {
+        self.system.remove_file(PathBuf::from(INITRAMFS_CONF))?;
+        for link in self.managed_links()? {
+            self.system.remove_file(link.target)?;
+        }
+        Ok(())
+    }
+
+    fn update_initramfs(&self) -> Result<()> {
+        let cmd = Command::build("update-initramfs", &["-u"]);
+        self.system.run(&cmd)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_rustybox_enable() {
+        let runner = MockSystem::default();
+        let rustybox = RustyboxExperiment::new(&runner);
+
+        assert!(rustybox.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y rustybox",
+                "/usr/bin/rustybox sh -c exit 0",
+                "update-initramfs -u",
+            ]
+        );
+        assert_eq!(
+            runner.created_symlinks.clone().into_inner(),
+            &[(
+                "/usr/bin/rustybox".to_string(),
+                "/usr/lib/oxidizr/rustybox/busybox".to_string()
+            )]
+        );
+        assert_eq!(
+            runner.read_file(PathBuf::from(INITRAMFS_CONF)).unwrap(),
+            INITRAMFS_CONF_CONTENTS
+        );
+        // The system's busybox is left in place.
+        assert!(runner.backed_up_files.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_rustybox_enable_reverts_on_failure() {
+        let runner = MockSystem::default();
+        runner.mock_command_failure("update-initramfs -u");
+        let rustybox = RustyboxExperiment::new(&runner);
+
+        assert!(rustybox.enable().is_err());
+
+        assert!(!runner.file_exists(PathBuf::from(INITRAMFS_CONF)));
+        let removed_files = runner.removed_files.clone().into_inner();
+        assert!(removed_files.contains(&"/usr/lib/oxidizr/rustybox/busybox".to_string()));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands.last().unwrap(), "apt-get remove -y rustybox");
+    }
+
+    #[test]
+    fn test_rustybox_disable() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(INITRAMFS_CONF, INITRAMFS_CONF_CONTENTS, false)]);
+        runner.mock_install_package("rustybox");
+        let rustybox = RustyboxExperiment::new(&runner);
+
+        assert!(rustybox.disable().is_ok());
+
+        assert!(!runner.file_exists(PathBuf::from(INITRAMFS_CONF)));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &["update-initramfs -u", "apt-get remove -y rustybox"]
+        );
+    }
+}
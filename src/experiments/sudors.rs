@@ -1,77 +1,167 @@
-use crate::utils::Worker;
+use crate::experiments::{
+    ManagedLink, SupportedReleases, distribution_releases, release_supported,
+};
+use crate::state::State;
+use crate::utils::{Command, PackageManager, TUMBLEWEED, Worker, has_backup};
 use anyhow::Result;
 use std::path::{Path, PathBuf};
-use tracing::info;
+use tracing::{info, warn};
 
 const PACKAGE: &str = "sudo-rs";
 
-/// An experiment to install and configure sudo-rs as a replacement for sudo.
+/// Releases on which sudo-rs is packaged: Debian from trixie (13), Fedora from 41, Arch,
+/// openSUSE Tumbleweed and Leap 16.0 and Alpine from 3.21.
+const SUPPORTED_RELEASES: SupportedReleases = &[
+    ("Ubuntu", &["24.04", "24.10", "25.04"]),
+    ("Debian", &["13"]),
+    ("Fedora", &["41", "42"]),
+    ("Arch", &["rolling"]),
+    ("openSUSE", &[TUMBLEWEED, "16.0"]),
+    ("Alpine", &["3.21", "3.22"]),
+];
+
+/// Directory containing the binaries from the package.
+const BIN_DIR: &str = "/usr/lib/cargo/bin";
+
+/// Suffix of the binaries from the packages outside Ubuntu and Debian, which install them
+/// alongside the originals in `/usr/bin`, e.g. as `/usr/bin/sudo-rs`.
+const SUFFIX: &str = "-rs";
+
+const SUDOERS: &str = "/etc/sudoers";
+
+/// Locations of the AppArmor profile confining the original sudo. Profiles attach to the path of
+/// the binary, so don't apply to sudo-rs once sudo is replaced.
+const SUDO_PROFILES: &[&str] = &["/etc/apparmor.d/sudo", "/etc/apparmor.d/usr.bin.sudo"];
+
+/// The AppArmor profile for sudo-rs, derived from the profile for the original sudo.
+const SUDORS_PROFILE: &str = "/etc/apparmor.d/oxidizr-sudo-rs";
+
+/// Directory of links to AppArmor profiles that aren't loaded at boot.
+const APPARMOR_DISABLE_DIR: &str = "/etc/apparmor.d/disable";
+
+/// An experiment to install and configure sudo-rs as a replacement for sudo, or for su. The two
+/// are separate experiments sharing the package, so each can be enabled on its own.
 pub struct SudoRsExperiment<'a> {
+    name: &'static str,
     system: &'a dyn Worker,
+    binaries: &'static [&'static str],
 }
 
 impl<'a> SudoRsExperiment<'a> {
-    /// Create a new SudoRsExperiment.
+    /// Create the experiment replacing sudo and visudo.
     pub fn new(system: &'a dyn Worker) -> Self {
-        Self { system }
+        Self {
+            name: "sudo-rs",
+            system,
+            binaries: &["sudo", "visudo"],
+        }
+    }
+
+    /// Create the experiment replacing su.
+    pub fn su(system: &'a dyn Worker) -> Self {
+        Self {
+            name: "su",
+            system,
+            binaries: &["su"],
+        }
     }
 
     /// Check if the system is compatible with the experiment.
     pub fn check_compatible(&self) -> bool {
-        self.supported_releases().contains(
-            &self
-                .system
-                .distribution()
-                .expect("unable to determine distribution information")
-                .release,
-        )
+        release_supported(self.system, SUPPORTED_RELEASES)
     }
 
-    /// Reports the first supported release for the experiment.
+    /// Reports the releases of the running distribution supported by the experiment.
     pub fn supported_releases(&self) -> Vec<String> {
-        vec![
-            "24.04".to_string(),
-            "24.10".to_string(),
-            "25.04".to_string(),
-        ]
+        distribution_releases(self.system, SUPPORTED_RELEASES)
     }
 
     /// Check if the package is installed.
+    ///
+    /// The package is shared, so su is only reported as installed while it's enabled or linked
+    /// to sudo-rs, and sudo unless only su is.
     pub fn check_installed(&self) -> bool {
-        self.system.check_installed(PACKAGE).unwrap_or(false)
+        if !self.system.check_installed(PACKAGE).unwrap_or(false) {
+            return false;
+        }
+        match self.replaces_sudo() {
+            true => self.claimed() || !Self::su(self.system).claimed(),
+            false => self.claimed(),
+        }
+    }
+
+    /// Report whether the experiment is recorded as enabled, or any of its binaries are linked
+    /// to sudo-rs.
+    fn claimed(&self) -> bool {
+        let recorded =
+            State::load(self.system).is_ok_and(|state| state.experiments.contains_key(self.name));
+        recorded
+            || self.managed_links().is_ok_and(|links| {
+                links
+                    .into_iter()
+                    .any(|l| self.system.read_link(l.target).ok() == Some(l.source))
+            })
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        PACKAGE.to_string()
     }
 
     /// Report the name of the experiment.
     pub fn name(&self) -> String {
-        String::from("sudo-rs")
+        self.name.to_string()
     }
 
-    /// Enable the experiment by installing and configuring the package.
+    /// Enable the experiment by installing and configuring the package, and confining sudo-rs
+    /// with an AppArmor profile in place of the one for the original sudo.
     pub fn enable(&self) -> Result<()> {
+        if self.replaces_sudo() {
+            self.check_sudoers()?;
+        }
+
         info!("Installing and configuring {}", PACKAGE);
         self.system.install_package(PACKAGE)?;
 
-        for f in Self::sudors_files() {
-            let filename = f.file_name().unwrap().to_str().unwrap();
-            let existing = match self.system.which(filename) {
-                Ok(path) => path,
-                Err(_) => Path::new("/usr/bin").join(filename),
-            };
-            self.system.replace_file_with_symlink(f, existing)?;
+        for link in self.managed_links()? {
+            self.system
+                .replace_file_with_symlink(link.source, link.target)?;
         }
 
+        if self.replaces_sudo() {
+            self.confine()?;
+        }
         Ok(())
     }
 
-    /// Disable the experiment by removing the package and restoring the original files.
+    /// Disable the experiment by removing the package and restoring the original files and
+    /// AppArmor profile.
     pub fn disable(&self) -> Result<()> {
-        for f in Self::sudors_files() {
-            let filename = f.file_name().unwrap().to_str().unwrap();
-            let existing = match self.system.which(filename) {
-                Ok(path) => path,
-                Err(_) => Path::new("/usr/bin").join(filename),
-            };
-            self.system.restore_file(existing.clone())?;
+        if self.replaces_sudo() {
+            self.unconfine()?;
+        }
+
+        for link in self.managed_links()? {
+            self.system.restore_file(link.target)?;
+        }
+
+        let state = State::load(self.system)?;
+        let other = match self.replaces_sudo() {
+            true => Self::su(self.system),
+            false => Self::new(self.system),
+        };
+        if state.experiments.contains_key(&other.name()) {
+            info!("Keeping {}, as it's used by '{}'", PACKAGE, other.name());
+            return Ok(());
+        }
+
+        // su was replaced by sudo-rs before it was split into its own experiment.
+        for link in other.managed_links()? {
+            if has_backup(self.system, &link.target)
+                && self.system.read_link(link.target.clone()).ok() == Some(link.source)
+            {
+                self.system.restore_file(link.target)?;
+            }
         }
 
         info!("Removing {}", PACKAGE);
@@ -80,16 +170,118 @@ impl<'a> SudoRsExperiment<'a> {
         Ok(())
     }
 
-    /// List of files from the package to replace system equivalents with.
-    fn sudors_files() -> Vec<PathBuf> {
-        vec![
-            PathBuf::from("/usr/lib/cargo/bin/su"),
-            PathBuf::from("/usr/lib/cargo/bin/sudo"),
-            PathBuf::from("/usr/lib/cargo/bin/visudo"),
-        ]
+    /// Report whether the experiment replaces sudo, rather than su.
+    fn replaces_sudo(&self) -> bool {
+        self.binaries.contains(&"sudo")
+    }
+
+    /// List the symlinks the experiment manages.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(self
+            .binaries
+            .iter()
+            .map(|b| {
+                let existing = match self.system.which(b) {
+                    Ok(path) => path,
+                    Err(_) => Path::new("/usr/bin").join(b),
+                };
+                ManagedLink::new(self.binary_path(b), existing)
+            })
+            .collect())
+    }
+
+    /// Check that `/etc/sudoers` is owned by root and is writable by no one else, since sudo-rs
+    /// refuses to read it otherwise, which would leave no way to gain privileges.
+    fn check_sudoers(&self) -> Result<()> {
+        let cmd = Command::build("stat", &["-c", "%u %a", SUDOERS]);
+        let output = String::from_utf8(self.system.run(&cmd)?.stdout)?;
+        let (uid, mode) = output
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| anyhow::anyhow!("Unable to read the ownership of {SUDOERS}"))?;
+        let mode = u32::from_str_radix(mode, 8)?;
+        anyhow::ensure!(
+            uid == "0" && mode & 0o022 == 0,
+            "{SUDOERS} must be owned by root and writable only by root, but has owner {uid} and \
+             mode {mode:o}"
+        );
+        Ok(())
+    }
+
+    /// Find the AppArmor profile for the original sudo, if AppArmor is in use.
+    fn sudo_profile(&self) -> Option<PathBuf> {
+        if self.system.which("apparmor_parser").is_err() {
+            return None;
+        }
+        SUDO_PROFILES
+            .iter()
+            .map(PathBuf::from)
+            .find(|p| self.system.file_exists(p.clone()))
+    }
+
+    /// Load an AppArmor profile for sudo-rs, derived from the profile for the original sudo,
+    /// and disable the original profile.
+    fn confine(&self) -> Result<()> {
+        let Some(profile) = self.sudo_profile() else {
+            return Ok(());
+        };
+
+        info!("Confining sudo-rs with AppArmor, in place of the sudo profile");
+        let contents = self
+            .system
+            .read_file(profile.clone())?
+            .replace("profile sudo ", "profile sudo-rs ")
+            .replace("/usr/bin/sudo", &self.binary_path("sudo").to_string_lossy());
+        self.system.write_file(
+            PathBuf::from(SUDORS_PROFILE),
+            &format!("# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.\n{contents}"),
+            0o644,
+        )?;
+        self.apparmor_parser("-r", Path::new(SUDORS_PROFILE))?;
+
+        self.system
+            .create_symlink(profile.clone(), disabled_link(&profile))?;
+        self.apparmor_parser("-R", &profile)
+    }
+
+    /// Remove the AppArmor profile for sudo-rs, and re-enable the profile for the original sudo.
+    fn unconfine(&self) -> Result<()> {
+        let sudors_profile = PathBuf::from(SUDORS_PROFILE);
+        if self.system.file_exists(sudors_profile.clone()) {
+            info!("Removing the AppArmor profile for sudo-rs");
+            if let Err(e) = self.apparmor_parser("-R", &sudors_profile) {
+                warn!("Unable to unload the AppArmor profile for sudo-rs: {e}");
+            }
+            self.system.remove_file(sudors_profile)?;
+        }
+
+        if let Some(profile) = self.sudo_profile() {
+            self.system.remove_file(disabled_link(&profile))?;
+            self.apparmor_parser("-r", &profile)?;
+        }
+        Ok(())
+    }
+
+    fn apparmor_parser(&self, action: &str, profile: &Path) -> Result<()> {
+        let cmd = Command::build("apparmor_parser", &[action, &profile.to_string_lossy()]);
+        self.system.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Find the binary from the package replacing a system binary, e.g. `sudo`.
+    fn binary_path(&self, binary: &str) -> PathBuf {
+        match self.system.package_manager() {
+            PackageManager::Apt => Path::new(BIN_DIR).join(binary),
+            _ => Path::new("/usr/bin").join(format!("{binary}{SUFFIX}")),
+        }
     }
 }
 
+/// The link disabling an AppArmor profile, e.g. `/etc/apparmor.d/disable/sudo`.
+fn disabled_link(profile: &Path) -> PathBuf {
+    Path::new(APPARMOR_DISABLE_DIR).join(profile.file_name().unwrap_or_default())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,6 +294,97 @@ mod tests {
         assert!(!coreutils.check_compatible());
     }
 
+    #[test]
+    fn test_sudors_debian_compatibility() {
+        let trixie = MockSystem::new(Distribution {
+            id: "Debian".to_string(),
+            release: "13".to_string(),
+        });
+        assert!(sudors_fixture(&trixie).check_compatible());
+
+        let bookworm = MockSystem::new(Distribution {
+            id: "Debian".to_string(),
+            release: "12".to_string(),
+        });
+        assert!(!sudors_fixture(&bookworm).check_compatible());
+
+        // Releases are matched within the distribution, so Fedora 13 isn't mistaken for trixie.
+        let fedora = MockSystem::new(Distribution {
+            id: "Fedora".to_string(),
+            release: "13".to_string(),
+        });
+        assert!(!sudors_fixture(&fedora).check_compatible());
+        assert_eq!(sudors_fixture(&trixie).supported_releases(), &["13"]);
+    }
+
+    #[test]
+    fn test_sudors_fedora() {
+        let runner = MockSystem::new(Distribution {
+            id: "Fedora".to_string(),
+            release: "42".to_string(),
+        });
+        runner.mock_files(vec![
+            ("/usr/bin/sudo", "", true),
+            ("/usr/bin/su", "", true),
+            ("/usr/sbin/visudo", "", true),
+        ]);
+        runner.mock_command("stat -c %u %a /etc/sudoers", "0 440\n");
+        let sudors = sudors_fixture(&runner);
+
+        assert!(sudors.check_compatible());
+        assert!(sudors.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert!(commands.contains(&"dnf install -y sudo-rs".to_string()));
+
+        let created_symlinks = runner.created_symlinks.clone().into_inner();
+        assert!(
+            created_symlinks
+                .contains(&("/usr/bin/sudo-rs".to_string(), "/usr/bin/sudo".to_string()))
+        );
+        assert!(created_symlinks.contains(&(
+            "/usr/bin/visudo-rs".to_string(),
+            "/usr/sbin/visudo".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_sudors_arch() {
+        let runner = MockSystem::new(Distribution {
+            id: "Arch".to_string(),
+            release: "rolling".to_string(),
+        });
+        runner.mock_files(vec![("/usr/bin/su", "", true)]);
+        let sudors = SudoRsExperiment::su(&runner);
+
+        assert!(sudors.check_compatible());
+        assert!(sudors.enable().is_ok());
+
+        assert_eq!(
+            runner.commands.borrow().last().unwrap(),
+            "pacman -S --needed --noconfirm sudo-rs"
+        );
+        assert_eq!(
+            runner.created_symlinks.clone().into_inner(),
+            &[("/usr/bin/su-rs".to_string(), "/usr/bin/su".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_sudors_tumbleweed() {
+        let runner = MockSystem::new(Distribution {
+            id: "openSUSE".to_string(),
+            release: "tumbleweed".to_string(),
+        });
+        let su = SudoRsExperiment::su(&runner);
+
+        assert!(su.check_compatible());
+        assert_eq!(
+            su.managed_links().unwrap()[0].source,
+            PathBuf::from("/usr/bin/su-rs")
+        );
+    }
+
     #[test]
     fn test_sudors_install_success() {
         let runner = sudors_compatible_runner();
@@ -110,22 +393,17 @@ mod tests {
         assert!(sudors.enable().is_ok());
 
         let commands = runner.commands.clone().into_inner();
-        assert_eq!(commands, &["apt-get install -y sudo-rs"]);
+        assert_eq!(
+            commands,
+            &["stat -c %u %a /etc/sudoers", "apt-get install -y sudo-rs"]
+        );
 
         let backed_up_files = runner.backed_up_files.clone().into_inner();
-        let expected = vec![
-            "/usr/bin/sudo".to_string(),
-            "/usr/bin/su".to_string(),
-            "/usr/sbin/visudo".to_string(),
-        ];
+        let expected = vec!["/usr/bin/sudo".to_string(), "/usr/sbin/visudo".to_string()];
         assert!(vecs_eq(backed_up_files, expected));
 
         let created_symlinks = runner.created_symlinks.clone().into_inner();
         let expected = vec![
-            (
-                "/usr/lib/cargo/bin/su".to_string(),
-                "/usr/bin/su".to_string(),
-            ),
             (
                 "/usr/lib/cargo/bin/sudo".to_string(),
                 "/usr/bin/sudo".to_string(),
@@ -156,15 +434,134 @@ mod tests {
         assert!(commands.contains(&"apt-get remove -y sudo-rs".to_string()));
 
         let restored_files = runner.restored_files.clone().into_inner();
-        let expected = vec![
-            "/usr/bin/sudo".to_string(),
-            "/usr/bin/su".to_string(),
-            "/usr/sbin/visudo".to_string(),
-        ];
+        let expected = vec!["/usr/bin/sudo".to_string(), "/usr/sbin/visudo".to_string()];
         assert!(vecs_eq(restored_files, expected));
     }
 
-    fn sudors_fixture(system: &MockSystem) -> SudoRsExperiment {
+    #[test]
+    fn test_su_enable_disable() {
+        let runner = sudors_compatible_runner();
+        let su = SudoRsExperiment::su(&runner);
+
+        su.enable().unwrap();
+        assert_eq!(
+            *runner.created_symlinks.borrow(),
+            vec![(
+                "/usr/lib/cargo/bin/su".to_string(),
+                "/usr/bin/su".to_string()
+            )]
+        );
+        // su doesn't read /etc/sudoers.
+        assert_eq!(
+            *runner.commands.borrow(),
+            vec!["apt-get install -y sudo-rs"]
+        );
+
+        // The package is kept while sudo-rs is enabled.
+        runner.mock_install_package("sudo-rs");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"sudo-rs": {}, "su": {}}}"#,
+            false,
+        )]);
+        su.disable().unwrap();
+        assert_eq!(*runner.restored_files.borrow(), vec!["/usr/bin/su"]);
+        assert!(
+            !runner
+                .commands
+                .borrow()
+                .iter()
+                .any(|c| c.contains("remove"))
+        );
+    }
+
+    #[test]
+    fn test_sudors_restore_previously_linked_su() {
+        let runner = sudors_compatible_runner();
+        runner.mock_install_package("sudo-rs");
+        // su was linked by sudo-rs before it was split into its own experiment.
+        runner.mock_files(vec![
+            ("/usr/bin/.su.oxidizr.bak", "", false),
+            (
+                "/var/lib/oxidizr/state.json",
+                r#"{"experiments": {"sudo-rs": {}}}"#,
+                false,
+            ),
+        ]);
+        runner.mock_symlinks(vec![("/usr/bin/su", "/usr/lib/cargo/bin/su")]);
+
+        let sudors = sudors_fixture(&runner);
+        assert!(sudors.check_installed());
+        assert!(SudoRsExperiment::su(&runner).check_installed());
+        sudors.disable().unwrap();
+
+        assert!(
+            runner
+                .restored_files
+                .borrow()
+                .contains(&"/usr/bin/su".to_string())
+        );
+        assert!(
+            runner
+                .commands
+                .borrow()
+                .contains(&"apt-get remove -y sudo-rs".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sudors_insecure_sudoers() {
+        let runner = sudors_compatible_runner();
+        runner.mock_command("stat -c %u %a /etc/sudoers", "0 664\n");
+        let sudors = sudors_fixture(&runner);
+
+        assert!(sudors.enable().is_err());
+        assert!(
+            !runner
+                .commands
+                .borrow()
+                .iter()
+                .any(|c| c.contains("apt-get"))
+        );
+        assert!(runner.created_symlinks.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_sudors_apparmor() {
+        let runner = sudors_compatible_runner();
+        runner.mock_files(vec![
+            ("/usr/sbin/apparmor_parser", "", true),
+            (
+                "/etc/apparmor.d/sudo",
+                "profile sudo /usr/bin/sudo flags=(unconfined) {\n}\n",
+                false,
+            ),
+        ]);
+        let sudors = sudors_fixture(&runner);
+
+        sudors.enable().unwrap();
+        let profile = runner.read_file(PathBuf::from(SUDORS_PROFILE)).unwrap();
+        assert!(profile.contains("profile sudo-rs /usr/lib/cargo/bin/sudo flags=(unconfined) {"));
+        assert!(runner.created_symlinks.borrow().contains(&(
+            "/etc/apparmor.d/sudo".to_string(),
+            "/etc/apparmor.d/disable/sudo".to_string()
+        )));
+        let commands = runner.commands.borrow().clone();
+        assert!(commands.contains(&format!("apparmor_parser -r {SUDORS_PROFILE}")));
+        assert!(commands.contains(&"apparmor_parser -R /etc/apparmor.d/sudo".to_string()));
+
+        runner.commands.borrow_mut().clear();
+        runner.mock_install_package("sudo-rs");
+        sudors.disable().unwrap();
+        let removed = runner.removed_files.borrow().clone();
+        assert!(removed.contains(&SUDORS_PROFILE.to_string()));
+        assert!(removed.contains(&"/etc/apparmor.d/disable/sudo".to_string()));
+        let commands = runner.commands.borrow().clone();
+        assert!(commands.contains(&format!("apparmor_parser -R {SUDORS_PROFILE}")));
+        assert!(commands.contains(&"apparmor_parser -r /etc/apparmor.d/sudo".to_string()));
+    }
+
+    fn sudors_fixture(system: &MockSystem) -> SudoRsExperiment<'_> {
         SudoRsExperiment::new(system)
     }
 
@@ -178,6 +575,7 @@ mod tests {
             ("/usr/bin/su", "", true),
             ("/usr/sbin/visudo", "", true),
         ]);
+        runner.mock_command("stat -c %u %a /etc/sudoers", "0 440\n");
         runner
     }
 
@@ -0,0 +1,242 @@
+use crate::experiments::{Exposure, ManagedLink, Shim, WrapperExperiment};
+use crate::utils::{Command, Worker};
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const PACKAGE: &str = "tealdeer";
+
+/// Cache of tldr pages shared by every user, so pages are available without each user first
+/// downloading them.
+const CACHE_DIR: &str = "/var/cache/oxidizr/tealdeer";
+
+/// Profile script pointing tealdeer at the shared cache for login shells.
+const PROFILE: &str = "/etc/profile.d/oxidizr-tldr.sh";
+
+const PROFILE_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+export TEALDEER_CACHE_DIR="${TEALDEER_CACHE_DIR:-/var/cache/oxidizr/tealdeer}"
+"#;
+
+/// An experiment to install tealdeer alongside man, with a shared cache of tldr pages, and
+/// optionally show tldr pages from `man` for commands without a manual page.
+pub struct TldrExperiment<'a> {
+    system: &'a dyn Worker,
+    wrapper: WrapperExperiment<'a>,
+}
+
+impl<'a> TldrExperiment<'a> {
+    /// Create a new TldrExperiment. If `man_wrapper` is set, `man` is wrapped in interactive
+    /// login shells to fall through to tldr pages.
+    pub fn new(system: &'a dyn Worker, man_wrapper: bool) -> Self {
+        let shims = match man_wrapper {
+            true => vec![Shim::script("man", include_str!("wrappers/man.sh"))],
+            false => vec![],
+        };
+        Self {
+            system,
+            wrapper: WrapperExperiment::new(
+                "tldr",
+                system,
+                PACKAGE,
+                &["24.04", "24.10", "25.04"],
+                Exposure::ShadowPath,
+                shims,
+            ),
+        }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.wrapper.check_compatible()
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        self.wrapper.supported_releases()
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.wrapper.check_installed()
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        self.wrapper.package()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        self.wrapper.name()
+    }
+
+    /// Enable the experiment by installing the package, pointing tealdeer at the shared cache
+    /// and downloading the pages into it. A failed download isn't fatal, as the cache can be
+    /// populated later with `tldr --update`, and the pages aren't downloaded when offline.
+    pub fn enable(&self) -> Result<()> {
+        self.wrapper.enable()?;
+        self.system
+            .write_file(PathBuf::from(PROFILE), PROFILE_CONTENTS, 0o644)?;
+
+        if self.system.offline() {
+            info!(
+                "Not downloading tldr pages while offline, run 'sudo {}' once online",
+                update_cache().command()
+            );
+            return Ok(());
+        }
+
+        info!("Downloading tldr pages into {CACHE_DIR}");
+        if let Err(e) = self.system.run(&update_cache()) {
+            warn!(
+                "Unable to download tldr pages, run 'sudo {}': {e}",
+                update_cache().command()
+            );
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by removing the shared cache and its configuration, removing
+    /// the `man` wrapper and removing the package.
+    pub fn disable(&self) -> Result<()> {
+        info!("Removing the tldr pages in {CACHE_DIR}");
+        self.system
+            .run(&Command::build("rm", &["-rf", CACHE_DIR]))?;
+        self.system.remove_file(PathBuf::from(PROFILE))?;
+
+        self.wrapper.disable()
+    }
+
+    /// List the symlinks the experiment manages.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        self.wrapper.managed_links()
+    }
+}
+
+/// Command downloading the tldr pages into the shared cache.
+fn update_cache() -> Command {
+    Command::build(
+        "env",
+        &[
+            &format!("TEALDEER_CACHE_DIR={CACHE_DIR}"),
+            "tldr",
+            "--update",
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiments::wrapper::SHADOW_PATH_DIR;
+    use crate::experiments::wrapper::testing::WrapperRun;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_tldr_enable() {
+        let runner = MockSystem::default();
+        let tldr = TldrExperiment::new(&runner, false);
+
+        assert!(tldr.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y tealdeer",
+                "env TEALDEER_CACHE_DIR=/var/cache/oxidizr/tealdeer tldr --update",
+            ]
+        );
+        assert_eq!(
+            runner.read_file(PathBuf::from(PROFILE)).unwrap(),
+            PROFILE_CONTENTS
+        );
+        assert!(!runner.file_exists(PathBuf::from(SHADOW_PATH_DIR).join("man")));
+        assert!(tldr.managed_links().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tldr_enable_man_wrapper() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/bin/man", "", false)]);
+        let tldr = TldrExperiment::new(&runner, true);
+
+        assert!(tldr.enable().is_ok());
+
+        let wrapper = runner
+            .read_file(PathBuf::from(SHADOW_PATH_DIR).join("man"))
+            .unwrap();
+        assert!(wrapper.contains("original=\"/usr/bin/man\""));
+        // man itself is left in place.
+        assert!(runner.backed_up_files.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_man_wrapper() {
+        let man = WrapperRun::new(include_str!("wrappers/man.sh"), &["tldr"]);
+        // Pages with a manual page, and anything but a single page, go to the original.
+        assert_eq!(man.run(&["ls"]), "original ls");
+        assert_eq!(man.run(&["5", "passwd"]), "original 5 passwd");
+        assert_eq!(man.run(&["-k", "passwd"]), "original -k passwd");
+
+        // The original can't find a manual page for anything.
+        let missing = WrapperRun::new(include_str!("wrappers/man.sh"), &["tldr"])
+            .with_original("#!/bin/sh\n[ \"$1\" != -w ] || exit 16\necho \"original $*\"\n");
+        assert_eq!(missing.run(&["eza"]), "tldr --quiet eza");
+        assert_eq!(missing.run(&["-a", "eza"]), "original -a eza");
+    }
+
+    #[test]
+    fn test_tldr_enable_offline() {
+        let runner = MockSystem::default();
+        runner.mock_command_failure(
+            "env TEALDEER_CACHE_DIR=/var/cache/oxidizr/tealdeer tldr --update",
+        );
+        let tldr = TldrExperiment::new(&runner, false);
+
+        assert!(tldr.enable().is_ok());
+        assert!(runner.file_exists(PathBuf::from(PROFILE)));
+    }
+
+    #[test]
+    fn test_tldr_enable_no_network() {
+        let runner = MockSystem::default();
+        runner.mock_offline("/srv/oxidizr-debs");
+        runner.mock_files(vec![(
+            "/srv/oxidizr-debs/tealdeer_1.7.1-1_amd64.deb",
+            "",
+            false,
+        )]);
+        let tldr = TldrExperiment::new(&runner, false);
+
+        assert!(tldr.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert!(!commands.iter().any(|c| c.contains("tldr --update")));
+        assert!(runner.file_exists(PathBuf::from(PROFILE)));
+    }
+
+    #[test]
+    fn test_tldr_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("tealdeer");
+        runner.mock_files(vec![
+            (PROFILE, PROFILE_CONTENTS, false),
+            ("/usr/lib/oxidizr/shadow/man", "", false),
+        ]);
+        let tldr = TldrExperiment::new(&runner, true);
+
+        assert!(tldr.disable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "rm -rf /var/cache/oxidizr/tealdeer",
+                "apt-get remove -y tealdeer",
+            ]
+        );
+        assert!(!runner.file_exists(PathBuf::from(PROFILE)));
+        assert!(!runner.file_exists(PathBuf::from(SHADOW_PATH_DIR).join("man")));
+    }
+}
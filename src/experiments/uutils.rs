@@ -1,16 +1,32 @@
-use crate::utils::Worker;
+use crate::experiments::compat::{HookConflicts, KERNEL_HOOK_DIRECTORIES, scan_scripts};
+use crate::experiments::wrapper::{ORIGINAL_PLACEHOLDER, WRAPPER_DIR};
+use crate::experiments::{
+    DiffFrontend, ManagedLink, SupportedReleases, distribution_releases, release_supported,
+};
+use crate::state::State;
+use crate::utils::{Command, Ppa, Worker, add_ppa, remove_ppa};
 use anyhow::Result;
 use std::path::{Path, PathBuf};
-use tracing::info;
+use tracing::{info, warn};
+
+/// Binaries that are rarely used in boot, packaging or login paths, and so are linked first when
+/// an experiment is rolled out as a canary.
+const CANARY_BINARIES: &[&str] = &[
+    "basename", "cmp", "dirname", "expand", "factor", "fmt", "fold", "nl", "numfmt", "paste",
+    "seq", "shuf", "tac", "tsort", "unexpand", "yes",
+];
 
 /// An experiment to install and configure a Rust-based replacement for a system utility.
 pub struct UutilsExperiment<'a> {
     name: String,
     system: &'a dyn Worker,
     package: String,
-    supported_releases: Vec<String>,
+    supported_releases: SupportedReleases,
     unified_binary: Option<PathBuf>,
     bin_directory: PathBuf,
+    prefix: String,
+    diff_frontend: Option<DiffFrontend>,
+    ppa: Option<Ppa>,
 }
 
 impl<'a> UutilsExperiment<'a> {
@@ -19,7 +35,7 @@ impl<'a> UutilsExperiment<'a> {
         name: &str,
         system: &'a dyn Worker,
         package: &str,
-        supported_releases: &[&str],
+        supported_releases: SupportedReleases,
         unified_binary: Option<PathBuf>,
         bin_directory: PathBuf,
     ) -> Self {
@@ -27,29 +43,43 @@ impl<'a> UutilsExperiment<'a> {
             name: name.to_string(),
             system,
             package: package.to_string(),
-            supported_releases: supported_releases
-                .iter()
-                .map(|&release| release.to_string())
-                .collect(),
+            supported_releases,
             unified_binary,
             bin_directory,
+            prefix: String::new(),
+            diff_frontend: None,
+            ppa: None,
         }
     }
 
+    /// Only consider binaries in the binary directory whose names start with `prefix`, e.g.
+    /// `uu-` for `/usr/bin/uu-date`, where the package installs them alongside the originals.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Point `diff` at a wrapper script running `frontend` on terminals, rather than linking it
+    /// to the replacement directly.
+    pub fn with_diff_frontend(mut self, frontend: Option<DiffFrontend>) -> Self {
+        self.diff_frontend = frontend;
+        self
+    }
+
+    /// Install the package from `ppa`, e.g. for newer builds than the release's archive has.
+    pub fn with_ppa(mut self, ppa: Option<Ppa>) -> Self {
+        self.ppa = ppa;
+        self
+    }
+
     /// Check if the system is compatible with the experiment.
     pub fn check_compatible(&self) -> bool {
-        self.supported_releases().contains(
-            &self
-                .system
-                .distribution()
-                .expect("unable to determine distribution information")
-                .release,
-        )
+        release_supported(self.system, self.supported_releases)
     }
 
-    /// Reports the first supported release for the experiment.
+    /// Reports the releases of the running distribution supported by the experiment.
     pub fn supported_releases(&self) -> Vec<String> {
-        self.supported_releases.clone()
+        distribution_releases(self.system, self.supported_releases)
     }
 
     /// Check if the package is installed.
@@ -57,6 +87,11 @@ impl<'a> UutilsExperiment<'a> {
         self.system.check_installed(&self.package).unwrap_or(false)
     }
 
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        self.package.clone()
+    }
+
     /// Report the name of the experiment.
     pub fn name(&self) -> String {
         self.name.clone()
@@ -65,46 +100,246 @@ impl<'a> UutilsExperiment<'a> {
     /// Enable the experiment by installing and configuring the package.
     pub fn enable(&self) -> Result<()> {
         info!("Installing and configuring {}", self.package);
-        self.system.install_package(&self.package)?;
+        self.install()?;
+        let links = self.managed_links()?;
+        if let Err(e) = self.check_kernel_hooks(&links) {
+            self.system.remove_package(&self.package)?;
+            remove_ppa(self.system, &self.name)?;
+            return Err(e);
+        }
+        self.link(links.clone())?;
+        self.update_locate_db(&links)
+    }
 
-        let files = self.system.list_files(self.bin_directory.clone())?;
+    /// Check the initramfs and kernel hooks for uses of features that the replacements don't
+    /// support, since a failing hook breaks apt when the kernel is next updated.
+    fn check_kernel_hooks(&self, links: &[ManagedLink]) -> Result<()> {
+        let names: Vec<String> = links
+            .iter()
+            .filter_map(|l| l.target.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        let conflicts = scan_scripts(self.system, KERNEL_HOOK_DIRECTORIES, &names);
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        Err(HookConflicts {
+            experiment: self.name.clone(),
+            conflicts,
+        }
+        .into())
+    }
 
-        for f in files {
-            let filename = f.file_name().unwrap().to_str().unwrap();
-            let existing = match self.system.which(filename) {
-                Ok(path) => path,
-                Err(_) => Path::new("/usr/bin").join(filename),
-            };
-
-            if let Some(unified_binary) = &self.unified_binary {
-                self.system
-                    .replace_file_with_symlink(unified_binary.to_path_buf(), existing.clone())?;
-            } else {
-                self.system.replace_file_with_symlink(f, existing)?;
-            }
+    /// Enable the experiment for its canary binaries only, leaving the remaining binaries to be
+    /// linked when the experiment is promoted.
+    pub fn enable_canary(&self) -> Result<()> {
+        let links = self.canary_links()?;
+        anyhow::ensure!(
+            !links.is_empty(),
+            "'{}' has no canary binaries, and must be enabled in full",
+            self.name
+        );
+        info!("Installing and configuring {} (canary)", self.package);
+        self.install()?;
+        self.link(links)
+    }
+
+    /// Install the package, first adding the experiment's PPA if one is configured. The PPA
+    /// isn't needed when installing from local `.deb` files.
+    fn install(&self) -> Result<()> {
+        if let Some(ppa) = &self.ppa
+            && self.system.local_package_dir().is_none()
+        {
+            add_ppa(self.system, &self.name, ppa)?;
         }
+        self.system.install_package(&self.package)
+    }
 
-        Ok(())
+    /// Link the binaries that weren't linked when the experiment was enabled as a canary.
+    pub fn promote(&self) -> Result<()> {
+        let links = self
+            .managed_links()?
+            .into_iter()
+            .filter(|l| l.drifted(self.system))
+            .collect::<Vec<ManagedLink>>();
+        self.check_kernel_hooks(&links)?;
+        self.link(links.clone())?;
+        self.update_locate_db(&links)
     }
 
-    /// Disable the experiment by removing the package and restoring the original files.
+    /// Disable the experiment by removing the package and restoring the original files. If the
+    /// package was installed from a PPA, the PPA is removed too, so the archive's version is
+    /// installed the next time the package is.
     pub fn disable(&self) -> Result<()> {
-        let files = self.system.list_files(self.bin_directory.clone())?;
+        let links = self.managed_links()?;
+        for link in &links {
+            self.system.restore_file(link.target.clone())?;
+        }
+
+        // Rebuild the database in the format the original locate reads.
+        if let Some(link) = updatedb_link(&links)
+            && self.system.file_exists(link.target.clone())
+        {
+            info!("Regenerating the locate database with the original updatedb");
+            if let Err(e) = self.run_updatedb(&link.target) {
+                warn!("Unable to regenerate the locate database, run 'updatedb' manually: {e}");
+            }
+        }
 
-        for f in files {
-            let filename = f.file_name().unwrap().to_str().unwrap();
-            let existing = match self.system.which(filename) {
-                Ok(path) => path,
-                Err(_) => Path::new("/usr/bin").join(filename),
-            };
-            self.system.restore_file(existing)?;
+        if let Some(frontend) = self.diff_frontend {
+            self.remove_diff_frontend(frontend)?;
         }
 
         info!("Removing {}", self.package);
         self.system.remove_package(&self.package)?;
+        remove_ppa(self.system, &self.name)
+    }
+
+    /// Regenerate the locate database with the replacement updatedb, if it was linked, since
+    /// locate implementations don't read each other's database formats.
+    fn update_locate_db(&self, links: &[ManagedLink]) -> Result<()> {
+        let Some(link) = updatedb_link(links) else {
+            return Ok(());
+        };
+        if link.drifted(self.system) {
+            warn!(
+                "{} wasn't replaced, so locate continues to use its existing database",
+                link.target.display()
+            );
+            return Ok(());
+        }
+        info!("Regenerating the locate database");
+        self.run_updatedb(&link.target)
+    }
+
+    fn run_updatedb(&self, updatedb: &Path) -> Result<()> {
+        let cmd = Command::build(&updatedb.to_string_lossy(), &[]);
+        self.system.run(&cmd)?;
+        Ok(())
+    }
 
+    /// Install the frontend package, and write the wrapper script that `diff` is linked to. The
+    /// wrapper runs the replacement `diff` when not writing to a terminal.
+    fn install_diff_frontend(&self, frontend: DiffFrontend) -> Result<()> {
+        info!("Installing {} as the frontend for diff", frontend.package());
+        self.system.install_package(frontend.package())?;
+        let original = self.bin_directory.join(format!("{}diff", self.prefix));
+        let contents = frontend
+            .script()
+            .replace(ORIGINAL_PLACEHOLDER, &original.display().to_string());
+        self.system
+            .write_file(Path::new(WRAPPER_DIR).join("diff"), &contents, 0o755)
+    }
+
+    /// Remove the `diff` wrapper script, and the frontend package unless another enabled
+    /// experiment installed it, e.g. `delta`.
+    fn remove_diff_frontend(&self, frontend: DiffFrontend) -> Result<()> {
+        self.system
+            .remove_file(Path::new(WRAPPER_DIR).join("diff"))?;
+
+        let in_use = State::load(self.system)?
+            .experiments
+            .iter()
+            .any(|(name, recorded)| {
+                *name != self.name
+                    && recorded
+                        .packages
+                        .iter()
+                        .any(|p| p.name == frontend.package())
+            });
+        if !in_use {
+            info!("Removing {}", frontend.package());
+            self.system.remove_package(frontend.package())?;
+        }
         Ok(())
     }
+
+    /// List the symlinks for the low-risk binaries that are linked first in a canary rollout.
+    pub fn canary_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(self
+            .managed_links()?
+            .into_iter()
+            .filter(|l| {
+                l.target
+                    .file_name()
+                    .is_some_and(|n| CANARY_BINARIES.contains(&&*n.to_string_lossy()))
+            })
+            .collect())
+    }
+
+    fn link(&self, links: Vec<ManagedLink>) -> Result<()> {
+        for link in links {
+            if let Some(frontend) = self.diff_frontend
+                && link.source.starts_with(WRAPPER_DIR)
+            {
+                self.install_diff_frontend(frontend)?;
+            }
+            self.system
+                .replace_file_with_symlink(link.source, link.target)?;
+        }
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages, one for each binary shipped by the package.
+    /// The package must be installed for the binaries to be listed.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        let excluded = State::load(self.system)?.excluded;
+
+        Ok(self
+            .binaries()?
+            .into_iter()
+            .filter(|(utility, _)| !excluded.contains(utility))
+            .map(|(utility, f)| self.link_for(&utility, &f))
+            .collect())
+    }
+
+    /// Find the symlink the experiment manages for a single utility, e.g. `date`, whether or not
+    /// the utility is excluded. The package must be installed for the utility to be found.
+    pub fn utility_link(&self, utility: &str) -> Result<Option<ManagedLink>> {
+        Ok(self
+            .binaries()?
+            .into_iter()
+            .find(|(name, _)| name == utility)
+            .map(|(name, f)| self.link_for(&name, &f)))
+    }
+
+    /// List the binaries shipped by the package, with the utility each replaces.
+    fn binaries(&self) -> Result<Vec<(String, PathBuf)>> {
+        let files = self.system.list_files(self.bin_directory.clone())?;
+        Ok(files
+            .into_iter()
+            .filter_map(|f| {
+                let name = f.file_name()?.to_string_lossy().to_string();
+                let utility = name.strip_prefix(&self.prefix)?.to_string();
+                Some((utility, f))
+            })
+            .collect())
+    }
+
+    /// Build the symlink replacing the system's copy of a utility with a binary shipped by the
+    /// package.
+    fn link_for(&self, filename: &str, file: &Path) -> ManagedLink {
+        let existing = match self.system.which(filename) {
+            Ok(path) => path,
+            Err(_) => Path::new("/usr/bin").join(filename),
+        };
+
+        let source = match (&self.unified_binary, self.diff_frontend) {
+            (_, Some(_)) if filename == "diff" => Path::new(WRAPPER_DIR).join(filename),
+            (Some(unified_binary), _) => unified_binary.to_path_buf(),
+            (None, _) => file.to_path_buf(),
+        };
+        ManagedLink::new(source, existing)
+    }
+}
+
+/// Find the link for `updatedb`, which builds the database read by `locate`.
+fn updatedb_link(links: &[ManagedLink]) -> Option<&ManagedLink> {
+    links
+        .iter()
+        .find(|l| l.target.file_name().is_some_and(|n| n == "updatedb"))
 }
 
 #[cfg(test)]
@@ -179,6 +414,63 @@ mod tests {
         assert_eq!(runner.restored_files.clone().into_inner().len(), 0);
     }
 
+    #[test]
+    fn test_uutils_locate_db() {
+        let runner = findutils_compatible_runner();
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/findutils/updatedb", "", false),
+            ("/usr/bin/updatedb", "", true),
+        ]);
+        let findutils = findutils_fixture(&runner);
+
+        assert!(findutils.enable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["apt-get install -y rust-findutils", "/usr/bin/updatedb"]
+        );
+
+        runner.mock_install_package("rust-findutils");
+        assert!(findutils.disable().is_ok());
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands[2..],
+            ["/usr/bin/updatedb", "apt-get remove -y rust-findutils"]
+        );
+    }
+
+    #[test]
+    fn test_uutils_install_from_local_deb() {
+        let runner = coreutils_compatible_runner();
+        runner.mock_local_packages("/srv/debs");
+        runner.mock_files(vec![(
+            "/srv/debs/rust-coreutils_0.0.24-1_amd64.deb",
+            "",
+            false,
+        )]);
+        let coreutils = coreutils_fixture(&runner);
+
+        assert!(coreutils.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "dpkg --no-act -i /srv/debs/rust-coreutils_0.0.24-1_amd64.deb",
+                "dpkg -i /srv/debs/rust-coreutils_0.0.24-1_amd64.deb"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uutils_install_from_local_deb_missing() {
+        let runner = coreutils_compatible_runner();
+        runner.mock_local_packages("/srv/debs");
+        let coreutils = coreutils_fixture(&runner);
+
+        assert!(coreutils.enable().is_err());
+        assert_eq!(runner.commands.clone().into_inner().len(), 0);
+    }
+
     #[test]
     fn test_uutils_restore_installed() {
         let runner = coreutils_compatible_runner();
@@ -199,12 +491,216 @@ mod tests {
         assert!(vecs_eq(restored_files, expected));
     }
 
-    fn coreutils_fixture(system: &MockSystem) -> UutilsExperiment {
+    #[test]
+    fn test_uutils_ppa() {
+        let runner = coreutils_compatible_runner();
+        runner.mock_command(
+            "gpg --show-keys --with-colons /etc/apt/keyrings/oxidizr-coreutils.asc",
+            "pub:-:4096:1:89ABCDEF01234567:1700000000:::-:::scSC::::::23::0:\n\
+             fpr:::::::::0123456789ABCDEF0123456789ABCDEF01234567:\n",
+        );
+        let coreutils = coreutils_fixture(&runner).with_ppa(Some(Ppa {
+            name: "ppa:uutils/rust-coreutils".to_string(),
+            fingerprint: "0123456789ABCDEF0123456789ABCDEF01234567".to_string(),
+        }));
+
+        assert!(coreutils.enable().is_ok());
+        let commands = runner.commands.clone().into_inner();
+        // The release's codename is read, and the key fetched and checked, before the PPA is
+        // added.
+        assert_eq!(
+            commands[4..],
+            ["apt-get update", "apt-get install -y rust-coreutils"]
+        );
+        let sources = PathBuf::from("/etc/apt/sources.list.d/oxidizr-coreutils.sources");
+        assert!(runner.file_exists(sources.clone()));
+
+        runner.commands.borrow_mut().clear();
+        runner.mock_install_package("rust-coreutils");
+        assert!(coreutils.disable().is_ok());
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &["apt-get remove -y rust-coreutils", "apt-get update"]
+        );
+        assert!(!runner.file_exists(sources));
+    }
+
+    #[test]
+    fn test_uutils_kernel_hook_conflict() {
+        let runner = coreutils_compatible_runner();
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/coreutils/cp", "", false),
+            ("/usr/bin/cp", "", true),
+            (
+                "/etc/kernel/postinst.d/selinux",
+                "cp -Z /boot/a /boot/b",
+                false,
+            ),
+        ]);
+        let coreutils = coreutils_fixture(&runner);
+
+        let err = coreutils.enable().unwrap_err();
+        let conflicts = err.downcast_ref::<HookConflicts>().unwrap();
+        assert_eq!(conflicts.commands(), vec!["cp"]);
+        assert!(runner.created_symlinks.borrow().is_empty());
+        assert!(
+            runner
+                .commands
+                .borrow()
+                .contains(&"apt-get remove -y rust-coreutils".to_string())
+        );
+
+        // Once cp is excluded, the remaining utilities are replaced.
+        let mut state = State::default();
+        state.exclude(&["cp".to_string()]);
+        state.save(&runner).unwrap();
+        assert!(coreutils.enable().is_ok());
+        assert_eq!(runner.created_symlinks.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_uutils_canary_and_promote() {
+        let runner = coreutils_compatible_runner();
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/coreutils/seq", "", false),
+            ("/usr/bin/seq", "", true),
+        ]);
+        let coreutils = coreutils_fixture(&runner);
+
+        assert!(coreutils.enable_canary().is_ok());
+        assert_eq!(
+            runner.created_symlinks.borrow().clone(),
+            vec![("/usr/bin/coreutils".to_string(), "/usr/bin/seq".to_string())]
+        );
+
+        assert!(coreutils.promote().is_ok());
+        let created = runner.created_symlinks.borrow().clone();
+        assert_eq!(created.len(), 3);
+        assert!(created.contains(&(
+            "/usr/bin/coreutils".to_string(),
+            "/usr/bin/date".to_string()
+        )));
+        assert!(created.contains(&(
+            "/usr/bin/coreutils".to_string(),
+            "/usr/bin/sort".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_uutils_canary_no_canary_binaries() {
+        let runner = findutils_compatible_runner();
+        let findutils = findutils_fixture(&runner);
+        assert!(findutils.enable_canary().is_err());
+        assert!(runner.commands.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_uutils_prefixed_binaries() {
+        let runner = MockSystem::new(Distribution {
+            id: "Fedora".to_string(),
+            release: "42".to_string(),
+        });
+        runner.mock_files(vec![
+            ("/usr/bin/uu-date", "", false),
+            ("/usr/bin/uu-sort", "", false),
+            ("/usr/bin/date", "", false),
+            ("/usr/bin/sort", "", false),
+        ]);
+        let coreutils = UutilsExperiment::new(
+            "coreutils",
+            &runner,
+            "uutils-coreutils",
+            &[("Fedora", &["42"])],
+            None,
+            PathBuf::from("/usr/bin"),
+        )
+        .with_prefix("uu-");
+
+        assert!(coreutils.enable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["dnf install -y uutils-coreutils"]
+        );
+        assert!(vecs_eq(
+            runner.created_symlinks.clone().into_inner(),
+            vec![
+                ("/usr/bin/uu-date".to_string(), "/usr/bin/date".to_string()),
+                ("/usr/bin/uu-sort".to_string(), "/usr/bin/sort".to_string()),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_uutils_diff_frontend() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/lib/cargo/bin/diffutils/cmp", "", false),
+            ("/usr/lib/cargo/bin/diffutils/diff", "", false),
+            ("/usr/bin/cmp", "", true),
+            ("/usr/bin/diff", "", true),
+        ]);
+        let diffutils = UutilsExperiment::new(
+            "diffutils",
+            &runner,
+            "rust-diffutils",
+            &[("Ubuntu", &["24.04"])],
+            Some(PathBuf::from("/usr/lib/cargo/bin/diffutils/diffutils")),
+            PathBuf::from("/usr/lib/cargo/bin/diffutils"),
+        )
+        .with_diff_frontend(Some(DiffFrontend::Delta));
+
+        assert!(diffutils.enable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &[
+                "apt-get install -y rust-diffutils",
+                "apt-get install -y git-delta"
+            ]
+        );
+        assert!(vecs_eq(
+            runner.created_symlinks.clone().into_inner(),
+            vec![
+                (
+                    "/usr/lib/cargo/bin/diffutils/diffutils".to_string(),
+                    "/usr/bin/cmp".to_string()
+                ),
+                (
+                    "/usr/lib/oxidizr/wrappers/diff".to_string(),
+                    "/usr/bin/diff".to_string()
+                ),
+            ]
+        ));
+        // The wrapper runs the Rust diff when not writing to a terminal.
+        let wrapper = runner
+            .read_file(PathBuf::from("/usr/lib/oxidizr/wrappers/diff"))
+            .unwrap();
+        assert!(wrapper.contains("original=\"/usr/lib/cargo/bin/diffutils/diff\""));
+
+        runner.mock_install_package("rust-diffutils");
+        assert!(diffutils.disable().is_ok());
+        assert!(
+            runner
+                .removed_files
+                .clone()
+                .into_inner()
+                .contains(&"/usr/lib/oxidizr/wrappers/diff".to_string())
+        );
+        assert!(
+            runner
+                .commands
+                .clone()
+                .into_inner()
+                .contains(&"apt-get remove -y git-delta".to_string())
+        );
+    }
+
+    fn coreutils_fixture(system: &MockSystem) -> UutilsExperiment<'_> {
         UutilsExperiment::new(
             "coreutils",
             system,
             "rust-coreutils",
-            &["24.04", "24.10", "25.04"],
+            &[("Ubuntu", &["24.04", "24.10", "25.04"])],
             Some(PathBuf::from("/usr/bin/coreutils")),
             PathBuf::from("/usr/lib/cargo/bin/coreutils"),
         )
@@ -221,12 +717,12 @@ mod tests {
         runner
     }
 
-    fn findutils_fixture(system: &MockSystem) -> UutilsExperiment {
+    fn findutils_fixture(system: &MockSystem) -> UutilsExperiment<'_> {
         UutilsExperiment::new(
             "findutils",
             system,
             "rust-findutils",
-            &["24.04", "24.10", "25.04"],
+            &[("Ubuntu", &["24.04", "24.10", "25.04"])],
             None,
             PathBuf::from("/usr/lib/cargo/bin/findutils"),
         )
@@ -0,0 +1,1403 @@
+use crate::experiments::ManagedLink;
+use crate::experiments::compat::{SCRIPT_DIRECTORIES, known_incompatibilities, scan_scripts};
+use crate::state::State;
+use crate::utils::{
+    RELEASE_PREFIX, SNAP_BIN_DIR, SNAP_PREFIX, Worker, release, release_name, snap_name,
+};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Directory containing commands exposed through the shadow `PATH`.
+pub const SHADOW_PATH_DIR: &str = "/usr/lib/oxidizr/shadow";
+
+/// Directory containing wrapper scripts that replace system utilities.
+pub const WRAPPER_DIR: &str = "/usr/lib/oxidizr/wrappers";
+
+/// Placeholder in wrapper scripts that is substituted with the path of the original
+/// implementation, allowing wrappers to fall back to it for unsupported invocations.
+pub const ORIGINAL_PLACEHOLDER: &str = "@ORIGINAL@";
+
+/// Directories searched for the original implementation of a command, ahead of `PATH`.
+const ORIGINAL_DIRS: [&str; 4] = ["/usr/bin", "/bin", "/usr/sbin", "/sbin"];
+
+/// Directory under which oxidizr installs its shims, which never hold an original.
+const OXIDIZR_DIR: &str = "/usr/lib/oxidizr";
+
+/// Profile snippet that prepends the shadow `PATH` directory for interactive login shells.
+const SHADOW_PATH_PROFILE: &str = "/etc/profile.d/oxidizr-shadow.sh";
+
+/// Contents of the profile snippet. Only login shells source `/etc/profile.d`, so system
+/// scripts, cron jobs and services continue to resolve the original implementations.
+const SHADOW_PATH_PROFILE_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+case ":${PATH}:" in
+  *:/usr/lib/oxidizr/shadow:*) ;;
+  *) PATH="/usr/lib/oxidizr/shadow:${PATH}" ;;
+esac
+"#;
+
+/// How the commands provided by a `WrapperExperiment` are made available on the system.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exposure {
+    /// Commands are placed in a directory that is prepended to `PATH` for interactive login
+    /// shells only. The original implementations are never touched.
+    ShadowPath,
+    /// The original implementations are backed up and replaced with symlinks to the commands.
+    Replace,
+}
+
+/// A command provided by a `WrapperExperiment`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shim {
+    /// Expose `name` as a symlink to an existing binary.
+    Link { name: String, binary: PathBuf },
+    /// Expose `name` as a wrapper script, typically translating arguments for the replacement.
+    Script {
+        name: String,
+        contents: &'static str,
+    },
+}
+
+impl Shim {
+    /// Create a shim exposing `name` as a symlink to `binary`.
+    pub fn link(name: &str, binary: &str) -> Self {
+        Self::Link {
+            name: name.to_string(),
+            binary: PathBuf::from(binary),
+        }
+    }
+
+    /// Create a shim exposing `name` as a wrapper script with the given contents.
+    pub fn script(name: &str, contents: &'static str) -> Self {
+        Self::Script {
+            name: name.to_string(),
+            contents,
+        }
+    }
+
+    /// Report the name of the command provided by the shim.
+    pub fn name(&self) -> &str {
+        match self {
+            Shim::Link { name, .. } => name,
+            Shim::Script { name, .. } => name,
+        }
+    }
+}
+
+/// An experiment to install a Rust-based alternative to a system utility and expose it
+/// alongside, or in place of, the original implementation.
+pub struct WrapperExperiment<'a> {
+    name: String,
+    system: &'a dyn Worker,
+    package: String,
+    supported_releases: Vec<String>,
+    exposure: Exposure,
+    shims: Vec<Shim>,
+    snap: Option<String>,
+    release: Option<String>,
+}
+
+impl<'a> WrapperExperiment<'a> {
+    /// Create a new WrapperExperiment.
+    pub fn new(
+        name: &str,
+        system: &'a dyn Worker,
+        package: &str,
+        supported_releases: &[&str],
+        exposure: Exposure,
+        shims: Vec<Shim>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            system,
+            package: package.to_string(),
+            supported_releases: supported_releases
+                .iter()
+                .map(|&release| release.to_string())
+                .collect(),
+            exposure,
+            shims,
+            snap: None,
+            release: None,
+        }
+    }
+
+    /// Declare `snap` as an alternative source of the replacement, installed from the snap
+    /// store in place of the package when experiments are installed from snaps.
+    pub fn with_snap(mut self, snap: &str) -> Self {
+        self.snap = Some(snap.to_string());
+        self
+    }
+
+    /// Declare `release` as an alternative source of the replacement, downloaded from GitHub in
+    /// place of the package when experiments are installed from releases.
+    pub fn with_release(mut self, release: &str) -> Self {
+        self.release = Some(release.to_string());
+        self
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.supported_releases().contains(
+            &self
+                .system
+                .distribution()
+                .expect("unable to determine distribution information")
+                .release,
+        )
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        self.supported_releases.clone()
+    }
+
+    /// Check if the package is installed. If another enabled experiment installed the same
+    /// package, the experiment is only installed once it's recorded as enabled itself.
+    pub fn check_installed(&self) -> bool {
+        self.system
+            .check_installed(&self.package())
+            .unwrap_or(false)
+            && (self.recorded() || self.shared_with().is_none())
+    }
+
+    /// Report the package installed by the experiment. If the experiment has a snap, it's
+    /// installed in place of the package while experiments are installed from snaps, and is
+    /// reported for as long as it remains installed, e.g. `snap:dust`. Releases are reported in
+    /// the same way while experiments are installed from releases, e.g. `release:ripgrep`.
+    pub fn package(&self) -> String {
+        if let Some(snap) = &self.snap {
+            let snap = format!("{SNAP_PREFIX}{snap}");
+            if self.system.snap_channel().is_some()
+                || self.system.check_installed(&snap).unwrap_or(false)
+            {
+                return snap;
+            }
+        }
+        if let Some(release) = &self.release {
+            let release = format!("{RELEASE_PREFIX}{release}");
+            if self.system.use_releases() || self.system.check_installed(&release).unwrap_or(false)
+            {
+                return release;
+            }
+        }
+        self.package.clone()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Enable the experiment by installing the package and exposing its commands.
+    pub fn enable(&self) -> Result<()> {
+        if self.exposure == Exposure::Replace {
+            for shim in &self.shims {
+                if let Some(other) = self.replaced_by(&self.existing(shim.name())) {
+                    anyhow::bail!(
+                        "'{}' can't replace {}, as it's already replaced by '{other}'",
+                        self.name,
+                        shim.name()
+                    );
+                }
+            }
+            // Wrapper scripts fall back to the original, so it must remain uncompressed.
+            if let Some(shim) = self.shims.iter().find(|s| matches!(s, Shim::Script { .. })) {
+                anyhow::ensure!(
+                    !self.system.compress_backups(),
+                    "'{}' wraps the original {}, so its backup cannot be compressed",
+                    self.name,
+                    shim.name()
+                );
+            }
+            self.preflight();
+        }
+
+        let package = self.package();
+        info!("Installing and configuring {package}");
+        self.system.install_package(&package)?;
+
+        for shim in &self.shims {
+            self.expose(shim)?;
+
+            for i in known_incompatibilities(shim.name()) {
+                warn!(
+                    "'{}' incompatibility ({}): {}",
+                    i.command, i.feature, i.note
+                );
+            }
+        }
+
+        if self.exposure == Exposure::ShadowPath && !self.shims.is_empty() {
+            self.system.write_file(
+                PathBuf::from(SHADOW_PATH_PROFILE),
+                SHADOW_PATH_PROFILE_CONTENTS,
+                0o644,
+            )?;
+            info!(
+                "Commands from '{}' are available in new login shells via {}",
+                self.name, SHADOW_PATH_DIR
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Disable the experiment by removing the exposed commands and the package.
+    pub fn disable(&self) -> Result<()> {
+        for shim in &self.shims {
+            self.unexpose(shim)?;
+        }
+
+        // Only remove the profile snippet once no other experiment has commands in the shadow
+        // directory.
+        if self.exposure == Exposure::ShadowPath
+            && self
+                .system
+                .list_files(PathBuf::from(SHADOW_PATH_DIR))
+                .map(|files| files.is_empty())
+                .unwrap_or(true)
+        {
+            self.system
+                .remove_file(PathBuf::from(SHADOW_PATH_PROFILE))?;
+        }
+
+        let package = self.package();
+        if let Some(other) = self.shared_with() {
+            info!("Keeping {package}, as it's used by '{other}'");
+            return Ok(());
+        }
+
+        info!("Removing {package}");
+        self.system.remove_package(&package)?;
+
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages. Wrapper scripts placed directly in the shadow
+    /// `PATH` directory are not symlinks, and are not included.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(self
+            .shims
+            .iter()
+            .filter_map(|shim| match (&self.exposure, shim) {
+                (Exposure::ShadowPath, Shim::Link { name, binary }) => Some(ManagedLink::new(
+                    self.binary(binary),
+                    Path::new(SHADOW_PATH_DIR).join(name),
+                )),
+                (Exposure::ShadowPath, Shim::Script { .. }) => None,
+                (Exposure::Replace, Shim::Link { name, binary }) => {
+                    Some(ManagedLink::new(self.binary(binary), self.existing(name)))
+                }
+                (Exposure::Replace, Shim::Script { name, .. }) => Some(ManagedLink::new(
+                    Path::new(WRAPPER_DIR).join(name),
+                    self.existing(name),
+                )),
+            })
+            .collect())
+    }
+
+    /// Report whether the experiment is recorded as enabled.
+    fn recorded(&self) -> bool {
+        State::load(self.system).is_ok_and(|state| state.experiments.contains_key(&self.name))
+    }
+
+    /// Find another experiment recorded as enabled that installed the same package, such as
+    /// `frawk` and `awk`.
+    fn shared_with(&self) -> Option<String> {
+        let package = self.package();
+        State::load(self.system)
+            .ok()?
+            .experiments
+            .into_iter()
+            .find(|(name, recorded)| {
+                *name != self.name && recorded.packages.iter().any(|p| p.name == package)
+            })
+            .map(|(name, _)| name)
+    }
+
+    /// Find another experiment recorded as enabled that replaced `file`, such as `http` and
+    /// `wget` for `/usr/bin/wget`.
+    fn replaced_by(&self, file: &Path) -> Option<String> {
+        State::load(self.system)
+            .ok()?
+            .experiments
+            .into_iter()
+            .find(|(name, recorded)| *name != self.name && recorded.links.contains(file))
+            .map(|(name, _)| name)
+    }
+
+    /// Warn about scripts on the system that use features of the replaced commands which the
+    /// replacement does not support.
+    fn preflight(&self) {
+        let commands: Vec<&str> = self.shims.iter().map(|s| s.name()).collect();
+        for (file, i) in scan_scripts(self.system, SCRIPT_DIRECTORIES, &commands) {
+            warn!(
+                "{} uses '{} {}': {}",
+                file.display(),
+                i.command,
+                i.feature,
+                i.note
+            );
+        }
+    }
+
+    /// Make the command provided by a shim available according to the experiment's exposure.
+    fn expose(&self, shim: &Shim) -> Result<()> {
+        let existing = self.existing(shim.name());
+
+        let (target, original) = match self.exposure {
+            Exposure::ShadowPath => (Path::new(SHADOW_PATH_DIR).join(shim.name()), existing),
+            Exposure::Replace => (existing.clone(), self.system.backup_location(&existing)),
+        };
+
+        let source = match shim {
+            Shim::Link { binary, .. } => self.binary(binary),
+            Shim::Script { contents, .. } => {
+                let script = match self.exposure {
+                    Exposure::ShadowPath => target.clone(),
+                    Exposure::Replace => Path::new(WRAPPER_DIR).join(shim.name()),
+                };
+                let contents =
+                    contents.replace(ORIGINAL_PLACEHOLDER, &original.display().to_string());
+                self.system.write_file(script.clone(), &contents, 0o755)?;
+                script
+            }
+        };
+
+        match self.exposure {
+            Exposure::ShadowPath if matches!(shim, Shim::Script { .. }) => Ok(()),
+            Exposure::ShadowPath => self.system.create_symlink(source, target),
+            Exposure::Replace => self.system.replace_file_with_symlink(source, target),
+        }
+    }
+
+    /// Remove the command provided by a shim, restoring the original where it was replaced.
+    fn unexpose(&self, shim: &Shim) -> Result<()> {
+        match self.exposure {
+            Exposure::ShadowPath => self
+                .system
+                .remove_file(Path::new(SHADOW_PATH_DIR).join(shim.name())),
+            Exposure::Replace => {
+                self.system.restore_file(self.existing(shim.name()))?;
+                if let Shim::Script { name, .. } = shim {
+                    self.system.remove_file(Path::new(WRAPPER_DIR).join(name))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Find the path of a binary provided by the package, which snapd exposes in its own
+    /// directory when the snap is installed instead, as do releases.
+    fn binary(&self, binary: &Path) -> PathBuf {
+        let package = self.package();
+        let Some(name) = binary.file_name() else {
+            return binary.to_path_buf();
+        };
+        if snap_name(&package).is_some() {
+            return Path::new(SNAP_BIN_DIR).join(name);
+        }
+        match release_name(&package).map(release) {
+            Some(Ok(release)) => release.dir().join(name),
+            _ => binary.to_path_buf(),
+        }
+    }
+
+    /// Find the path of the existing implementation of a command. The system directories are
+    /// searched first, as `PATH` may lead to the shims of another enabled experiment, such as
+    /// `frawk` in the shadow `PATH`, which must never be taken for the original.
+    fn existing(&self, command: &str) -> PathBuf {
+        let installed = ORIGINAL_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(command))
+            .find(|path| {
+                self.system.file_exists(path.clone()) || self.system.read_link(path.clone()).is_ok()
+            });
+        if let Some(path) = installed {
+            return path;
+        }
+        match self.system.which(command) {
+            Ok(path) if !path.starts_with(OXIDIZR_DIR) => path,
+            _ => Path::new("/usr/bin").join(command),
+        }
+    }
+}
+
+/// Helpers running the bundled wrapper scripts, to check how they translate their arguments.
+#[cfg(test)]
+pub mod testing {
+    use super::{ORIGINAL_PLACEHOLDER, WRAPPER_DIR};
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    /// A wrapper script installed in a scratch directory, with stubs of its replacement and of
+    /// the original. The stubs print their name and the arguments they were run with, e.g.
+    /// `xh --follow https://example.com` or `original -r https://example.com`, followed by
+    /// anything piped to them.
+    pub struct WrapperRun {
+        dir: PathBuf,
+        contents: String,
+        wrappers: Vec<(String, String)>,
+    }
+
+    impl WrapperRun {
+        pub fn new(contents: &str, replacements: &[&str]) -> Self {
+            let id = RUNS.fetch_add(1, Ordering::Relaxed);
+            let dir =
+                std::env::temp_dir().join(format!("oxidizr-wrapper-{}-{id}", std::process::id()));
+            std::fs::create_dir_all(dir.join("bin")).unwrap();
+            std::fs::create_dir_all(dir.join("wrappers")).unwrap();
+            let run = Self {
+                dir,
+                contents: contents.to_string(),
+                wrappers: Vec::new(),
+            };
+            for name in replacements {
+                run.stub(&run.dir.join("bin").join(name), name);
+            }
+            run.stub(&run.dir.join("original"), "original");
+            run
+        }
+
+        /// Install another wrapper script that the script runs, e.g. `ls` for `dir`.
+        pub fn with_wrapper(mut self, name: &str, contents: &str) -> Self {
+            self.wrappers.push((name.to_string(), contents.to_string()));
+            self
+        }
+
+        /// Install a command that prints `output`, in place of the system's, e.g. `id` to run the
+        /// script as a particular user.
+        pub fn with_command(self, name: &str, output: &str) -> Self {
+            let path = self.dir.join("bin").join(name);
+            self.write(&path, &format!("#!/bin/sh\necho '{output}'\n"));
+            self
+        }
+
+        /// Replace the stub original with a script, e.g. one failing for some arguments.
+        pub fn with_original(self, contents: &str) -> Self {
+            self.write(&self.dir.join("original"), contents);
+            self
+        }
+
+        /// Create a file in the directory the script is run in.
+        pub fn with_file(self, name: &str, contents: &str) -> Self {
+            std::fs::write(self.dir.join(name), contents).unwrap();
+            self
+        }
+
+        /// Run the script with `args`, with its output piped, returning what the stubs printed.
+        pub fn run(&self, args: &[&str]) -> String {
+            self.run_script(args, false)
+        }
+
+        /// Run the script with `args` as if standard input and output were a terminal.
+        pub fn run_terminal(&self, args: &[&str]) -> String {
+            self.run_script(args, true)
+        }
+
+        fn run_script(&self, args: &[&str], terminal: bool) -> String {
+            let script = self.dir.join("script");
+            self.write(&script, &self.prepare(&self.contents, terminal));
+            for (name, contents) in &self.wrappers {
+                let wrapper = self.dir.join("wrappers").join(name);
+                self.write(&wrapper, &self.prepare(contents, terminal));
+            }
+            let path = format!(
+                "{}:{}",
+                self.dir.join("bin").display(),
+                std::env::var("PATH").unwrap_or_default()
+            );
+            let output = Command::new("sh")
+                .arg(&script)
+                .args(args)
+                .current_dir(&self.dir)
+                .env("PATH", path)
+                .stdin(Stdio::null())
+                .output()
+                .unwrap();
+            String::from_utf8(output.stdout)
+                .unwrap()
+                .trim_end()
+                .to_string()
+        }
+
+        /// Point the script at the stub original and the other wrappers. A terminal is
+        /// simulated by making every `-t` test succeed.
+        fn prepare(&self, contents: &str, terminal: bool) -> String {
+            let contents = contents
+                .replace(
+                    ORIGINAL_PLACEHOLDER,
+                    &self.dir.join("original").display().to_string(),
+                )
+                .replace(
+                    WRAPPER_DIR,
+                    &self.dir.join("wrappers").display().to_string(),
+                );
+            match terminal {
+                true => contents.replace("-t 0", "1").replace("-t 1", "1"),
+                false => contents,
+            }
+        }
+
+        fn stub(&self, path: &PathBuf, name: &str) {
+            let contents = format!(
+                "#!/bin/sh\nprintf '%s' '{name}'\nfor arg in \"$@\"; do printf ' %s' \"$arg\"; done\necho\ncat\n"
+            );
+            self.write(path, &contents);
+        }
+
+        fn write(&self, path: &PathBuf, contents: &str) {
+            std::fs::write(path, contents).unwrap();
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    impl Drop for WrapperRun {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::WrapperRun;
+    use super::*;
+    use crate::utils::{Distribution, MockSystem, vecs_eq};
+
+    #[test]
+    fn test_wrapper_incompatible_distribution() {
+        let runner = MockSystem::new(Distribution {
+            id: "Ubuntu".to_string(),
+            release: "20.04".to_string(),
+        });
+        let frawk = frawk_fixture(&runner);
+        assert!(!frawk.check_compatible());
+    }
+
+    #[test]
+    fn test_wrapper_shadow_path_enable() {
+        let runner = MockSystem::default();
+        let frawk = frawk_fixture(&runner);
+
+        assert!(frawk.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands, &["apt-get install -y frawk"]);
+
+        let created_symlinks = runner.created_symlinks.clone().into_inner();
+        assert_eq!(
+            created_symlinks,
+            &[(
+                "/usr/bin/frawk".to_string(),
+                "/usr/lib/oxidizr/shadow/awk".to_string()
+            )]
+        );
+
+        // The original awk is never backed up or replaced.
+        assert_eq!(runner.backed_up_files.clone().into_inner().len(), 0);
+
+        let written_files = runner.written_files.clone().into_inner();
+        assert_eq!(written_files, &[SHADOW_PATH_PROFILE]);
+    }
+
+    #[test]
+    fn test_wrapper_shadow_path_enable_without_shims() {
+        let runner = MockSystem::default();
+        let dust = WrapperExperiment::new(
+            "dust",
+            &runner,
+            "du-dust",
+            &["24.04"],
+            Exposure::ShadowPath,
+            vec![],
+        );
+
+        assert!(dust.enable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["apt-get install -y du-dust"]
+        );
+        assert!(runner.written_files.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_wrapper_snap() {
+        let dust = |runner| {
+            WrapperExperiment::new(
+                "dust",
+                runner,
+                "du-dust",
+                &["24.04"],
+                Exposure::ShadowPath,
+                vec![Shim::link("du", "/usr/bin/dust")],
+            )
+            .with_snap("dust")
+        };
+
+        let runner = MockSystem::default();
+        runner.mock_snap_channel("latest/edge");
+        assert_eq!(dust(&runner).package(), "snap:dust");
+        assert!(dust(&runner).enable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["snap install dust --channel=latest/edge"]
+        );
+        assert_eq!(
+            runner.created_symlinks.clone().into_inner(),
+            &[(
+                "/snap/bin/dust".to_string(),
+                "/usr/lib/oxidizr/shadow/du".to_string()
+            )]
+        );
+
+        // The snap is removed on disable, without experiments being installed from snaps.
+        let runner = MockSystem::default();
+        runner.mock_install_package("snap:dust");
+        assert!(dust(&runner).check_installed());
+        assert!(dust(&runner).disable().is_ok());
+        assert_eq!(runner.commands.clone().into_inner(), &["snap remove dust"]);
+
+        // Without the snap, the package is installed as usual.
+        let runner = MockSystem::default();
+        assert_eq!(dust(&runner).package(), "du-dust");
+    }
+
+    #[test]
+    fn test_wrapper_release() {
+        let ripgrep = |runner| {
+            WrapperExperiment::new(
+                "rg",
+                runner,
+                "ripgrep",
+                &["24.04"],
+                Exposure::ShadowPath,
+                vec![Shim::link("grep", "/usr/bin/rg")],
+            )
+            .with_release("ripgrep")
+        };
+
+        let runner = MockSystem::default();
+        assert_eq!(ripgrep(&runner).package(), "ripgrep");
+
+        runner.mock_from_releases();
+        assert_eq!(ripgrep(&runner).package(), "release:ripgrep");
+        assert_eq!(
+            ripgrep(&runner).managed_links().unwrap(),
+            &[ManagedLink::new(
+                PathBuf::from("/usr/lib/oxidizr/releases/ripgrep/rg"),
+                PathBuf::from("/usr/lib/oxidizr/shadow/grep"),
+            )]
+        );
+
+        // The release is removed on disable, without experiments being installed from releases.
+        let runner = MockSystem::default();
+        runner.mock_install_package("release:ripgrep");
+        assert!(ripgrep(&runner).disable().is_ok());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["rm -rf /usr/lib/oxidizr/releases/ripgrep"]
+        );
+    }
+
+    #[test]
+    fn test_wrapper_shadow_path_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("frawk");
+        runner.mock_files(vec![
+            (SHADOW_PATH_PROFILE, SHADOW_PATH_PROFILE_CONTENTS, false),
+            ("/usr/lib/oxidizr/shadow/awk", "", false),
+        ]);
+
+        let frawk = frawk_fixture(&runner);
+        assert!(frawk.disable().is_ok());
+
+        let removed_files = runner.removed_files.clone().into_inner();
+        assert_eq!(
+            removed_files,
+            &["/usr/lib/oxidizr/shadow/awk", SHADOW_PATH_PROFILE]
+        );
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands, &["apt-get remove -y frawk"]);
+    }
+
+    #[test]
+    fn test_wrapper_shadow_path_disable_keeps_profile_in_use() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            (SHADOW_PATH_PROFILE, SHADOW_PATH_PROFILE_CONTENTS, false),
+            ("/usr/lib/oxidizr/shadow/awk", "", false),
+            ("/usr/lib/oxidizr/shadow/other", "", false),
+        ]);
+
+        let frawk = frawk_fixture(&runner);
+        assert!(frawk.disable().is_ok());
+
+        let removed_files = runner.removed_files.clone().into_inner();
+        assert_eq!(removed_files, &["/usr/lib/oxidizr/shadow/awk"]);
+    }
+
+    #[test]
+    fn test_wrapper_replace_enable() {
+        let runner = http_compatible_runner();
+        let http = http_fixture(&runner);
+
+        assert!(http.enable().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands, &["apt-get install -y xh"]);
+
+        let backed_up_files = runner.backed_up_files.clone().into_inner();
+        assert!(vecs_eq(
+            backed_up_files,
+            vec!["/usr/bin/curl".to_string(), "/usr/bin/wget".to_string()]
+        ));
+
+        let created_symlinks = runner.created_symlinks.clone().into_inner();
+        let expected = vec![
+            (
+                "/usr/lib/oxidizr/wrappers/curl".to_string(),
+                "/usr/bin/curl".to_string(),
+            ),
+            (
+                "/usr/lib/oxidizr/wrappers/wget".to_string(),
+                "/usr/bin/wget".to_string(),
+            ),
+        ];
+        assert!(vecs_eq(created_symlinks, expected));
+
+        // The wrapper falls back to the backup of the original implementation.
+        let wrapper = runner
+            .read_file(PathBuf::from("/usr/lib/oxidizr/wrappers/curl"))
+            .unwrap();
+        assert!(wrapper.contains("original=\"/usr/bin/.curl.oxidizr.bak\""));
+        assert!(!wrapper.contains(ORIGINAL_PLACEHOLDER));
+    }
+
+    #[test]
+    fn test_wrapper_replace_backup_dir() {
+        let runner = http_compatible_runner();
+        runner
+            .backup_dir
+            .replace(Some(PathBuf::from("/srv/backups")));
+        let http = http_fixture(&runner);
+        assert!(http.enable().is_ok());
+
+        let wrapper = runner
+            .read_file(PathBuf::from("/usr/lib/oxidizr/wrappers/curl"))
+            .unwrap();
+        assert!(wrapper.contains("original=\"/srv/backups/usr/bin/curl\""));
+    }
+
+    #[test]
+    fn test_wrapper_replace_compressed_backups() {
+        let runner = http_compatible_runner();
+        runner.compress_backups.replace(true);
+        let http = http_fixture(&runner);
+
+        // The wrappers fall back to the original, which can't be compressed.
+        assert!(http.enable().is_err());
+        assert!(runner.commands.borrow().is_empty());
+        assert!(runner.backed_up_files.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_wrapper_replace_disable() {
+        let runner = http_compatible_runner();
+        runner.mock_install_package("xh");
+
+        let http = http_fixture(&runner);
+        assert!(http.disable().is_ok());
+
+        let restored_files = runner.restored_files.clone().into_inner();
+        assert!(vecs_eq(
+            restored_files,
+            vec!["/usr/bin/curl".to_string(), "/usr/bin/wget".to_string()]
+        ));
+
+        let removed_files = runner.removed_files.clone().into_inner();
+        assert!(vecs_eq(
+            removed_files,
+            vec![
+                "/usr/lib/oxidizr/wrappers/curl".to_string(),
+                "/usr/lib/oxidizr/wrappers/wget".to_string()
+            ]
+        ));
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands, &["apt-get remove -y xh"]);
+    }
+
+    #[test]
+    fn test_wrapper_replace_already_replaced() {
+        let runner = http_compatible_runner();
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"http": {"packages": [{"name": "xh"}], "links": ["/usr/bin/curl", "/usr/bin/wget"]}}}"#,
+            false,
+        )]);
+        let wget = WrapperExperiment::new(
+            "wget",
+            &runner,
+            "xh",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![Shim::script("wget", "original=\"@ORIGINAL@\"")],
+        );
+
+        // The backup of wget would be the wrapper installed by http.
+        assert!(wget.enable().is_err());
+        assert!(runner.commands.borrow().is_empty());
+        assert!(runner.backed_up_files.borrow().is_empty());
+
+        // Re-enabling the experiment that replaced it is allowed.
+        assert!(http_fixture(&runner).enable().is_ok());
+    }
+
+    #[test]
+    fn test_wrapper_shared_package() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("frawk");
+        runner.mock_files(vec![(
+            "/var/lib/oxidizr/state.json",
+            r#"{"experiments": {"frawk": {"packages": [{"name": "frawk"}]}}}"#,
+            false,
+        )]);
+        let awk = WrapperExperiment::new(
+            "awk",
+            &runner,
+            "frawk",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![Shim::script("awk", "#!/bin/sh\n")],
+        );
+
+        // frawk was installed by the frawk experiment, so awk isn't enabled.
+        assert!(!awk.check_installed());
+        assert!(frawk_fixture(&runner).check_installed());
+
+        // The package is kept while the frawk experiment is enabled.
+        assert!(awk.disable().is_ok());
+        assert!(runner.commands.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_wrapper_shadow_path_script() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/bin/git", "", true)]);
+        let gitoxide = WrapperExperiment::new(
+            "gitoxide",
+            &runner,
+            "gitoxide",
+            &["24.04", "24.10", "25.04"],
+            Exposure::ShadowPath,
+            vec![Shim::script("git", include_str!("wrappers/git.sh"))],
+        );
+
+        assert!(gitoxide.enable().is_ok());
+        let wrapper = runner
+            .read_file(PathBuf::from(SHADOW_PATH_DIR).join("git"))
+            .unwrap();
+        assert!(wrapper.contains("original=\"/usr/bin/git\""));
+        // git itself is left in place.
+        assert!(runner.backed_up_files.borrow().is_empty());
+
+        runner.mock_install_package("gitoxide");
+        assert!(gitoxide.disable().is_ok());
+        assert_eq!(
+            runner.removed_files.clone().into_inner(),
+            &["/usr/lib/oxidizr/shadow/git", SHADOW_PATH_PROFILE]
+        );
+        assert!(runner.restored_files.borrow().is_empty());
+        assert_eq!(
+            runner.commands.clone().into_inner(),
+            &["apt-get install -y gitoxide", "apt-get remove -y gitoxide"]
+        );
+    }
+
+    #[test]
+    fn test_wrapper_original_ignores_shims() {
+        let runner = MockSystem::default();
+        // With frawk enabled in the shadow PATH, which finds its shim ahead of the system awk.
+        runner.mock_files(vec![
+            ("/usr/bin/awk", "", false),
+            ("/usr/lib/oxidizr/shadow/awk", "", true),
+            ("/usr/lib/oxidizr/shadow/git", "", true),
+        ]);
+        runner.mock_install_package("frawk");
+        assert!(frawk_fixture(&runner).enable().is_ok());
+
+        let awk = WrapperExperiment::new(
+            "awk",
+            &runner,
+            "frawk",
+            &["24.04"],
+            Exposure::Replace,
+            vec![Shim::script("awk", "original=\"@ORIGINAL@\"")],
+        );
+        assert!(awk.enable().is_ok());
+        assert_eq!(
+            awk.managed_links().unwrap(),
+            &[ManagedLink::new(
+                PathBuf::from("/usr/lib/oxidizr/wrappers/awk"),
+                PathBuf::from("/usr/bin/awk"),
+            )]
+        );
+        let wrapper = runner
+            .read_file(PathBuf::from("/usr/lib/oxidizr/wrappers/awk"))
+            .unwrap();
+        assert_eq!(wrapper, "original=\"/usr/bin/.awk.oxidizr.bak\"");
+
+        // Scripts in the shadow PATH fall back to the system command, rather than themselves.
+        let git = WrapperExperiment::new(
+            "git",
+            &runner,
+            "gitoxide",
+            &["24.04"],
+            Exposure::ShadowPath,
+            vec![Shim::script("git", "original=\"@ORIGINAL@\"")],
+        );
+        assert!(git.enable().is_ok());
+        let wrapper = runner
+            .read_file(PathBuf::from("/usr/lib/oxidizr/shadow/git"))
+            .unwrap();
+        assert_eq!(wrapper, "original=\"/usr/bin/git\"");
+    }
+
+    #[test]
+    fn test_curl_wrapper() {
+        let curl = WrapperRun::new(include_str!("wrappers/curl.sh"), &["xh"]);
+        assert_eq!(
+            curl.run(&["-fsSL", "https://example.com"]),
+            "xh --check-status --follow --ignore-stdin --print=b https://example.com"
+        );
+        assert_eq!(
+            curl.run(&[
+                "-o",
+                "out",
+                "-H",
+                "Accept: application/json",
+                "https://example.com"
+            ]),
+            "xh --output out --ignore-stdin --print=b https://example.com Accept:application/json"
+        );
+        assert_eq!(
+            curl.run(&["-X", "POST", "-d", "{}", "https://example.com"]),
+            "xh --raw {} --print=b POST https://example.com"
+        );
+        assert_eq!(
+            curl.run(&["-I", "--insecure", "https://example.com"]),
+            "xh --verify=no --ignore-stdin --print=h HEAD https://example.com"
+        );
+
+        // Options that aren't translated fall back to the original curl.
+        assert_eq!(
+            curl.run(&["--compressed", "https://example.com"]),
+            "original --compressed https://example.com"
+        );
+        assert_eq!(
+            curl.run(&["-sv", "https://example.com"]),
+            "original -sv https://example.com"
+        );
+    }
+
+    #[test]
+    fn test_wget_wrapper() {
+        let wget = WrapperRun::new(include_str!("wrappers/wget.sh"), &["xh"]);
+        assert_eq!(
+            wget.run(&["https://example.com/file"]),
+            "xh --ignore-stdin --follow --download https://example.com/file"
+        );
+        assert_eq!(
+            wget.run(&["-q", "-O", "out", "https://example.com"]),
+            "xh --quiet --ignore-stdin --follow --download --output out https://example.com"
+        );
+        assert_eq!(
+            wget.run(&[
+                "--header",
+                "Accept: text/html",
+                "-U",
+                "agent",
+                "https://example.com"
+            ]),
+            "xh --ignore-stdin --follow --download https://example.com Accept:text/html User-Agent:agent"
+        );
+
+        // Bundled short options are split before being translated.
+        assert_eq!(
+            wget.run(&["-qO-", "https://example.com"]),
+            "xh --quiet --ignore-stdin --follow --print=b https://example.com"
+        );
+        assert_eq!(
+            wget.run(&["-qcOout", "https://example.com"]),
+            "xh --quiet --continue --ignore-stdin --follow --download --output out https://example.com"
+        );
+
+        // Options that aren't translated fall back to the original wget.
+        assert_eq!(
+            wget.run(&["-r", "https://example.com"]),
+            "original -r https://example.com"
+        );
+        assert_eq!(
+            wget.run(&["--mirror", "https://example.com"]),
+            "original --mirror https://example.com"
+        );
+    }
+
+    #[test]
+    fn test_grep_wrapper() {
+        let grep = WrapperRun::new(include_str!("wrappers/grep.sh"), &["rg"]);
+        let rg = "rg --no-config --no-heading --color=never";
+        assert_eq!(
+            grep.run(&["-in", "foo", "file"]),
+            format!("{rg} --no-filename -i -n --regexp=foo file")
+        );
+        assert_eq!(
+            grep.run(&["foo", "a", "b"]),
+            format!("{rg} --no-line-number --with-filename --regexp=foo a b")
+        );
+        assert_eq!(
+            grep.run(&["-E", "-e", "a|b", "-e", "c+", "file"]),
+            format!("{rg} --no-line-number --no-filename --regexp=a|b --regexp=c+ file")
+        );
+        // grep -r searches hidden and ignored files, and grep reads standard input by default.
+        assert_eq!(
+            grep.run(&["-r", "TODO"]),
+            format!("{rg} --no-line-number -uuu --with-filename --regexp=TODO")
+        );
+        assert_eq!(
+            grep.run(&["-v", "foo"]),
+            format!("{rg} --no-line-number --no-filename -v --regexp=foo -")
+        );
+
+        // Untranslated options, and patterns that mean something else to ripgrep, fall back.
+        assert_eq!(grep.run(&["-P", "x", "file"]), "original -P x file");
+        assert_eq!(grep.run(&["a\\(b\\)", "file"]), "original a\\(b\\) file");
+        assert_eq!(grep.run(&["a|b", "file"]), "original a|b file");
+        assert_eq!(
+            grep.run(&["-E", "\\<a\\>", "file"]),
+            "original -E \\<a\\> file"
+        );
+        assert_eq!(grep.run(&["--", "-x", "file"]), "original -- -x file");
+    }
+
+    #[test]
+    fn test_egrep_fgrep_wrappers() {
+        let rg = "rg --no-config --no-heading --color=never --no-line-number --no-filename";
+        let egrep = WrapperRun::new(include_str!("wrappers/egrep.sh"), &["rg"])
+            .with_wrapper("grep", include_str!("wrappers/grep.sh"));
+        assert_eq!(
+            egrep.run(&["a|b", "file"]),
+            format!("{rg} --regexp=a|b file")
+        );
+        assert_eq!(egrep.run(&["a\\1", "file"]), "original -E a\\1 file");
+
+        let fgrep = WrapperRun::new(include_str!("wrappers/fgrep.sh"), &["rg"])
+            .with_wrapper("grep", include_str!("wrappers/grep.sh"));
+        assert_eq!(
+            fgrep.run(&["a.b", "file"]),
+            format!("{rg} --fixed-strings --regexp=a.b file")
+        );
+    }
+
+    #[test]
+    fn test_sed_wrapper() {
+        let sed =
+            WrapperRun::new(include_str!("wrappers/sed.sh"), &["sd"]).with_file("file", "aa\n");
+        assert_eq!(
+            sed.run(&["-i", "s/foo/bar/g", "file"]),
+            "sd -- foo bar file"
+        );
+        assert_eq!(sed.run(&["s|/usr|/opt|g"]), "sd -- /usr /opt");
+        assert_eq!(sed.run(&["-e", "s/foo/[&]/g"]), "sd -- foo [${0}]");
+        // Files that sed prints are piped through sd, which otherwise edits them in place.
+        assert_eq!(
+            sed.run(&["-E", "s/(a+)/<\\1>/g", "file"]),
+            "sd -- (a+) <${1}>\naa"
+        );
+
+        // Other commands, and patterns that mean something else to sd, fall back.
+        assert_eq!(sed.run(&["s/foo/bar/", "file"]), "original s/foo/bar/ file");
+        assert_eq!(sed.run(&["-n", "p", "file"]), "original -n p file");
+        assert_eq!(
+            sed.run(&["-i.bak", "s/a/b/g", "file"]),
+            "original -i.bak s/a/b/g file"
+        );
+        assert_eq!(
+            sed.run(&["s/a\\(b\\)/x/g", "file"]),
+            "original s/a\\(b\\)/x/g file"
+        );
+        assert_eq!(sed.run(&["s/\\s/_/g", "file"]), "original s/\\s/_/g file");
+    }
+
+    #[test]
+    fn test_ls_wrappers() {
+        let ls = WrapperRun::new(include_str!("wrappers/ls.sh"), &["eza"]);
+        assert_eq!(
+            ls.run_terminal(&["-la"]),
+            "eza --bytes --group -l --all --all"
+        );
+        // ls sorts the newest files first, and eza last.
+        assert_eq!(
+            ls.run_terminal(&["-lhtr", "dir"]),
+            "eza --group --sort=modified -l dir"
+        );
+        assert_eq!(
+            ls.run_terminal(&["-1", "--directory", "dir"]),
+            "eza -1 --list-dirs dir"
+        );
+
+        // Output that is piped or redirected, and untranslated options, are left to the original.
+        assert_eq!(ls.run(&["-la"]), "original -la");
+        assert_eq!(ls.run_terminal(&["-Z"]), "original -Z");
+        assert_eq!(ls.run_terminal(&["--", "-l"]), "original -- -l");
+
+        let dir = WrapperRun::new(include_str!("wrappers/dir.sh"), &["eza"])
+            .with_wrapper("ls", include_str!("wrappers/ls.sh"));
+        assert_eq!(dir.run_terminal(&["/tmp"]), "eza --grid /tmp");
+        assert_eq!(dir.run(&["/tmp"]), "original -C /tmp");
+
+        let vdir = WrapperRun::new(include_str!("wrappers/vdir.sh"), &["eza"])
+            .with_wrapper("ls", include_str!("wrappers/ls.sh"));
+        assert_eq!(vdir.run_terminal(&[]), "eza --bytes --group -l");
+    }
+
+    #[test]
+    fn test_hexdump_xxd_wrappers() {
+        let hexdump = WrapperRun::new(include_str!("wrappers/hexdump.sh"), &["hexyl"]);
+        assert_eq!(
+            hexdump.run_terminal(&["-C", "-n", "64", "-s", "16", "file"]),
+            "hexyl --length=64 --skip=16 file"
+        );
+        assert_eq!(hexdump.run(&["-C", "file"]), "original -C file");
+        assert_eq!(hexdump.run_terminal(&["-x", "file"]), "original -x file");
+        assert_eq!(hexdump.run_terminal(&["a", "b"]), "original a b");
+
+        let xxd = WrapperRun::new(include_str!("wrappers/xxd.sh"), &["hexyl"]);
+        assert_eq!(
+            xxd.run_terminal(&["-l", "64", "-s", "16", "file"]),
+            "hexyl --length=64 --skip=16 file"
+        );
+        // Piped output may be reverted with `xxd -r`, so is left to the original, as are plain
+        // hex dumps and writes to a second file.
+        assert_eq!(xxd.run(&["file"]), "original file");
+        assert_eq!(xxd.run_terminal(&["-p", "file"]), "original -p file");
+        assert_eq!(xxd.run_terminal(&["file", "out"]), "original file out");
+    }
+
+    #[test]
+    fn test_tar_unzip_wrappers() {
+        let tar =
+            WrapperRun::new(include_str!("wrappers/tar.sh"), &["ouch"]).with_command("id", "1000");
+        assert_eq!(
+            tar.run(&["-xzf", "archive.tar.gz", "-C", "dir"]),
+            "ouch decompress --yes --no-smart-unpack --dir dir --quiet -- archive.tar.gz"
+        );
+        assert_eq!(
+            tar.run(&["xvf", "archive.tar.xz", "--directory=dir"]),
+            "ouch decompress --yes --no-smart-unpack --dir dir -- archive.tar.xz"
+        );
+        // Creating and listing archives, extracting members and unknown formats are left to the
+        // original.
+        assert_eq!(
+            tar.run(&["-czf", "a.tar.gz", "dir"]),
+            "original -czf a.tar.gz dir"
+        );
+        assert_eq!(tar.run(&["-tf", "a.tar"]), "original -tf a.tar");
+        assert_eq!(
+            tar.run(&["-xf", "a.tar", "member"]),
+            "original -xf a.tar member"
+        );
+        assert_eq!(tar.run(&["-xf", "a.cpio"]), "original -xf a.cpio");
+        // ouch doesn't restore owners, as tar does when run by root.
+        let root =
+            WrapperRun::new(include_str!("wrappers/tar.sh"), &["ouch"]).with_command("id", "0");
+        assert_eq!(root.run(&["-xf", "a.tar"]), "original -xf a.tar");
+
+        let unzip = WrapperRun::new(include_str!("wrappers/unzip.sh"), &["ouch"]);
+        assert_eq!(
+            unzip.run(&["-oq", "archive.zip", "-d", "dir"]),
+            "ouch decompress --no-smart-unpack --dir dir --quiet --yes -- archive.zip"
+        );
+        assert_eq!(
+            unzip.run(&["archive.zip"]),
+            "ouch decompress --no-smart-unpack --dir . -- archive.zip"
+        );
+        assert_eq!(unzip.run(&["-l", "archive.zip"]), "original -l archive.zip");
+        assert_eq!(
+            unzip.run(&["archive.zip", "member"]),
+            "original archive.zip member"
+        );
+        assert_eq!(unzip.run(&["archive"]), "original archive");
+    }
+
+    #[test]
+    fn test_compression_wrappers() {
+        let gzip = WrapperRun::new(include_str!("wrappers/gzip.sh"), &["crabz"]);
+        assert_eq!(
+            gzip.run(&["-9c", "file"]),
+            "crabz --compression-level 9 file"
+        );
+        assert_eq!(gzip.run(&["-dc", "file.gz"]), "crabz --decompress file.gz");
+        assert_eq!(gzip.run(&["--fast", "-"]), "crabz --compression-level 1");
+        // Files are compressed in place, and compressed data isn't written to a terminal, by the
+        // original.
+        assert_eq!(gzip.run(&["file"]), "original file");
+        assert_eq!(gzip.run_terminal(&["-c", "file"]), "original -c file");
+        assert_eq!(gzip.run(&["-rc", "dir"]), "original -rc dir");
+
+        let gunzip = WrapperRun::new(include_str!("wrappers/gunzip.sh"), &["crabz"])
+            .with_wrapper("gzip", include_str!("wrappers/gzip.sh"));
+        assert_eq!(gunzip.run(&["-c", "file.gz"]), "crabz --decompress file.gz");
+        assert_eq!(gunzip.run(&["file.gz"]), "original -d file.gz");
+
+        let zcat = WrapperRun::new(include_str!("wrappers/zcat.sh"), &["crabz"])
+            .with_wrapper("gzip", include_str!("wrappers/gzip.sh"));
+        assert_eq!(
+            zcat.run_terminal(&["file.gz"]),
+            "crabz --decompress file.gz"
+        );
+    }
+
+    #[test]
+    fn test_bc_wrapper() {
+        let bc = WrapperRun::new(include_str!("wrappers/bc.sh"), &["fend"]);
+        assert_eq!(bc.run_terminal(&["-ql"]), "fend");
+        // Input from files or pipes is left to the original, as are other options.
+        assert_eq!(bc.run(&["-l"]), "original -l");
+        assert_eq!(bc.run_terminal(&["script.bc"]), "original script.bc");
+        assert_eq!(bc.run_terminal(&["-s"]), "original -s");
+    }
+
+    #[test]
+    fn test_parallel_wrapper() {
+        let parallel = WrapperRun::new(include_str!("wrappers/parallel.sh"), &["rust-parallel"]);
+        assert_eq!(
+            parallel.run(&["--will-cite", "-j4", "-0", "gzip", ":::", "a", "b"]),
+            "rust-parallel --jobs 4 -0 gzip ::: a b"
+        );
+        assert_eq!(
+            parallel.run(&["--jobs", "2", "echo", "-j3"]),
+            "rust-parallel --jobs 2 echo -j3"
+        );
+        // Replacement strings, untranslated options and invalid job counts are left to the
+        // original.
+        assert_eq!(
+            parallel.run(&["gzip", "{}", ":::", "a"]),
+            "original gzip {} ::: a"
+        );
+        assert_eq!(parallel.run(&["--pipe", "wc"]), "original --pipe wc");
+        assert_eq!(parallel.run(&["-j", "50%", "wc"]), "original -j 50% wc");
+    }
+
+    #[test]
+    fn test_awk_wrapper() {
+        let awk = WrapperRun::new(include_str!("wrappers/awk.sh"), &["frawk"])
+            .with_file("posix.awk", "{ print $1 }\n")
+            .with_file("gawk.awk", "{ print gensub(/a/, \"b\", \"g\") }\n");
+        assert_eq!(
+            awk.run(&["-F:", "-v", "n=1", "{ print $n }", "file"]),
+            "frawk -F: -v n=1 { print $n } file"
+        );
+        assert_eq!(
+            awk.run(&["-f", "posix.awk", "file"]),
+            "frawk -f posix.awk file"
+        );
+
+        // gawk extensions, in the program or a program file, and other options are left to the
+        // original.
+        assert_eq!(
+            awk.run(&["{ print strftime() }"]),
+            "original { print strftime() }"
+        );
+        assert_eq!(awk.run(&["-fgawk.awk", "file"]), "original -fgawk.awk file");
+        assert_eq!(awk.run(&["--posix", "{}"]), "original --posix {}");
+        assert_eq!(awk.run(&["-F"]), "original -F");
+    }
+
+    #[test]
+    fn test_file_wrapper() {
+        let file = WrapperRun::new(include_str!("wrappers/file.sh"), &["magika"]);
+        assert_eq!(file.run(&["a", "b"]), "magika --format %p: %d -- a b");
+        assert_eq!(file.run(&["-bi", "a"]), "magika --format %m -- a");
+        assert_eq!(
+            file.run(&["--mime-type", "--", "-a"]),
+            "magika --format %p: %m -- -a"
+        );
+        // Other options, standard input and a missing file are left to the original.
+        assert_eq!(file.run(&["-L", "a"]), "original -L a");
+        assert_eq!(file.run(&["-"]), "original -");
+        assert_eq!(file.run(&["-b"]), "original -b");
+    }
+
+    #[test]
+    fn test_git_wrapper() {
+        let git = WrapperRun::new(include_str!("wrappers/git.sh"), &["gix"]);
+        assert_eq!(git.run_terminal(&["status"]), "gix status");
+        assert_eq!(git.run_terminal(&["log"]), "gix log");
+        assert_eq!(
+            git.run_terminal(&["cat-file", "-p", "HEAD"]),
+            "gix cat HEAD"
+        );
+        assert_eq!(
+            git.run_terminal(&["rev-parse", "HEAD~1"]),
+            "gix rev resolve HEAD~1"
+        );
+
+        // Piped output, options and other subcommands are left to the original.
+        assert_eq!(git.run(&["status"]), "original status");
+        assert_eq!(git.run_terminal(&["status", "-s"]), "original status -s");
+        assert_eq!(
+            git.run_terminal(&["-C", "dir", "log"]),
+            "original -C dir log"
+        );
+        assert_eq!(
+            git.run_terminal(&["rev-parse", "--show-toplevel"]),
+            "original rev-parse --show-toplevel"
+        );
+        assert_eq!(
+            git.run_terminal(&["commit", "-m", "x"]),
+            "original commit -m x"
+        );
+    }
+
+    fn http_fixture(system: &MockSystem) -> WrapperExperiment<'_> {
+        WrapperExperiment::new(
+            "http",
+            system,
+            "xh",
+            &["24.04", "24.10", "25.04"],
+            Exposure::Replace,
+            vec![
+                Shim::script("curl", "original=\"@ORIGINAL@\""),
+                Shim::script("wget", "original=\"@ORIGINAL@\""),
+            ],
+        )
+    }
+
+    fn http_compatible_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/bin/curl", "", true),
+            ("/usr/bin/wget", "", true),
+        ]);
+        runner
+    }
+
+    fn frawk_fixture(system: &MockSystem) -> WrapperExperiment<'_> {
+        WrapperExperiment::new(
+            "frawk",
+            system,
+            "frawk",
+            &["24.04", "24.10", "25.04"],
+            Exposure::ShadowPath,
+            vec![Shim::link("awk", "/usr/bin/frawk")],
+        )
+    }
+}
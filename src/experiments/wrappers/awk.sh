@@ -0,0 +1,54 @@
+#!/bin/sh
+# Managed by oxidizr. awk compatibility wrapper for frawk.
+#
+# Runs POSIX awk programs with frawk, which accepts the same -F, -v and -f options. Any other
+# option, and any program using a gawk extension that frawk doesn't support, is passed
+# unmodified to the original awk.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported awk invocation and original awk is unavailable" >&2
+  exit 2
+}
+
+# gawk extensions which frawk doesn't support.
+extensions='gensub|strftime|systime|mktime|PROCINFO|asorti?\(|@include|BEGINFILE|ENDFILE|\|&'
+
+# First pass: ensure every option is supported, and find the program.
+expect_value=""
+program_file=0
+program=""
+for arg in "$@"; do
+  if [ -n "$expect_value" ]; then
+    if [ "$expect_value" = -f ]; then
+      program_file=1
+      grep -Eq "$extensions" "$arg" 2>/dev/null && fallback "$@"
+    fi
+    expect_value=""
+    continue
+  fi
+  case "$arg" in
+    --) continue ;;
+    -F | -v | -f) expect_value="$arg" ;;
+    -F?* | -v?*) ;;
+    -f?*)
+      program_file=1
+      grep -Eq "$extensions" "${arg#-f}" 2>/dev/null && fallback "$@"
+      ;;
+    -?*) fallback "$@" ;;
+    *)
+      [ "$program_file" = 1 ] || program="$arg"
+      break
+      ;;
+  esac
+done
+[ -z "$expect_value" ] || fallback "$@"
+[ "$program_file" = 1 ] || [ -n "$program" ] || fallback "$@"
+if printf '%s\n' "$program" | grep -Eq "$extensions"; then
+  fallback "$@"
+fi
+
+exec frawk "$@"
@@ -0,0 +1,28 @@
+#!/bin/sh
+# Managed by oxidizr. bc compatibility wrapper for fend.
+#
+# Interactive sessions on a terminal, optionally with -q or -l, are run with fend, which
+# calculates with arbitrary precision rather than bc's fixed scale. Input from files or pipes,
+# which scripts may depend on bc's exact output for, is handled by the original bc.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported bc invocation and original bc is unavailable" >&2
+  exit 1
+}
+
+if [ ! -t 0 ] || [ ! -t 1 ]; then
+  fallback "$@"
+fi
+
+for arg in "$@"; do
+  case "$arg" in
+    -q | --quiet | -l | --mathlib | -ql | -lq) ;;
+    *) fallback "$@" ;;
+  esac
+done
+
+exec fend
@@ -0,0 +1,19 @@
+#!/bin/sh
+# Managed by oxidizr. Shows files with bat when writing to a terminal.
+#
+# Output that is piped or redirected, and any invocation with options, is handled by the original
+# cat, so scripts see exactly the bytes they expect.
+original="@ORIGINAL@"
+
+if [ ! -t 1 ]; then
+  exec "$original" "$@"
+fi
+
+for arg in "$@"; do
+  case "$arg" in
+    -?*) exec "$original" "$@" ;;
+  esac
+done
+
+# Ubuntu installs bat as batcat, to avoid a conflict with another package.
+exec batcat --plain --paging=never "$@"
@@ -0,0 +1,115 @@
+#!/bin/sh
+# Managed by oxidizr. curl compatibility wrapper for xh.
+#
+# Translates the most commonly used curl options to their xh equivalents. Any invocation using
+# an option that is not translated is passed unmodified to the original curl.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported curl invocation and original curl is unavailable" >&2
+  exit 2
+}
+
+# First pass: ensure every option can be translated before consuming the arguments.
+expect_value=0
+for arg in "$@"; do
+  if [ "$expect_value" = 1 ]; then
+    expect_value=0
+    continue
+  fi
+  case "$arg" in
+    --silent | --show-error | --location | --remote-name | --fail | --head | --insecure) ;;
+    -o | --output | -X | --request | -H | --header | -d | --data | -u | --user) expect_value=1 ;;
+    --*) fallback "$@" ;;
+    -?*)
+      case "${arg#-}" in
+        *[!sSLOfIk]*) fallback "$@" ;;
+      esac
+      ;;
+  esac
+done
+
+print="b"
+method=""
+url=""
+items=""
+data=0
+n=$#
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  case "$arg" in
+    -o | --output)
+      set -- "$@" --output "$1"
+      shift
+      n=$((n - 1))
+      ;;
+    -X | --request)
+      method="$1"
+      shift
+      n=$((n - 1))
+      ;;
+    -H | --header)
+      items="${items}$(printf '%s' "$1" | sed 's/: */:/')
+"
+      shift
+      n=$((n - 1))
+      ;;
+    -d | --data)
+      set -- "$@" --raw "$1"
+      data=1
+      shift
+      n=$((n - 1))
+      ;;
+    -u | --user)
+      set -- "$@" --auth "$1"
+      shift
+      n=$((n - 1))
+      ;;
+    --silent | --show-error) ;;
+    --location) set -- "$@" --follow ;;
+    --remote-name) set -- "$@" --download ;;
+    --fail) set -- "$@" --check-status ;;
+    --head) print="h" method="HEAD" ;;
+    --insecure) set -- "$@" --verify=no ;;
+    -?*)
+      flags="${arg#-}"
+      while [ -n "$flags" ]; do
+        rest="${flags#?}"
+        case "${flags%"$rest"}" in
+          L) set -- "$@" --follow ;;
+          O) set -- "$@" --download ;;
+          f) set -- "$@" --check-status ;;
+          I) print="h" method="HEAD" ;;
+          k) set -- "$@" --verify=no ;;
+        esac
+        flags="$rest"
+      done
+      ;;
+    *) url="$arg" ;;
+  esac
+done
+
+if [ "$data" = 0 ]; then
+  set -- "$@" --ignore-stdin
+fi
+
+set -- "$@" --print="$print"
+if [ -n "$method" ]; then
+  set -- "$@" "$method"
+fi
+set -- "$@" "$url"
+
+old_ifs="$IFS"
+IFS='
+'
+for item in $items; do
+  set -- "$@" "$item"
+done
+IFS="$old_ifs"
+
+exec xh "$@"
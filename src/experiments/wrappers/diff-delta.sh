@@ -0,0 +1,17 @@
+#!/bin/sh
+# Managed by oxidizr. Pipes diff output through delta when writing to a terminal.
+original="@ORIGINAL@"
+
+if [ ! -t 1 ]; then
+  exec "$original" "$@"
+fi
+
+# Preserve the exit status of diff, which is significant (0: same, 1: different, 2: trouble).
+status_file="$(mktemp)"
+{
+  "$original" "$@"
+  echo "$?" >"$status_file"
+} | delta
+status="$(cat "$status_file")"
+rm -f "$status_file"
+exit "${status:-2}"
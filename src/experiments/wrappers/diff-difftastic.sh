@@ -0,0 +1,18 @@
+#!/bin/sh
+# Managed by oxidizr. Shows the differences between two files with difftastic when writing to a
+# terminal.
+original="@ORIGINAL@"
+
+if [ ! -t 1 ] || [ "$#" -ne 2 ]; then
+  exec "$original" "$@"
+fi
+
+# Options, and standard input given as -, are handled by the original diff.
+for arg in "$@"; do
+  case "$arg" in
+    -*) exec "$original" "$@" ;;
+  esac
+done
+
+# Like diff, report whether the files differ in the exit status.
+exec difft --exit-code "$1" "$2"
@@ -0,0 +1,3 @@
+#!/bin/sh
+# Managed by oxidizr. dir compatibility wrapper for eza, equivalent to 'ls -C'.
+exec /usr/lib/oxidizr/wrappers/ls -C "$@"
@@ -0,0 +1,64 @@
+#!/bin/sh
+# Managed by oxidizr. doas compatibility wrapper for please.
+#
+# Translates `doas [-u user] command` and `doas [-u user] -s` to their please equivalents. Any
+# other invocation, such as one using -n, -C or -L, is passed unmodified to the original doas.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported doas invocation and original doas is unavailable" >&2
+  exit 1
+}
+
+# First pass: ensure every option can be translated before consuming the arguments.
+expect_user=0
+shell=0
+options=0
+for arg in "$@"; do
+  if [ "$expect_user" = 1 ]; then
+    expect_user=0
+    options=$((options + 1))
+    continue
+  fi
+  case "$arg" in
+    --)
+      options=$((options + 1))
+      break
+      ;;
+    -u) expect_user=1 ;;
+    -s) shell=1 ;;
+    -*) fallback "$@" ;;
+    *) break ;;
+  esac
+  options=$((options + 1))
+done
+[ "$expect_user" = 0 ] || fallback "$@"
+# doas runs either a shell or a command, never both.
+if [ "$shell" = 1 ]; then
+  [ "$#" = "$options" ] || fallback "$@"
+else
+  [ "$#" -gt "$options" ] || fallback "$@"
+fi
+
+# Second pass: consume the options, leaving the command.
+target=""
+while [ "$options" -gt 0 ]; do
+  case "$1" in
+    -u)
+      target="$2"
+      shift
+      options=$((options - 1))
+      ;;
+  esac
+  shift
+  options=$((options - 1))
+done
+[ "$shell" = 0 ] || set -- "${SHELL:-/bin/sh}"
+
+if [ -n "$target" ]; then
+  exec please --target "$target" -- "$@"
+fi
+exec please -- "$@"
@@ -0,0 +1,3 @@
+#!/bin/sh
+# Managed by oxidizr. egrep compatibility wrapper for ripgrep, equivalent to 'grep -E'.
+exec /usr/lib/oxidizr/wrappers/grep -E "$@"
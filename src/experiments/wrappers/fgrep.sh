@@ -0,0 +1,3 @@
+#!/bin/sh
+# Managed by oxidizr. fgrep compatibility wrapper for ripgrep, equivalent to 'grep -F'.
+exec /usr/lib/oxidizr/wrappers/grep -F "$@"
@@ -0,0 +1,50 @@
+#!/bin/sh
+# Managed by oxidizr. file compatibility wrapper for magika.
+#
+# Translates the -b, -i and --mime-type output formats to magika's --format. Any invocation
+# using another option, or reading from standard input, is passed unmodified to the original
+# file.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported file invocation and original file is unavailable" >&2
+  exit 1
+}
+
+# First pass: ensure every option can be translated before consuming the arguments.
+brief=0
+mime=0
+options=0
+for arg in "$@"; do
+  case "$arg" in
+    --)
+      options=$((options + 1))
+      break
+      ;;
+    -b | --brief) brief=1 ;;
+    -i | --mime | --mime-type) mime=1 ;;
+    -bi | -ib)
+      brief=1
+      mime=1
+      ;;
+    -*) fallback "$@" ;;
+    *) break ;;
+  esac
+  options=$((options + 1))
+done
+[ "$#" -gt "$options" ] || fallback "$@"
+for arg in "$@"; do
+  [ "$arg" != - ] || fallback "$@"
+done
+
+# Second pass: consume the options, leaving the files.
+shift "$options"
+
+format="%d"
+[ "$mime" = 0 ] || format="%m"
+[ "$brief" = 1 ] || format="%p: $format"
+
+exec magika --format "$format" -- "$@"
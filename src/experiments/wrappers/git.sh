@@ -0,0 +1,32 @@
+#!/bin/sh
+# Managed by oxidizr. git wrapper dispatching read-only subcommands to gitoxide.
+#
+# Runs `git status`, `git log`, `git cat-file -p <object>` and `git rev-parse <revision>` with gix
+# when writing to a terminal. Output that is piped or redirected, which scripts may parse, and
+# every other invocation, including any using global options such as -C, is handled by the
+# original git.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported git invocation and original git is unavailable" >&2
+  exit 1
+}
+
+[ -t 1 ] || fallback "$@"
+
+case "$#:$1:$2" in
+  1:status:) exec gix status ;;
+  1:log:) exec gix log ;;
+  3:cat-file:-p)
+    case "$3" in
+      -*) ;;
+      *) exec gix cat "$3" ;;
+    esac
+    ;;
+  2:rev-parse:-*) ;;
+  2:rev-parse:*) exec gix rev resolve "$2" ;;
+esac
+fallback "$@"
@@ -0,0 +1,181 @@
+#!/bin/sh
+# Managed by oxidizr. grep compatibility wrapper for ripgrep.
+#
+# Translates the most commonly used grep options to their ripgrep equivalents. Any invocation
+# using an option that is not translated, or a pattern whose meaning differs in ripgrep's regex
+# syntax (such as basic regular expression groups and back-references), is passed unmodified to
+# the original grep.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported grep invocation and original grep is unavailable" >&2
+  exit 2
+}
+
+# Record whether a pattern means something different to ripgrep as a basic or extended regular
+# expression. In basic expressions, characters such as ( and + are literal unless escaped.
+basic_unsafe=0
+extended_unsafe=0
+classify() {
+  case "$1" in
+    *'\'[0-9]* | *'\<'* | *'\>'*)
+      basic_unsafe=1
+      extended_unsafe=1
+      ;;
+    '*'* | *['(){}|+?']*) basic_unsafe=1 ;;
+  esac
+}
+
+# First pass: ensure every option can be translated before consuming the arguments, and find
+# the syntax of the patterns.
+syntax=basic
+expect_pattern=0
+have_pattern=0
+first_operand=""
+operands=0
+options=1
+recursive=0
+line_number=0
+for arg in "$@"; do
+  if [ "$expect_pattern" = 1 ]; then
+    expect_pattern=0
+    classify "$arg"
+    continue
+  fi
+  case "$options:$arg" in
+    1:--) options=0 ;;
+    1:-e | 1:--regexp)
+      expect_pattern=1
+      have_pattern=1
+      ;;
+    1:--regexp=*)
+      have_pattern=1
+      classify "${arg#--regexp=}"
+      ;;
+    1:--extended-regexp) syntax=extended ;;
+    1:--fixed-strings) syntax=fixed ;;
+    1:--basic-regexp) syntax=basic ;;
+    1:--recursive | 1:--dereference-recursive) recursive=1 ;;
+    1:--line-number) line_number=1 ;;
+    1:--ignore-case | 1:--invert-match | 1:--count | 1:--files-with-matches) ;;
+    1:--files-without-match | 1:--word-regexp | 1:--line-regexp | 1:--only-matching) ;;
+    1:--quiet | 1:--silent | 1:--no-messages | 1:--no-filename | 1:--with-filename) ;;
+    1:--color=* | 1:--colour=*) ;;
+    1:--*) fallback "$@" ;;
+    1:-?*)
+      flags="${arg#-}"
+      while [ -n "$flags" ]; do
+        flag="${flags%"${flags#?}"}"
+        flags="${flags#?}"
+        case "$flag" in
+          E) syntax=extended ;;
+          F) syntax=fixed ;;
+          G) syntax=basic ;;
+          r | R) recursive=1 ;;
+          n) line_number=1 ;;
+          i | v | c | l | L | w | x | o | q | s | h | H) ;;
+          # A pattern attached to -e, e.g. -efoo, isn't translated.
+          e)
+            [ -z "$flags" ] || fallback "$@"
+            expect_pattern=1
+            have_pattern=1
+            ;;
+          *) fallback "$@" ;;
+        esac
+      done
+      ;;
+    *)
+      # Operands that look like options can't be passed to ripgrep unambiguously.
+      case "$arg" in
+        -?*) fallback "$@" ;;
+      esac
+      [ "$operands" = 0 ] && first_operand="$arg"
+      operands=$((operands + 1))
+      ;;
+  esac
+done
+
+files=$operands
+if [ "$have_pattern" = 0 ]; then
+  [ "$operands" -gt 0 ] || fallback "$@"
+  classify "$first_operand"
+  files=$((operands - 1))
+fi
+case "$syntax" in
+  basic) [ "$basic_unsafe" = 0 ] || fallback "$@" ;;
+  extended) [ "$extended_unsafe" = 0 ] || fallback "$@" ;;
+esac
+
+# grep prints plain lines, prefixed with file names only when searching several files.
+n=$#
+set -- "$@" --no-config --no-heading --color=never
+[ "$line_number" = 1 ] || set -- "$@" --no-line-number
+if [ "$recursive" = 1 ]; then
+  # grep searches hidden, ignored and binary files that ripgrep skips by default.
+  set -- "$@" -uuu --with-filename
+elif [ "$files" -gt 1 ]; then
+  set -- "$@" --with-filename
+else
+  set -- "$@" --no-filename
+fi
+
+options=1
+pattern_pending=$((1 - have_pattern))
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  case "$options:$arg" in
+    1:--) options=0 ;;
+    1:-e | 1:--regexp)
+      set -- "$@" "--regexp=$1"
+      shift
+      n=$((n - 1))
+      ;;
+    1:--regexp=*) set -- "$@" "$arg" ;;
+    1:--extended-regexp | 1:--basic-regexp) ;;
+    1:--dereference-recursive) set -- "$@" --follow ;;
+    1:--recursive) ;;
+    1:--silent) set -- "$@" --quiet ;;
+    1:--colour=*) set -- "$@" "--color=${arg#--colour=}" ;;
+    1:--*) set -- "$@" "$arg" ;;
+    1:-?*)
+      flags="${arg#-}"
+      while [ -n "$flags" ]; do
+        flag="${flags%"${flags#?}"}"
+        flags="${flags#?}"
+        case "$flag" in
+          E | G | r) ;;
+          R) set -- "$@" --follow ;;
+          F) set -- "$@" --fixed-strings ;;
+          L) set -- "$@" --files-without-match ;;
+          s) set -- "$@" --no-messages ;;
+          h) set -- "$@" --no-filename ;;
+          H) set -- "$@" --with-filename ;;
+          e)
+            set -- "$@" "--regexp=$1"
+            shift
+            n=$((n - 1))
+            ;;
+          *) set -- "$@" "-$flag" ;;
+        esac
+      done
+      ;;
+    *)
+      if [ "$pattern_pending" = 1 ]; then
+        set -- "$@" "--regexp=$arg"
+        pattern_pending=0
+      else
+        set -- "$@" "$arg"
+      fi
+      ;;
+  esac
+done
+
+# Without files to search, grep reads standard input, and grep -r searches the working directory.
+[ "$files" -gt 0 ] || [ "$recursive" = 1 ] || set -- "$@" -
+
+exec rg "$@"
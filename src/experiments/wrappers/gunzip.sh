@@ -0,0 +1,3 @@
+#!/bin/sh
+# Managed by oxidizr. gunzip compatibility wrapper for crabz, equivalent to 'gzip -d'.
+exec /usr/lib/oxidizr/wrappers/gzip -d "$@"
@@ -0,0 +1,67 @@
+#!/bin/sh
+# Managed by oxidizr. gzip compatibility wrapper for crabz.
+#
+# Translates compression and decompression of a single file or standard input to standard output,
+# e.g. `gzip -dc file.gz`, to their crabz equivalents. Any other invocation, including compressing
+# files in place, is passed unmodified to the original gzip.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported gzip invocation and original gzip is unavailable" >&2
+  exit 2
+}
+
+stdout=0
+decompress=0
+level=""
+file=""
+operands=0
+options=1
+for arg in "$@"; do
+  case "$options:$arg" in
+    1:--) options=0 ;;
+    1:--stdout | 1:--to-stdout) stdout=1 ;;
+    1:--decompress | 1:--uncompress) decompress=1 ;;
+    1:--keep | 1:--quiet | 1:--no-name) ;;
+    1:--fast) level=1 ;;
+    1:--best) level=9 ;;
+    1:--*) fallback "$@" ;;
+    1:-?*)
+      flags="${arg#-}"
+      while [ -n "$flags" ]; do
+        flag="${flags%"${flags#?}"}"
+        flags="${flags#?}"
+        case "$flag" in
+          c) stdout=1 ;;
+          d) decompress=1 ;;
+          [1-9]) level="$flag" ;;
+          # The file name and time stamp aren't recorded by crabz.
+          k | q | n) ;;
+          *) fallback "$@" ;;
+        esac
+      done
+      ;;
+    *)
+      [ "$arg" = - ] || file="$arg"
+      operands=$((operands + 1))
+      ;;
+  esac
+done
+
+# Files are compressed in place unless writing to standard output.
+[ "$operands" -le 1 ] || fallback "$@"
+[ -z "$file" ] || [ "$stdout" = 1 ] || fallback "$@"
+# gzip refuses to write compressed data to a terminal.
+[ "$decompress" = 1 ] || [ ! -t 1 ] || fallback "$@"
+case "$file" in
+  -*) fallback "$@" ;;
+esac
+
+set --
+[ "$decompress" = 0 ] || set -- "$@" --decompress
+[ -z "$level" ] || set -- "$@" --compression-level "$level"
+[ -z "$file" ] || set -- "$@" "$file"
+exec crabz "$@"
@@ -0,0 +1,112 @@
+#!/bin/sh
+# Managed by oxidizr. ls compatibility wrapper for eza.
+#
+# Translates the most commonly used ls options to their eza equivalents when writing to a
+# terminal. Output that is piped or redirected, which scripts may parse, and any invocation using
+# an option that is not translated, is handled by the original ls.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported ls invocation and original ls is unavailable" >&2
+  exit 2
+}
+
+[ -t 1 ] || fallback "$@"
+
+# First pass: ensure every option can be translated before consuming the arguments.
+long=0
+human=0
+group=1
+reverse=0
+sort=""
+options=1
+for arg in "$@"; do
+  case "$options:$arg" in
+    1:--) options=0 ;;
+    1:--all | 1:--almost-all | 1:--directory | 1:--recursive | 1:--inode | 1:--classify) ;;
+    1:--group-directories-first | 1:--color=* | 1:--colour=*) ;;
+    1:--human-readable) human=1 ;;
+    1:--no-group) group=0 ;;
+    1:--reverse) reverse=1 ;;
+    1:--*) fallback "$@" ;;
+    1:-?*)
+      flags="${arg#-}"
+      while [ -n "$flags" ]; do
+        flag="${flags%"${flags#?}"}"
+        flags="${flags#?}"
+        case "$flag" in
+          l) long=1 ;;
+          h) human=1 ;;
+          G) group=0 ;;
+          r) reverse=1 ;;
+          t) sort=modified ;;
+          S) sort=size ;;
+          X) sort=extension ;;
+          U) sort=none ;;
+          a | A | d | R | i | F | 1 | C | x) ;;
+          *) fallback "$@" ;;
+        esac
+      done
+      ;;
+    *)
+      # Operands that look like options can't be passed to eza unambiguously.
+      case "$arg" in
+        -?*) fallback "$@" ;;
+      esac
+      ;;
+  esac
+done
+
+# ls sorts the newest and largest files first, whereas eza sorts them last.
+case "$sort" in
+  modified | size) reverse=$((1 - reverse)) ;;
+esac
+
+# eza shows human-readable sizes and hides the group by default, unlike ls -l.
+n=$#
+if [ "$long" = 1 ]; then
+  [ "$human" = 1 ] || set -- "$@" --bytes
+  [ "$group" = 0 ] || set -- "$@" --group
+fi
+[ -z "$sort" ] || set -- "$@" "--sort=$sort"
+[ "$reverse" = 0 ] || set -- "$@" --reverse
+
+options=1
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  case "$options:$arg" in
+    1:--) options=0 ;;
+    # eza lists . and .. only when --all is given twice.
+    1:--all) set -- "$@" --all --all ;;
+    1:--almost-all) set -- "$@" --all ;;
+    1:--directory) set -- "$@" --list-dirs ;;
+    1:--recursive) set -- "$@" --recurse ;;
+    1:--colour=*) set -- "$@" "--color=${arg#--colour=}" ;;
+    1:--human-readable | 1:--no-group | 1:--reverse) ;;
+    1:--*) set -- "$@" "$arg" ;;
+    1:-?*)
+      flags="${arg#-}"
+      while [ -n "$flags" ]; do
+        flag="${flags%"${flags#?}"}"
+        flags="${flags#?}"
+        case "$flag" in
+          a) set -- "$@" --all --all ;;
+          A) set -- "$@" --all ;;
+          d) set -- "$@" --list-dirs ;;
+          C) set -- "$@" --grid ;;
+          x) set -- "$@" --grid --across ;;
+          h | G | r | t | S | X | U) ;;
+          *) set -- "$@" "-$flag" ;;
+        esac
+      done
+      ;;
+    *) set -- "$@" "$arg" ;;
+  esac
+done
+
+exec eza "$@"
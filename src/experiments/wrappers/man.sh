@@ -0,0 +1,20 @@
+#!/bin/sh
+# Managed by oxidizr. man wrapper falling through to tealdeer.
+#
+# Runs the original man, unless it's asked for a single page that has no manual page. The tldr
+# page is shown instead if tealdeer has one, otherwise the original man reports the missing page.
+original="@ORIGINAL@"
+export TEALDEER_CACHE_DIR="${TEALDEER_CACHE_DIR:-/var/cache/oxidizr/tealdeer}"
+
+if [ "$#" = 1 ]; then
+  case "$1" in
+    -*) ;;
+    *)
+      if ! "$original" -w "$1" >/dev/null 2>&1 && tldr --quiet "$1" 2>/dev/null; then
+        echo "oxidizr: no manual entry for $1, showing its tldr page" >&2
+        exit 0
+      fi
+      ;;
+  esac
+fi
+exec "$original" "$@"
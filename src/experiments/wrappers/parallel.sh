@@ -0,0 +1,89 @@
+#!/bin/sh
+# Managed by oxidizr. GNU parallel compatibility wrapper for rust-parallel.
+#
+# Translates invocations running a command for each input, read from standard input or given
+# after :::, with -j and -0. --will-cite is accepted and ignored, as rust-parallel shows no
+# citation notice. Any other option, and commands using replacement strings such as {} or {.},
+# are passed unmodified to the original parallel.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported parallel invocation and original parallel is unavailable" >&2
+  exit 255
+}
+
+# First pass: ensure every option can be translated before consuming the arguments. Options
+# end at the first argument that isn't one, which starts the command.
+options=1
+expect_jobs=0
+for arg in "$@"; do
+  if [ "$expect_jobs" = 1 ]; then
+    expect_jobs=0
+    case "$arg" in
+      '' | *[!0-9]*) fallback "$@" ;;
+    esac
+    continue
+  fi
+  if [ "$options" = 1 ]; then
+    case "$arg" in
+      --will-cite | -0 | --null) continue ;;
+      -j | --jobs)
+        expect_jobs=1
+        continue
+        ;;
+      -j[0-9]*)
+        case "${arg#-j}" in *[!0-9]*) fallback "$@" ;; esac
+        continue
+        ;;
+      --jobs=[0-9]*)
+        case "${arg#--jobs=}" in *[!0-9]*) fallback "$@" ;; esac
+        continue
+        ;;
+      -*) fallback "$@" ;;
+    esac
+    options=0
+  fi
+  case "$arg" in
+    *'{'*'}'* | '::::' | ':::+' | '::::+') fallback "$@" ;;
+  esac
+done
+[ "$expect_jobs" = 0 ] || fallback "$@"
+
+# Second pass: translate the options, keeping the command and its inputs.
+n=$#
+options=1
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  if [ "$options" = 1 ]; then
+    case "$arg" in
+      --will-cite) continue ;;
+      -0 | --null)
+        set -- "$@" -0
+        continue
+        ;;
+      -j | --jobs)
+        set -- "$@" --jobs "$1"
+        shift
+        n=$((n - 1))
+        continue
+        ;;
+      --jobs=*)
+        set -- "$@" --jobs "${arg#--jobs=}"
+        continue
+        ;;
+      -j*)
+        set -- "$@" --jobs "${arg#-j}"
+        continue
+        ;;
+    esac
+    options=0
+  fi
+  set -- "$@" "$arg"
+done
+
+exec rust-parallel "$@"
@@ -0,0 +1,217 @@
+#!/bin/sh
+# Managed by oxidizr. sed compatibility wrapper for sd.
+#
+# Translates single global substitutions, e.g. `sed -i 's/foo/bar/g' file`, to their sd
+# equivalents. Any other invocation, including other sed commands, substitutions without the `g`
+# flag and patterns whose meaning differs in sd's regex syntax, is passed unmodified to the
+# original sed.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported sed invocation and original sed is unavailable" >&2
+  exit 2
+}
+
+# Split a substitution such as s/foo/bar/g into its pattern, replacement and flags. Escaped
+# delimiters are unescaped, other escapes are kept.
+parse() {
+  case "$1" in
+    s?*) ;;
+    *) return 1 ;;
+  esac
+  rest="${1#s}"
+  delim="${rest%"${rest#?}"}"
+  rest="${rest#?}"
+  [ "$delim" != '\' ] || return 1
+  field=0
+  current=""
+  while [ -n "$rest" ]; do
+    c="${rest%"${rest#?}"}"
+    rest="${rest#?}"
+    if [ "$c" = '\' ]; then
+      next="${rest%"${rest#?}"}"
+      rest="${rest#?}"
+      [ -n "$next" ] || return 1
+      if [ "$next" = "$delim" ]; then
+        current="$current$next"
+      else
+        current="$current\\$next"
+      fi
+    elif [ "$c" = "$delim" ]; then
+      if [ "$field" = 0 ]; then
+        pattern="$current"
+        current=""
+        field=1
+      else
+        replacement="$current"
+        flags="$rest"
+        return 0
+      fi
+    else
+      current="$current$c"
+    fi
+  done
+  return 1
+}
+
+# Check that a pattern matches the same text in a single line of input for sed and sd. sd
+# searches whole files at once, so classes that can match a newline aren't translated. In basic
+# expressions, characters such as ( and + are literal unless escaped.
+translatable() {
+  case "$1" in
+    "" | *'\'[0-9]* | *'\<'* | *'\>'* | *'\`'* | *"\\'"* | *'[^'* | *'\s'* | *'[:space:]'*)
+      return 1
+      ;;
+  esac
+  if [ "$extended" = 0 ]; then
+    case "$1" in
+      '*'* | *['(){}|+?']*) return 1 ;;
+    esac
+  fi
+  return 0
+}
+
+# Convert a sed replacement to sd's syntax, where groups are referenced as ${1} and a literal $
+# is written $$.
+convert() {
+  rest="$1"
+  converted=""
+  while [ -n "$rest" ]; do
+    c="${rest%"${rest#?}"}"
+    rest="${rest#?}"
+    case "$c" in
+      '\')
+        next="${rest%"${rest#?}"}"
+        rest="${rest#?}"
+        case "$next" in
+          [0-9]) converted="$converted\${$next}" ;;
+          '&' | '\') converted="$converted$next" ;;
+          *) return 1 ;;
+        esac
+        ;;
+      '&') converted="$converted\${0}" ;;
+      '$') converted="$converted\$\$" ;;
+      *) converted="$converted$c" ;;
+    esac
+  done
+}
+
+# First pass: ensure every option can be translated before consuming the arguments, and find
+# the script.
+inplace=0
+extended=0
+expect_script=0
+script=""
+have_script=0
+operands=0
+options=1
+for arg in "$@"; do
+  if [ "$expect_script" = 1 ]; then
+    expect_script=0
+    script="$arg"
+    continue
+  fi
+  case "$options:$arg" in
+    1:--) options=0 ;;
+    1:--in-place) inplace=1 ;;
+    1:--regexp-extended) extended=1 ;;
+    1:--expression=*)
+      [ "$have_script" = 0 ] || fallback "$@"
+      have_script=1
+      script="${arg#--expression=}"
+      ;;
+    1:--expression)
+      [ "$have_script" = 0 ] || fallback "$@"
+      have_script=1
+      expect_script=1
+      ;;
+    1:--*) fallback "$@" ;;
+    1:-?*)
+      flags="${arg#-}"
+      while [ -n "$flags" ]; do
+        flag="${flags%"${flags#?}"}"
+        flags="${flags#?}"
+        case "$flag" in
+          # Anything following -i is a suffix, e.g. -i.bak, for a backup that sd doesn't write.
+          i)
+            [ -z "$flags" ] || fallback "$@"
+            inplace=1
+            ;;
+          E | r) extended=1 ;;
+          e)
+            [ -z "$flags" ] && [ "$have_script" = 0 ] || fallback "$@"
+            have_script=1
+            expect_script=1
+            ;;
+          *) fallback "$@" ;;
+        esac
+      done
+      ;;
+    *)
+      # Operands that look like options can't be passed to sd unambiguously.
+      case "$arg" in
+        -?*) fallback "$@" ;;
+      esac
+      if [ "$have_script" = 0 ] && [ "$operands" = 0 ]; then
+        script="$arg"
+      fi
+      operands=$((operands + 1))
+      ;;
+  esac
+done
+
+files=$operands
+if [ "$have_script" = 0 ]; then
+  files=$((operands - 1))
+  [ "$operands" -gt 0 ] || fallback "$@"
+fi
+[ "$inplace" = 0 ] || [ "$files" -gt 0 ] || fallback "$@"
+parse "$script" || fallback "$@"
+[ "$flags" = g ] || fallback "$@"
+translatable "$pattern" || fallback "$@"
+convert "$replacement" || fallback "$@"
+
+# Second pass: keep only the files.
+n=$#
+options=1
+skip_script=$((1 - have_script))
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  case "$options:$arg" in
+    1:--) options=0 ;;
+    1:--expression)
+      shift
+      n=$((n - 1))
+      ;;
+    1:-?*)
+      case "$arg" in
+        --*) ;;
+        *e)
+          shift
+          n=$((n - 1))
+          ;;
+      esac
+      ;;
+    *)
+      if [ "$skip_script" = 1 ]; then
+        skip_script=0
+      else
+        set -- "$@" "$arg"
+      fi
+      ;;
+  esac
+done
+
+# sd edits files in place, so files that sed would print are piped through it instead.
+if [ "$inplace" = 1 ]; then
+  exec sd -- "$pattern" "$converted" "$@"
+elif [ "$#" -gt 0 ]; then
+  cat -- "$@" | sd -- "$pattern" "$converted"
+else
+  exec sd -- "$pattern" "$converted"
+fi
@@ -0,0 +1,63 @@
+#!/bin/sh
+# Managed by oxidizr. tar compatibility wrapper for ouch.
+#
+# Translates simple extractions of a compressed archive, e.g. `tar -xzf archive.tar.gz -C dir`,
+# to their ouch equivalents. Any other invocation, including creating or listing archives,
+# extracting selected members or reading from standard input, is passed unmodified to the
+# original tar.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported tar invocation and original tar is unavailable" >&2
+  exit 2
+}
+
+# ouch doesn't restore the owners recorded in an archive, as tar does for root.
+[ "$(id -u)" != 0 ] || fallback "$@"
+
+# The first argument holds the bundled options, with or without a leading dash, and the archive
+# follows it since f must come last.
+[ "$#" -gt 1 ] || fallback "$@"
+flags="${1#-}"
+case "$flags" in
+  *f) ;;
+  *) fallback "$@" ;;
+esac
+extract=0
+verbose=0
+rest="$flags"
+while [ -n "$rest" ]; do
+  flag="${rest%"${rest#?}"}"
+  rest="${rest#?}"
+  case "$flag" in
+    x) extract=1 ;;
+    v) verbose=1 ;;
+    # ouch detects the compression from the archive itself.
+    f | z | j | J) ;;
+    *) fallback "$@" ;;
+  esac
+done
+[ "$extract" = 1 ] || fallback "$@"
+
+archive="$2"
+case "$archive" in
+  -*) fallback "$@" ;;
+  *.tar | *.tar.gz | *.tgz | *.tar.bz2 | *.tbz2 | *.tar.xz | *.txz | *.tar.zst) ;;
+  *) fallback "$@" ;;
+esac
+
+dir="."
+case "$#:$3" in
+  2:) ;;
+  4:-C) dir="$4" ;;
+  3:--directory=*) dir="${3#--directory=}" ;;
+  *) fallback "$@" ;;
+esac
+
+# tar extracts the contents of an archive as they are, and overwrites existing files.
+set -- decompress --yes --no-smart-unpack --dir "$dir"
+[ "$verbose" = 1 ] || set -- "$@" --quiet
+exec ouch "$@" -- "$archive"
@@ -0,0 +1,56 @@
+#!/bin/sh
+# Managed by oxidizr. unzip compatibility wrapper for ouch.
+#
+# Translates extractions of a whole archive, e.g. `unzip -q archive.zip -d dir`, to their ouch
+# equivalents. Any other invocation, including listing archives or extracting selected members,
+# is passed unmodified to the original unzip.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported unzip invocation and original unzip is unavailable" >&2
+  exit 2
+}
+
+quiet=0
+overwrite=0
+dir="."
+archive=""
+expect_dir=0
+for arg in "$@"; do
+  if [ "$expect_dir" = 1 ]; then
+    expect_dir=0
+    dir="$arg"
+    continue
+  fi
+  case "$arg" in
+    -d) expect_dir=1 ;;
+    -q | -qq) quiet=1 ;;
+    -o) overwrite=1 ;;
+    -oq | -qo)
+      quiet=1
+      overwrite=1
+      ;;
+    -*) fallback "$@" ;;
+    *)
+      # Further operands select members to extract.
+      [ -z "$archive" ] || fallback "$@"
+      archive="$arg"
+      ;;
+  esac
+done
+[ "$expect_dir" = 0 ] || fallback "$@"
+
+# unzip also looks for archive.zip when given archive, which isn't translated.
+case "$archive" in
+  *.zip) ;;
+  *) fallback "$@" ;;
+esac
+
+# Without -o, ouch asks before overwriting files, as unzip does.
+set -- decompress --no-smart-unpack --dir "$dir"
+[ "$quiet" = 0 ] || set -- "$@" --quiet
+[ "$overwrite" = 0 ] || set -- "$@" --yes
+exec ouch "$@" -- "$archive"
@@ -0,0 +1,3 @@
+#!/bin/sh
+# Managed by oxidizr. vdir compatibility wrapper for eza, equivalent to 'ls -l'.
+exec /usr/lib/oxidizr/wrappers/ls -l "$@"
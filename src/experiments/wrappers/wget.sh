@@ -0,0 +1,121 @@
+#!/bin/sh
+# Managed by oxidizr. wget compatibility wrapper for xh.
+#
+# Translates the most commonly used wget options to their xh equivalents, including bundled
+# short options such as -qO-. Any invocation using an option that is not translated is passed
+# unmodified to the original wget.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported wget invocation and original wget is unavailable" >&2
+  exit 2
+}
+
+# Split bundled short options, such as -qO- or -qc, into separate arguments.
+expect_value=0
+n=$#
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  if [ "$expect_value" = 1 ]; then
+    expect_value=0
+    set -- "$@" "$arg"
+    continue
+  fi
+  while :; do
+    case "$arg" in
+      -[qc]?*)
+        rest="${arg#??}"
+        set -- "$@" "${arg%"$rest"}"
+        arg="-$rest"
+        ;;
+      *) break ;;
+    esac
+  done
+  case "$arg" in
+    -O?*) set -- "$@" -O "${arg#-O}" ;;
+    -O | --header | -U | --user-agent)
+      expect_value=1
+      set -- "$@" "$arg"
+      ;;
+    *) set -- "$@" "$arg" ;;
+  esac
+done
+
+# First pass: ensure every option can be translated before consuming the arguments.
+expect_value=0
+for arg in "$@"; do
+  if [ "$expect_value" = 1 ]; then
+    expect_value=0
+    continue
+  fi
+  case "$arg" in
+    -q | --quiet | -c | --continue | --header=* | --user-agent=* | --output-document=*) ;;
+    -O | --header | -U | --user-agent) expect_value=1 ;;
+    -*) fallback "$@" ;;
+  esac
+done
+
+output=""
+items=""
+url=""
+n=$#
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  case "$arg" in
+    -q | --quiet) set -- "$@" --quiet ;;
+    -c | --continue) set -- "$@" --continue ;;
+    -O)
+      output="$1"
+      shift
+      n=$((n - 1))
+      ;;
+    --output-document=*) output="${arg#--output-document=}" ;;
+    --header)
+      items="${items}$(printf '%s' "$1" | sed 's/: */:/')
+"
+      shift
+      n=$((n - 1))
+      ;;
+    --header=*)
+      items="${items}$(printf '%s' "${arg#--header=}" | sed 's/: */:/')
+"
+      ;;
+    -U | --user-agent)
+      items="${items}User-Agent:$1
+"
+      shift
+      n=$((n - 1))
+      ;;
+    --user-agent=*)
+      items="${items}User-Agent:${arg#--user-agent=}
+"
+      ;;
+    *) url="$arg" ;;
+  esac
+done
+
+# wget follows redirects and writes the response body to a file by default.
+set -- "$@" --ignore-stdin --follow
+case "$output" in
+  "") set -- "$@" --download ;;
+  -) set -- "$@" --print=b ;;
+  *) set -- "$@" --download --output "$output" ;;
+esac
+set -- "$@" "$url"
+
+old_ifs="$IFS"
+IFS='
+'
+for item in $items; do
+  set -- "$@" "$item"
+done
+IFS="$old_ifs"
+
+exec xh "$@"
@@ -0,0 +1,56 @@
+#!/bin/sh
+# Managed by oxidizr. xxd compatibility wrapper for hexyl.
+#
+# Shows dumps, e.g. `xxd file`, with hexyl when writing to a terminal. Output that is piped or
+# redirected, which may be reverted with `xxd -r`, and any invocation using an option that is not
+# translated, such as the plain hex of `xxd -p`, is handled by the original xxd.
+original="@ORIGINAL@"
+
+fallback() {
+  if [ -x "$original" ]; then
+    exec "$original" "$@"
+  fi
+  echo "oxidizr: unsupported xxd invocation and original xxd is unavailable" >&2
+  exit 2
+}
+
+[ -t 1 ] || fallback "$@"
+
+# First pass: ensure every option can be translated, and at most one file is given, since a
+# second operand is the file xxd writes to.
+expect_value=0
+operands=0
+for arg in "$@"; do
+  if [ "$expect_value" = 1 ]; then
+    expect_value=0
+    continue
+  fi
+  case "$arg" in
+    -l | -s) expect_value=1 ;;
+    -*) fallback "$@" ;;
+    *) operands=$((operands + 1)) ;;
+  esac
+done
+[ "$operands" -le 1 ] || fallback "$@"
+
+n=$#
+while [ "$n" -gt 0 ]; do
+  arg="$1"
+  shift
+  n=$((n - 1))
+  case "$arg" in
+    -l)
+      set -- "$@" "--length=$1"
+      shift
+      n=$((n - 1))
+      ;;
+    -s)
+      set -- "$@" "--skip=$1"
+      shift
+      n=$((n - 1))
+      ;;
+    *) set -- "$@" "$arg" ;;
+  esac
+done
+
+exec hexyl "$@"
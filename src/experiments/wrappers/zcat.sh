@@ -0,0 +1,3 @@
+#!/bin/sh
+# Managed by oxidizr. zcat compatibility wrapper for crabz, equivalent to 'gzip -dc'.
+exec /usr/lib/oxidizr/wrappers/gzip -dc "$@"
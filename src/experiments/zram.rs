@@ -0,0 +1,212 @@
+use crate::experiments::ManagedLink;
+use crate::utils::Worker;
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const PACKAGE: &str = "systemd-zram-generator";
+
+/// Configuration read by zram-generator, which creates a swap unit for each device it lists.
+const CONFIG: &str = "/etc/systemd/zram-generator.conf";
+
+const CONFIG_MARKER: &str =
+    "# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.";
+
+/// A compressed swap device of half the memory, up to 8 GiB, preferred over any swap on disk.
+const CONFIG_CONTENTS: &str = r#"# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.
+[zram0]
+zram-size = min(ram / 2, 8192)
+compression-algorithm = zstd
+swap-priority = 100
+"#;
+
+/// The swap unit generated for the device, which pulls in the service formatting it.
+const SWAP_UNIT: &str = "dev-zram0.swap";
+
+/// The service that formats the device, and resets it when stopped.
+const SETUP_SERVICE: &str = "systemd-zram-setup@zram0.service";
+
+/// An experiment to install zram-generator and use it to provide compressed swap in memory.
+pub struct ZramExperiment<'a> {
+    system: &'a dyn Worker,
+}
+
+impl<'a> ZramExperiment<'a> {
+    /// Create a new ZramExperiment.
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self { system }
+    }
+
+    /// Check if the system is compatible with the experiment.
+    pub fn check_compatible(&self) -> bool {
+        self.supported_releases().contains(
+            &self
+                .system
+                .distribution()
+                .expect("unable to determine distribution information")
+                .release,
+        )
+    }
+
+    /// Reports the supported releases for the experiment.
+    pub fn supported_releases(&self) -> Vec<String> {
+        vec![
+            "24.04".to_string(),
+            "24.10".to_string(),
+            "25.04".to_string(),
+        ]
+    }
+
+    /// Check if the package is installed.
+    pub fn check_installed(&self) -> bool {
+        self.system.check_installed(PACKAGE).unwrap_or(false)
+    }
+
+    /// Report the package installed by the experiment.
+    pub fn package(&self) -> String {
+        PACKAGE.to_string()
+    }
+
+    /// Report the name of the experiment.
+    pub fn name(&self) -> String {
+        String::from("zram")
+    }
+
+    /// Enable the experiment by installing zram-generator, configuring a compressed swap device
+    /// and activating it. An existing configuration written by the administrator is never
+    /// replaced.
+    pub fn enable(&self) -> Result<()> {
+        if let Ok(contents) = self.system.read_file(PathBuf::from(CONFIG)) {
+            anyhow::ensure!(
+                contents.starts_with(CONFIG_MARKER),
+                "{CONFIG} already exists, so zram-generator is already configured"
+            );
+        }
+
+        info!("Installing and configuring {}", PACKAGE);
+        self.system.install_package(PACKAGE)?;
+        self.system
+            .write_file(PathBuf::from(CONFIG), CONFIG_CONTENTS, 0o644)?;
+
+        info!("Activating compressed swap on /dev/zram0");
+        self.system.reload_service_manager()?;
+        if let Err(e) = self.system.start_service(SWAP_UNIT) {
+            warn!("Removing the zram-generator configuration");
+            self.unconfigure()?;
+            self.system.remove_package(PACKAGE)?;
+            return Err(e.context("Failed to activate swap on /dev/zram0"));
+        }
+        Ok(())
+    }
+
+    /// Disable the experiment by deactivating the swap device, removing the configuration and
+    /// removing the package.
+    pub fn disable(&self) -> Result<()> {
+        info!("Deactivating compressed swap on /dev/zram0");
+        self.unconfigure()?;
+
+        info!("Removing {}", PACKAGE);
+        self.system.remove_package(PACKAGE)?;
+
+        Ok(())
+    }
+
+    /// List the symlinks the experiment manages. The experiment manages a swap device and its
+    /// configuration rather than symlinks.
+    pub fn managed_links(&self) -> Result<Vec<ManagedLink>> {
+        Ok(Vec::new())
+    }
+
+    /// Deactivate the swap device and reset it, then remove the configuration so it isn't
+    /// generated again.
+    fn unconfigure(&self) -> Result<()> {
+        for unit in [SWAP_UNIT, SETUP_SERVICE] {
+            if let Err(e) = self.system.stop_service(unit) {
+                warn!("Unable to stop {unit}: {e}");
+            }
+        }
+        self.system.remove_file(PathBuf::from(CONFIG))?;
+        self.system.reload_service_manager()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_zram_enable() {
+        let runner = MockSystem::default();
+        let zram = ZramExperiment::new(&runner);
+
+        assert!(zram.enable().is_ok());
+
+        assert_eq!(
+            runner.read_file(PathBuf::from(CONFIG)).unwrap(),
+            CONFIG_CONTENTS
+        );
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "apt-get install -y systemd-zram-generator",
+                "systemctl daemon-reload",
+                "systemctl start dev-zram0.swap",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zram_enable_existing_config() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(CONFIG, "[zram0]\nzram-size = ram\n", false)]);
+        let zram = ZramExperiment::new(&runner);
+
+        assert!(zram.enable().is_err());
+
+        assert!(runner.commands.clone().into_inner().is_empty());
+        assert_eq!(
+            runner.read_file(PathBuf::from(CONFIG)).unwrap(),
+            "[zram0]\nzram-size = ram\n"
+        );
+    }
+
+    #[test]
+    fn test_zram_enable_swap_failure() {
+        let runner = MockSystem::default();
+        runner.mock_command_failure("systemctl start dev-zram0.swap");
+        let zram = ZramExperiment::new(&runner);
+
+        assert!(zram.enable().is_err());
+
+        assert!(!runner.file_exists(PathBuf::from(CONFIG)));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands.last().unwrap(),
+            "apt-get remove -y systemd-zram-generator"
+        );
+    }
+
+    #[test]
+    fn test_zram_disable() {
+        let runner = MockSystem::default();
+        runner.mock_install_package("systemd-zram-generator");
+        runner.mock_files(vec![(CONFIG, CONFIG_CONTENTS, false)]);
+        let zram = ZramExperiment::new(&runner);
+
+        assert!(zram.disable().is_ok());
+
+        assert!(!runner.file_exists(PathBuf::from(CONFIG)));
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "systemctl stop dev-zram0.swap",
+                "systemctl stop systemd-zram-setup@zram0.service",
+                "systemctl daemon-reload",
+                "apt-get remove -y systemd-zram-generator",
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 //! A command-line utility for managing system experiments that replace traditional Unix utilities
-//! with modern Rust-based alternatives on Ubuntu systems.
+//! with modern Rust-based alternatives on Ubuntu, Debian, Fedora, Arch Linux, openSUSE and Alpine Linux systems.
 //!
 //! # Overview
 //! This utility allows users to replace traditional Unix utilities (like coreutils, findutils,
@@ -26,23 +26,47 @@
 //! This utility can make significant system changes that might affect system stability
 //! and functionality. Users should proceed with caution and understand the implications
 //! of replacing system utilities.
+pub mod commands;
+pub mod config;
 pub mod experiments;
+pub mod state;
 pub mod utils;
 
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::Shell;
 use clap_verbosity_flag::{InfoLevel, Verbosity};
-use experiments::{Experiment, all_experiments};
-use inquire::Confirm;
+use commands::{
+    CheckFormat, ConfigKey, DEFAULT_LOCKFILE, ExportedState, FactsFormat, GenerateFormat,
+    Implementation, Lockfile, PlanAction, ReportFormat, Selection, Thresholds, backup_checksums,
+    benchmark, check, choose, compare, compat_scan, completions, config_get, config_list,
+    config_set, config_unset, doctor, export_state, facts, generate, history, list, lock, man,
+    parse_duration, pin, plan, promote, purge_backups, purge_summary, purgeable_backups, recover,
+    refresh, repair, report, restore_originals, restore_recorded, restore_snapshot, rollback,
+    self_test, snapshot, switch, sysext, trial, try_shell, undo_enable, unpin, upgrade, utility,
+    verify,
+};
+use config::{APT_OPTIONS_ENV, Config, PROXY_ENV};
+use experiments::compat::HookConflicts;
+use experiments::{Experiment, all_experiments, expand_bundles};
+use inquire::{Confirm, Text};
+use state::{Operation, OperationKind, PackageRecord, State};
 use tracing::{info, warn};
 use tracing_subscriber::{fmt, prelude::*};
-use utils::{System, Worker, vecs_eq};
+use utils::{
+    Distribution, DryRun, Etckeeper, ImmutableSystem, JOURNAL_FILE, Journal, Progress,
+    SUPPORTED_DISTRIBUTIONS, System, Worker, fallback_session_available, has_backup,
+    original_packages, owning_package, remote_session, services_needing_restart, vecs_eq,
+};
 
 /// A command-line utility to install modern Rust-based replacements of essential
 /// packages such as coreutils, findutils, diffutils and sudo and make them the
-/// default on an Ubuntu system.
+/// default on an Ubuntu, Debian, Fedora, Arch Linux, openSUSE or Alpine Linux system.
 #[derive(Debug, Parser)]
 #[command(version, about, long_about)]
 struct Args {
@@ -63,7 +87,7 @@ struct Args {
         long,
         default_value_t = false,
         global = true,
-        help = "Enable/disable all known experiments"
+        help = "Enable/disable all known experiments (opt-in experiments are only enabled if selected explicitly)"
     )]
     all: bool,
 
@@ -75,16 +99,40 @@ struct Args {
     )]
     no_compatibility_check: bool,
 
+    #[arg(
+        long,
+        global = true,
+        value_name = "RELEASE",
+        help = "Treat the system as this Ubuntu release, e.g. 24.04, for derivatives that aren't recognised"
+    )]
+    treat_as: Option<String>,
+
     #[arg(
         short,
         long,
         global = true,
         num_args = 1..,
         default_values_t = default_experiments(),
-        help = "Select experiments to enable or disable"
+        help = "Select experiments, or bundles of experiments, to enable or disable"
     )]
     experiments: Vec<String>,
 
+    #[arg(
+        long,
+        global = true,
+        value_name = "FD",
+        help = "Write machine-readable progress records to file descriptor FD"
+    )]
+    status_fd: Option<i32>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        global = true,
+        help = "Restart services still using replaced binaries without prompting"
+    )]
+    restart_services: bool,
+
     #[command(subcommand)]
     cmd: Commands,
 }
@@ -92,83 +140,1059 @@ struct Args {
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Enable experiments with oxidizr.
-    Enable,
+    Enable(EnableArgs),
     /// Disable any previous experiments enabled with oxidizr.
-    Disable,
+    Disable {
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Restore backups over files that were changed after oxidizr replaced them"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Install the packages that shipped the replaced files at the versions they were at when the experiments were enabled"
+        )]
+        restore_versions: bool,
+    },
+    /// Preview the commands and file changes that `enable` or `disable` would make.
+    Plan {
+        #[arg(value_enum)]
+        action: PlanAction,
+    },
+    /// Generate configuration management code reproducing the enabled experiments.
+    Generate {
+        #[arg(value_enum)]
+        format: GenerateFormat,
+    },
+    /// Check that enabled experiments are still in place, for use as a monitoring plugin.
+    Check(CheckArgs),
+    /// Print facts about the enabled experiments for configuration management tools.
+    Facts {
+        #[arg(long, value_enum, default_value_t = FactsFormat::KeyValue, help = "Output format")]
+        format: FactsFormat,
+    },
+    /// Describe every file managed by oxidizr, with its backup, symlink target and package.
+    Report {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text, help = "Output format")]
+        format: ReportFormat,
+    },
+    /// Compare the behaviour of a replaced tool with the original it was backed up from.
+    Compare {
+        /// The tool to compare, e.g. `ls`.
+        tool: String,
+    },
+    /// Scan scripts for flags and features the Rust replacements don't support.
+    CompatScan {
+        /// Scripts or directories of scripts to scan. Defaults to the cron, local bin and
+        /// kernel hook directories.
+        paths: Vec<PathBuf>,
+    },
+    /// Time representative workloads against the original and Rust implementations.
+    Benchmark {
+        #[arg(
+            long,
+            default_value_t = 3,
+            help = "Number of times to run each workload, reporting the fastest"
+        )]
+        runs: usize,
+    },
+    /// Rehearse enabling experiments in a throwaway systemd-nspawn container.
+    Trial {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Boot the container from a disk image instead of a copy of the host's root"
+        )]
+        image: Option<PathBuf>,
+    },
+    /// Start a shell in which the Rust replacements come first in PATH, without changing the system.
+    Try,
+    /// Write a lockfile pinning the package versions and checksums of the selected experiments.
+    Lock {
+        #[arg(
+            long,
+            short,
+            value_name = "FILE",
+            default_value = DEFAULT_LOCKFILE,
+            help = "Where to write the lockfile"
+        )]
+        output: PathBuf,
+    },
+    /// Link the remaining binaries of experiments enabled with `--canary`.
+    Promote {
+        /// Experiments to promote. Defaults to all canaries awaiting promotion.
+        experiments: Vec<String>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Only promote canaries whose soak period has elapsed"
+        )]
+        if_soaked: bool,
+    },
+    /// Diagnose problems left behind by interrupted runs, and print how to fix them.
+    Doctor,
+    /// Run sanity checks against the replaced utilities, reporting whether each passed.
+    SelfTest,
+    /// Verify that managed symlinks and backups are intact, exiting non-zero if they aren't.
+    Verify,
+    /// List every known experiment, and whether it's compatible with and enabled on this system.
+    List,
+    /// Undo every action recorded by `enable` for the experiments still enabled.
+    Rollback,
+    /// Revert the most recent `enable` or `disable`, leaving earlier runs untouched.
+    Undo,
+    /// Show past runs of `enable`, `disable` and `repair`, with the package versions involved.
+    History {
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "List the files changed by each run"
+        )]
+        files: bool,
+    },
+    /// Switch a single utility between its Rust replacement and the original.
+    Switch {
+        /// The utility to switch, e.g. `date`.
+        utility: String,
+
+        #[arg(long, value_enum, help = "The implementation to switch to")]
+        to: Implementation,
+    },
+    /// Restore replaced files from their backups without using apt, e.g. when apt is broken.
+    Recover,
+    /// Recreate symlinks of enabled experiments that are missing or point elsewhere.
+    Repair,
+    /// Link utilities newly added to enabled experiments by package upgrades.
+    Refresh,
+    /// Report experiments that have become supported since a release upgrade.
+    Upgrade {
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Enable the newly supported experiments"
+        )]
+        enable: bool,
+    },
+    /// Remove the backups of files replaced by experiments, to reclaim space.
+    PurgeBackups,
+    /// Hold the packages of enabled experiments, so apt can't upgrade or remove them.
+    Pin,
+    /// Release the holds placed on the packages of enabled experiments by `pin`.
+    Unpin,
+    /// Snapshot the root filesystem with snapper, Timeshift, ZFS or LVM.
+    Snapshot {
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Revert the root filesystem to the snapshot most recently taken by oxidizr"
+        )]
+        restore: bool,
+    },
+    /// Write the enabled experiments, their package versions and excluded utilities to a file.
+    ExportState {
+        /// Where to write the state, as TOML if it ends in `.toml` and as JSON otherwise.
+        output: PathBuf,
+    },
+    /// Enable and disable experiments to converge on a state written by `export-state`.
+    ImportState {
+        /// The file written by `export-state`.
+        file: PathBuf,
+
+        #[command(flatten)]
+        opts: EnableArgs,
+    },
+    /// Choose the experiments and utilities to enable from an interactive checklist.
+    Tui {
+        #[command(flatten)]
+        opts: EnableArgs,
+    },
+    /// Print a shell completion script, e.g. for `/usr/share/bash-completion/completions`.
+    Completions {
+        /// The shell to generate completions for.
+        shell: Shell,
+    },
+    /// Print the oxidizr(8) manual page.
+    Man,
+    /// View and change persistent settings in /etc/oxidizr/config.toml.
+    Config {
+        #[command(subcommand)]
+        cmd: ConfigCommand,
+    },
+    /// Manage systemd system extension images providing the Rust replacements.
+    Sysext {
+        #[command(subcommand)]
+        cmd: SysextCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print every setting that is set.
+    List,
+    /// Print the value of a setting.
+    Get { key: ConfigKey },
+    /// Change a setting. List settings, such as `experiments`, take each item as a value.
+    Set {
+        key: ConfigKey,
+        #[arg(required = true, num_args = 1..)]
+        values: Vec<String>,
+    },
+    /// Reset a setting to its default.
+    Unset { key: ConfigKey },
+}
+
+#[derive(Subcommand, Debug)]
+enum SysextCommand {
+    /// Build an image overlaying the Rust replacements of the selected experiments onto /usr.
+    Build {
+        #[arg(
+            long,
+            short,
+            value_name = "FILE",
+            default_value = "oxidizr.raw",
+            help = "Where to write the image"
+        )]
+        output: PathBuf,
+    },
+}
+
+impl Commands {
+    /// Report whether the command makes changes to the system, and so must be run as root.
+    fn requires_root(&self) -> bool {
+        matches!(
+            self,
+            Commands::Enable(_)
+                | Commands::Disable { .. }
+                | Commands::Trial { .. }
+                | Commands::Promote { .. }
+                | Commands::Rollback
+                | Commands::Undo
+                | Commands::Switch { .. }
+                | Commands::Recover
+                | Commands::Repair
+                | Commands::Refresh
+                | Commands::Upgrade { enable: true }
+                | Commands::PurgeBackups
+                | Commands::Pin
+                | Commands::Unpin
+                | Commands::Snapshot { .. }
+                | Commands::Config {
+                    cmd: ConfigCommand::Set { .. } | ConfigCommand::Unset { .. }
+                }
+                | Commands::ImportState { .. }
+                | Commands::Tui { .. }
+        )
+    }
+}
+
+#[derive(clap::Args, Clone, Debug, Default)]
+struct EnableArgs {
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Fail if any selected experiment is skipped as incompatible"
+    )]
+    strict: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Replace sudo/su over SSH without an extra confirmation (dangerous)"
+    )]
+    force_remote: bool,
+
+    #[arg(
+        long,
+        visible_alias = "deb-dir",
+        value_name = "DIR",
+        help = "Install packages from local .deb files in DIR instead of the archive"
+    )]
+    from_debs: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "from_debs",
+        help = "Don't use the network, installing packages from the .deb files given with --from-debs"
+    )]
+    offline: bool,
+
+    #[arg(
+        long,
+        value_name = "CHANNEL",
+        num_args = 0..=1,
+        default_missing_value = "stable",
+        conflicts_with = "from_debs",
+        help = "Install experiments available as snaps from CHANNEL of the snap store [default: stable]"
+    )]
+    from_snaps: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["from_debs", "from_snaps"],
+        help = "Download experiments available as prebuilt releases from GitHub, verifying their checksums"
+    )]
+    from_releases: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_LOCKFILE,
+        help = "Install the exact package versions recorded in a lockfile (default: oxidizr.lock)"
+    )]
+    locked: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "BINARY",
+        num_args = 1..,
+        help = "Leave the given utilities unreplaced, e.g. 'cp' if kernel hooks rely on 'cp -Z'"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "EXPERIMENT",
+        num_args = 1..,
+        help = "Enable high-risk experiments with --yes, without typing their name to confirm"
+    )]
+    accept_risk: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Link only a low-risk subset of binaries until the experiment is promoted"
+    )]
+    canary: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "24h",
+        value_parser = parse_duration,
+        help = "How long a canary soaks before `promote --if-soaked` promotes it (e.g. 30m, 24h, 7d)"
+    )]
+    soak: u64,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Compress backups of the original binaries with zstd into /var/lib/oxidizr/backups"
+    )]
+    compress_backups: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "compress_backups",
+        help = "Move the original binaries aside with dpkg-divert, so package upgrades don't undo the replacement"
+    )]
+    divert: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["canary", "compress_backups", "divert", "exclude"],
+        help = "Provide the experiments with a merged systemd system extension image, rather than replacing files"
+    )]
+    via_sysext: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Snapshot the root filesystem before changing anything, for `snapshot --restore`"
+    )]
+    snapshot: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct CheckArgs {
+    #[arg(long, value_enum, default_value_t = CheckFormat::Text, help = "Output format")]
+    format: CheckFormat,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 1,
+        help = "Number of drifted binaries at which to report a warning"
+    )]
+    warning: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 10,
+        help = "Number of drifted binaries at which to report a critical status"
+    )]
+    critical: usize,
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
 
-    // The application must run as root - exit immediately if it's not.
+    // Commands that change the system must run as root - exit immediately if not.
     anyhow::ensure!(
-        uzers::get_current_uid() == 0,
+        !args.cmd.requires_root() || uzers::get_current_uid() == 0,
         "This program must be run as root"
     );
 
     // Initialise the tracing system to enable nice logging. Take into account the verbosity
-    // specified at the command line.
+    // specified at the command line. Logs are written to stderr so they don't interfere with
+    // the output of commands that print configuration or reports.
     tracing_subscriber::registry()
         .with(args.verbose.tracing_level_filter())
-        .with(fmt::layer().compact().with_target(false))
+        .with(
+            fmt::layer()
+                .compact()
+                .with_target(false)
+                .with_writer(std::io::stderr),
+        )
         .init();
 
+    // Completions and the manual page are generated when packaging, which needn't happen on Ubuntu.
+    if let Commands::Completions { shell } = args.cmd {
+        let names = all_experiments(&System::new()?)
+            .iter()
+            .map(|e| e.name())
+            .collect();
+        return completions(Args::command(), shell, names, &mut std::io::stdout());
+    }
+    if let Commands::Man = args.cmd {
+        return man(Args::command(), &mut std::io::stdout());
+    }
+
     // Initialise the system, gather system information.
-    let system = System::new()?;
+    let mut system = System::new()?;
+    if let Some(release) = &args.treat_as {
+        system = system.with_distribution(Distribution {
+            id: "Ubuntu".to_string(),
+            release: release.clone(),
+        });
+    }
+    // Undoing a `disable` enables the experiments again, which is journaled like `enable`.
+    let undoing_disable = matches!(args.cmd, Commands::Undo)
+        && State::load(&system)?
+            .last_undoable()
+            .is_some_and(|o| o.kind == OperationKind::Disable);
+    if undoing_disable
+        || matches!(
+            args.cmd,
+            Commands::Enable(_)
+                | Commands::ImportState { .. }
+                | Commands::Tui { .. }
+                | Commands::Promote { .. }
+                | Commands::Repair
+                | Commands::Refresh
+                | Commands::Upgrade { enable: true }
+        )
+    {
+        system = system.with_journal(PathBuf::from(JOURNAL_FILE));
+    }
+    // Settings in the configuration file apply unless overridden on the command line.
+    let mut config = Config::load(&system)?;
+    config.override_network(
+        std::env::var(PROXY_ENV).ok(),
+        std::env::var(APT_OPTIONS_ENV).ok(),
+    )?;
+    if let Some(dir) = config.backup_dir {
+        system = system.with_backup_dir(dir);
+    }
+    if let Some(proxy) = config.proxy {
+        system = system.with_proxy(proxy);
+    }
+    system = system.with_apt_options(config.apt_options);
+    if let Some(experiments) = config.experiments
+        && !args.all
+        && matches.value_source("experiments") == Some(ValueSource::DefaultValue)
+    {
+        args.experiments = experiments;
+    }
+    args.yes |= config.yes;
+    if let Commands::Enable(opts) | Commands::ImportState { opts, .. } | Commands::Tui { opts } =
+        &mut args.cmd
+    {
+        opts.exclude.extend(config.exclude);
+        // Diversions and system extensions don't take backups to compress.
+        opts.compress_backups |= config.compress_backups && !opts.divert && !opts.via_sysext;
+    }
+    if let Commands::Enable(opts) | Commands::ImportState { opts, .. } | Commands::Tui { opts } =
+        &args.cmd
+    {
+        match (&opts.from_debs, opts.offline) {
+            (Some(dir), true) => system = system.with_offline(dir.clone()),
+            (Some(dir), false) => system = system.with_local_packages(dir.clone()),
+            (None, _) => {}
+        }
+        if let Some(channel) = &opts.from_snaps {
+            system = system.with_snaps(channel.clone());
+        }
+        if opts.from_releases {
+            system = system.with_releases();
+        }
+        if opts.compress_backups {
+            anyhow::ensure!(
+                system.which("zstd").is_ok(),
+                "zstd must be installed to compress backups"
+            );
+            system = system.with_compressed_backups();
+        }
+        if opts.divert {
+            anyhow::ensure!(
+                system.which("dpkg-divert").is_ok(),
+                "dpkg-divert must be installed to divert the original binaries"
+            );
+            system = system.with_diversions();
+        }
+        if let Some(file) = &opts.locked {
+            let versions = Lockfile::load(&system, file.clone())?.versions();
+            system = system.with_pinned_versions(versions);
+        }
+    }
+    // Unless a lockfile is given, experiments are imported at the exported package versions.
+    if let Commands::ImportState { file, opts } = &args.cmd
+        && opts.locked.is_none()
+    {
+        let versions = ExportedState::load(&system, file)?.versions();
+        system = system.with_pinned_versions(versions);
+    }
 
-    // Exit if the application is run on a non-Ubuntu machine (unless compatibility check is skipped).
+    // Exit if the application is run on an unsupported distribution (unless compatibility check
+    // is skipped).
     if !args.no_compatibility_check {
         anyhow::ensure!(
-            system.distribution()?.id == "Ubuntu",
-            "This program only supports Ubuntu"
+            system.distribution()?.is_supported(),
+            "This program only supports {}",
+            SUPPORTED_DISTRIBUTIONS.join(" and ")
         );
-    } else if system.distribution()?.id != "Ubuntu" {
+    } else if !system.distribution()?.is_supported() {
         warn!(
-            "Running on a non-Ubuntu distribution. This is unsupported and may cause system instability."
+            "Running on an unsupported distribution. This is unsupported and may cause system instability."
         );
     }
 
-    // Get selected experiments from the command line arguments
-    let selected = selected_experiments(args.all, args.experiments.clone(), &system);
-
     // Handle subcommands
-    match args.cmd {
-        Commands::Enable => enable(&system, selected, args.yes, args.no_compatibility_check),
-        Commands::Disable => disable(selected, args.yes),
+    match &args.cmd {
+        Commands::Enable(opts) => {
+            let selected = selected_experiments(args.all, args.experiments.clone(), false, &system);
+            // The package list update is counted as an extra step.
+            let mut progress = Progress::new(args.status_fd, selected.len() + 1)?;
+            enable(&system, selected, &args, opts, &mut progress)
+        }
+        Commands::Disable {
+            force,
+            restore_versions,
+        } => {
+            // Opt-in experiments are only considered by `--all` when disabling.
+            let selected = selected_experiments(args.all, args.experiments.clone(), true, &system);
+            let mut progress = Progress::new(args.status_fd, selected.len())?;
+            disable(
+                &system,
+                selected,
+                &args,
+                *force,
+                *restore_versions,
+                &mut progress,
+            )
+        }
+        Commands::Plan { action } => {
+            let dry_run = DryRun::new(&system);
+            let include_opt_in = *action == PlanAction::Disable;
+            let selected =
+                selected_experiments(args.all, args.experiments.clone(), include_opt_in, &dry_run);
+            plan(&dry_run, &selected, *action, args.no_compatibility_check)
+        }
+        Commands::Generate { format } => generate(&system, *format),
+        Commands::Check(opts) => {
+            let thresholds = Thresholds {
+                warning: opts.warning,
+                critical: opts.critical,
+            };
+            std::process::exit(check(&system, opts.format, thresholds))
+        }
+        Commands::Facts { format } => facts(&system, *format),
+        Commands::Report { format } => report(&system, *format),
+        Commands::Compare { tool } => compare(&system, tool),
+        Commands::Benchmark { runs } => benchmark(&system, *runs),
+        Commands::CompatScan { paths } => compat_scan(&system, paths),
+        Commands::Trial { image } => {
+            let selected = selected_experiments(args.all, args.experiments.clone(), false, &system);
+            trial(
+                &system,
+                &selected,
+                image.clone(),
+                args.no_compatibility_check,
+            )
+        }
+        Commands::Lock { output } => {
+            let selected = selected_experiments(args.all, args.experiments.clone(), false, &system);
+            lock(&system, &selected, output.clone())
+        }
+        Commands::Promote {
+            experiments,
+            if_soaked,
+        } => promote(&system, experiments, *if_soaked),
+        Commands::Try => {
+            let selected = selected_experiments(args.all, args.experiments.clone(), false, &system);
+            try_shell(&system, &selected)
+        }
+        Commands::Doctor => doctor(&system),
+        Commands::List => list(&system),
+        Commands::Verify => verify(&system),
+        Commands::SelfTest => self_test(&system),
+        Commands::Switch { utility, to } => switch(&system, utility, *to),
+        Commands::Recover => {
+            confirm_or_exit(args.yes);
+            recover(&system)
+        }
+        Commands::Repair => repair(&system),
+        Commands::Refresh => refresh(&system),
+        Commands::Upgrade { enable } => {
+            let available = upgrade(&system)?;
+            match *enable && !available.is_empty() {
+                true => converge(&system, available, vec![], &args, &EnableArgs::default()),
+                false => Ok(()),
+            }
+        }
+        Commands::Pin => pin(&system),
+        Commands::Unpin => unpin(&system),
+        Commands::Snapshot { restore: false } => snapshot(&system),
+        Commands::Snapshot { restore: true } => {
+            confirm_or_exit(args.yes);
+            restore_snapshot(&system)
+        }
+        Commands::PurgeBackups => {
+            let selected = selected_experiments(args.all, args.experiments.clone(), true, &system);
+            let backups = purgeable_backups(&system, &selected)?;
+            if backups.is_empty() {
+                info!("No backups to purge");
+                return Ok(());
+            }
+            print!("{}", purge_summary(&backups));
+            confirm_or_exit(args.yes);
+            purge_backups(&system, &backups)
+        }
+        Commands::Undo => undo(&system, &args),
+        Commands::History { files } => history(&system, *files),
+        Commands::Config { cmd } => match cmd {
+            ConfigCommand::List => config_list(&system),
+            ConfigCommand::Get { key } => config_get(&system, *key),
+            ConfigCommand::Set { key, values } => config_set(&system, *key, values),
+            ConfigCommand::Unset { key } => config_unset(&system, *key),
+        },
+        Commands::Rollback => {
+            confirm_or_exit(args.yes);
+            rollback(&system)
+        }
+        Commands::Completions { .. } | Commands::Man => unreachable!("generated above"),
+        Commands::ExportState { output } => export_state(&system, output.clone()),
+        Commands::ImportState { file, opts } => import_state(&system, file, &args, opts),
+        Commands::Tui { opts } => tui(&system, &args, opts),
+        Commands::Sysext {
+            cmd: SysextCommand::Build { output },
+        } => {
+            let selected = selected_experiments(args.all, args.experiments.clone(), false, &system);
+            sysext::build(&system, &selected, output.clone())
+        }
     }
 }
 
-/// Enables selected experiments
+/// Enables selected experiments. If `--strict` is set, an error is returned if any of the
+/// experiments were skipped.
 fn enable(
     system: &impl Worker,
     experiments: Vec<Experiment>,
-    yes: bool,
-    no_compatibility_check: bool,
+    args: &Args,
+    opts: &EnableArgs,
+    progress: &mut Progress,
 ) -> Result<()> {
-    confirm_or_exit(yes);
+    confirm_or_exit(args.yes);
+    confirm_high_risk(&experiments, args.yes, &opts.accept_risk)?;
 
-    info!("Updating apt package cache");
-    system.update_package_lists()?;
+    if !opts.force_remote && experiments.iter().any(|e| e.affects_privilege_escalation()) {
+        confirm_remote_session(system)?;
+    }
 
-    for e in experiments.iter() {
-        e.enable(no_compatibility_check)?;
+    // The actions this run takes are recorded after the journal's existing entries.
+    let mut operation = Operation::new(OperationKind::Enable, Some(Journal::load(system)?.len()));
+
+    let etckeeper = Etckeeper::detect(system);
+    if let Some(etckeeper) = &etckeeper {
+        etckeeper.pre_commit()?;
+    }
+
+    if opts.snapshot {
+        snapshot(system)?;
+    }
+
+    // Immutable systems can't have files in /usr replaced, so the experiments are provided by a
+    // system extension image where the system supports one, and refused otherwise.
+    let immutable = ImmutableSystem::detect(system);
+    if let Some(immutable) = &immutable {
+        check_immutable(immutable, &experiments, opts)?;
+    }
+    let via_sysext = opts.via_sysext || immutable.is_some();
+
+    // Packages installed from local .deb files don't require the network archive, and packages
+    // on immutable systems must already be installed.
+    if system.local_package_dir().is_none() && immutable.is_none() {
+        info!("Updating apt package cache");
+        progress.status("apt", "Updating apt package cache");
+        system.update_package_lists().inspect_err(|e| {
+            progress.error("apt", &e.to_string());
+        })?;
+    }
+    progress.advance();
+
+    // Make sure every package is available at exactly the locked version before changing anything.
+    if let Some(file) = &opts.locked {
+        Lockfile::load(system, file.clone())?.verify(system, &experiments)?;
+    }
+
+    let mut state = State::load(system)?;
+    if !opts.exclude.is_empty() {
+        state.exclude(&opts.exclude);
+        state.save(system)?;
+    }
+
+    // Experiments provided by a system extension image are enabled together, by merging it.
+    let mut skipped = Vec::new();
+    let mut standalone = experiments.as_slice();
+    if via_sysext {
+        progress.status("sysext", "Merging system extension image");
+        skipped = sysext::enable(
+            system,
+            &experiments,
+            &mut state,
+            args.no_compatibility_check,
+        )
+        .inspect_err(|err| {
+            progress.error("sysext", &err.to_string());
+        })?;
+        experiments.iter().for_each(|_| progress.advance());
+        standalone = &[];
+    }
+
+    for e in standalone {
+        progress.status(&e.name(), &format!("Enabling {}", e.name()));
+        let enabled = match opts.canary {
+            true => e.enable_canary(args.no_compatibility_check),
+            false => e.enable(args.no_compatibility_check).or_else(|err| {
+                // Offer to leave utilities used in an incompatible way by kernel hooks unreplaced.
+                let Some(conflicts) = err.downcast_ref::<HookConflicts>() else {
+                    return Err(err);
+                };
+                if args.yes || !confirm_exclusions(conflicts) {
+                    return Err(err);
+                }
+                state.exclude(&conflicts.commands());
+                state.save(system)?;
+                e.enable(args.no_compatibility_check)
+            }),
+        }
+        .inspect_err(|err| {
+            progress.error(&e.name(), &err.to_string());
+        })?;
+        progress.advance();
+
+        if !enabled {
+            skipped.push(e.name());
+            continue;
+        }
+        state.record(&e.name(), vec![PackageRecord::new(system, &e.package())?]);
+        if opts.canary {
+            state.start_canary(&e.name(), opts.soak);
+        }
+        let links = e.expected_links(&state)?;
+        state.record_links(&e.name(), links.into_iter().map(|l| l.target));
+        let backups = backup_checksums(system, e, &state)?;
+        state.record_backups(&e.name(), backups);
+        let links = &state.experiments[&e.name()].links;
+        let originals = original_packages(system, links, &e.package());
+        state.record_originals(&e.name(), originals);
+        state.save(system)?;
+    }
+    for e in experiments.iter().filter(|e| !skipped.contains(&e.name())) {
+        let links = state.experiments[&e.name()].links.clone();
+        operation.add(system, &e.name(), &e.package(), links);
+    }
+    state.record_operation(operation);
+    state.mark_run(system);
+    state.save(system)?;
+    progress.status("oxidizr", "Done");
+
+    if let Some(etckeeper) = &etckeeper {
+        etckeeper.post_commit(&format!("oxidizr: enable {}", names(&experiments)))?;
+    }
+
+    offer_service_restarts(system, args.yes, args.restart_services);
+
+    if opts.strict && !skipped.is_empty() {
+        anyhow::bail!(
+            "The following experiments were not enabled: {}",
+            skipped.join(", ")
+        );
     }
     Ok(())
 }
 
 // Disable selected experiments
-fn disable(experiments: Vec<Experiment<'_>>, yes: bool) -> Result<()> {
-    confirm_or_exit(yes);
+fn disable(
+    system: &impl Worker,
+    experiments: Vec<Experiment<'_>>,
+    args: &Args,
+    force: bool,
+    restore_versions: bool,
+    progress: &mut Progress,
+) -> Result<()> {
+    confirm_or_exit(args.yes);
+    if !force {
+        confirm_replaced(system, &experiments, args.yes)?;
+    }
+
+    let etckeeper = Etckeeper::detect(system);
+    if let Some(etckeeper) = &etckeeper {
+        etckeeper.pre_commit()?;
+    }
+
+    let mut state = State::load(system)?;
+    let held = system.held_packages()?;
+    let message = format!("oxidizr: disable {}", names(&experiments));
+    // Package versions are recorded before the packages are removed.
+    let mut operation = Operation::new(OperationKind::Disable, None);
+    for e in &experiments {
+        if let Some(recorded) = state.experiments.get(&e.name()) {
+            operation.add(system, &e.name(), &e.package(), recorded.links.clone());
+        }
+    }
+    let count = experiments.len();
+    let experiments = sysext::disable(system, experiments, &mut state)?;
+    (experiments.len()..count).for_each(|_| progress.advance());
+
     for e in experiments.iter() {
-        e.disable()?;
+        progress.status(&e.name(), &format!("Disabling {}", e.name()));
+        let owners = unbacked_owners(system, e)?;
+        // Held packages can't be removed, so release any hold placed by `pin`.
+        if held.contains(&e.package()) {
+            info!("Releasing the hold on {}", e.package());
+            system.unhold_package(&e.package())?;
+        }
+        e.disable().inspect_err(|err| {
+            progress.error(&e.name(), &err.to_string());
+        })?;
+        // The state records every file replaced, even those the experiment no longer finds.
+        if let Some(recorded) = state.experiments.get(&e.name()) {
+            restore_recorded(system, &recorded.links)?;
+            restore_originals(system, &recorded.originals, restore_versions)?;
+        }
+        for package in owners {
+            info!("Reinstalling {package} to restore files whose backups were purged");
+            system.reinstall_package(&package)?;
+        }
+        progress.advance();
+
+        state.remove(&e.name());
+        state.save(system)?;
+    }
+    // Once disabled, the actions recorded by the runs of `enable` that enabled the experiments
+    // can't be rolled back.
+    state.trim_journal(system)?;
+    state.record_operation(operation);
+    state.mark_run(system);
+    state.save(system)?;
+    progress.status("oxidizr", "Done");
+
+    if let Some(etckeeper) = &etckeeper {
+        etckeeper.post_commit(&message)?;
+    }
+
+    offer_service_restarts(system, args.yes, args.restart_services);
+    Ok(())
+}
+
+/// Revert the most recent `enable` or `disable`. Undoing a `disable` enables the experiments it
+/// disabled again, with the default options.
+fn undo(system: &impl Worker, args: &Args) -> Result<()> {
+    let state = State::load(system)?;
+    let Some(operation) = state.last_undoable().cloned() else {
+        info!("Nothing to undo");
+        return Ok(());
+    };
+    info!(
+        "Undoing '{} {}'",
+        operation.kind,
+        operation.experiments.join(" ")
+    );
+
+    match operation.kind {
+        OperationKind::Enable => {
+            confirm_or_exit(args.yes);
+            undo_enable(system, &operation)
+        }
+        OperationKind::Disable => {
+            converge(
+                system,
+                operation.experiments.clone(),
+                Vec::new(),
+                args,
+                &EnableArgs::default(),
+            )?;
+            let mut state = State::load(system)?;
+            state.mark_undone(&operation);
+            state.save(system)
+        }
+        OperationKind::Repair => unreachable!("repairs can't be undone"),
+    }
+}
+
+/// Enable and disable experiments to converge on the state exported to `file`. Utilities
+/// excluded in the exported state are also excluded when enabling experiments.
+fn import_state(system: &impl Worker, file: &Path, args: &Args, opts: &EnableArgs) -> Result<()> {
+    let exported = ExportedState::load(system, file)?;
+    let (to_enable, to_disable) = exported.plan(system)?;
+    if to_enable.is_empty() && to_disable.is_empty() {
+        info!("Already converged on {}", file.display());
+        return Ok(());
+    }
+
+    let mut opts = opts.clone();
+    opts.exclude.extend(exported.excluded);
+    converge(system, to_enable, to_disable, args, &opts)
+}
+
+/// Apply the experiments and utilities chosen from an interactive checklist. Utilities
+/// deselected from experiments that are already enabled are restored straight away.
+fn tui(system: &impl Worker, args: &Args, opts: &EnableArgs) -> Result<()> {
+    let selection = choose(system)?;
+    if selection == Selection::default() {
+        info!("Nothing to change");
+        return Ok(());
+    }
+
+    let mut state = State::load(system)?;
+    let mut excluded = Vec::new();
+    for (experiment, link) in &selection.exclude {
+        if !selection.enable.contains(experiment) {
+            info!("Restoring {}", link.target.display());
+            system.restore_file(link.target.clone())?;
+        }
+        excluded.push(utility(link));
+    }
+    if !excluded.is_empty() {
+        state.exclude(&excluded);
+        state.save(system)?;
+    }
+
+    converge(system, selection.enable, selection.disable, args, opts)
+}
+
+/// Disable `to_disable`, then enable `to_enable`, reporting progress across both.
+fn converge(
+    system: &impl Worker,
+    to_enable: Vec<String>,
+    to_disable: Vec<String>,
+    args: &Args,
+    opts: &EnableArgs,
+) -> Result<()> {
+    let select = |names: &[String]| -> Vec<Experiment> {
+        all_experiments(system)
+            .into_iter()
+            .filter(|e| names.contains(&e.name()))
+            .collect()
+    };
+    // The package list update is counted as an extra step.
+    let mut progress = Progress::new(args.status_fd, to_enable.len() + to_disable.len() + 1)?;
+
+    if !to_disable.is_empty() {
+        disable(
+            system,
+            select(&to_disable),
+            args,
+            false,
+            false,
+            &mut progress,
+        )?;
+    }
+    if !to_enable.is_empty() {
+        enable(system, select(&to_enable), args, opts, &mut progress)?;
     }
     Ok(())
 }
 
+/// Find the packages shipping files linked by an experiment that have no backup to restore,
+/// for example because they were removed by `purge-backups`.
+fn unbacked_owners(system: &impl Worker, experiment: &Experiment) -> Result<BTreeSet<String>> {
+    let mut owners = BTreeSet::new();
+    if !experiment.check_installed() {
+        return Ok(owners);
+    }
+    for link in experiment.managed_links()? {
+        if system.read_link(link.target.clone()).is_ok() && !has_backup(system, &link.target) {
+            owners.extend(owning_package(system, &link.target));
+        }
+    }
+    Ok(owners)
+}
+
+/// Format the names of a list of experiments for display.
+fn names(experiments: &[Experiment]) -> String {
+    experiments
+        .iter()
+        .map(|e| e.name())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Report any services still running binaries or libraries that were replaced, and offer to
+/// restart them. With `--yes`, services are only restarted if `--restart-services` is set.
+fn offer_service_restarts(system: &impl Worker, yes: bool, restart: bool) {
+    let services = match services_needing_restart(system) {
+        Ok(services) => services,
+        Err(e) => {
+            warn!("Unable to determine which services need restarting: {}", e);
+            return;
+        }
+    };
+
+    if services.is_empty() {
+        return;
+    }
+
+    warn!(
+        "The following services are still using replaced binaries or libraries: {}",
+        services.join(", ")
+    );
+
+    let restart = restart
+        || (!yes
+            && Confirm::new("Restart these services now?")
+                .with_default(false)
+                .prompt()
+                .unwrap_or(false));
+
+    if !restart {
+        info!("Restart these services, or reboot, to complete the change");
+        return;
+    }
+
+    for service in services {
+        info!("Restarting {}", service);
+        if let Err(e) = system.restart_service(&service) {
+            warn!("Failed to restart {}: {}", service, e);
+        }
+    }
+}
+
 /// Get selected experiments from the command line arguments.
 fn selected_experiments(
     all: bool,
     selected: Vec<String>,
+    include_opt_in: bool,
     system: &impl Worker,
 ) -> Vec<Experiment<'_>> {
     let all_experiments = all_experiments(system);
@@ -181,6 +1205,9 @@ fn selected_experiments(
             }
 
             all_experiments
+                .into_iter()
+                .filter(|e| include_opt_in || !e.opt_in())
+                .collect()
         }
         false => {
             // If no experiments are selected, default to coreutils and sudo-rs
@@ -189,6 +1216,8 @@ fn selected_experiments(
                 _ => selected,
             };
 
+            let filter = expand_bundles(filter, &all_experiments);
+
             // Filter the list of all experiments to only include the selected ones
             all_experiments
                 .into_iter()
@@ -198,6 +1227,46 @@ fn selected_experiments(
     }
 }
 
+/// Check that the files about to be restored are still the symlinks oxidizr created. Files that
+/// were replaced since, e.g. by an administrator or a package upgrade, are only overwritten with
+/// their backups once confirmed, which `--yes` doesn't skip.
+fn confirm_replaced(system: &impl Worker, experiments: &[Experiment], yes: bool) -> Result<()> {
+    let state = State::load(system)?;
+    let mut replaced = Vec::new();
+    for e in experiments {
+        let provided_by_sysext = state.experiments.get(&e.name()).is_some_and(|s| s.sysext);
+        if provided_by_sysext || !e.check_installed() {
+            continue;
+        }
+        for link in e.expected_links(&state)? {
+            if link.replaced(system) && has_backup(system, &link.target) {
+                replaced.push(link.target);
+            }
+        }
+    }
+    if replaced.is_empty() {
+        return Ok(());
+    }
+
+    for file in &replaced {
+        warn!(
+            "{} was changed after oxidizr replaced it, restoring its backup will overwrite the change",
+            file.display()
+        );
+    }
+    let refusal = "Refusing to overwrite files changed after they were replaced, use '--force' to restore their backups anyway";
+    if yes {
+        anyhow::bail!(refusal);
+    }
+    let ans = Confirm::new("Overwrite the changed files with their backups?")
+        .with_default(false)
+        .prompt();
+    match ans {
+        Ok(true) => Ok(()),
+        _ => anyhow::bail!(refusal),
+    }
+}
+
 /// Display a confirmation prompt to the user asking whether they'd like to continue.
 /// If they select no, or there is an error - exit the program.
 /// If `--yes` was supplied on the command line, skip the check and return.
@@ -220,6 +1289,100 @@ fn confirm_or_exit(yes: bool) {
     }
 }
 
+/// Require the name of each high-risk experiment to be typed before enabling it, so that it
+/// can't be replaced by reflexively accepting a prompt. With `--yes`, high-risk experiments must
+/// instead be named with `--accept-risk`.
+fn confirm_high_risk(experiments: &[Experiment], yes: bool, accepted: &[String]) -> Result<()> {
+    for e in experiments.iter().filter(|e| e.high_risk()) {
+        let name = e.name();
+        if yes {
+            anyhow::ensure!(
+                accepted.contains(&name),
+                "'{name}' is a high-risk experiment; pass '--accept-risk {name}' to enable it with '--yes'"
+            );
+            continue;
+        }
+
+        let ans = Text::new(&format!("Type '{name}' to confirm enabling it:"))
+            .with_help_message(&format!("⚠️ '{name}' is a high-risk experiment! ⚠️\nIf it fails, you may be unable to regain root access to this machine."))
+            .prompt();
+        match ans {
+            Ok(typed) if typed.trim() == name => (),
+            _ => anyhow::bail!("Confirmation for '{name}' did not match, aborting"),
+        }
+    }
+    Ok(())
+}
+
+/// Explain which kernel or initramfs hooks would break, and ask whether to leave the utilities
+/// they rely on unreplaced.
+fn confirm_exclusions(conflicts: &HookConflicts) -> bool {
+    warn!("{conflicts}");
+    Confirm::new(&format!(
+        "Leave {} unreplaced and continue enabling '{}'?",
+        conflicts.commands().join(", "),
+        conflicts.experiment
+    ))
+    .with_default(true)
+    .prompt()
+    .unwrap_or(false)
+}
+
+/// Check that `experiments` can be enabled on an immutable system, which is only possible with
+/// a system extension image, once their packages are layered onto the deployment.
+fn check_immutable(
+    immutable: &ImmutableSystem,
+    experiments: &[Experiment],
+    opts: &EnableArgs,
+) -> Result<()> {
+    anyhow::ensure!(immutable.supports_sysext(), "{}", immutable.explanation());
+    anyhow::ensure!(
+        !opts.canary,
+        "--canary isn't supported on {}, as /usr can't be modified",
+        immutable.name()
+    );
+    let missing: Vec<String> = experiments
+        .iter()
+        .filter(|e| !e.check_installed())
+        .map(|e| e.package())
+        .collect();
+    anyhow::ensure!(
+        missing.is_empty(),
+        "{}. Missing: {}",
+        immutable.explanation(),
+        missing.join(" ")
+    );
+    if !opts.via_sysext {
+        info!(
+            "/usr can't be modified on {}, so the experiments are provided by a system extension image",
+            immutable.name()
+        );
+    }
+    Ok(())
+}
+
+/// If running over SSH with no other root session available, require an explicit confirmation
+/// before replacing sudo/su, since a failure could lock the operator out of the machine. This
+/// prompt is not skipped by `--yes`, only by `--force-remote`.
+fn confirm_remote_session(system: &impl Worker) -> Result<()> {
+    if !remote_session(system) || fallback_session_available(system) {
+        return Ok(());
+    }
+
+    warn!("Running over SSH with no other root session available");
+    let ans = Confirm::new("Replace sudo/su over this remote session?")
+        .with_default(false)
+        .with_help_message("If the replacement fails, you may be unable to regain root access\nto this machine remotely. Open a separate root session first, or\nre-run with '--force-remote' to skip this check.")
+        .prompt();
+
+    match ans {
+        Ok(true) => Ok(()),
+        _ => anyhow::bail!(
+            "Refusing to replace sudo/su over SSH without confirmation, use '--force-remote' to override"
+        ),
+    }
+}
+
 // Default experiments to enable if none are specified
 fn default_experiments() -> Vec<String> {
     let mut defaults = vec!["coreutils".to_string(), "sudo-rs".to_string()];
@@ -0,0 +1,567 @@
+use crate::utils::{
+    JOURNAL_FILE, Journal, Migration, Snapshot, Worker, migrate, set_version, stamp_version,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Location of the file recording what oxidizr manages on the system.
+pub const STATE_FILE: &str = "/var/lib/oxidizr/state.json";
+
+/// Migrations of the state file's format, where the `n`th migrates from version `n`. Its
+/// length is the version of the format written by this version of oxidizr.
+const STATE_MIGRATIONS: [Migration; 1] = [stamp_version];
+
+/// Persistent record of the experiments enabled by oxidizr.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub experiments: BTreeMap<String, ExperimentState>,
+    /// When oxidizr last enabled or disabled experiments, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<u64>,
+    /// The Ubuntu release oxidizr last enabled or disabled experiments on, e.g. `24.04`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+    /// Utilities that are left unreplaced by any experiment, e.g. `cp`.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub excluded: BTreeSet<String>,
+    /// The snapshot of the root filesystem most recently taken by oxidizr.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<Snapshot>,
+    /// Past runs of `enable`, `disable` and `repair`, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Operation>,
+}
+
+/// Persistent record of a single enabled experiment.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentState {
+    #[serde(default)]
+    pub packages: Vec<PackageRecord>,
+    /// Set while the experiment is enabled for its canary binaries only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<Canary>,
+    /// Set if the experiment is provided by the oxidizr system extension image, rather than by
+    /// replacing files.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sysext: bool,
+    /// The files replaced with symlinks by the experiment.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub links: BTreeSet<PathBuf>,
+    /// The sha256 checksums of the backups of replaced files, keyed by the replaced file.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub backups: BTreeMap<PathBuf, String>,
+    /// The installed versions of the packages that shipped the replaced files, keyed by package.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub originals: BTreeMap<String, String>,
+}
+
+/// Persistent record of a canary rollout awaiting promotion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Canary {
+    /// When the canary was enabled, in seconds since the Unix epoch.
+    pub started: u64,
+    /// How long the canary must soak before it's promoted automatically, in seconds.
+    pub soak: u64,
+}
+
+impl Canary {
+    /// Report whether the soak period has elapsed.
+    pub fn soaked(&self) -> bool {
+        now().is_some_and(|now| now >= self.started + self.soak)
+    }
+}
+
+/// Persistent record of a run of `enable`, `disable` or `repair`, for auditing and so that it
+/// can be undone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operation {
+    pub kind: OperationKind,
+    /// When the operation started, in seconds since the Unix epoch.
+    pub time: u64,
+    /// The experiments enabled, disabled or repaired.
+    pub experiments: Vec<String>,
+    /// The installed versions of the experiments' packages, keyed by package.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub packages: BTreeMap<String, String>,
+    /// The files replaced with symlinks, restored or relinked.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub files: BTreeSet<PathBuf>,
+    /// The number of journal entries recorded before an `enable` began. The entries after it
+    /// are the actions it took. Unset once the journal is discarded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal: Option<usize>,
+    /// Set once the operation has been reverted by `undo`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undone: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationKind {
+    Enable,
+    Disable,
+    Repair,
+}
+
+impl std::fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Enable => write!(f, "enable"),
+            Self::Disable => write!(f, "disable"),
+            Self::Repair => write!(f, "repair"),
+        }
+    }
+}
+
+impl Operation {
+    /// Start recording an operation. `journal` is the number of entries already in the journal.
+    pub fn new(kind: OperationKind, journal: Option<usize>) -> Self {
+        Self {
+            kind,
+            time: now().unwrap_or_default(),
+            experiments: Vec::new(),
+            packages: BTreeMap::new(),
+            files: BTreeSet::new(),
+            journal,
+            undone: false,
+        }
+    }
+
+    /// Record an experiment the operation applies to, the installed version of its package and
+    /// the files changed. The version is left out if it can't be found, e.g. when the package
+    /// was removed outside oxidizr, so that the experiment can still be disabled.
+    pub fn add(
+        &mut self,
+        system: &dyn Worker,
+        experiment: &str,
+        package: &str,
+        files: impl IntoIterator<Item = PathBuf>,
+    ) {
+        self.experiments.push(experiment.to_string());
+        let version = system.package_version(package).ok();
+        if let Some(version) = version.filter(|v| !v.is_empty()) {
+            self.packages.insert(package.to_string(), version);
+        }
+        self.files.extend(files);
+    }
+}
+
+/// Persistent record of a package installed by an experiment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageRecord {
+    pub name: String,
+    /// The version of the package installed when the experiment was enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The local `.deb` file the package was installed from, if not installed from the archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deb: Option<PathBuf>,
+    /// The sha256 checksum of the local `.deb` file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl PackageRecord {
+    /// Create a record for a package installed on the system, with its installed version. If the
+    /// package was installed from a local `.deb` file, the file and its checksum are recorded.
+    pub fn new(system: &dyn Worker, package: &str) -> Result<Self> {
+        let (deb, sha256) = match system.local_package_dir() {
+            Some(_) => {
+                let deb = system.local_package(package)?;
+                let sha256 = system.sha256(deb.clone())?;
+                (Some(deb), Some(sha256))
+            }
+            None => (None, None),
+        };
+
+        let version = system.package_version(package).ok();
+        Ok(Self {
+            name: package.to_string(),
+            version: version.filter(|v| !v.is_empty()),
+            deb,
+            sha256,
+        })
+    }
+}
+
+impl State {
+    /// Load the state from disk. If no state file exists, an empty state is returned.
+    pub fn load(system: &dyn Worker) -> Result<Self> {
+        let file = PathBuf::from(STATE_FILE);
+        if !system.file_exists(file.clone()) {
+            return Ok(Self::default());
+        }
+        // State written by older versions of oxidizr is migrated to the current format.
+        let mut value: serde_json::Value = serde_json::from_str(&system.read_file(file)?)?;
+        migrate(STATE_FILE, &mut value, &STATE_MIGRATIONS)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Write the state to disk, in the current format.
+    pub fn save(&self, system: &dyn Worker) -> Result<()> {
+        let mut value = serde_json::to_value(self)?;
+        set_version(&mut value, STATE_MIGRATIONS.len());
+        let contents = serde_json::to_string_pretty(&value)?;
+        system.write_file(PathBuf::from(STATE_FILE), &contents, 0o644)
+    }
+
+    /// Record an experiment as enabled, along with the packages it installed.
+    pub fn record(&mut self, experiment: &str, packages: Vec<PackageRecord>) {
+        self.experiments.insert(
+            experiment.to_string(),
+            ExperimentState {
+                packages,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Remove an experiment from the state.
+    pub fn remove(&mut self, experiment: &str) {
+        self.experiments.remove(experiment);
+    }
+
+    /// Leave `binaries` unreplaced when enabling experiments.
+    pub fn exclude(&mut self, binaries: &[String]) {
+        self.excluded.extend(binaries.iter().cloned());
+    }
+
+    /// Replace `binary` again when enabling experiments, reversing `exclude`.
+    pub fn include(&mut self, binary: &str) {
+        self.excluded.remove(binary);
+    }
+
+    /// Record an operation in the history, unless it applied to no experiments.
+    pub fn record_operation(&mut self, operation: Operation) {
+        if !operation.experiments.is_empty() {
+            self.history.push(operation);
+        }
+    }
+
+    /// The most recent `enable` or `disable` that hasn't been undone.
+    pub fn last_undoable(&self) -> Option<&Operation> {
+        self.history
+            .iter()
+            .rev()
+            .find(|o| o.kind != OperationKind::Repair && !o.undone)
+    }
+
+    /// Mark an operation in the history as reverted by `undo`.
+    pub fn mark_undone(&mut self, operation: &Operation) {
+        if let Some(o) = self.history.iter_mut().rev().find(|o| *o == operation) {
+            o.undone = true;
+        }
+    }
+
+    /// Forget where past operations start in the journal, once the journal is discarded.
+    pub fn discard_journal(&mut self) {
+        for operation in &mut self.history {
+            operation.journal = None;
+        }
+    }
+
+    /// Drop the journal entries recorded by runs of `enable` whose experiments have all since
+    /// been disabled, so that `rollback` and `undo` only replay the actions of experiments that
+    /// are still enabled. Once none are, the journal is discarded.
+    pub fn trim_journal(&mut self, system: &dyn Worker) -> Result<()> {
+        if self.experiments.is_empty() {
+            system.remove_file(PathBuf::from(JOURNAL_FILE))?;
+            self.discard_journal();
+            return Ok(());
+        }
+
+        let entries = Journal::load(system)?;
+        // Each run recorded its actions after those of the runs before it.
+        let mut runs: Vec<(usize, usize)> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter_map(|(i, operation)| operation.journal.map(|start| (start, i)))
+            .collect();
+        runs.sort();
+
+        let first = runs.first().map_or(entries.len(), |(start, _)| *start);
+        let mut kept = entries[..first.min(entries.len())].to_vec();
+        for (n, (start, i)) in runs.iter().enumerate() {
+            let end = runs.get(n + 1).map_or(entries.len(), |(next, _)| *next);
+            let operation = &mut self.history[*i];
+            if operation
+                .experiments
+                .iter()
+                .all(|e| !self.experiments.contains_key(e))
+            {
+                operation.journal = None;
+                continue;
+            }
+            operation.journal = Some(kept.len());
+            kept.extend_from_slice(&entries[(*start).min(end)..end.min(entries.len())]);
+        }
+        Journal::replace(system, &kept)
+    }
+
+    /// Record that oxidizr has just been run, and the release it was run on.
+    pub fn mark_run(&mut self, system: &dyn Worker) {
+        self.last_run = now();
+        if let Ok(distribution) = system.distribution() {
+            self.release = Some(distribution.release);
+        }
+    }
+
+    /// Record that a recorded experiment has been enabled as a canary, to be promoted
+    /// automatically once `soak` seconds have elapsed.
+    pub fn start_canary(&mut self, experiment: &str, soak: u64) {
+        if let Some(e) = self.experiments.get_mut(experiment) {
+            e.canary = Some(Canary {
+                started: now().unwrap_or_default(),
+                soak,
+            });
+        }
+    }
+
+    /// Record files replaced with symlinks by a recorded experiment.
+    pub fn record_links(&mut self, experiment: &str, links: impl IntoIterator<Item = PathBuf>) {
+        if let Some(e) = self.experiments.get_mut(experiment) {
+            e.links.extend(links);
+        }
+    }
+
+    /// Record the checksums of backups taken by a recorded experiment, keyed by replaced file.
+    pub fn record_backups(&mut self, experiment: &str, backups: BTreeMap<PathBuf, String>) {
+        if let Some(e) = self.experiments.get_mut(experiment) {
+            e.backups.extend(backups);
+        }
+    }
+
+    /// Record the packages that shipped the files replaced by a recorded experiment, with their
+    /// installed versions.
+    pub fn record_originals(&mut self, experiment: &str, originals: BTreeMap<String, String>) {
+        if let Some(e) = self.experiments.get_mut(experiment) {
+            e.originals.extend(originals);
+        }
+    }
+
+    /// Forget the recorded checksum of the backup of `file`, once the backup is removed.
+    pub fn forget_backup(&mut self, file: &Path) {
+        for e in self.experiments.values_mut() {
+            e.backups.remove(file);
+        }
+    }
+
+    /// Forget that `file` was replaced with a symlink, once it's restored from its backup.
+    pub fn forget_link(&mut self, file: &Path) {
+        for e in self.experiments.values_mut() {
+            e.links.remove(file);
+        }
+    }
+
+    /// Record that a recorded experiment is provided by the system extension image.
+    pub fn mark_sysext(&mut self, experiment: &str) {
+        if let Some(e) = self.experiments.get_mut(experiment) {
+            e.sysext = true;
+        }
+    }
+
+    /// List the experiments provided by the system extension image.
+    pub fn sysext_experiments(&self) -> Vec<String> {
+        self.experiments
+            .iter()
+            .filter(|(_, e)| e.sysext)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Record that a canary has been promoted, and the experiment is fully enabled.
+    pub fn finish_canary(&mut self, experiment: &str) {
+        if let Some(e) = self.experiments.get_mut(experiment) {
+            e.canary = None;
+        }
+    }
+}
+
+/// The current time, in seconds since the Unix epoch.
+fn now() -> Option<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{JournalEntry, MockSystem};
+
+    #[test]
+    fn test_state_load_missing() {
+        let runner = MockSystem::default();
+        assert_eq!(State::load(&runner).unwrap(), State::default());
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let runner = MockSystem::default();
+
+        let mut state = State::default();
+        state.record(
+            "coreutils",
+            vec![PackageRecord::new(&runner, "rust-coreutils").unwrap()],
+        );
+        state.record(
+            "sudo-rs",
+            vec![PackageRecord::new(&runner, "sudo-rs").unwrap()],
+        );
+        state.remove("sudo-rs");
+        state.save(&runner).unwrap();
+
+        let loaded = State::load(&runner).unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.experiments.len(), 1);
+        assert_eq!(
+            loaded.experiments["coreutils"].packages[0],
+            PackageRecord {
+                name: "rust-coreutils".to_string(),
+                version: None,
+                deb: None,
+                sha256: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_operation_add_missing_package() {
+        let runner = MockSystem::default();
+        runner.mock_command("dpkg-query -W -f=${Version} rust-coreutils", "0.0.29");
+        // sudo-rs was removed outside oxidizr, so its version can't be found.
+        runner.mock_command_failure("dpkg-query -W -f=${Version} sudo-rs");
+
+        let mut operation = Operation::new(OperationKind::Disable, None);
+        operation.add(&runner, "coreutils", "rust-coreutils", []);
+        operation.add(
+            &runner,
+            "sudo-rs",
+            "sudo-rs",
+            [PathBuf::from("/usr/bin/sudo")],
+        );
+
+        assert_eq!(operation.experiments, &["coreutils", "sudo-rs"]);
+        assert_eq!(
+            operation.packages,
+            BTreeMap::from([("rust-coreutils".to_string(), "0.0.29".to_string())])
+        );
+        assert!(operation.files.contains(&PathBuf::from("/usr/bin/sudo")));
+    }
+
+    #[test]
+    fn test_state_migrate() {
+        let runner = MockSystem::default();
+
+        // State written before the format was versioned is still read, and saved versioned.
+        runner.mock_files(vec![(
+            STATE_FILE,
+            r#"{"experiments": {"coreutils": {"packages": [{"name": "rust-coreutils"}]}}}"#,
+            false,
+        )]);
+        let state = State::load(&runner).unwrap();
+        assert!(state.experiments.contains_key("coreutils"));
+        state.save(&runner).unwrap();
+        let saved = runner.read_file(PathBuf::from(STATE_FILE)).unwrap();
+        assert!(saved.contains(r#""version": 1"#));
+
+        runner.mock_files(vec![(STATE_FILE, r#"{"version": 2}"#, false)]);
+        assert!(State::load(&runner).is_err());
+    }
+
+    #[test]
+    fn test_state_trim_journal() {
+        let runner = MockSystem::default();
+        let entry = |file: &str| JournalEntry::ReplaceFile {
+            file: PathBuf::from(file),
+        };
+        Journal::replace(
+            &runner,
+            &[
+                entry("/usr/bin/sudo"),
+                entry("/usr/bin/su"),
+                entry("/usr/bin/date"),
+            ],
+        )
+        .unwrap();
+
+        let mut state = State::default();
+        let mut sudo_rs = Operation::new(OperationKind::Enable, Some(0));
+        sudo_rs.experiments = vec!["sudo-rs".to_string(), "su".to_string()];
+        state.record_operation(sudo_rs);
+        let mut coreutils = Operation::new(OperationKind::Enable, Some(2));
+        coreutils.experiments = vec!["coreutils".to_string()];
+        state.record_operation(coreutils);
+        state.record("su", vec![]);
+        state.record("coreutils", vec![]);
+
+        // Runs are kept while any of their experiments remain enabled.
+        state.trim_journal(&runner).unwrap();
+        assert_eq!(Journal::load(&runner).unwrap().len(), 3);
+        assert_eq!(state.history[1].journal, Some(2));
+
+        state.remove("su");
+        state.trim_journal(&runner).unwrap();
+        assert_eq!(
+            Journal::load(&runner).unwrap(),
+            vec![entry("/usr/bin/date")]
+        );
+        assert_eq!(state.history[0].journal, None);
+        assert_eq!(state.history[1].journal, Some(0));
+
+        state.remove("coreutils");
+        state.trim_journal(&runner).unwrap();
+        assert!(!runner.file_exists(PathBuf::from(JOURNAL_FILE)));
+        assert_eq!(state.history[1].journal, None);
+    }
+
+    #[test]
+    fn test_state_canary() {
+        let mut state = State::default();
+        state.record("coreutils", vec![]);
+        state.start_canary("coreutils", 3600);
+
+        let canary = state.experiments["coreutils"].canary.clone().unwrap();
+        assert_eq!(canary.soak, 3600);
+        assert!(!canary.soaked());
+        assert!(
+            Canary {
+                started: 0,
+                soak: 3600
+            }
+            .soaked()
+        );
+
+        state.finish_canary("coreutils");
+        assert_eq!(state.experiments["coreutils"].canary, None);
+    }
+
+    #[test]
+    fn test_package_record_local_deb() {
+        let runner = MockSystem::default();
+        runner.mock_local_packages("/srv/debs");
+        runner.mock_files(vec![
+            ("/srv/debs/rust-coreutils_0.0.24-1_amd64.deb", "", false),
+            ("/srv/debs/sudo-rs_0.2.2-1_amd64.deb", "", false),
+        ]);
+        runner.mock_command("dpkg-query -W -f=${Version} rust-coreutils", "0.0.24-1");
+        runner.mock_command(
+            "sha256sum /srv/debs/rust-coreutils_0.0.24-1_amd64.deb",
+            "abc123  /srv/debs/rust-coreutils_0.0.24-1_amd64.deb",
+        );
+
+        let record = PackageRecord::new(&runner, "rust-coreutils").unwrap();
+        assert_eq!(
+            record.deb,
+            Some(PathBuf::from("/srv/debs/rust-coreutils_0.0.24-1_amd64.deb"))
+        );
+        assert_eq!(record.sha256, Some("abc123".to_string()));
+        assert_eq!(record.version, Some("0.0.24-1".to_string()));
+    }
+}
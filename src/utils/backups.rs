@@ -0,0 +1,673 @@
+use crate::utils::{Command, Worker, backup_filename};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tracing::{trace, warn};
+
+/// Location of the centralized store holding compressed backups.
+pub const BACKUP_STORE_DIR: &str = "/var/lib/oxidizr/backups";
+
+/// Location of the manifest describing each uncompressed backup, and each compressed backup of
+/// a hard-linked file, as it was when it was taken, keyed by the path of the original file.
+pub const BACKUP_MANIFEST: &str = "/var/lib/oxidizr/backups.json";
+
+/// Metadata recorded alongside a compressed backup, describing the uncompressed original, and
+/// in the backup manifest for uncompressed backups.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupMeta {
+    /// The sha256 checksum of the uncompressed content.
+    pub sha256: String,
+    /// The size of the uncompressed content, in bytes.
+    #[serde(default)]
+    pub size: u64,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// The device and inode of the original, if other paths were hard links to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardlink: Option<(u64, u64)>,
+    /// A path sharing the original's inode that has already been restored, which this file is
+    /// restored as a hard link to rather than as a separate copy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_to: Option<PathBuf>,
+}
+
+/// Get the path at which the backup of `file` is kept in the backup store `dir`, e.g.
+/// `/srv/backups/usr/bin/date` for `/usr/bin/date`.
+pub fn stored_backup_filename(dir: &Path, file: &Path) -> PathBuf {
+    dir.join(file.strip_prefix("/").unwrap_or(file))
+}
+
+/// Get the path in the backup store of the compressed backup of `file`, e.g.
+/// `/var/lib/oxidizr/backups/usr/bin/date.zst` for `/usr/bin/date`.
+pub fn compressed_backup_filename(system: &dyn Worker, file: &Path) -> PathBuf {
+    let store = system
+        .backup_dir()
+        .unwrap_or(PathBuf::from(BACKUP_STORE_DIR));
+    let mut backup = stored_backup_filename(&store, file).into_os_string();
+    backup.push(".zst");
+    PathBuf::from(backup)
+}
+
+fn meta_filename(system: &dyn Worker, file: &Path) -> PathBuf {
+    let mut meta = compressed_backup_filename(system, file).into_os_string();
+    meta.push(".json");
+    PathBuf::from(meta)
+}
+
+/// Check that the filesystem holding `dir` has room for `needed` more bytes.
+pub fn ensure_free_space(system: &dyn Worker, dir: &Path, needed: u64) -> Result<()> {
+    let available = system.free_space(dir)?;
+    anyhow::ensure!(
+        available >= needed,
+        "Not enough space for backups in {}: {needed} bytes needed, {available} available",
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Move `source` to `dest`, replacing it atomically. If the two are on different filesystems,
+/// `source` is first copied alongside `dest` and flushed to disk, so the final rename stays on
+/// one filesystem and `dest` is never left partially written. The directory holding `dest` is
+/// synced once the rename is done, so the move survives a crash.
+pub fn move_file(source: &Path, dest: &Path) -> Result<()> {
+    match fs::rename(source, dest) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            trace!(
+                "{} is on another filesystem, copying to {}",
+                source.display(),
+                dest.display()
+            );
+            let staged = backup_filename(dest);
+            if fs::symlink_metadata(source)?.is_symlink() {
+                std::os::unix::fs::symlink(fs::read_link(source)?, &staged)?;
+            } else {
+                fs::copy(source, &staged)?;
+                let metadata = fs::metadata(source)?;
+                std::os::unix::fs::chown(&staged, Some(metadata.uid()), Some(metadata.gid()))?;
+                fs::set_permissions(&staged, metadata.permissions())?;
+                fs::File::open(&staged)?.sync_all()?;
+            }
+            fs::rename(&staged, dest)?;
+            fs::remove_file(source)?;
+        }
+        result => result?,
+    }
+    sync_parent(dest)
+}
+
+/// Flush the directory holding `file` to disk, so that renames into or out of it are durable.
+fn sync_parent(file: &Path) -> Result<()> {
+    if let Some(parent) = file.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Report whether a backup of `file` exists, in any of the locations backups are kept.
+pub fn has_backup(system: &dyn Worker, file: &Path) -> bool {
+    find_backup(system, file).is_some() || has_compressed_backup(system, file)
+}
+
+/// Find the uncompressed backup of `file`, in the configured backup directory or alongside it.
+pub fn find_backup(system: &dyn Worker, file: &Path) -> Option<PathBuf> {
+    [system.backup_location(file), backup_filename(file)]
+        .into_iter()
+        .find(|backup| system.file_exists(backup.clone()))
+}
+
+/// Find the package that ships `file`, according to the package manager.
+pub fn owning_package(system: &dyn Worker, file: &Path) -> Option<String> {
+    let package_manager = system.package_manager();
+    let output = system
+        .run(&package_manager.query_owner(&file.to_string_lossy()))
+        .ok()?;
+    package_manager.parse_owner(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Find the packages other than `replacement` that ship `files`, with their installed versions.
+/// These provide the originals of the files an experiment replaced.
+pub fn original_packages(
+    system: &dyn Worker,
+    files: &BTreeSet<PathBuf>,
+    replacement: &str,
+) -> BTreeMap<String, String> {
+    let mut packages = BTreeMap::new();
+    for file in files {
+        let Some(package) = owning_package(system, file) else {
+            continue;
+        };
+        if package == replacement || packages.contains_key(&package) {
+            continue;
+        }
+        if let Ok(version) = system.package_version(&package) {
+            packages.insert(package, version);
+        }
+    }
+    packages
+}
+
+/// Calculate the checksum of the original content of `file` from its backup, if it has one.
+/// For compressed backups, the checksum recorded when the backup was taken is used.
+pub fn backup_checksum(system: &dyn Worker, file: &Path) -> Result<Option<String>> {
+    if let Some(backup) = find_backup(system, file) {
+        return Ok(Some(system.sha256(backup)?));
+    }
+    if has_compressed_backup(system, file) {
+        let meta: BackupMeta =
+            serde_json::from_str(&system.read_file(meta_filename(system, file))?)?;
+        return Ok(Some(meta.sha256));
+    }
+    Ok(None)
+}
+
+/// Describe a file by its checksum, size, permissions and ownership.
+pub fn backup_meta(system: &dyn Worker, file: &Path) -> Result<BackupMeta> {
+    let metadata = fs::metadata(file)?;
+    Ok(BackupMeta {
+        sha256: system.sha256(file.to_path_buf())?,
+        size: metadata.len(),
+        mode: metadata.permissions().mode(),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        ..Default::default()
+    })
+}
+
+/// Identify the inode of a file by its device and inode number, if other paths are hard links
+/// to it. Once the others have been replaced, the file has a single link left, but its inode
+/// is still recorded for the backups taken of them.
+pub fn hardlink_id(system: &dyn Worker, file: &Path) -> Result<Option<(u64, u64)>> {
+    let metadata = fs::symlink_metadata(file)?;
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+    let id = (metadata.dev(), metadata.ino());
+    let linked = metadata.nlink() > 1
+        || read_manifest(system)?
+            .values()
+            .any(|meta| meta.hardlink == Some(id));
+    Ok(linked.then_some(id))
+}
+
+fn read_manifest(system: &dyn Worker) -> Result<BTreeMap<PathBuf, BackupMeta>> {
+    let manifest = PathBuf::from(BACKUP_MANIFEST);
+    if !system.file_exists(manifest.clone()) {
+        return Ok(BTreeMap::new());
+    }
+    serde_json::from_str(&system.read_file(manifest)?)
+        .map_err(|e| anyhow::anyhow!("Invalid backup manifest in {BACKUP_MANIFEST}: {e}"))
+}
+
+fn write_manifest(system: &dyn Worker, manifest: &BTreeMap<PathBuf, BackupMeta>) -> Result<()> {
+    system.write_file(
+        PathBuf::from(BACKUP_MANIFEST),
+        &serde_json::to_string_pretty(manifest)?,
+        0o600,
+    )
+}
+
+/// Record the uncompressed backup of `file` in the backup manifest, as described by `meta`.
+pub fn record_backup(system: &dyn Worker, file: &Path, meta: BackupMeta) -> Result<()> {
+    let mut manifest = read_manifest(system)?;
+    manifest.insert(file.to_path_buf(), meta);
+    write_manifest(system, &manifest)
+}
+
+/// Check the backup of `file` against the backup manifest before it's restored, warning if it
+/// was modified or truncated since it was taken. Backups taken before the manifest was kept
+/// aren't checked.
+pub fn verify_backup(system: &dyn Worker, file: &Path, backup: &Path) -> Result<()> {
+    let Some(expected) = read_manifest(system)?.remove(file) else {
+        return Ok(());
+    };
+    for discrepancy in backup_discrepancies(&expected, &backup_meta(system, backup)?) {
+        warn!(
+            "The backup of {} has changed since it was taken: {discrepancy}. It's restored \
+             anyway, check it before relying on it",
+            file.display()
+        );
+    }
+    Ok(())
+}
+
+/// Find the restored file that `file` should be restored as a hard link to, because their
+/// originals were hard links to each other. The other file must still be a regular file.
+pub fn restored_hardlink(system: &dyn Worker, file: &Path) -> Result<Option<PathBuf>> {
+    let manifest = read_manifest(system)?;
+    Ok(manifest
+        .get(file)
+        .and_then(|meta| meta.link_to.clone())
+        .filter(|other| {
+            system.file_exists(other.clone()) && system.read_link(other.clone()).is_err()
+        }))
+}
+
+/// Forget the backup of `file` once it has been restored. Backups of other hard links to the
+/// same original are then restored as hard links to `file`, so the files don't diverge.
+pub fn forget_backup(system: &dyn Worker, file: &Path) -> Result<()> {
+    let mut manifest = read_manifest(system)?;
+    let Some(restored) = manifest.remove(file) else {
+        return Ok(());
+    };
+    if let Some(id) = restored.hardlink {
+        for meta in manifest.values_mut() {
+            if meta.hardlink == Some(id) && meta.link_to.is_none() {
+                meta.link_to = Some(file.to_path_buf());
+            }
+        }
+    }
+    write_manifest(system, &manifest)
+}
+
+/// Forget the backups of `files` once they've been purged. They're never restored, so no other
+/// backups are redirected to them.
+pub fn forget_purged_backups(system: &dyn Worker, files: &[PathBuf]) -> Result<()> {
+    let mut manifest = read_manifest(system)?;
+    let recorded = manifest.len();
+    manifest.retain(|file, _| !files.contains(file));
+    if manifest.len() == recorded {
+        return Ok(());
+    }
+    write_manifest(system, &manifest)
+}
+
+/// Replace `dest` with a hard link to `original`, atomically.
+pub fn link_file(original: &Path, dest: &Path) -> Result<()> {
+    let name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let staged = dest.with_file_name(format!(".{name}.oxidizr.link"));
+    if fs::symlink_metadata(&staged).is_ok() {
+        fs::remove_file(&staged)?;
+    }
+    fs::hard_link(original, &staged)?;
+    fs::rename(&staged, dest)?;
+    sync_parent(dest)
+}
+
+/// Describe how a backup differs from the metadata recorded when it was taken.
+fn backup_discrepancies(expected: &BackupMeta, actual: &BackupMeta) -> Vec<String> {
+    let mut found = Vec::new();
+    if actual.size != expected.size {
+        found.push(format!(
+            "its size is {} bytes, expected {}",
+            actual.size, expected.size
+        ));
+    }
+    if actual.sha256 != expected.sha256 {
+        found.push(format!(
+            "its checksum is {}, expected {}",
+            actual.sha256, expected.sha256
+        ));
+    }
+    if actual.mode != expected.mode {
+        found.push(format!(
+            "its mode is {:o}, expected {:o}",
+            actual.mode, expected.mode
+        ));
+    }
+    if (actual.uid, actual.gid) != (expected.uid, expected.gid) {
+        found.push(format!(
+            "it's owned by {}:{}, expected {}:{}",
+            actual.uid, actual.gid, expected.uid, expected.gid
+        ));
+    }
+    found
+}
+
+/// Report whether a compressed backup of `file` exists in the backup store.
+pub fn has_compressed_backup(system: &dyn Worker, file: &Path) -> bool {
+    system.file_exists(compressed_backup_filename(system, file))
+}
+
+/// Compress a copy of `file` into the backup store with zstd, recording the checksum of its
+/// content along with its permissions and ownership. Backups of hard-linked files are also
+/// recorded in the backup manifest, so they're restored as links again.
+pub fn compress_backup(system: &dyn Worker, file: &Path) -> Result<()> {
+    let backup = compressed_backup_filename(system, file);
+    trace!("Compressing {} -> {}", file.display(), backup.display());
+    if let Some(parent) = backup.parent() {
+        fs::create_dir_all(parent)?;
+        ensure_free_space(system, parent, fs::metadata(file)?.len())?;
+    }
+
+    let meta = BackupMeta {
+        hardlink: hardlink_id(system, file)?,
+        ..backup_meta(system, file)?
+    };
+
+    let cmd = Command::build(
+        "zstd",
+        &[
+            "-q",
+            "-f",
+            "-o",
+            &backup.to_string_lossy(),
+            &file.to_string_lossy(),
+        ],
+    );
+    system.run(&cmd)?;
+    if meta.hardlink.is_some() {
+        record_backup(system, file, meta.clone())?;
+    }
+    system.write_file(
+        meta_filename(system, file),
+        &serde_json::to_string(&meta)?,
+        0o600,
+    )
+}
+
+/// Decompress the backup of `file` to `dest`, restoring its permissions and ownership. Fails if
+/// the decompressed content doesn't match the checksum recorded when the backup was taken.
+pub fn extract_backup(system: &dyn Worker, file: &Path, dest: &Path) -> Result<()> {
+    let backup = compressed_backup_filename(system, file);
+    let meta: BackupMeta = serde_json::from_str(&system.read_file(meta_filename(system, file))?)?;
+
+    trace!("Decompressing {} -> {}", backup.display(), dest.display());
+    let cmd = Command::build(
+        "zstd",
+        &[
+            "-d",
+            "-q",
+            "-f",
+            "-o",
+            &dest.to_string_lossy(),
+            &backup.to_string_lossy(),
+        ],
+    );
+    system.run(&cmd)?;
+
+    let sha256 = system.sha256(dest.to_path_buf())?;
+    if sha256 != meta.sha256 {
+        system.remove_file(dest.to_path_buf())?;
+        anyhow::bail!(
+            "Backup of {} is corrupt: expected checksum {}, found {sha256}",
+            file.display(),
+            meta.sha256
+        );
+    }
+
+    std::os::unix::fs::chown(dest, Some(meta.uid), Some(meta.gid))?;
+    fs::set_permissions(dest, fs::Permissions::from_mode(meta.mode))?;
+    // Flushed before it's renamed over the original, so a crash can't leave it empty.
+    fs::File::open(dest)?.sync_all()?;
+    Ok(())
+}
+
+/// Restore `file` from the backup store, as a hard link to an already restored file if their
+/// originals were hard links to each other.
+pub fn restore_compressed_backup(system: &dyn Worker, file: &Path) -> Result<()> {
+    match restored_hardlink(system, file)? {
+        Some(original) => {
+            trace!("Linking {} to {}", file.display(), original.display());
+            link_file(&original, file)?;
+        }
+        None => {
+            // Decompressed next to the original, so it can be moved into place atomically.
+            let sibling = backup_filename(file);
+            extract_backup(system, file, &sibling)?;
+            move_file(&sibling, file)?;
+        }
+    }
+    remove_compressed_backup(system, file)?;
+    forget_backup(system, file)
+}
+
+/// Remove the compressed backup of `file` from the backup store.
+pub fn remove_compressed_backup(system: &dyn Worker, file: &Path) -> Result<()> {
+    system.remove_file(compressed_backup_filename(system, file))?;
+    system.remove_file(meta_filename(system, file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_compressed_backup_filename() {
+        let runner = MockSystem::default();
+        assert_eq!(
+            compressed_backup_filename(&runner, Path::new("/usr/bin/date")),
+            PathBuf::from("/var/lib/oxidizr/backups/usr/bin/date.zst")
+        );
+        assert_eq!(
+            meta_filename(&runner, Path::new("/usr/bin/date")),
+            PathBuf::from("/var/lib/oxidizr/backups/usr/bin/date.zst.json")
+        );
+
+        runner
+            .backup_dir
+            .replace(Some(PathBuf::from("/srv/backups")));
+        assert_eq!(
+            compressed_backup_filename(&runner, Path::new("/usr/bin/date")),
+            PathBuf::from("/srv/backups/usr/bin/date.zst")
+        );
+    }
+
+    /// Create a scratch directory under `parent` for a test of moving files.
+    fn scratch_dir(parent: &Path, name: &str) -> PathBuf {
+        let dir = parent.join(format!("oxidizr-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_move_file() {
+        let dir = scratch_dir(&std::env::temp_dir(), "move-file");
+        let (source, dest) = (dir.join("date.new"), dir.join("date"));
+        fs::write(&source, "new").unwrap();
+        fs::write(&dest, "old").unwrap();
+
+        move_file(&source, &dest).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "new");
+        assert!(!source.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_move_file_across_filesystems() {
+        // /dev/shm is a tmpfs, separate from the temporary directory on most systems.
+        let shm = Path::new("/dev/shm");
+        if !shm.is_dir() {
+            return;
+        }
+        let from = scratch_dir(shm, "move-file-from");
+        let to = scratch_dir(&std::env::temp_dir(), "move-file-to");
+        let (source, dest) = (from.join("date"), to.join("date"));
+        fs::write(&source, "new").unwrap();
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o750)).unwrap();
+        fs::write(&dest, "old").unwrap();
+
+        move_file(&source, &dest).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "new");
+        assert_eq!(fs::metadata(&dest).unwrap().mode() & 0o777, 0o750);
+        assert!(!source.exists());
+        // The copy staged alongside the destination is renamed over it.
+        assert!(!backup_filename(&dest).exists());
+
+        let (link, dest) = (from.join("sh"), to.join("sh"));
+        std::os::unix::fs::symlink("dash", &link).unwrap();
+        move_file(&link, &dest).unwrap();
+        assert_eq!(fs::read_link(&dest).unwrap(), PathBuf::from("dash"));
+        assert!(fs::symlink_metadata(&link).is_err());
+
+        fs::remove_dir_all(from).unwrap();
+        fs::remove_dir_all(to).unwrap();
+    }
+
+    #[test]
+    fn test_compressed_hardlinked_backups() {
+        let dir = scratch_dir(&std::env::temp_dir(), "compressed-hardlinks");
+        let (sha1sum, sha256sum) = (dir.join("sha1sum"), dir.join("sha256sum"));
+        fs::write(&sha1sum, "original").unwrap();
+        fs::hard_link(&sha1sum, &sha256sum).unwrap();
+        let metadata = fs::metadata(&sha1sum).unwrap();
+        let id = (metadata.dev(), metadata.ino());
+
+        let runner = MockSystem::default();
+        runner.backup_dir.replace(Some(dir.join("store")));
+        let store = stored_backup_filename(&dir.join("store"), &dir);
+        runner.mock_command(
+            &format!("df -P -k {}", store.display()),
+            "Filesystem 1024-blocks Used Available Capacity Mounted on\n/dev/sda1 2048 1024 1024 50% /",
+        );
+        for file in [&sha1sum, &sha256sum, &backup_filename(&sha1sum)] {
+            runner.mock_command(&format!("sha256sum {}", file.display()), "abc  file");
+        }
+
+        // The second file has a single link left once the first is replaced, but is still
+        // recorded as a hard link to it.
+        compress_backup(&runner, &sha1sum).unwrap();
+        fs::remove_file(&sha1sum).unwrap();
+        compress_backup(&runner, &sha256sum).unwrap();
+        fs::remove_file(&sha256sum).unwrap();
+        let manifest = read_manifest(&runner).unwrap();
+        assert_eq!(manifest[&sha1sum].hardlink, Some(id));
+        assert_eq!(manifest[&sha256sum].hardlink, Some(id));
+
+        // The first is decompressed, which the mocked zstd leaves to the test.
+        fs::write(backup_filename(&sha1sum), "original").unwrap();
+        restore_compressed_backup(&runner, &sha1sum).unwrap();
+        runner.mock_files(vec![(sha1sum.to_str().unwrap(), "original", false)]);
+
+        // The second is restored as a link to the first, rather than as a copy.
+        restore_compressed_backup(&runner, &sha256sum).unwrap();
+        assert_eq!(fs::read_to_string(&sha256sum).unwrap(), "original");
+        assert_eq!(
+            fs::metadata(&sha256sum).unwrap().ino(),
+            fs::metadata(&sha1sum).unwrap().ino()
+        );
+        assert!(read_manifest(&runner).unwrap().is_empty());
+        assert!(!has_compressed_backup(&runner, &sha256sum));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stored_backup_filename() {
+        assert_eq!(
+            stored_backup_filename(Path::new("/srv/backups"), Path::new("/usr/bin/date")),
+            PathBuf::from("/srv/backups/usr/bin/date")
+        );
+    }
+
+    #[test]
+    fn test_record_backup() {
+        let runner = MockSystem::default();
+        let meta = BackupMeta {
+            sha256: "abc".to_string(),
+            size: 4096,
+            mode: 0o100755,
+            uid: 0,
+            gid: 0,
+            ..Default::default()
+        };
+        record_backup(&runner, Path::new("/usr/bin/date"), meta.clone()).unwrap();
+        assert_eq!(
+            read_manifest(&runner).unwrap(),
+            BTreeMap::from([(PathBuf::from("/usr/bin/date"), meta)])
+        );
+    }
+
+    #[test]
+    fn test_forget_hardlinked_backup() {
+        let runner = MockSystem::default();
+        let linked = BackupMeta {
+            hardlink: Some((2049, 1234)),
+            ..Default::default()
+        };
+        record_backup(&runner, Path::new("/usr/bin/sha1sum"), linked.clone()).unwrap();
+        record_backup(&runner, Path::new("/usr/bin/sha256sum"), linked).unwrap();
+        record_backup(&runner, Path::new("/usr/bin/date"), BackupMeta::default()).unwrap();
+
+        forget_backup(&runner, Path::new("/usr/bin/sha1sum")).unwrap();
+        let manifest = read_manifest(&runner).unwrap();
+        assert!(!manifest.contains_key(Path::new("/usr/bin/sha1sum")));
+        assert_eq!(
+            manifest[Path::new("/usr/bin/sha256sum")].link_to,
+            Some(PathBuf::from("/usr/bin/sha1sum"))
+        );
+        assert_eq!(manifest[Path::new("/usr/bin/date")].link_to, None);
+
+        // The restored file must be in place to be linked to.
+        let sha256sum = Path::new("/usr/bin/sha256sum");
+        assert_eq!(restored_hardlink(&runner, sha256sum).unwrap(), None);
+        runner.mock_files(vec![("/usr/bin/sha1sum", "", false)]);
+        assert_eq!(
+            restored_hardlink(&runner, sha256sum).unwrap(),
+            Some(PathBuf::from("/usr/bin/sha1sum"))
+        );
+    }
+
+    #[test]
+    fn test_backup_discrepancies() {
+        let expected = BackupMeta {
+            sha256: "abc".to_string(),
+            size: 4096,
+            mode: 0o104755,
+            uid: 0,
+            gid: 0,
+            ..Default::default()
+        };
+        assert!(backup_discrepancies(&expected, &expected).is_empty());
+
+        let truncated = BackupMeta {
+            sha256: "def".to_string(),
+            size: 0,
+            mode: 0o100755,
+            ..expected.clone()
+        };
+        assert_eq!(
+            backup_discrepancies(&expected, &truncated),
+            [
+                "its size is 0 bytes, expected 4096",
+                "its checksum is def, expected abc",
+                "its mode is 100755, expected 104755",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ensure_free_space() {
+        let runner = MockSystem::default();
+        runner.mock_command(
+            "df -P -k /srv/backups",
+            "Filesystem     1024-blocks  Used Available Capacity Mounted on\n\
+             /dev/sdb1              16    12         4      75% /srv\n",
+        );
+        assert!(ensure_free_space(&runner, Path::new("/srv/backups"), 4096).is_ok());
+        assert!(ensure_free_space(&runner, Path::new("/srv/backups"), 4097).is_err());
+
+        // Output that can't be parsed is an error rather than no space.
+        runner.mock_command("df -P -k /srv/other", "df: /srv/other: No such file\n");
+        assert!(ensure_free_space(&runner, Path::new("/srv/other"), 1).is_err());
+    }
+
+    #[test]
+    fn test_original_packages() {
+        let runner = MockSystem::default();
+        runner.mock_command("dpkg-query -S /usr/bin/date", "coreutils: /usr/bin/date\n");
+        runner.mock_command("dpkg-query -S /usr/bin/ls", "coreutils: /usr/bin/ls\n");
+        runner.mock_command(
+            "dpkg-query -S /usr/bin/coreutils",
+            "rust-coreutils: /usr/bin/coreutils\n",
+        );
+        runner.mock_command("dpkg-query -W -f=${Version} coreutils", "9.4-3ubuntu6");
+
+        let files = [
+            "/usr/bin/date",
+            "/usr/bin/ls",
+            "/usr/bin/coreutils",
+            "/usr/bin/b2sum",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(
+            original_packages(&runner, &files, "rust-coreutils"),
+            BTreeMap::from([("coreutils".to_string(), "9.4-3ubuntu6".to_string())])
+        );
+    }
+}
@@ -16,6 +16,9 @@ impl Command {
 
     /// Get the full command string.
     pub fn command(&self) -> String {
-        format!("{} {}", self.command, self.args.join(" "))
+        match self.args.is_empty() {
+            true => self.command.clone(),
+            false => format!("{} {}", self.command, self.args.join(" ")),
+        }
     }
 }
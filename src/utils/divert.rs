@@ -0,0 +1,79 @@
+use super::{Command, Worker};
+use anyhow::Result;
+use std::path::Path;
+
+/// Package in whose name oxidizr registers its diversions with dpkg.
+pub const DIVERSION_PACKAGE: &str = "oxidizr";
+
+/// Build the command diverting `file` to `backup`, or removing the diversion, renaming the
+/// file either way so that it ends up where dpkg expects it.
+pub fn dpkg_divert(file: &Path, backup: &Path, add: bool) -> Command {
+    let action = match add {
+        true => "--add",
+        false => "--remove",
+    };
+    Command::build(
+        "dpkg-divert",
+        &[
+            "--package",
+            DIVERSION_PACKAGE,
+            "--divert",
+            &backup.to_string_lossy(),
+            "--rename",
+            action,
+            &file.to_string_lossy(),
+        ],
+    )
+}
+
+/// Report whether `file` has been diverted by oxidizr. Systems without dpkg have no diversions.
+pub fn is_diverted(system: &dyn Worker, file: &Path) -> bool {
+    let cmd = Command::build("dpkg-divert", &["--listpackage", &file.to_string_lossy()]);
+    system
+        .run(&cmd)
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == DIVERSION_PACKAGE)
+}
+
+/// Move `file` aside to `backup` with a dpkg diversion, so that package upgrades install the
+/// new version of the file to `backup` rather than replacing what oxidizr puts in its place.
+pub fn divert_file(system: &dyn Worker, file: &Path, backup: &Path) -> Result<()> {
+    system.run(&dpkg_divert(file, backup, true))?;
+    Ok(())
+}
+
+/// Remove the diversion of `file`, moving the original back from `backup`. Whatever replaced
+/// the file must already have been removed.
+pub fn undivert_file(system: &dyn Worker, file: &Path, backup: &Path) -> Result<()> {
+    system.run(&dpkg_divert(file, backup, false))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_dpkg_divert() {
+        let file = Path::new("/usr/bin/date");
+        let backup = Path::new("/usr/bin/.date.oxidizr.bak");
+        assert_eq!(
+            dpkg_divert(file, backup, true).command(),
+            "dpkg-divert --package oxidizr --divert /usr/bin/.date.oxidizr.bak --rename --add /usr/bin/date"
+        );
+        assert_eq!(
+            dpkg_divert(file, backup, false).command(),
+            "dpkg-divert --package oxidizr --divert /usr/bin/.date.oxidizr.bak --rename --remove /usr/bin/date"
+        );
+    }
+
+    #[test]
+    fn test_is_diverted() {
+        let runner = MockSystem::default();
+        runner.mock_command("dpkg-divert --listpackage /usr/bin/date", "oxidizr\n");
+        runner.mock_command("dpkg-divert --listpackage /usr/bin/ls", "LOCAL\n");
+        assert!(is_diverted(&runner, Path::new("/usr/bin/date")));
+        assert!(!is_diverted(&runner, Path::new("/usr/bin/ls")));
+        assert!(!is_diverted(&runner, Path::new("/usr/bin/sort")));
+    }
+}
@@ -0,0 +1,257 @@
+use super::{Command, Distribution, PackageManager, Worker};
+use anyhow::Result;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+
+/// Programs that only inspect the system, and so are run for real during a dry run.
+const READ_ONLY_PROGRAMS: &[&str] = &[
+    "apt-cache",
+    "df",
+    "dpkg-query",
+    "findmnt",
+    "lsb_release",
+    "lvs",
+    "rpm",
+    "sha256sum",
+    "stat",
+];
+
+/// A worker that inspects the system it wraps, but records the changes it's asked to make as
+/// descriptions rather than making them.
+pub struct DryRun<'a> {
+    system: &'a dyn Worker,
+    actions: RefCell<Vec<String>>,
+}
+
+impl<'a> DryRun<'a> {
+    pub fn new(system: &'a dyn Worker) -> Self {
+        Self {
+            system,
+            actions: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Return the actions recorded since the last call, in the order they'd be taken.
+    pub fn take_actions(&self) -> Vec<String> {
+        self.actions.take()
+    }
+
+    fn record(&self, action: String) {
+        self.actions.borrow_mut().push(action);
+    }
+
+    fn read_only(cmd: &Command) -> bool {
+        let command = cmd.command();
+        let pacman_query =
+            cmd.command == "pacman" && cmd.args.first().is_some_and(|a| a.starts_with("-Q"));
+        let apk_query = cmd.command == "apk" && cmd.args.first().is_some_and(|a| a == "info");
+        READ_ONLY_PROGRAMS.contains(&cmd.command.as_str())
+            || pacman_query
+            || apk_query
+            || command == "apt-mark showhold"
+            || command == "dnf versionlock list"
+            || command == "zypper locks"
+            || command.starts_with("snap list ")
+    }
+}
+
+impl Worker for DryRun<'_> {
+    fn run(&self, cmd: &Command) -> Result<Output> {
+        if Self::read_only(cmd) {
+            return self.system.run(cmd);
+        }
+        self.record(format!("run '{}'", cmd.command()));
+        Ok(Output {
+            status: ExitStatus::default(),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn capture(&self, cmd: &Command, dir: &Path) -> Result<Output> {
+        if Self::read_only(cmd) {
+            return self.system.capture(cmd, dir);
+        }
+        self.run(cmd)
+    }
+
+    fn list_files(&self, directory: PathBuf) -> Result<Vec<PathBuf>> {
+        self.system.list_files(directory)
+    }
+
+    fn which(&self, binary_name: &str) -> Result<PathBuf> {
+        self.system.which(binary_name)
+    }
+
+    fn pinned_version(&self, package: &str) -> Option<String> {
+        self.system.pinned_version(package)
+    }
+
+    fn offline(&self) -> bool {
+        self.system.offline()
+    }
+
+    fn use_releases(&self) -> bool {
+        self.system.use_releases()
+    }
+
+    fn diversions(&self) -> bool {
+        self.system.diversions()
+    }
+
+    fn compress_backups(&self) -> bool {
+        self.system.compress_backups()
+    }
+
+    fn backup_dir(&self) -> Option<PathBuf> {
+        self.system.backup_dir()
+    }
+
+    fn local_package_dir(&self) -> Option<PathBuf> {
+        self.system.local_package_dir()
+    }
+
+    fn distribution(&self) -> Result<Distribution> {
+        self.system.distribution()
+    }
+
+    fn snap_channel(&self) -> Option<String> {
+        self.system.snap_channel()
+    }
+
+    fn package_manager(&self) -> PackageManager {
+        self.system.package_manager()
+    }
+
+    fn stale_services(&self) -> Result<Vec<String>> {
+        self.system.stale_services()
+    }
+
+    fn replace_file_with_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()> {
+        // Mirrors `System`: existing symlinks are only left alone if they already point at
+        // `source`.
+        let link = self.read_link(target.clone()).ok();
+        if link.as_ref() == Some(&source) {
+            return Ok(());
+        }
+        if link.is_none() && self.diversions() && self.file_exists(target.clone()) {
+            let backup = self.backup_location(&target);
+            self.record(format!(
+                "divert {} to {}",
+                target.display(),
+                backup.display()
+            ));
+        } else if link.is_some() || self.file_exists(target.clone()) {
+            self.backup_file(target.clone())?;
+        }
+        self.create_symlink(source, target)
+    }
+
+    fn backup_file(&self, file: PathBuf) -> Result<()> {
+        let backup = self.backup_location(&file);
+        self.record(format!(
+            "back up {} to {}",
+            file.display(),
+            backup.display()
+        ));
+        Ok(())
+    }
+
+    fn restore_file(&self, file: PathBuf) -> Result<()> {
+        self.record(format!("restore {} from its backup", file.display()));
+        Ok(())
+    }
+
+    fn create_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()> {
+        self.record(format!("link {} -> {}", target.display(), source.display()));
+        Ok(())
+    }
+
+    fn copy_file(&self, source: PathBuf, dest: PathBuf) -> Result<()> {
+        self.record(format!("copy {} to {}", source.display(), dest.display()));
+        Ok(())
+    }
+
+    fn file_exists(&self, file: PathBuf) -> bool {
+        self.system.file_exists(file)
+    }
+
+    fn read_link(&self, file: PathBuf) -> Result<PathBuf> {
+        self.system.read_link(file)
+    }
+
+    fn read_file(&self, file: PathBuf) -> Result<String> {
+        self.system.read_file(file)
+    }
+
+    fn write_file(&self, file: PathBuf, _contents: &str, mode: u32) -> Result<()> {
+        self.record(format!("write {} (mode {mode:o})", file.display()));
+        Ok(())
+    }
+
+    fn remove_file(&self, file: PathBuf) -> Result<()> {
+        if self.file_exists(file.clone()) {
+            self.record(format!("remove {}", file.display()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_dry_run_records_changes() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/bin/date", "date", false)]);
+        runner.mock_symlinks(vec![
+            ("/usr/bin/sudo", "/usr/lib/cargo/bin/sudo"),
+            ("/usr/bin/updatedb", "/etc/alternatives/updatedb"),
+        ]);
+        runner.mock_command("dpkg-query -W -f=${Version} rust-coreutils", "0.0.29\n");
+
+        let dry_run = DryRun::new(&runner);
+        assert_eq!(dry_run.package_version("rust-coreutils").unwrap(), "0.0.29");
+        dry_run.install_package("rust-coreutils").unwrap();
+        dry_run
+            .replace_file_with_symlink(
+                PathBuf::from("/usr/lib/cargo/bin/coreutils/date"),
+                PathBuf::from("/usr/bin/date"),
+            )
+            .unwrap();
+        dry_run
+            .replace_file_with_symlink(
+                PathBuf::from("/usr/lib/cargo/bin/sudo"),
+                PathBuf::from("/usr/bin/sudo"),
+            )
+            .unwrap();
+        dry_run
+            .replace_file_with_symlink(
+                PathBuf::from("/usr/lib/cargo/bin/findutils/updatedb"),
+                PathBuf::from("/usr/bin/updatedb"),
+            )
+            .unwrap();
+        dry_run
+            .remove_file(PathBuf::from("/usr/bin/missing"))
+            .unwrap();
+
+        assert_eq!(
+            dry_run.take_actions(),
+            vec![
+                "run 'apt-get install -y rust-coreutils'",
+                "back up /usr/bin/date to /usr/bin/.date.oxidizr.bak",
+                "link /usr/bin/date -> /usr/lib/cargo/bin/coreutils/date",
+                "back up /usr/bin/updatedb to /usr/bin/.updatedb.oxidizr.bak",
+                "link /usr/bin/updatedb -> /usr/lib/cargo/bin/findutils/updatedb",
+            ]
+        );
+        assert_eq!(
+            *runner.commands.borrow(),
+            vec!["dpkg-query -W -f=${Version} rust-coreutils"]
+        );
+        assert!(runner.file_exists(PathBuf::from("/usr/bin/date")));
+    }
+}
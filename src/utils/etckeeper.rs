@@ -0,0 +1,135 @@
+use super::{Command, Worker};
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Integration with etckeeper, so that changes made by oxidizr to files in `/etc` are recorded
+/// in the `/etc` repository alongside changes made by apt and the administrator.
+pub struct Etckeeper<'a> {
+    system: &'a dyn Worker,
+}
+
+impl<'a> Etckeeper<'a> {
+    /// Detect whether etckeeper is installed and managing `/etc`.
+    pub fn detect(system: &'a dyn Worker) -> Option<Self> {
+        if system.which("etckeeper").is_err()
+            || !system.file_exists(PathBuf::from("/etc/.etckeeper"))
+        {
+            return None;
+        }
+        Some(Self { system })
+    }
+
+    /// Commit any uncommitted changes in `/etc` before oxidizr makes changes, in the same way
+    /// etckeeper does before an apt run. If etckeeper is configured to avoid committing before
+    /// installs, an error is returned instead so the administrator can commit their changes.
+    pub fn pre_commit(&self) -> Result<()> {
+        if !self.unclean() {
+            return Ok(());
+        }
+
+        if self.avoid_commit_before_install() {
+            anyhow::bail!(
+                "/etc has uncommitted changes and etckeeper is configured to avoid committing them automatically. Commit them with 'etckeeper commit' before continuing"
+            );
+        }
+
+        self.commit("saving uncommitted changes in /etc prior to oxidizr run")
+    }
+
+    /// Commit the changes made by oxidizr to `/etc`, if there are any.
+    pub fn post_commit(&self, message: &str) -> Result<()> {
+        if !self.unclean() {
+            return Ok(());
+        }
+        self.commit(message)
+    }
+
+    /// Report whether `/etc` has uncommitted changes. `etckeeper unclean` exits successfully
+    /// if there are uncommitted changes.
+    fn unclean(&self) -> bool {
+        let cmd = Command::build("etckeeper", &["unclean"]);
+        self.system.run(&cmd).is_ok()
+    }
+
+    fn commit(&self, message: &str) -> Result<()> {
+        info!("Committing changes to /etc with etckeeper");
+        let cmd = Command::build("etckeeper", &["commit", message]);
+        self.system.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Report whether `AVOID_COMMIT_BEFORE_INSTALL` is set in the etckeeper configuration.
+    fn avoid_commit_before_install(&self) -> bool {
+        self.system
+            .read_file(PathBuf::from("/etc/etckeeper/etckeeper.conf"))
+            .map(|conf| {
+                conf.lines()
+                    .map(str::trim)
+                    .any(|l| l == "AVOID_COMMIT_BEFORE_INSTALL=1")
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_etckeeper_not_installed() {
+        let runner = MockSystem::default();
+        assert!(Etckeeper::detect(&runner).is_none());
+    }
+
+    #[test]
+    fn test_etckeeper_pre_commit_dirty() {
+        let runner = etckeeper_runner();
+        let etckeeper = Etckeeper::detect(&runner).unwrap();
+
+        assert!(etckeeper.pre_commit().is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands,
+            &[
+                "etckeeper unclean",
+                "etckeeper commit saving uncommitted changes in /etc prior to oxidizr run"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_etckeeper_pre_commit_avoided() {
+        let runner = etckeeper_runner();
+        runner.mock_files(vec![(
+            "/etc/etckeeper/etckeeper.conf",
+            "VCS=\"git\"\nAVOID_COMMIT_BEFORE_INSTALL=1\n",
+            false,
+        )]);
+        let etckeeper = Etckeeper::detect(&runner).unwrap();
+
+        assert!(etckeeper.pre_commit().is_err());
+        assert_eq!(runner.commands.clone().into_inner(), &["etckeeper unclean"]);
+    }
+
+    #[test]
+    fn test_etckeeper_post_commit_clean() {
+        let runner = etckeeper_runner();
+        runner.mock_command_failure("etckeeper unclean");
+        let etckeeper = Etckeeper::detect(&runner).unwrap();
+
+        assert!(etckeeper.post_commit("oxidizr: enable coreutils").is_ok());
+        assert_eq!(runner.commands.clone().into_inner(), &["etckeeper unclean"]);
+    }
+
+    fn etckeeper_runner() -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![
+            ("/usr/bin/etckeeper", "", true),
+            ("/etc/.etckeeper", "", false),
+        ]);
+        runner
+    }
+}
@@ -0,0 +1,113 @@
+use super::Worker;
+use std::path::PathBuf;
+
+/// A system whose `/usr` is read-only or generated from a declarative configuration, so the
+/// files in it can't be replaced with symlinks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImmutableSystem {
+    /// NixOS, which builds the system from `configuration.nix`.
+    NixOS,
+    /// A system deployed with ostree, such as Fedora Silverblue or Kinoite.
+    Ostree,
+    /// Ubuntu Core, which is assembled from read-only snaps.
+    UbuntuCore,
+}
+
+impl ImmutableSystem {
+    /// Detect whether the system is immutable, from the markers each leaves in the filesystem.
+    pub fn detect(system: &dyn Worker) -> Option<Self> {
+        if system.file_exists(PathBuf::from("/etc/NIXOS")) {
+            return Some(Self::NixOS);
+        }
+        if system.file_exists(PathBuf::from("/run/ostree-booted")) {
+            return Some(Self::Ostree);
+        }
+        let os_release = system
+            .read_file(PathBuf::from("/etc/os-release"))
+            .unwrap_or_default();
+        if os_release.lines().any(|l| {
+            l.strip_prefix("ID=")
+                .is_some_and(|id| id.trim().trim_matches('"') == "ubuntu-core")
+        }) {
+            return Some(Self::UbuntuCore);
+        }
+        None
+    }
+
+    /// Report the name of the system, as used in messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NixOS => "NixOS",
+            Self::Ostree => "ostree-based systems such as Fedora Silverblue",
+            Self::UbuntuCore => "Ubuntu Core",
+        }
+    }
+
+    /// Report whether experiments can be provided by a system extension image merged over
+    /// `/usr`, rather than by replacing files.
+    pub fn supports_sysext(&self) -> bool {
+        matches!(self, Self::Ostree)
+    }
+
+    /// Explain why experiments can't be enabled on the system, and how to use the Rust
+    /// replacements instead.
+    pub fn explanation(&self) -> String {
+        let advice = match self {
+            Self::NixOS => {
+                "The system is built from configuration.nix, so add the replacements there \
+                 instead, e.g. 'uutils-coreutils-noprefix' to environment.systemPackages and \
+                 'security.sudo-rs.enable = true', then run 'nixos-rebuild switch'"
+            }
+            Self::Ostree => {
+                "Layer the packages with 'rpm-ostree install' and reboot, and they'll be \
+                 provided by a system extension image"
+            }
+            Self::UbuntuCore => {
+                "The system is assembled from read-only snaps, which can't be modified. Run \
+                 the replacements from a classic Ubuntu system, or a container on this one"
+            }
+        };
+        format!("/usr can't be modified on {}. {advice}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_detect_immutable_system() {
+        let runner = MockSystem::default();
+        assert_eq!(ImmutableSystem::detect(&runner), None);
+
+        runner.mock_files(vec![("/run/ostree-booted", "", false)]);
+        assert_eq!(
+            ImmutableSystem::detect(&runner),
+            Some(ImmutableSystem::Ostree)
+        );
+        assert!(ImmutableSystem::Ostree.supports_sysext());
+
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/etc/NIXOS", "", false)]);
+        assert_eq!(
+            ImmutableSystem::detect(&runner),
+            Some(ImmutableSystem::NixOS)
+        );
+        assert!(!ImmutableSystem::NixOS.supports_sysext());
+    }
+
+    #[test]
+    fn test_detect_ubuntu_core() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![(
+            "/etc/os-release",
+            "NAME=\"Ubuntu Core\"\nVERSION_ID=\"24\"\nID=ubuntu-core\n",
+            false,
+        )]);
+        assert_eq!(
+            ImmutableSystem::detect(&runner),
+            Some(ImmutableSystem::UbuntuCore)
+        );
+    }
+}
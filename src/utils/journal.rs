@@ -0,0 +1,145 @@
+use crate::utils::{Migration, Worker, migrate, set_version, stamp_version};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Location of the journal of actions taken by `enable`, replayed in reverse by `rollback`.
+pub const JOURNAL_FILE: &str = "/var/lib/oxidizr/journal.jsonl";
+
+/// Migrations of the format of journal entries, where the `n`th migrates from version `n`. Its
+/// length is the version of the format written by this version of oxidizr.
+const JOURNAL_MIGRATIONS: [Migration; 1] = [stamp_version];
+
+/// An action taken on the system, recorded before it's carried out along with enough detail to
+/// undo it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum JournalEntry {
+    /// A package was installed. `installed` records whether it was already installed.
+    InstallPackage { package: String, installed: bool },
+    /// A file was backed up, and replaced with a symlink.
+    ReplaceFile { file: PathBuf },
+    /// A symlink was created, replacing the symlink to `previous` if there was one.
+    CreateSymlink {
+        link: PathBuf,
+        previous: Option<PathBuf>,
+    },
+    /// A file was written, replacing the `previous` contents and permissions if it existed.
+    WriteFile {
+        file: PathBuf,
+        previous: Option<(String, u32)>,
+    },
+}
+
+/// An append-only, line-delimited JSON record of the actions taken on the system.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    file: PathBuf,
+}
+
+impl Journal {
+    pub fn new(file: PathBuf) -> Self {
+        Self { file }
+    }
+
+    /// Append an entry to the journal, and flush it to disk before returning so the entry
+    /// survives the action being interrupted.
+    pub fn record(&self, entry: &JournalEntry) -> Result<()> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file)?;
+        // Each entry records its format version, so it can be migrated by later versions.
+        let mut value = serde_json::to_value(entry)?;
+        set_version(&mut value, JOURNAL_MIGRATIONS.len());
+        writeln!(file, "{}", serde_json::to_string(&value)?)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Replace the entries in the journal with `entries`, removing the journal if there are none.
+    pub fn replace(system: &dyn Worker, entries: &[JournalEntry]) -> Result<()> {
+        let file = PathBuf::from(JOURNAL_FILE);
+        if entries.is_empty() {
+            return system.remove_file(file);
+        }
+        let mut contents = String::new();
+        for entry in entries {
+            let mut value = serde_json::to_value(entry)?;
+            set_version(&mut value, JOURNAL_MIGRATIONS.len());
+            contents.push_str(&serde_json::to_string(&value)?);
+            contents.push('\n');
+        }
+        system.write_file(file, &contents, 0o644)
+    }
+
+    /// Read the entries from the journal, oldest first. If no journal exists, there are none.
+    pub fn load(system: &dyn Worker) -> Result<Vec<JournalEntry>> {
+        let file = PathBuf::from(JOURNAL_FILE);
+        if !system.file_exists(file.clone()) {
+            return Ok(Vec::new());
+        }
+
+        system
+            .read_file(file)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut value = serde_json::from_str(line)?;
+                migrate(JOURNAL_FILE, &mut value, &JOURNAL_MIGRATIONS)?;
+                Ok(serde_json::from_value(value)?)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_journal_load() {
+        let runner = MockSystem::default();
+        assert!(Journal::load(&runner).unwrap().is_empty());
+
+        runner.mock_files(vec![(
+            JOURNAL_FILE,
+            concat!(
+                "{\"action\":\"install-package\",\"package\":\"sudo-rs\",\"installed\":false}\n",
+                "{\"action\":\"replace-file\",\"file\":\"/usr/bin/sudo\"}\n",
+                "{\"action\":\"create-symlink\",\"version\":1,\"link\":\"/usr/bin/sudo\",\"previous\":null}\n",
+            ),
+            false,
+        )]);
+        assert_eq!(
+            Journal::load(&runner).unwrap(),
+            vec![
+                JournalEntry::InstallPackage {
+                    package: "sudo-rs".to_string(),
+                    installed: false
+                },
+                JournalEntry::ReplaceFile {
+                    file: PathBuf::from("/usr/bin/sudo")
+                },
+                JournalEntry::CreateSymlink {
+                    link: PathBuf::from("/usr/bin/sudo"),
+                    previous: None
+                },
+            ]
+        );
+
+        // Entries written by a newer version of oxidizr aren't misread.
+        runner.mock_files(vec![(
+            JOURNAL_FILE,
+            "{\"action\":\"replace-file\",\"version\":2,\"file\":\"/usr/bin/sudo\"}\n",
+            false,
+        )]);
+        assert!(Journal::load(&runner).is_err());
+    }
+}
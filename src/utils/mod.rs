@@ -1,10 +1,43 @@
+mod backups;
 mod command;
+mod divert;
+mod dry_run;
+mod etckeeper;
+mod immutable;
+mod journal;
+mod packages;
+mod ppa;
+mod progress;
+mod proxy;
+mod releases;
+mod schema;
+mod services;
+mod session;
+mod snaps;
+mod snapshot;
 mod worker;
 
+use anyhow::Result;
 use std::collections::HashSet;
 use std::hash::Hash;
 
+pub use backups::*;
 pub use command::*;
+pub use divert::*;
+pub use dry_run::*;
+pub use etckeeper::*;
+pub use immutable::*;
+pub use journal::*;
+pub use packages::*;
+pub use ppa::*;
+pub use progress::*;
+pub use proxy::*;
+pub use releases::*;
+pub use schema::*;
+pub use services::*;
+pub use session::*;
+pub use snaps::*;
+pub use snapshot::*;
 pub use worker::*;
 
 #[cfg(test)]
@@ -12,6 +45,24 @@ mod worker_mock;
 #[cfg(test)]
 pub use worker_mock::tests::*;
 
+/// Distributions oxidizr supports, as reported by `lsb_release -is`. Experiments declare the
+/// releases of each they support, e.g. `24.04` for Ubuntu, `13` for Debian trixie, `42` for
+/// Fedora or `rolling` for Arch.
+pub const SUPPORTED_DISTRIBUTIONS: &[&str] =
+    &["Ubuntu", "Debian", "Fedora", "Arch", "openSUSE", "Alpine"];
+
+/// Ubuntu releases by codename, used to find the release an Ubuntu derivative is based on.
+const UBUNTU_CODENAMES: &[(&str, &str)] = &[
+    ("jammy", "22.04"),
+    ("noble", "24.04"),
+    ("oracular", "24.10"),
+    ("plucky", "25.04"),
+    ("questing", "25.10"),
+];
+
+/// The release reported for openSUSE Tumbleweed, whose version is the date of its snapshot.
+pub const TUMBLEWEED: &str = "tumbleweed";
+
 /// A representation for Linux distribution information for the system.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Distribution {
@@ -19,6 +70,98 @@ pub struct Distribution {
     pub release: String,
 }
 
+impl Distribution {
+    /// Report whether oxidizr supports the distribution.
+    pub fn is_supported(&self) -> bool {
+        SUPPORTED_DISTRIBUTIONS.contains(&self.id.as_str())
+    }
+
+    /// Read the distribution information from the contents of `/etc/os-release`, naming the
+    /// distribution as `lsb_release` does, e.g. `Fedora` for `ID=fedora`.
+    pub fn from_os_release(contents: &str) -> Result<Self> {
+        let field = |key: &str| os_release_field(contents, key);
+
+        let Some(id) = field("ID") else {
+            anyhow::bail!("Unable to determine the distribution from /etc/os-release");
+        };
+        if let Some(base) = Self::ubuntu_base(contents) {
+            return Ok(base);
+        }
+        // openSUSE names each edition separately, e.g. `ID=opensuse-leap`.
+        let id = match id.starts_with("opensuse") {
+            true => "openSUSE".to_string(),
+            false => {
+                let mut chars = id.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => id,
+                }
+            }
+        };
+        Ok(Self {
+            id,
+            release: field("VERSION_ID").unwrap_or_else(|| "rolling".to_string()),
+        }
+        .normalized())
+    }
+
+    /// Report the codename of an Ubuntu release, e.g. `noble` for 24.04.
+    pub fn ubuntu_codename(&self) -> Option<&'static str> {
+        if self.id != "Ubuntu" {
+            return None;
+        }
+        UBUNTU_CODENAMES
+            .iter()
+            .find(|(_, release)| *release == self.release)
+            .map(|(codename, _)| *codename)
+    }
+
+    /// Find the Ubuntu release a derivative such as Linux Mint or Pop!_OS is based on, from the
+    /// `ID_LIKE` and `UBUNTU_CODENAME` fields of `/etc/os-release`. Ubuntu itself isn't reported.
+    pub fn ubuntu_base(os_release: &str) -> Option<Self> {
+        let field = |key: &str| os_release_field(os_release, key);
+        if !field("ID_LIKE")?
+            .split_whitespace()
+            .any(|id| id == "ubuntu")
+        {
+            return None;
+        }
+        let codename = field("UBUNTU_CODENAME")?;
+        let (_, release) = UBUNTU_CODENAMES.iter().find(|(c, _)| *c == codename)?;
+        Some(Self {
+            id: "Ubuntu".to_string(),
+            release: release.to_string(),
+        })
+    }
+
+    /// Report openSUSE Tumbleweed's snapshots, e.g. `20261010`, as a single release, and
+    /// Alpine's point releases, e.g. `3.21.3`, as their stable branch, so that experiments can
+    /// declare support for each as a whole.
+    pub fn normalized(self) -> Self {
+        let snapshot = self.release.len() == 8 && self.release.chars().all(|c| c.is_ascii_digit());
+        let release = match self.id.as_str() {
+            "openSUSE" if snapshot => TUMBLEWEED.to_string(),
+            "Alpine" => self
+                .release
+                .splitn(3, '.')
+                .take(2)
+                .collect::<Vec<_>>()
+                .join("."),
+            _ => return self,
+        };
+        Self { release, ..self }
+    }
+}
+
+/// Read the value of a field from the contents of `/etc/os-release`, without any quotes.
+fn os_release_field(contents: &str, key: &str) -> Option<String> {
+    contents.lines().find_map(|l| {
+        l.strip_prefix(key)
+            .and_then(|v| v.strip_prefix('='))
+            .map(|v| v.trim().trim_matches('"').to_string())
+    })
+}
+
 /// Return true if the two (potentially unordered) vecs contain identical elements.
 pub fn vecs_eq<T>(v1: Vec<T>, v2: Vec<T>) -> bool
 where
@@ -30,3 +173,65 @@ where
     let hs: HashSet<_> = v1.iter().collect();
     v2.iter().all(|i| hs.contains(i))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distribution_from_os_release() {
+        let fedora = "NAME=\"Fedora Linux\"\nVERSION_ID=42\nID=fedora\nID_LIKE=\"rhel centos\"\n";
+        assert_eq!(
+            Distribution::from_os_release(fedora).unwrap(),
+            Distribution {
+                id: "Fedora".to_string(),
+                release: "42".to_string(),
+            }
+        );
+        let arch = "NAME=\"Arch Linux\"\nID=arch\nBUILD_ID=rolling\n";
+        assert_eq!(
+            Distribution::from_os_release(arch).unwrap(),
+            Distribution {
+                id: "Arch".to_string(),
+                release: "rolling".to_string(),
+            }
+        );
+        let tumbleweed =
+            "NAME=\"openSUSE Tumbleweed\"\nID=\"opensuse-tumbleweed\"\nVERSION_ID=\"20261010\"\n";
+        assert_eq!(
+            Distribution::from_os_release(tumbleweed).unwrap(),
+            Distribution {
+                id: "openSUSE".to_string(),
+                release: "tumbleweed".to_string(),
+            }
+        );
+        let leap = "NAME=\"openSUSE Leap\"\nID=\"opensuse-leap\"\nVERSION_ID=\"16.0\"\n";
+        assert_eq!(Distribution::from_os_release(leap).unwrap().release, "16.0");
+        let alpine = "NAME=\"Alpine Linux\"\nID=alpine\nVERSION_ID=3.21.3\n";
+        assert_eq!(
+            Distribution::from_os_release(alpine).unwrap(),
+            Distribution {
+                id: "Alpine".to_string(),
+                release: "3.21".to_string(),
+            }
+        );
+        assert!(Distribution::from_os_release("NAME=Unknown\n").is_err());
+    }
+
+    #[test]
+    fn test_distribution_ubuntu_base() {
+        let mint = "NAME=\"Linux Mint\"\nVERSION_ID=\"22\"\nID=linuxmint\nID_LIKE=\"ubuntu debian\"\nUBUNTU_CODENAME=noble\n";
+        let expected = Distribution {
+            id: "Ubuntu".to_string(),
+            release: "24.04".to_string(),
+        };
+        assert_eq!(Distribution::ubuntu_base(mint), Some(expected.clone()));
+        assert_eq!(Distribution::from_os_release(mint).unwrap(), expected);
+
+        let ubuntu = "NAME=\"Ubuntu\"\nVERSION_ID=\"24.04\"\nID=ubuntu\nID_LIKE=debian\nUBUNTU_CODENAME=noble\n";
+        assert_eq!(Distribution::ubuntu_base(ubuntu), None);
+
+        let unknown = "ID=pop\nID_LIKE=\"ubuntu debian\"\nUBUNTU_CODENAME=hardy\n";
+        assert_eq!(Distribution::ubuntu_base(unknown), None);
+    }
+}
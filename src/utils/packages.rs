@@ -0,0 +1,414 @@
+use super::Command;
+
+/// The package manager used to install and remove the packages of experiments, selected from
+/// the distribution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageManager {
+    /// apt and dpkg, on Ubuntu and Debian.
+    Apt,
+    /// dnf and rpm, on Fedora.
+    Dnf,
+    /// pacman, on Arch Linux.
+    Pacman,
+    /// zypper and rpm, on openSUSE.
+    Zypper,
+    /// apk, on Alpine Linux.
+    Apk,
+}
+
+impl PackageManager {
+    /// Select the package manager for a distribution, as reported by `lsb_release -is`.
+    /// Distributions that aren't known to use another package manager are assumed to use apt.
+    pub fn for_distribution(id: &str) -> Self {
+        match id {
+            "Fedora" => Self::Dnf,
+            "Arch" => Self::Pacman,
+            "openSUSE" => Self::Zypper,
+            "Alpine" => Self::Apk,
+            _ => Self::Apt,
+        }
+    }
+
+    /// Build the command installing a package, at an exact version if one is given.
+    pub fn install(&self, package: &str, version: Option<&str>) -> Command {
+        match (self, version) {
+            (Self::Apt, Some(version)) => Command::build(
+                "apt-get",
+                &[
+                    "install",
+                    "-y",
+                    "--allow-downgrades",
+                    &format!("{package}={version}"),
+                ],
+            ),
+            (Self::Apt, None) => Command::build("apt-get", &["install", "-y", package]),
+            (Self::Dnf, Some(version)) => {
+                Command::build("dnf", &["install", "-y", &format!("{package}-{version}")])
+            }
+            (Self::Dnf, None) => Command::build("dnf", &["install", "-y", package]),
+            // Only the version in the sync databases can be installed, so installing any other
+            // version fails.
+            (Self::Pacman, Some(version)) => Command::build(
+                "pacman",
+                &[
+                    "-S",
+                    "--needed",
+                    "--noconfirm",
+                    &format!("{package}={version}"),
+                ],
+            ),
+            (Self::Pacman, None) => {
+                Command::build("pacman", &["-S", "--needed", "--noconfirm", package])
+            }
+            (Self::Zypper, Some(version)) => Command::build(
+                "zypper",
+                &[
+                    "--non-interactive",
+                    "install",
+                    "--oldpackage",
+                    &format!("{package}={version}"),
+                ],
+            ),
+            (Self::Zypper, None) => {
+                Command::build("zypper", &["--non-interactive", "install", package])
+            }
+            (Self::Apk, Some(version)) => {
+                Command::build("apk", &["add", &format!("{package}={version}")])
+            }
+            (Self::Apk, None) => Command::build("apk", &["add", package]),
+        }
+    }
+
+    /// Build the command removing a package.
+    pub fn remove(&self, package: &str) -> Command {
+        match self {
+            Self::Apt => Command::build("apt-get", &["remove", "-y", package]),
+            Self::Dnf => Command::build("dnf", &["remove", "-y", package]),
+            Self::Pacman => Command::build("pacman", &["-R", "--noconfirm", package]),
+            Self::Zypper => Command::build("zypper", &["--non-interactive", "remove", package]),
+            Self::Apk => Command::build("apk", &["del", package]),
+        }
+    }
+
+    /// Build the command reinstalling a package, restoring any of its files that were removed
+    /// or replaced.
+    pub fn reinstall(&self, package: &str) -> Command {
+        match self {
+            Self::Apt => Command::build("apt-get", &["install", "--reinstall", "-y", package]),
+            Self::Dnf => Command::build("dnf", &["reinstall", "-y", package]),
+            Self::Pacman => Command::build("pacman", &["-S", "--noconfirm", package]),
+            Self::Zypper => Command::build(
+                "zypper",
+                &["--non-interactive", "install", "--force", package],
+            ),
+            Self::Apk => Command::build("apk", &["fix", package]),
+        }
+    }
+
+    /// Build the command updating the package lists. Arch doesn't support refreshing the sync
+    /// databases without upgrading the packages installed from them, so pacman upgrades the
+    /// system as well.
+    pub fn update(&self) -> Command {
+        match self {
+            Self::Apt => Command::build("apt-get", &["update"]),
+            Self::Dnf => Command::build("dnf", &["makecache"]),
+            Self::Pacman => Command::build("pacman", &["-Syu", "--noconfirm"]),
+            Self::Zypper => Command::build("zypper", &["--non-interactive", "refresh"]),
+            Self::Apk => Command::build("apk", &["update"]),
+        }
+    }
+
+    /// Build the command that succeeds only if a package is installed.
+    pub fn query_installed(&self, package: &str) -> Command {
+        match self {
+            Self::Apt => Command::build("dpkg-query", &["-s", package]),
+            Self::Dnf | Self::Zypper => Command::build("rpm", &["-q", package]),
+            Self::Pacman => Command::build("pacman", &["-Q", package]),
+            Self::Apk => Command::build("apk", &["info", "-e", package]),
+        }
+    }
+
+    /// Build the command printing the installed version of a package.
+    pub fn query_version(&self, package: &str) -> Command {
+        match self {
+            Self::Apt => Command::build("dpkg-query", &["-W", "-f=${Version}", package]),
+            Self::Dnf | Self::Zypper => {
+                Command::build("rpm", &["-q", "--qf", "%{VERSION}-%{RELEASE}", package])
+            }
+            Self::Pacman => Command::build("pacman", &["-Q", package]),
+            Self::Apk => Command::build("apk", &["info", "-e", "-v", package]),
+        }
+    }
+
+    /// Parse the version of a package from the output of the `query_version` command.
+    pub fn parse_version(&self, package: &str, output: &str) -> String {
+        let output = output.trim();
+        match self {
+            // apk prints the name and version together, e.g. `sudo-rs-0.2.3-r0`.
+            Self::Apk => output
+                .strip_prefix(&format!("{package}-"))
+                .unwrap_or(output)
+                .to_string(),
+            // pacman prints the name before the version, e.g. `sudo-rs 0.2.3-1`.
+            Self::Pacman => output
+                .split_once(' ')
+                .map(|(_, version)| version)
+                .unwrap_or(output)
+                .to_string(),
+            _ => output.to_string(),
+        }
+    }
+
+    /// Build the command printing the name of the package that ships a file.
+    pub fn query_owner(&self, file: &str) -> Command {
+        match self {
+            Self::Apt => Command::build("dpkg-query", &["-S", file]),
+            Self::Dnf | Self::Zypper => Command::build("rpm", &["-qf", "--qf", "%{NAME}\\n", file]),
+            Self::Pacman => Command::build("pacman", &["-Qqo", file]),
+            Self::Apk => Command::build("apk", &["info", "-W", file]),
+        }
+    }
+
+    /// Parse the name of the package that ships a file from the output of the `query_owner`
+    /// command.
+    pub fn parse_owner(&self, output: &str) -> Option<String> {
+        // dpkg lists any diversion of the file ahead of its owner.
+        let line = output
+            .lines()
+            .find(|line| !line.starts_with("diversion by "))?;
+        let package = match self {
+            // Each line is of the form `coreutils: /usr/bin/date`.
+            Self::Apt => line.split_once(':')?.0,
+            Self::Dnf | Self::Pacman | Self::Zypper => line,
+            // Each line is of the form `/bin/ls is owned by busybox-1.36.1-r29`, ending with the
+            // version and revision of the package.
+            Self::Apk => {
+                let (_, package) = line.split_once(" is owned by ")?;
+                package.trim().rsplitn(3, '-').nth(2)?
+            }
+        };
+        Some(package.trim().to_string()).filter(|p| !p.is_empty())
+    }
+
+    /// Build the command holding a package at its installed version, if the package manager
+    /// can hold packages. pacman only ignores the packages listed in `pacman.conf`, and apk only
+    /// keeps packages at the versions constrained in `/etc/apk/world`.
+    pub fn hold(&self, package: &str) -> Option<Command> {
+        match self {
+            Self::Apt => Some(Command::build("apt-mark", &["hold", package])),
+            Self::Dnf => Some(Command::build("dnf", &["versionlock", "add", package])),
+            Self::Pacman => None,
+            Self::Zypper => Some(Command::build("zypper", &["addlock", package])),
+            Self::Apk => None,
+        }
+    }
+
+    /// Build the command releasing a hold placed by `hold`.
+    pub fn unhold(&self, package: &str) -> Option<Command> {
+        match self {
+            Self::Apt => Some(Command::build("apt-mark", &["unhold", package])),
+            Self::Dnf => Some(Command::build("dnf", &["versionlock", "delete", package])),
+            Self::Pacman => None,
+            Self::Zypper => Some(Command::build("zypper", &["removelock", package])),
+            Self::Apk => None,
+        }
+    }
+
+    /// Build the command listing the held packages.
+    pub fn list_held(&self) -> Option<Command> {
+        match self {
+            Self::Apt => Some(Command::build("apt-mark", &["showhold"])),
+            Self::Dnf => Some(Command::build("dnf", &["versionlock", "list"])),
+            Self::Pacman => None,
+            Self::Zypper => Some(Command::build("zypper", &["locks"])),
+            Self::Apk => None,
+        }
+    }
+
+    /// Report the name of the package manager, as used in messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Apt => "apt",
+            Self::Dnf => "dnf",
+            Self::Pacman => "pacman",
+            Self::Zypper => "zypper",
+            Self::Apk => "apk",
+        }
+    }
+
+    /// Parse the names of the held packages from the output of the `list_held` command.
+    pub fn parse_held(&self, output: &str) -> Vec<String> {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| match self {
+                Self::Apt | Self::Pacman | Self::Apk => Some(l.to_string()),
+                // Each lock is of the form `sudo-rs-0:0.2.3-1.fc41.*`, with the epoch before
+                // the colon.
+                Self::Dnf => {
+                    let (name, _) = l.split_once(':')?;
+                    let (name, _) = name.rsplit_once('-')?;
+                    Some(name.to_string())
+                }
+                // Locks are listed in a table, e.g. `1 | sudo-rs | package | (any)`, under a
+                // header and a separator.
+                Self::Zypper => {
+                    let mut columns = l.split('|').map(str::trim);
+                    columns.next()?.parse::<u32>().ok()?;
+                    columns.next().map(str::to_string)
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_manager_for_distribution() {
+        assert_eq!(
+            PackageManager::for_distribution("Ubuntu"),
+            PackageManager::Apt
+        );
+        assert_eq!(
+            PackageManager::for_distribution("Debian"),
+            PackageManager::Apt
+        );
+        assert_eq!(
+            PackageManager::for_distribution("Fedora"),
+            PackageManager::Dnf
+        );
+        assert_eq!(
+            PackageManager::for_distribution("Arch"),
+            PackageManager::Pacman
+        );
+        assert_eq!(
+            PackageManager::for_distribution("openSUSE"),
+            PackageManager::Zypper
+        );
+        assert_eq!(
+            PackageManager::for_distribution("Alpine"),
+            PackageManager::Apk
+        );
+    }
+
+    #[test]
+    fn test_package_manager_commands() {
+        let dnf = PackageManager::Dnf;
+        assert_eq!(
+            dnf.install("sudo-rs", None).command(),
+            "dnf install -y sudo-rs"
+        );
+        assert_eq!(
+            dnf.install("sudo-rs", Some("0.2.3-1.fc41")).command(),
+            "dnf install -y sudo-rs-0.2.3-1.fc41"
+        );
+        assert_eq!(dnf.remove("sudo-rs").command(), "dnf remove -y sudo-rs");
+        assert_eq!(dnf.query_installed("sudo-rs").command(), "rpm -q sudo-rs");
+        assert_eq!(dnf.update().command(), "dnf makecache");
+
+        let apt = PackageManager::Apt;
+        assert_eq!(
+            apt.install("sudo-rs", Some("0.2.2-1")).command(),
+            "apt-get install -y --allow-downgrades sudo-rs=0.2.2-1"
+        );
+        assert_eq!(
+            apt.hold("sudo-rs").unwrap().command(),
+            "apt-mark hold sudo-rs"
+        );
+
+        let pacman = PackageManager::Pacman;
+        assert_eq!(
+            pacman.install("sudo-rs", None).command(),
+            "pacman -S --needed --noconfirm sudo-rs"
+        );
+        assert_eq!(
+            pacman.remove("sudo-rs").command(),
+            "pacman -R --noconfirm sudo-rs"
+        );
+        assert_eq!(
+            pacman.query_owner("/usr/bin/date").command(),
+            "pacman -Qqo /usr/bin/date"
+        );
+        assert!(pacman.hold("sudo-rs").is_none());
+
+        let zypper = PackageManager::Zypper;
+        assert_eq!(
+            zypper.install("sudo-rs", Some("0.2.3-1.1")).command(),
+            "zypper --non-interactive install --oldpackage sudo-rs=0.2.3-1.1"
+        );
+        assert_eq!(
+            zypper.query_installed("sudo-rs").command(),
+            "rpm -q sudo-rs"
+        );
+        assert_eq!(
+            zypper.hold("sudo-rs").unwrap().command(),
+            "zypper addlock sudo-rs"
+        );
+    }
+
+    #[test]
+    fn test_package_manager_parse_queries() {
+        assert_eq!(
+            PackageManager::Pacman.parse_version("sudo-rs", "sudo-rs 0.2.3-1\n"),
+            "0.2.3-1"
+        );
+        assert_eq!(
+            PackageManager::Apt.parse_version("sudo-rs", "0.2.2-1\n"),
+            "0.2.2-1"
+        );
+        assert_eq!(
+            PackageManager::Apt.parse_owner("coreutils: /usr/bin/date\n"),
+            Some("coreutils".to_string())
+        );
+        assert_eq!(
+            PackageManager::Apt.parse_owner(
+                "diversion by oxidizr from: /usr/bin/date\n\
+                 diversion by oxidizr to: /usr/bin/.date.oxidizr.bak\n\
+                 coreutils: /usr/bin/date\n"
+            ),
+            Some("coreutils".to_string())
+        );
+        assert_eq!(
+            PackageManager::Pacman.parse_owner("coreutils\n"),
+            Some("coreutils".to_string())
+        );
+        assert_eq!(PackageManager::Pacman.parse_owner(""), None);
+        assert_eq!(
+            PackageManager::Apk.parse_version("sudo-rs", "sudo-rs-0.2.3-r0\n"),
+            "0.2.3-r0"
+        );
+        assert_eq!(
+            PackageManager::Apk.parse_owner("/bin/busybox is owned by busybox-1.36.1-r29\n"),
+            Some("busybox".to_string())
+        );
+        assert_eq!(
+            PackageManager::Apk
+                .parse_owner("/usr/bin/uu-ls is owned by uutils-coreutils-0.0.30-r0\n"),
+            Some("uutils-coreutils".to_string())
+        );
+    }
+
+    #[test]
+    fn test_package_manager_parse_held() {
+        assert_eq!(
+            PackageManager::Apt.parse_held("rust-coreutils\nsudo-rs\n\n"),
+            &["rust-coreutils", "sudo-rs"]
+        );
+        assert_eq!(
+            PackageManager::Dnf.parse_held(
+                "# Added lock on Wed Oct 14 09:30:00 2026\nsudo-rs-0:0.2.3-1.fc41.*\nuutils-coreutils-0:0.0.30-1.fc41.*\n"
+            ),
+            &["sudo-rs", "uutils-coreutils"]
+        );
+        assert_eq!(
+            PackageManager::Zypper.parse_held(
+                "\n# | Name    | Type    | Repository\n--+---------+---------+-----------\n1 | sudo-rs | package | (any)\n"
+            ),
+            &["sudo-rs"]
+        );
+    }
+}
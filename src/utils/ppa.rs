@@ -0,0 +1,285 @@
+use super::{Command, Worker};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Keyserver from which the signing keys of PPAs are fetched.
+const KEYSERVER: &str = "https://keyserver.ubuntu.com";
+
+/// A Launchpad PPA from which an experiment's package is installed in place of the archive's,
+/// with the fingerprint of the key its packages are signed with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ppa {
+    /// The PPA, e.g. `ppa:owner/archive`.
+    pub name: String,
+    /// The full fingerprint of the PPA's signing key.
+    pub fingerprint: String,
+}
+
+impl Ppa {
+    /// Check that the PPA is named as `ppa:owner/archive`, and that its fingerprint is a full
+    /// 40 character fingerprint rather than a short key ID.
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(
+            self.owner_and_archive().is_some(),
+            "Invalid PPA '{}', expected 'ppa:owner/archive'",
+            self.name
+        );
+        let fingerprint = self.normalized_fingerprint();
+        anyhow::ensure!(
+            fingerprint.len() == 40 && fingerprint.chars().all(|c| c.is_ascii_hexdigit()),
+            "Invalid key fingerprint '{}' for {}, expected the full 40 character fingerprint",
+            self.fingerprint,
+            self.name
+        );
+        Ok(())
+    }
+
+    /// Report the fingerprint in the form gpg prints it, without spaces and in upper case.
+    fn normalized_fingerprint(&self) -> String {
+        self.fingerprint
+            .split_whitespace()
+            .collect::<String>()
+            .to_uppercase()
+    }
+
+    fn owner_and_archive(&self) -> Option<(&str, &str)> {
+        let (owner, archive) = self.name.strip_prefix("ppa:")?.split_once('/')?;
+        (!owner.is_empty() && !archive.is_empty() && !archive.contains('/'))
+            .then_some((owner, archive))
+    }
+
+    /// Render the deb822 sources for the PPA's packages for an Ubuntu release, trusting only
+    /// the key in `keyring`.
+    fn sources(&self, codename: &str, keyring: &Path) -> String {
+        let (owner, archive) = self.owner_and_archive().unwrap_or_default();
+        format!(
+            "# Managed by oxidizr. Do not edit, this file is removed by 'oxidizr disable'.\n\
+             Types: deb\n\
+             URIs: https://ppa.launchpadcontent.net/{owner}/{archive}/ubuntu/\n\
+             Suites: {codename}\n\
+             Components: main\n\
+             Signed-By: {}\n",
+            keyring.display()
+        )
+    }
+}
+
+/// Get the path of the sources file adding an experiment's PPA.
+pub fn ppa_sources_file(experiment: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "/etc/apt/sources.list.d/oxidizr-{experiment}.sources"
+    ))
+}
+
+/// Get the path of the keyring holding the signing key of an experiment's PPA.
+pub fn ppa_keyring(experiment: &str) -> PathBuf {
+    PathBuf::from(format!("/etc/apt/keyrings/oxidizr-{experiment}.asc"))
+}
+
+/// Add an experiment's PPA and update the package lists, so that its package is installed from
+/// the PPA. The signing key is fetched from the keyserver and checked against the configured
+/// fingerprint before the PPA is added.
+pub fn add_ppa(system: &dyn Worker, experiment: &str, ppa: &Ppa) -> Result<()> {
+    ppa.validate()?;
+    let distribution = system.distribution()?;
+    let Some(codename) = distribution.ubuntu_codename() else {
+        anyhow::bail!(
+            "Unable to add {} for '{experiment}', PPAs are only available for Ubuntu releases",
+            ppa.name
+        );
+    };
+
+    let fingerprint = ppa.normalized_fingerprint();
+    let keyring = ppa_keyring(experiment);
+    info!("Fetching the signing key for {} from {KEYSERVER}", ppa.name);
+    let url = format!("{KEYSERVER}/pks/lookup?op=get&options=mr&search=0x{fingerprint}");
+    let cmd = Command::build(
+        "curl",
+        &[
+            "-fsSL",
+            "--create-dirs",
+            "-o",
+            &keyring.to_string_lossy(),
+            &url,
+        ],
+    );
+    system.run(&cmd)?;
+
+    if let Err(e) = verify_key(system, &keyring, &fingerprint) {
+        system.remove_file(keyring)?;
+        return Err(e.context(format!("Refusing to add {}", ppa.name)));
+    }
+
+    info!("Adding {} for '{experiment}'", ppa.name);
+    system.write_file(
+        ppa_sources_file(experiment),
+        &ppa.sources(codename, &keyring),
+        0o644,
+    )?;
+    system.update_package_lists()
+}
+
+/// Remove an experiment's PPA and its signing key, if it was added, and update the package
+/// lists so that packages are installed from the archive again.
+pub fn remove_ppa(system: &dyn Worker, experiment: &str) -> Result<()> {
+    let sources = ppa_sources_file(experiment);
+    if !system.file_exists(sources.clone()) {
+        return Ok(());
+    }
+    info!("Removing the PPA for '{experiment}'");
+    system.remove_file(sources)?;
+    system.remove_file(ppa_keyring(experiment))?;
+    system.update_package_lists()
+}
+
+/// Check that `keyring` holds exactly one key, with the expected fingerprint.
+fn verify_key(system: &dyn Worker, keyring: &Path, fingerprint: &str) -> Result<()> {
+    let cmd = Command::build(
+        "gpg",
+        &["--show-keys", "--with-colons", &keyring.to_string_lossy()],
+    );
+    let output = String::from_utf8(system.run(&cmd)?.stdout)?;
+    let found = parse_key_fingerprints(&output);
+    anyhow::ensure!(
+        found == [fingerprint],
+        "The fetched key has fingerprint {}, rather than the configured {fingerprint}",
+        match found.is_empty() {
+            true => "(none)".to_string(),
+            false => found.join(", "),
+        }
+    );
+    Ok(())
+}
+
+/// Parse the fingerprints of the primary keys, not their subkeys, from the output of
+/// `gpg --show-keys --with-colons`.
+fn parse_key_fingerprints(output: &str) -> Vec<String> {
+    let mut fingerprints = Vec::new();
+    // Each key's fingerprint immediately follows the key's own record.
+    let mut previous = "";
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields[0] == "fpr"
+            && previous == "pub"
+            && let Some(fingerprint) = fields.get(9)
+        {
+            fingerprints.push(fingerprint.to_string());
+        }
+        previous = fields[0];
+    }
+    fingerprints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    const FINGERPRINT: &str = "0123456789ABCDEF0123456789ABCDEF01234567";
+
+    const SHOW_KEYS: &str = "pub:-:4096:1:89ABCDEF01234567:1700000000:::-:::scSC::::::23::0:\n\
+                             fpr:::::::::0123456789ABCDEF0123456789ABCDEF01234567:\n\
+                             uid:-::::1700000000::0000::Launchpad PPA for uutils::::::::::0:\n\
+                             sub:-:4096:1:FEDCBA9876543210:1700000000::::::e::::::23:\n\
+                             fpr:::::::::FEDCBA9876543210FEDCBA9876543210FEDCBA98:\n";
+
+    fn ppa() -> Ppa {
+        Ppa {
+            name: "ppa:uutils/rust-coreutils".to_string(),
+            fingerprint: "0123 4567 89ab cdef 0123  4567 89AB CDEF 0123 4567".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_ppa_validate() {
+        assert!(ppa().validate().is_ok());
+
+        let mut invalid = ppa();
+        invalid.name = "uutils/rust-coreutils".to_string();
+        assert!(invalid.validate().is_err());
+
+        let mut short = ppa();
+        short.fingerprint = "01234567".to_string();
+        assert!(short.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_key_fingerprints() {
+        assert_eq!(parse_key_fingerprints(SHOW_KEYS), &[FINGERPRINT]);
+        assert!(parse_key_fingerprints("").is_empty());
+    }
+
+    #[test]
+    fn test_add_ppa() {
+        let runner = MockSystem::default();
+        let keyring = ppa_keyring("coreutils");
+        let show_keys = format!("gpg --show-keys --with-colons {}", keyring.display());
+        runner.mock_command(&show_keys, SHOW_KEYS);
+
+        assert!(add_ppa(&runner, "coreutils", &ppa()).is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(
+            commands[2..],
+            [
+                format!(
+                    "curl -fsSL --create-dirs -o {} {KEYSERVER}/pks/lookup?op=get&options=mr&search=0x{FINGERPRINT}",
+                    keyring.display()
+                ),
+                show_keys,
+                "apt-get update".to_string(),
+            ]
+        );
+        let sources = runner.read_file(ppa_sources_file("coreutils")).unwrap();
+        assert!(
+            sources
+                .contains("URIs: https://ppa.launchpadcontent.net/uutils/rust-coreutils/ubuntu/\n")
+        );
+        assert!(sources.contains("Suites: noble\n"));
+        assert!(sources.contains("Signed-By: /etc/apt/keyrings/oxidizr-coreutils.asc\n"));
+    }
+
+    #[test]
+    fn test_add_ppa_wrong_key() {
+        let runner = MockSystem::default();
+        let keyring = ppa_keyring("coreutils");
+        runner.mock_files(vec![("/etc/apt/keyrings/oxidizr-coreutils.asc", "", false)]);
+        runner.mock_command(
+            &format!("gpg --show-keys --with-colons {}", keyring.display()),
+            &SHOW_KEYS.replace(FINGERPRINT, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"),
+        );
+
+        assert!(add_ppa(&runner, "coreutils", &ppa()).is_err());
+        assert!(!runner.file_exists(keyring));
+        assert!(!runner.file_exists(ppa_sources_file("coreutils")));
+        assert!(
+            !runner
+                .commands
+                .clone()
+                .into_inner()
+                .contains(&"apt-get update".to_string())
+        );
+    }
+
+    #[test]
+    fn test_remove_ppa() {
+        let runner = MockSystem::default();
+        assert!(remove_ppa(&runner, "coreutils").is_ok());
+        assert!(runner.commands.clone().into_inner().is_empty());
+
+        runner.mock_files(vec![
+            (
+                "/etc/apt/sources.list.d/oxidizr-coreutils.sources",
+                "",
+                false,
+            ),
+            ("/etc/apt/keyrings/oxidizr-coreutils.asc", "", false),
+        ]);
+        assert!(remove_ppa(&runner, "coreutils").is_ok());
+        assert!(!runner.file_exists(ppa_sources_file("coreutils")));
+        assert!(!runner.file_exists(ppa_keyring("coreutils")));
+        assert_eq!(runner.commands.clone().into_inner(), &["apt-get update"]);
+    }
+}
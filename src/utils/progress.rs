@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use tracing::trace;
+
+/// Emits machine-readable progress records to a file descriptor, in a similar manner to apt's
+/// `APT::Status-Fd`. Each record is a single line of the form:
+///
+/// ```text
+/// status:<experiment>:<percent>:<description>
+/// error:<experiment>:<percent>:<description>
+/// ```
+///
+/// where `percent` is the overall progress of the operation as a number between 0 and 100.
+/// The description is free text, and may itself contain colons.
+pub struct Progress {
+    out: Option<File>,
+    total: usize,
+    done: usize,
+}
+
+impl Progress {
+    /// Create a new `Progress` for an operation with `total` steps, writing to the file
+    /// descriptor `fd` if specified. If `fd` is `None`, no records are emitted.
+    pub fn new(fd: Option<i32>, total: usize) -> Result<Self> {
+        let out = match fd {
+            Some(fd) => Some(
+                OpenOptions::new()
+                    .write(true)
+                    .open(format!("/proc/self/fd/{fd}"))
+                    .with_context(|| format!("Unable to open status file descriptor {fd}"))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            out,
+            total: total.max(1),
+            done: 0,
+        })
+    }
+
+    /// Emit a record describing the step currently in progress.
+    pub fn status(&mut self, item: &str, description: &str) {
+        self.emit("status", item, description);
+    }
+
+    /// Emit a record describing a failure of the step currently in progress.
+    pub fn error(&mut self, item: &str, description: &str) {
+        self.emit("error", item, description);
+    }
+
+    /// Mark the current step as completed, advancing the overall progress.
+    pub fn advance(&mut self) {
+        self.done = (self.done + 1).min(self.total);
+    }
+
+    /// Report the overall progress of the operation as a percentage.
+    pub fn percent(&self) -> f64 {
+        (self.done as f64 / self.total as f64) * 100.0
+    }
+
+    fn emit(&mut self, kind: &str, item: &str, description: &str) {
+        let line = record(kind, item, self.percent(), description);
+        if let Some(out) = self.out.as_mut() {
+            // A frontend going away must not interrupt changes that are halfway through.
+            if let Err(e) = out.write_all(line.as_bytes()).and_then(|_| out.flush()) {
+                trace!("Unable to write progress record: {}", e);
+            }
+        }
+    }
+}
+
+/// Format a single progress record, ensuring it occupies exactly one line.
+fn record(kind: &str, item: &str, percent: f64, description: &str) -> String {
+    let item = item.replace([':', '\n'], "_");
+    let description = description.replace('\n', " ");
+    format!("{kind}:{item}:{percent:.1}:{description}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_record() {
+        assert_eq!(
+            record("status", "coreutils", 50.0, "Enabling coreutils"),
+            "status:coreutils:50.0:Enabling coreutils\n"
+        );
+        assert_eq!(
+            record("error", "a:b", 12.5, "failed:\nbadly"),
+            "error:a_b:12.5:failed: badly\n"
+        );
+    }
+
+    #[test]
+    fn test_progress_percent() {
+        let mut progress = Progress::new(None, 4).unwrap();
+        assert_eq!(progress.percent(), 0.0);
+        progress.advance();
+        assert_eq!(progress.percent(), 25.0);
+        for _ in 0..10 {
+            progress.advance();
+        }
+        assert_eq!(progress.percent(), 100.0);
+    }
+}
@@ -0,0 +1,368 @@
+use super::{Command, Worker};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Prefix marking a package as a prebuilt release downloaded from GitHub, rather than a package
+/// from the distribution's archive, e.g. `release:ripgrep`.
+pub const RELEASE_PREFIX: &str = "release:";
+
+/// Directory into which releases are extracted, each in a directory named after the release.
+pub const RELEASE_ROOT: &str = "/usr/lib/oxidizr/releases";
+
+/// Directory in which the binaries of installed releases are linked, so they're found in `PATH`.
+pub const RELEASE_LINK_DIR: &str = "/usr/local/bin";
+
+/// File in a release's directory recording the version that was installed.
+const VERSION_FILE: &str = ".version";
+
+/// A prebuilt release published on GitHub, whose archive holds its binaries in a single
+/// top-level directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Release {
+    /// The name of the release, e.g. `ripgrep`.
+    pub name: &'static str,
+    /// The GitHub repository publishing the release, e.g. `BurntSushi/ripgrep`.
+    pub repo: &'static str,
+    /// The version installed, which is also the release's tag.
+    pub version: &'static str,
+    /// The Rust target triple of the build for each machine architecture reported by `uname -m`.
+    pub targets: &'static [(&'static str, &'static str)],
+    /// The name of the release's `.tar.gz` archive, in which `{version}` and `{target}` are
+    /// substituted.
+    pub asset: &'static str,
+    /// The name of the file listing the archive's sha256 checksum, in which `{asset}` is
+    /// substituted.
+    pub checksums: &'static str,
+    /// The minisign public key that signs the checksums file as `<checksums>.minisig`, if the
+    /// release is signed.
+    pub minisign_key: Option<&'static str>,
+    /// The binaries in the archive, which are linked into `RELEASE_LINK_DIR`.
+    pub binaries: &'static [&'static str],
+}
+
+/// Releases that experiments can be installed from with `--from-releases`.
+pub const RELEASES: &[Release] = &[Release {
+    name: "ripgrep",
+    repo: "BurntSushi/ripgrep",
+    version: "14.1.1",
+    targets: &[
+        ("x86_64", "x86_64-unknown-linux-musl"),
+        ("aarch64", "aarch64-unknown-linux-gnu"),
+    ],
+    asset: "ripgrep-{version}-{target}.tar.gz",
+    checksums: "{asset}.sha256",
+    minisign_key: None,
+    binaries: &["rg"],
+}];
+
+/// Report the name of the release a package refers to, if it is one.
+pub fn release_name(package: &str) -> Option<&str> {
+    package.strip_prefix(RELEASE_PREFIX)
+}
+
+/// Find a release by name.
+pub fn release(name: &str) -> Result<&'static Release> {
+    RELEASES
+        .iter()
+        .find(|r| r.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown release '{name}'"))
+}
+
+impl Release {
+    /// Get the directory the release is extracted into.
+    pub fn dir(&self) -> PathBuf {
+        Path::new(RELEASE_ROOT).join(self.name)
+    }
+
+    /// Get the name of the release's archive for a target.
+    fn asset(&self, target: &str) -> String {
+        self.asset
+            .replace("{version}", self.version)
+            .replace("{target}", target)
+    }
+
+    /// Get the URL from which a file published with the release is downloaded.
+    fn url(&self, file: &str) -> String {
+        format!(
+            "https://github.com/{}/releases/download/{}/{file}",
+            self.repo, self.version
+        )
+    }
+}
+
+/// Download a release for the machine's architecture, verify it against its published checksum
+/// and signature, extract it, and link its binaries into `RELEASE_LINK_DIR`.
+pub fn install_release<W: Worker + ?Sized>(system: &W, release: &Release) -> Result<()> {
+    anyhow::ensure!(
+        !system.offline(),
+        "Unable to download {} {} while offline",
+        release.name,
+        release.version
+    );
+    for binary in release.binaries {
+        let link = Path::new(RELEASE_LINK_DIR).join(binary);
+        anyhow::ensure!(
+            !system.file_exists(link.clone())
+                || system
+                    .read_link(link.clone())
+                    .is_ok_and(|s| s.starts_with(release.dir())),
+            "{} already exists, so {} can't be linked there",
+            link.display(),
+            release.name
+        );
+    }
+
+    let output = system.run(&Command::build("uname", &["-m"]))?;
+    let arch = String::from_utf8(output.stdout)?.trim().to_string();
+    let Some((_, target)) = release.targets.iter().find(|(a, _)| *a == arch) else {
+        anyhow::bail!("{} isn't published for {arch}", release.name);
+    };
+
+    let downloads = Path::new(RELEASE_ROOT).join(format!("{}.download", release.name));
+    let asset = release.asset(target);
+    let checksums = release.checksums.replace("{asset}", &asset);
+    info!(
+        "Downloading {} {} from github.com/{}",
+        release.name, release.version, release.repo
+    );
+    download(system, &release.url(&asset), &downloads.join(&asset))?;
+    download(
+        system,
+        &release.url(&checksums),
+        &downloads.join(&checksums),
+    )?;
+
+    let verified = verify(system, release, &downloads, &asset, &checksums);
+    if let Err(e) = verified {
+        system.run(&Command::build(
+            "rm",
+            &["-rf", &downloads.to_string_lossy()],
+        ))?;
+        return Err(e);
+    }
+
+    let dir = release.dir();
+    let dir_str = dir.to_string_lossy();
+    system.run(&Command::build("rm", &["-rf", &dir_str]))?;
+    system.run(&Command::build("mkdir", &["-p", &dir_str]))?;
+    system.run(&Command::build(
+        "tar",
+        &[
+            "-xzf",
+            &downloads.join(&asset).to_string_lossy(),
+            "-C",
+            &dir_str,
+            "--strip-components=1",
+        ],
+    ))?;
+    system.run(&Command::build(
+        "rm",
+        &["-rf", &downloads.to_string_lossy()],
+    ))?;
+    system.write_file(dir.join(VERSION_FILE), release.version, 0o644)?;
+
+    for binary in release.binaries {
+        system.create_symlink(dir.join(binary), Path::new(RELEASE_LINK_DIR).join(binary))?;
+    }
+    Ok(())
+}
+
+/// Remove the links to a release's binaries, and the release itself.
+pub fn remove_release<W: Worker + ?Sized>(system: &W, release: &Release) -> Result<()> {
+    for binary in release.binaries {
+        system.remove_file(Path::new(RELEASE_LINK_DIR).join(binary))?;
+    }
+    system.run(&Command::build(
+        "rm",
+        &["-rf", &release.dir().to_string_lossy()],
+    ))?;
+    Ok(())
+}
+
+/// Report the installed version of a release, if it's installed.
+pub fn release_version<W: Worker + ?Sized>(system: &W, release: &Release) -> Option<String> {
+    system
+        .read_file(release.dir().join(VERSION_FILE))
+        .ok()
+        .map(|v| v.trim().to_string())
+}
+
+fn download<W: Worker + ?Sized>(system: &W, url: &str, file: &Path) -> Result<()> {
+    let cmd = Command::build(
+        "curl",
+        &["-fsSL", "--create-dirs", "-o", &file.to_string_lossy(), url],
+    );
+    system.run(&cmd)?;
+    Ok(())
+}
+
+/// Check the downloaded archive against the checksums file, after checking the signature of
+/// the checksums file if the release is signed.
+fn verify<W: Worker + ?Sized>(
+    system: &W,
+    release: &Release,
+    downloads: &Path,
+    asset: &str,
+    checksums: &str,
+) -> Result<()> {
+    let checksums_file = downloads.join(checksums);
+    if let Some(key) = release.minisign_key {
+        let signature = format!("{checksums}.minisig");
+        download(
+            system,
+            &release.url(&signature),
+            &downloads.join(&signature),
+        )?;
+        let cmd = Command::build(
+            "minisign",
+            &[
+                "-V",
+                "-P",
+                key,
+                "-m",
+                &checksums_file.to_string_lossy(),
+                "-x",
+                &downloads.join(&signature).to_string_lossy(),
+            ],
+        );
+        system.run(&cmd).map_err(|e| {
+            anyhow::anyhow!(
+                "The signature of {checksums} for {} doesn't verify: {e}",
+                release.name
+            )
+        })?;
+    }
+
+    let expected = parse_checksum(&system.read_file(checksums_file)?, asset)
+        .ok_or_else(|| anyhow::anyhow!("{checksums} has no checksum for {asset}"))?;
+    let found = system.sha256(downloads.join(asset))?;
+    anyhow::ensure!(
+        found == expected,
+        "Checksum of {asset} does not match {checksums} (expected {expected}, found {found})"
+    );
+    Ok(())
+}
+
+/// Find the checksum of `asset` in the output of `sha256sum`, which lists each file as
+/// `<checksum>  <file>`. The file name may be prefixed with `*` for binary mode, or a path.
+fn parse_checksum(checksums: &str, asset: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let checksum = fields.next()?;
+        let file = fields.next()?.trim_start_matches('*');
+        (Path::new(file).file_name()? == asset).then(|| checksum.to_lowercase())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    const ASSET: &str = "ripgrep-14.1.1-x86_64-unknown-linux-musl.tar.gz";
+    const DOWNLOADS: &str = "/usr/lib/oxidizr/releases/ripgrep.download";
+
+    fn ripgrep_runner(checksum: &str) -> MockSystem {
+        let runner = MockSystem::default();
+        runner.mock_command("uname -m", "x86_64\n");
+        runner.mock_files(vec![(
+            &format!("{DOWNLOADS}/{ASSET}.sha256"),
+            &format!("{checksum}  {ASSET}\n"),
+            false,
+        )]);
+        runner.mock_command(
+            &format!("sha256sum {DOWNLOADS}/{ASSET}"),
+            &format!("4cf9f2741e6c465ffdb7c26f38056a59e2a2544b51f7cc128ef28337eeae4d8e  {DOWNLOADS}/{ASSET}\n"),
+        );
+        runner
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let checksums = format!("ABCD  *{ASSET}\n0123  other.tar.gz\n");
+        assert_eq!(parse_checksum(&checksums, ASSET), Some("abcd".to_string()));
+        assert_eq!(parse_checksum(&checksums, "missing.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_install_release() {
+        let runner =
+            ripgrep_runner("4cf9f2741e6c465ffdb7c26f38056a59e2a2544b51f7cc128ef28337eeae4d8e");
+        let ripgrep = release("ripgrep").unwrap();
+
+        assert!(install_release(&runner, ripgrep).is_ok());
+
+        let commands = runner.commands.clone().into_inner();
+        let url = "https://github.com/BurntSushi/ripgrep/releases/download/14.1.1";
+        assert_eq!(
+            commands,
+            &[
+                "uname -m".to_string(),
+                format!("curl -fsSL --create-dirs -o {DOWNLOADS}/{ASSET} {url}/{ASSET}"),
+                format!(
+                    "curl -fsSL --create-dirs -o {DOWNLOADS}/{ASSET}.sha256 {url}/{ASSET}.sha256"
+                ),
+                format!("sha256sum {DOWNLOADS}/{ASSET}"),
+                "rm -rf /usr/lib/oxidizr/releases/ripgrep".to_string(),
+                "mkdir -p /usr/lib/oxidizr/releases/ripgrep".to_string(),
+                format!(
+                    "tar -xzf {DOWNLOADS}/{ASSET} -C /usr/lib/oxidizr/releases/ripgrep --strip-components=1"
+                ),
+                format!("rm -rf {DOWNLOADS}"),
+            ]
+        );
+        assert_eq!(
+            runner.created_symlinks.clone().into_inner(),
+            &[(
+                "/usr/lib/oxidizr/releases/ripgrep/rg".to_string(),
+                "/usr/local/bin/rg".to_string()
+            )]
+        );
+        assert_eq!(
+            release_version(&runner, ripgrep),
+            Some("14.1.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_install_release_signed() {
+        let runner =
+            ripgrep_runner("4cf9f2741e6c465ffdb7c26f38056a59e2a2544b51f7cc128ef28337eeae4d8e");
+        let signed = Release {
+            minisign_key: Some("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"),
+            ..*release("ripgrep").unwrap()
+        };
+        let verify = format!(
+            "minisign -V -P {} -m {DOWNLOADS}/{ASSET}.sha256 -x {DOWNLOADS}/{ASSET}.sha256.minisig",
+            signed.minisign_key.unwrap()
+        );
+
+        runner.mock_command_failure(&verify);
+        assert!(install_release(&runner, &signed).is_err());
+        assert!(runner.commands.clone().into_inner().contains(&verify));
+        assert!(runner.created_symlinks.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_install_release_checksum_mismatch() {
+        let runner = ripgrep_runner("0000");
+        let ripgrep = release("ripgrep").unwrap();
+
+        assert!(install_release(&runner, ripgrep).is_err());
+        let commands = runner.commands.clone().into_inner();
+        assert_eq!(commands.last().unwrap(), &format!("rm -rf {DOWNLOADS}"));
+        assert!(!commands.iter().any(|c| c.starts_with("tar")));
+        assert!(runner.created_symlinks.clone().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_install_release_existing_binary() {
+        let runner =
+            ripgrep_runner("4cf9f2741e6c465ffdb7c26f38056a59e2a2544b51f7cc128ef28337eeae4d8e");
+        runner.mock_files(vec![("/usr/local/bin/rg", "", true)]);
+
+        assert!(install_release(&runner, release("ripgrep").unwrap()).is_err());
+        assert!(runner.commands.clone().into_inner().is_empty());
+    }
+}
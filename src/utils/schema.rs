@@ -0,0 +1,87 @@
+use anyhow::Result;
+use serde_json::Value;
+
+/// A forward migration of a persistent file's format from one version to the next.
+pub type Migration = fn(&mut Value) -> Result<()>;
+
+/// Migrate between formats that only differ in being versioned. Files written before formats
+/// were versioned are version 0.
+pub fn stamp_version(value: &mut Value) -> Result<()> {
+    set_version(value, 1);
+    Ok(())
+}
+
+/// Record the format version in a file's contents. Contents serialized by this version of
+/// oxidizr are already in the latest format, so are only stamped with its version on write.
+pub fn set_version(value: &mut Value, version: usize) {
+    if let Value::Object(map) = value {
+        map.insert("version".to_string(), version.into());
+    }
+}
+
+/// Report the format version of a file's contents, which is 0 if it predates versioning.
+pub fn format_version(value: &Value) -> Result<u32> {
+    match value.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid format version {version}")),
+    }
+}
+
+/// Migrate the contents of `file` to the latest format, where `migrations[n]` migrates from
+/// version `n` to `n + 1`, and record the latest version in them. Files written by a newer
+/// version of oxidizr can't be migrated, so are rejected rather than misread.
+pub fn migrate(file: &str, value: &mut Value, migrations: &[Migration]) -> Result<()> {
+    let version = format_version(value)?;
+    let latest = migrations.len() as u32;
+    anyhow::ensure!(
+        version <= latest,
+        "{file} was written by a newer version of oxidizr, in format version {version}, but \
+         this version only reads up to version {latest}. Upgrade oxidizr to manage it"
+    );
+    for migration in &migrations[version as usize..] {
+        migration(value)?;
+    }
+    set_version(value, migrations.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rename_links(value: &mut Value) -> Result<()> {
+        if let Some(files) = value.as_object_mut().and_then(|m| m.remove("files")) {
+            value["links"] = files;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate() {
+        let migrations: [Migration; 2] = [stamp_version, rename_links];
+
+        let mut unversioned = json!({"files": ["/usr/bin/date"]});
+        migrate("state.json", &mut unversioned, &migrations).unwrap();
+        assert_eq!(
+            unversioned,
+            json!({"version": 2, "links": ["/usr/bin/date"]})
+        );
+
+        // Migrations already applied aren't applied again.
+        let mut current = json!({"version": 2, "files": []});
+        migrate("state.json", &mut current, &migrations).unwrap();
+        assert_eq!(current, json!({"version": 2, "files": []}));
+
+        let mut unversioned = json!({"files": []});
+        stamp_version(&mut unversioned).unwrap();
+        assert_eq!(unversioned, json!({"version": 1, "files": []}));
+
+        let mut newer = json!({"version": 3});
+        assert!(migrate("state.json", &mut newer, &migrations).is_err());
+        assert!(migrate("state.json", &mut json!({"version": "1"}), &migrations).is_err());
+    }
+}
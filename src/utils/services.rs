@@ -0,0 +1,83 @@
+use super::{Command, Worker};
+use anyhow::Result;
+
+/// Report the systemd services that are still running binaries or libraries that have since
+/// been replaced on disk, and would need restarting to pick up the change. `needrestart` is
+/// used if it is installed, otherwise the process mappings in `/proc` are inspected directly.
+pub fn services_needing_restart(system: &dyn Worker) -> Result<Vec<String>> {
+    if system.which("needrestart").is_err() {
+        return system.stale_services();
+    }
+
+    // Batch mode, list only: never restart anything from needrestart itself.
+    let cmd = Command::build("needrestart", &["-b", "-r", "l"]);
+    let output = system.run(&cmd)?;
+    Ok(parse_needrestart(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse the batch mode output of `needrestart` into a list of services.
+fn parse_needrestart(output: &str) -> Vec<String> {
+    let mut services: Vec<String> = output
+        .lines()
+        .filter_map(|l| l.strip_prefix("NEEDRESTART-SVC:"))
+        .map(|s| s.trim().to_string())
+        .collect();
+    services.sort();
+    services.dedup();
+    services
+}
+
+/// Report the systemd service a process belongs to, given the contents of `/proc/<pid>/cgroup`.
+/// Only system services are reported, user sessions and scopes are ignored.
+pub fn service_from_cgroup(cgroup: &str) -> Option<String> {
+    let path = cgroup.lines().find_map(|l| l.strip_prefix("0::"))?;
+    if !path.starts_with("/system.slice/") {
+        return None;
+    }
+    path.split('/')
+        .find(|c| c.ends_with(".service"))
+        .map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_parse_needrestart() {
+        let output = "NEEDRESTART-VER: 3.6
+NEEDRESTART-KCUR: 6.8.0-51-generic
+NEEDRESTART-SVC: ssh.service
+NEEDRESTART-SVC: cron.service
+NEEDRESTART-SVC: ssh.service
+";
+        assert_eq!(
+            parse_needrestart(output),
+            vec!["cron.service".to_string(), "ssh.service".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_service_from_cgroup() {
+        assert_eq!(
+            service_from_cgroup("0::/system.slice/cron.service\n"),
+            Some("cron.service".to_string())
+        );
+        assert_eq!(
+            service_from_cgroup("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+        assert_eq!(service_from_cgroup("0::/init.scope\n"), None);
+    }
+
+    #[test]
+    fn test_services_needing_restart_needrestart() {
+        let runner = MockSystem::default();
+        runner.mock_files(vec![("/usr/sbin/needrestart", "", true)]);
+        runner.mock_command("needrestart -b -r l", "NEEDRESTART-SVC: cron.service\n");
+
+        let services = services_needing_restart(&runner).unwrap();
+        assert_eq!(services, vec!["cron.service".to_string()]);
+    }
+}
@@ -0,0 +1,114 @@
+use super::{Command, Worker};
+
+/// A login session as reported by `who`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub user: String,
+    pub tty: String,
+    pub host: Option<String>,
+}
+
+impl Session {
+    /// Report whether the session was established from a remote host. Sessions on a local X
+    /// display (e.g. `(:0)`) are not considered remote.
+    pub fn remote(&self) -> bool {
+        self.host.as_ref().is_some_and(|h| !h.starts_with(':'))
+    }
+}
+
+/// Report whether the program is being run from a remote (SSH) session.
+pub fn remote_session(system: &dyn Worker) -> bool {
+    if std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some() {
+        return true;
+    }
+    current_session(system).is_some_and(|s| s.remote())
+}
+
+/// Report whether there is another root session on the system, other than the current one,
+/// which could be used for recovery if replacing `sudo` or `su` locks out the current user.
+pub fn fallback_session_available(system: &dyn Worker) -> bool {
+    let current = current_session(system);
+    let cmd = Command::build("who", &[]);
+    let Ok(output) = system.run(&cmd) else {
+        return false;
+    };
+
+    parse_who(&String::from_utf8_lossy(&output.stdout))
+        .iter()
+        .any(|s| s.user == "root" && current.as_ref().is_none_or(|c| c.tty != s.tty))
+}
+
+/// Report the session attached to the current terminal, if any.
+fn current_session(system: &dyn Worker) -> Option<Session> {
+    let cmd = Command::build("who", &["-m"]);
+    let output = system.run(&cmd).ok()?;
+    parse_who(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .next()
+}
+
+/// Parse the output of `who` into a list of sessions.
+fn parse_who(output: &str) -> Vec<Session> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 2 {
+                return None;
+            }
+            let host = fields
+                .last()
+                .filter(|f| f.starts_with('(') && f.ends_with(')'))
+                .map(|f| f.trim_matches(|c| c == '(' || c == ')').to_string());
+            Some(Session {
+                user: fields[0].to_string(),
+                tty: fields[1].to_string(),
+                host,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    const WHO: &str = "jon      pts/0        2025-01-01 10:00 (192.168.1.10)
+root     tty1         2025-01-01 09:00
+jon      pts/1        2025-01-01 09:30 (:0)
+";
+
+    #[test]
+    fn test_parse_who() {
+        let sessions = parse_who(WHO);
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(
+            sessions[0],
+            Session {
+                user: "jon".to_string(),
+                tty: "pts/0".to_string(),
+                host: Some("192.168.1.10".to_string()),
+            }
+        );
+        assert!(sessions[0].remote());
+        assert!(!sessions[1].remote());
+        assert!(!sessions[2].remote());
+    }
+
+    #[test]
+    fn test_fallback_session_available() {
+        let runner = MockSystem::default();
+        runner.mock_command("who -m", WHO.lines().next().unwrap());
+        runner.mock_command("who", WHO);
+        assert!(fallback_session_available(&runner));
+
+        let runner = MockSystem::default();
+        runner.mock_command(
+            "who -m",
+            "root     pts/0        2025-01-01 10:00 (10.0.0.1)",
+        );
+        runner.mock_command("who", "root     pts/0        2025-01-01 10:00 (10.0.0.1)");
+        assert!(!fallback_session_available(&runner));
+    }
+}
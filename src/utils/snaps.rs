@@ -0,0 +1,75 @@
+use super::Command;
+
+/// Prefix marking a package as a snap from the snap store, rather than a package from the
+/// distribution's archive, e.g. `snap:dust`.
+pub const SNAP_PREFIX: &str = "snap:";
+
+/// Directory in which snapd exposes the commands of installed snaps.
+pub const SNAP_BIN_DIR: &str = "/snap/bin";
+
+/// Report the name of the snap a package refers to, if it is one.
+pub fn snap_name(package: &str) -> Option<&str> {
+    package.strip_prefix(SNAP_PREFIX)
+}
+
+/// Build the command installing a snap from `channel`, e.g. `latest/edge`.
+pub fn snap_install(snap: &str, channel: &str) -> Command {
+    Command::build("snap", &["install", snap, &format!("--channel={channel}")])
+}
+
+/// Build the command removing a snap.
+pub fn snap_remove(snap: &str) -> Command {
+    Command::build("snap", &["remove", snap])
+}
+
+/// Build the command listing an installed snap, which fails if the snap isn't installed.
+pub fn snap_list(snap: &str) -> Command {
+    Command::build("snap", &["list", snap])
+}
+
+/// Build the command holding a snap at its installed revision, or releasing the hold.
+pub fn snap_hold(snap: &str, hold: bool) -> Command {
+    let flag = match hold {
+        true => "--hold",
+        false => "--unhold",
+    };
+    Command::build("snap", &["refresh", flag, snap])
+}
+
+/// Parse the version of a snap from the output of `snap list`, which lists it in a table under
+/// a header, e.g. `dust  1.1.1  82  latest/stable  bootandy  -`.
+pub fn parse_snap_version(output: &str) -> Option<String> {
+    output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(1)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snap_commands() {
+        assert_eq!(snap_name("snap:dust"), Some("dust"));
+        assert_eq!(snap_name("du-dust"), None);
+        assert_eq!(
+            snap_install("dust", "latest/edge").command(),
+            "snap install dust --channel=latest/edge"
+        );
+        assert_eq!(
+            snap_hold("dust", false).command(),
+            "snap refresh --unhold dust"
+        );
+    }
+
+    #[test]
+    fn test_parse_snap_version() {
+        let output = "Name  Version  Rev  Tracking       Publisher  Notes\n\
+                      dust  1.1.1    82   latest/stable  bootandy   -\n";
+        assert_eq!(parse_snap_version(output), Some("1.1.1".to_string()));
+        assert_eq!(parse_snap_version(""), None);
+    }
+}
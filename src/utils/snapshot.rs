@@ -0,0 +1,265 @@
+use super::{Command, Worker};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// A tool that can snapshot, and later revert, the root filesystem.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Snapshotter {
+    /// snapper, managing btrfs snapshots of the root filesystem with its `root` configuration.
+    Snapper,
+    /// Timeshift, in either its btrfs or rsync mode.
+    Timeshift,
+    /// A ZFS root dataset, e.g. `rpool/ROOT/ubuntu_abc123`.
+    Zfs { dataset: String },
+    /// An LVM logical volume holding the root filesystem, e.g. `ubuntu-vg/ubuntu-lv`.
+    Lvm { volume: String },
+}
+
+/// A snapshot of the root filesystem taken by oxidizr.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tool: Snapshotter,
+    /// The name the tool knows the snapshot by, e.g. a snapper snapshot number.
+    pub name: String,
+    /// When the snapshot was taken, in seconds since the Unix epoch.
+    pub created: u64,
+}
+
+impl Snapshotter {
+    /// Detect a way to snapshot the root filesystem. Snapshot tools that are installed and
+    /// configured are preferred over snapshotting the root filesystem's volume directly.
+    pub fn detect(system: &dyn Worker) -> Option<Self> {
+        if system.which("snapper").is_ok()
+            && system
+                .run(&Command::build("snapper", &["-c", "root", "list"]))
+                .is_ok()
+        {
+            return Some(Self::Snapper);
+        }
+        if system.which("timeshift").is_ok() {
+            return Some(Self::Timeshift);
+        }
+
+        let cmd = Command::build("findmnt", &["-n", "-o", "FSTYPE,SOURCE", "/"]);
+        let output = String::from_utf8(system.run(&cmd).ok()?.stdout).ok()?;
+        let (fstype, source) = output.trim().split_once(char::is_whitespace)?;
+        let source = source.trim();
+        if fstype == "zfs" {
+            return Some(Self::Zfs {
+                dataset: source.to_string(),
+            });
+        }
+
+        let cmd = Command::build("lvs", &["--noheadings", "-o", "vg_name,lv_name", source]);
+        let output = String::from_utf8(system.run(&cmd).ok()?.stdout).ok()?;
+        let words: Vec<&str> = output.split_whitespace().collect();
+        match words.as_slice() {
+            [vg, lv] => Some(Self::Lvm {
+                volume: format!("{vg}/{lv}"),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Take a snapshot of the root filesystem, described by `label`. For ZFS and LVM the label is
+    /// also the name of the snapshot, so must be unique.
+    pub fn create(&self, system: &dyn Worker, label: &str) -> Result<Snapshot> {
+        info!("Taking a snapshot of the root filesystem with {self}");
+        let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let name = match self {
+            Self::Snapper => {
+                let cmd = Command::build(
+                    "snapper",
+                    &[
+                        "-c",
+                        "root",
+                        "create",
+                        "--description",
+                        label,
+                        "--print-number",
+                    ],
+                );
+                String::from_utf8(system.run(&cmd)?.stdout)?
+                    .trim()
+                    .to_string()
+            }
+            Self::Timeshift => {
+                let cmd = Command::build(
+                    "timeshift",
+                    &["--create", "--comments", label, "--scripted"],
+                );
+                system.run(&cmd)?;
+                timeshift_snapshot(system, label)?
+            }
+            Self::Zfs { dataset } => {
+                let snapshot = format!("{dataset}@{label}");
+                system.run(&Command::build("zfs", &["snapshot", &snapshot]))?;
+                label.to_string()
+            }
+            Self::Lvm { volume } => {
+                let cmd = Command::build(
+                    "lvcreate",
+                    &[
+                        "--snapshot",
+                        "--name",
+                        label,
+                        "--extents",
+                        "20%ORIGIN",
+                        volume,
+                    ],
+                );
+                system.run(&cmd)?;
+                label.to_string()
+            }
+        };
+        Ok(Snapshot {
+            tool: self.clone(),
+            name,
+            created,
+        })
+    }
+}
+
+impl Snapshot {
+    /// Revert the root filesystem to the snapshot. Reverts that only complete on the next boot
+    /// return true.
+    pub fn restore(&self, system: &dyn Worker) -> Result<bool> {
+        info!("Reverting the root filesystem to {self}");
+        let reboot = match &self.tool {
+            Snapshotter::Snapper => {
+                let range = format!("{}..0", self.name);
+                let cmd = Command::build("snapper", &["-c", "root", "undochange", &range]);
+                system.run(&cmd)?;
+                false
+            }
+            Snapshotter::Timeshift => {
+                let cmd = Command::build(
+                    "timeshift",
+                    &["--restore", "--snapshot", &self.name, "--scripted", "--yes"],
+                );
+                system.run(&cmd)?;
+                true
+            }
+            Snapshotter::Zfs { dataset } => {
+                let snapshot = format!("{dataset}@{}", self.name);
+                system.run(&Command::build("zfs", &["rollback", "-r", &snapshot]))?;
+                true
+            }
+            Snapshotter::Lvm { volume } => {
+                let (vg, _) = volume.split_once('/').unwrap_or_default();
+                let snapshot = format!("{vg}/{}", self.name);
+                system.run(&Command::build("lvconvert", &["--merge", &snapshot]))?;
+                true
+            }
+        };
+        Ok(reboot)
+    }
+}
+
+/// Find the name of the Timeshift snapshot with the comment `label`, e.g. `2025-01-31_12-00-00`.
+fn timeshift_snapshot(system: &dyn Worker, label: &str) -> Result<String> {
+    let cmd = Command::build("timeshift", &["--list", "--scripted"]);
+    let output = String::from_utf8(system.run(&cmd)?.stdout)?;
+    output
+        .lines()
+        .filter(|l| l.contains(label))
+        .flat_map(str::split_whitespace)
+        .find(|w| w.len() == 19 && w.as_bytes()[10] == b'_')
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Unable to find the Timeshift snapshot '{label}'"))
+}
+
+impl std::fmt::Display for Snapshotter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Snapper => write!(f, "snapper"),
+            Self::Timeshift => write!(f, "Timeshift"),
+            Self::Zfs { dataset } => write!(f, "ZFS ({dataset})"),
+            Self::Lvm { volume } => write!(f, "LVM ({volume})"),
+        }
+    }
+}
+
+impl std::fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "snapshot '{}' taken with {}", self.name, self.tool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MockSystem;
+
+    #[test]
+    fn test_snapshotter_detect() {
+        let runner = MockSystem::default();
+        assert_eq!(Snapshotter::detect(&runner), None);
+
+        runner.mock_command(
+            "findmnt -n -o FSTYPE,SOURCE /",
+            "ext4 /dev/mapper/vg-root\n",
+        );
+        runner.mock_command(
+            "lvs --noheadings -o vg_name,lv_name /dev/mapper/vg-root",
+            "  vg root\n",
+        );
+        assert_eq!(
+            Snapshotter::detect(&runner),
+            Some(Snapshotter::Lvm {
+                volume: "vg/root".to_string()
+            })
+        );
+
+        runner.mock_command("findmnt -n -o FSTYPE,SOURCE /", "zfs rpool/ROOT/ubuntu\n");
+        assert_eq!(
+            Snapshotter::detect(&runner),
+            Some(Snapshotter::Zfs {
+                dataset: "rpool/ROOT/ubuntu".to_string()
+            })
+        );
+
+        runner.mock_files(vec![("/usr/bin/snapper", "", true)]);
+        assert_eq!(Snapshotter::detect(&runner), Some(Snapshotter::Snapper));
+    }
+
+    #[test]
+    fn test_snapshot_create_restore() {
+        let runner = MockSystem::default();
+        runner.mock_command(
+            "snapper -c root create --description oxidizr-1 --print-number",
+            "42\n",
+        );
+        let snapshot = Snapshotter::Snapper.create(&runner, "oxidizr-1").unwrap();
+        assert_eq!(snapshot.name, "42");
+        assert!(!snapshot.restore(&runner).unwrap());
+        assert!(
+            runner
+                .commands
+                .borrow()
+                .contains(&"snapper -c root undochange 42..0".to_string())
+        );
+
+        runner.mock_command(
+            "timeshift --list --scripted",
+            "Num     Name                 Tags  Description\n\
+             0    >  2025-01-31_12-00-00  O     oxidizr-1\n",
+        );
+        let snapshot = Snapshotter::Timeshift.create(&runner, "oxidizr-1").unwrap();
+        assert_eq!(snapshot.name, "2025-01-31_12-00-00");
+
+        let lvm = Snapshotter::Lvm {
+            volume: "vg/root".to_string(),
+        };
+        let snapshot = lvm.create(&runner, "oxidizr-1").unwrap();
+        assert!(snapshot.restore(&runner).unwrap());
+        let commands = runner.commands.borrow();
+        assert!(commands.contains(
+            &"lvcreate --snapshot --name oxidizr-1 --extents 20%ORIGIN vg/root".to_string()
+        ));
+        assert!(commands.contains(&"lvconvert --merge vg/oxidizr-1".to_string()));
+    }
+}
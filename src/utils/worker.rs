@@ -4,98 +4,640 @@ use std::{
 };
 
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::sync::OnceLock;
 use tracing::{debug, trace, warn};
 use which::which;
 
-use super::{Command, Distribution};
+use super::{
+    BackupMeta, Command, Distribution, Journal, JournalEntry, PackageManager, apt_proxy_options,
+    backup_meta, compress_backup, divert_file, ensure_free_space, forget_backup, hardlink_id,
+    has_compressed_backup, install_release, is_diverted, link_file, move_file, parse_snap_version,
+    proxy_environment, record_backup, release, release_name, release_version, remove_release,
+    restore_compressed_backup, restored_hardlink, service_from_cgroup, snap_hold, snap_install,
+    snap_list, snap_name, snap_remove, stored_backup_filename, undivert_file, verify_backup,
+    with_apt_options,
+};
 
 pub trait Worker {
     /// Report the distribution information for the system.
     fn distribution(&self) -> Result<Distribution> {
-        let cmd = Command::build("lsb_release", &["-is"]);
-        let id = self.run(&cmd)?;
-
-        let cmd = Command::build("lsb_release", &["-rs"]);
-        let release = self.run(&cmd)?;
+        detect_distribution(self)
+    }
 
-        Ok(Distribution {
-            id: String::from_utf8(id.stdout)?.trim().to_string(),
-            release: String::from_utf8(release.stdout)?.trim().to_string(),
-        })
+    /// Report the package manager used to install packages, selected from the distribution.
+    fn package_manager(&self) -> PackageManager {
+        self.distribution()
+            .map(|d| PackageManager::for_distribution(&d.id))
+            .unwrap_or(PackageManager::Apt)
     }
 
     /// Run a command and return the output. If the command fails, an error will be returned.
     fn run(&self, cmd: &Command) -> Result<Output>;
 
+    /// Run a command in `dir` with no input, returning its output whether or not it succeeded.
+    fn capture(&self, cmd: &Command, dir: &Path) -> Result<Output>;
+
     /// List files in a directory, returning an error if the directory does not exist.
     fn list_files(&self, directory: PathBuf) -> Result<Vec<PathBuf>>;
 
     /// Find the path to a binary in the system's PATH.
     fn which(&self, binary_name: &str) -> Result<PathBuf>;
 
-    /// Install a package using the system package manager.
+    /// Install a package using the system package manager. If a local package directory is
+    /// configured, the package is installed from a `.deb` file in that directory instead, which
+    /// is only supported with apt. Snaps are installed with snapd, and releases are downloaded
+    /// from GitHub.
     fn install_package(&self, package: &str) -> Result<()> {
-        let cmd = Command::build("apt-get", &["install", "-y", package]);
+        if self.journaling() {
+            let installed = self.check_installed(package)?;
+            self.journal(JournalEntry::InstallPackage {
+                package: package.to_string(),
+                installed,
+            })?;
+        }
+
+        if let Some(snap) = snap_name(package) {
+            anyhow::ensure!(
+                !self.offline(),
+                "Unable to install the '{snap}' snap while offline"
+            );
+            let channel = self.snap_channel().unwrap_or_else(|| "stable".to_string());
+            self.run(&snap_install(snap, &channel))?;
+            return Ok(());
+        }
+
+        if let Some(name) = release_name(package) {
+            return install_release(self, release(name)?);
+        }
+
+        if self.local_package_dir().is_some() {
+            anyhow::ensure!(
+                self.package_manager() == PackageManager::Apt,
+                "Installing from local .deb files is only supported on Ubuntu and Debian"
+            );
+            let deb = self.local_package(package)?;
+            let deb = deb.to_string_lossy();
+
+            // Check that the package's dependencies are satisfied before making any changes, so
+            // that a missing dependency doesn't leave the package half-configured.
+            let cmd = Command::build("dpkg", &["--no-act", "-i", &deb]);
+            self.run(&cmd).map_err(|e| {
+                anyhow::anyhow!("Unable to install '{package}' from {deb}. Are its dependencies installed or provided? {e}")
+            })?;
+
+            let cmd = Command::build("dpkg", &["-i", &deb]);
+            self.run(&cmd)?;
+            return Ok(());
+        }
+
+        let version = self.pinned_version(package);
+        let cmd = self.package_manager().install(package, version.as_deref());
         self.run(&cmd)?;
         Ok(())
     }
 
-    /// Remove a package using the system package manager.
+    /// Report the exact version a package must be installed at, if versions are pinned.
+    fn pinned_version(&self, _package: &str) -> Option<String> {
+        None
+    }
+
+    /// Report the channel from which experiments available as snaps are installed, if they're
+    /// installed from the snap store rather than the archive.
+    fn snap_channel(&self) -> Option<String> {
+        None
+    }
+
+    /// Report whether experiments available as prebuilt releases are downloaded from GitHub,
+    /// rather than installed from the archive.
+    fn use_releases(&self) -> bool {
+        false
+    }
+
+    /// Report whether replaced files are moved aside with dpkg diversions, rather than backed
+    /// up, so that package upgrades don't undo the replacement.
+    fn diversions(&self) -> bool {
+        false
+    }
+
+    /// Report whether the network must not be used, e.g. on an air-gapped system, so that
+    /// packages are only installed from local `.deb` files.
+    fn offline(&self) -> bool {
+        false
+    }
+
+    /// Report whether backups are compressed into the centralized backup store.
+    fn compress_backups(&self) -> bool {
+        false
+    }
+
+    /// Report whether actions taken on the system are recorded in a journal.
+    fn journaling(&self) -> bool {
+        false
+    }
+
+    /// Record an action in the journal before it's carried out, if journaling is enabled.
+    fn journal(&self, _entry: JournalEntry) -> Result<()> {
+        Ok(())
+    }
+
+    /// Report the configured directory in which backups are stored, if they aren't kept
+    /// alongside the originals.
+    fn backup_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Get the path at which the uncompressed backup of `file` is kept.
+    fn backup_location(&self, file: &Path) -> PathBuf {
+        match self.backup_dir() {
+            Some(dir) => stored_backup_filename(&dir, file),
+            None => backup_filename(file),
+        }
+    }
+
+    /// Report the space available to unprivileged users on the filesystem holding `dir`, in
+    /// bytes. The POSIX output format of df is used, which busybox df also supports.
+    fn free_space(&self, dir: &Path) -> Result<u64> {
+        let cmd = Command::build("df", &["-P", "-k", &dir.to_string_lossy()]);
+        let output = String::from_utf8(self.run(&cmd)?.stdout)?;
+        // The available space is the fourth column, in KiB.
+        let available = output
+            .lines()
+            .last()
+            .and_then(|l| l.split_whitespace().nth(3))
+            .map(|kib| kib.parse::<u64>());
+        match available {
+            Some(Ok(kib)) => Ok(kib * 1024),
+            _ => anyhow::bail!("Unable to determine free space for {}", dir.display()),
+        }
+    }
+
+    /// Show the archive record for a package, e.g. `sudo-rs` or `sudo-rs=0.2.2-1`. If no version
+    /// is given, the record of the candidate version is shown.
+    fn package_record(&self, spec: &str) -> Result<String> {
+        let cmd = Command::build("apt-cache", &["show", "--no-all-versions", spec]);
+        Ok(String::from_utf8(self.run(&cmd)?.stdout)?)
+    }
+
+    /// Report the directory from which packages are installed as local `.deb` files, rather
+    /// than from the network archive.
+    fn local_package_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Find the `.deb` file for a package in the local package directory. If multiple versions
+    /// are present, the highest sorting filename is used.
+    fn local_package(&self, package: &str) -> Result<PathBuf> {
+        let Some(dir) = self.local_package_dir() else {
+            anyhow::bail!("No local package directory configured");
+        };
+
+        let prefix = format!("{package}_");
+        let mut debs: Vec<PathBuf> = self
+            .list_files(dir.clone())?
+            .into_iter()
+            .filter(|f| {
+                f.file_name()
+                    .map(|n| n.to_string_lossy())
+                    .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".deb"))
+            })
+            .collect();
+        debs.sort();
+
+        match debs.pop() {
+            Some(deb) => Ok(deb),
+            None => anyhow::bail!("No .deb file found for '{}' in {}", package, dir.display()),
+        }
+    }
+
+    /// Calculate the sha256 checksum of a file.
+    fn sha256(&self, file: PathBuf) -> Result<String> {
+        let cmd = Command::build("sha256sum", &[&file.to_string_lossy()]);
+        let output = self.run(&cmd)?;
+        match String::from_utf8(output.stdout)?.split_whitespace().next() {
+            Some(sum) => Ok(sum.to_string()),
+            None => anyhow::bail!("Unable to calculate checksum for {}", file.display()),
+        }
+    }
+
+    /// Remove a package using the system package manager, or snapd for snaps.
+    /// Releases are removed along with the links to their binaries.
     fn remove_package(&self, package: &str) -> Result<()> {
-        let cmd = Command::build("apt-get", &["remove", "-y", package]);
+        if let Some(name) = release_name(package) {
+            return remove_release(self, release(name)?);
+        }
+        let cmd = match snap_name(package) {
+            Some(snap) => snap_remove(snap),
+            None => self.package_manager().remove(package),
+        };
         self.run(&cmd)?;
         Ok(())
     }
 
+    /// Reinstall a package using the system package manager, restoring any of its files that
+    /// were removed or replaced.
+    fn reinstall_package(&self, package: &str) -> Result<()> {
+        if let Some(name) = release_name(package) {
+            return install_release(self, release(name)?);
+        }
+        let cmd = self.package_manager().reinstall(package);
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Hold a package at its installed version, so that upgrades and autoremoval leave it alone.
+    /// Releases are never upgraded by the package manager, so aren't held.
+    fn hold_package(&self, package: &str) -> Result<()> {
+        if release_name(package).is_some() {
+            return Ok(());
+        }
+        if let Some(snap) = snap_name(package) {
+            self.run(&snap_hold(snap, true))?;
+            return Ok(());
+        }
+        let package_manager = self.package_manager();
+        let Some(cmd) = package_manager.hold(package) else {
+            anyhow::bail!(
+                "Holding packages isn't supported by {}",
+                package_manager.name()
+            );
+        };
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Release a hold placed on a package by `hold_package`.
+    fn unhold_package(&self, package: &str) -> Result<()> {
+        if release_name(package).is_some() {
+            return Ok(());
+        }
+        if let Some(snap) = snap_name(package) {
+            self.run(&snap_hold(snap, false))?;
+            return Ok(());
+        }
+        let package_manager = self.package_manager();
+        let Some(cmd) = package_manager.unhold(package) else {
+            anyhow::bail!(
+                "Holding packages isn't supported by {}",
+                package_manager.name()
+            );
+        };
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Report the packages that are held, which is none if the package manager can't hold
+    /// packages.
+    fn held_packages(&self) -> Result<Vec<String>> {
+        let package_manager = self.package_manager();
+        let Some(cmd) = package_manager.list_held() else {
+            return Ok(Vec::new());
+        };
+        let output = self.run(&cmd)?;
+        Ok(package_manager.parse_held(&String::from_utf8(output.stdout)?))
+    }
+
     /// Update the package lists using the system package manager.
     fn update_package_lists(&self) -> Result<()> {
-        let cmd = Command::build("apt-get", &["update"]);
+        let cmd = self.package_manager().update();
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Restart a systemd service.
+    fn restart_service(&self, service: &str) -> Result<()> {
+        let cmd = Command::build("systemctl", &["restart", service]);
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Start a systemd unit.
+    fn start_service(&self, service: &str) -> Result<()> {
+        let cmd = Command::build("systemctl", &["start", service]);
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Stop a systemd unit.
+    fn stop_service(&self, service: &str) -> Result<()> {
+        let cmd = Command::build("systemctl", &["stop", service]);
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Enable a systemd service, and start it now.
+    fn enable_service(&self, service: &str) -> Result<()> {
+        let cmd = Command::build("systemctl", &["enable", "--now", service]);
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Disable a systemd service, and stop it now.
+    fn disable_service(&self, service: &str) -> Result<()> {
+        let cmd = Command::build("systemctl", &["disable", "--now", service]);
+        self.run(&cmd)?;
+        Ok(())
+    }
+
+    /// Reload the systemd configuration, picking up unit files and drop-ins that have been
+    /// written or removed.
+    fn reload_service_manager(&self) -> Result<()> {
+        let cmd = Command::build("systemctl", &["daemon-reload"]);
         self.run(&cmd)?;
         Ok(())
     }
 
-    /// Check if a package is installed using the system package manager.
+    /// Report the systemd services with processes that have mapped binaries or libraries which
+    /// have since been deleted or replaced on disk.
+    fn stale_services(&self) -> Result<Vec<String>>;
+
+    /// Check if a package is installed using the system package manager. Releases are installed
+    /// once they're extracted.
     fn check_installed(&self, package: &str) -> Result<bool> {
-        let cmd = Command::build("dpkg-query", &["-s", package]);
+        if release_name(package).is_some() {
+            return Ok(self.package_version(package).is_ok());
+        }
+        let cmd = match snap_name(package) {
+            Some(snap) => snap_list(snap),
+            None => self.package_manager().query_installed(package),
+        };
         match self.run(&cmd) {
             Ok(_) => Ok(true),
             Err(_) => Ok(false),
         }
     }
 
-    /// Replace a file with a symlink. If the target file already exists, it will be backed up.
+    /// Report the installed version of a package.
+    fn package_version(&self, package: &str) -> Result<String> {
+        if let Some(name) = release_name(package) {
+            return release_version(self, release(name)?)
+                .ok_or_else(|| anyhow::anyhow!("The {name} release isn't installed"));
+        }
+        if let Some(snap) = snap_name(package) {
+            let output = self.run(&snap_list(snap))?;
+            return parse_snap_version(&String::from_utf8(output.stdout)?).ok_or_else(|| {
+                anyhow::anyhow!("Unable to determine the version of the {snap} snap")
+            });
+        }
+        let package_manager = self.package_manager();
+        let output = self.run(&package_manager.query_version(package))?;
+        Ok(package_manager.parse_version(package, &String::from_utf8(output.stdout)?))
+    }
+
+    /// Replace a file with a symlink. If the target file already exists, it will be backed up,
+    /// including if it's a symlink to somewhere other than `source`.
     fn replace_file_with_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()>;
 
     /// Backup a file by copying it to a new file with a `.oxidizr.bak` extension.
     fn backup_file(&self, file: PathBuf) -> Result<()>;
 
-    /// Restore a file from a backup if the backup file exists, warn otherwise.
+    /// Restore a file from a backup if the backup file exists, warn otherwise. Compressed
+    /// backups in the centralized backup store are decompressed transparently.
     fn restore_file(&self, file: PathBuf) -> Result<()>;
 
     /// Create a symlink from `source` to `target`. If `target` already exists, it will be removed.
     fn create_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()>;
+
+    /// Copy a file, preserving its permissions and creating any missing parent directories.
+    fn copy_file(&self, source: PathBuf, dest: PathBuf) -> Result<()>;
+
+    /// Check whether a file exists.
+    fn file_exists(&self, file: PathBuf) -> bool;
+
+    /// Read the target of a symlink, returning an error if `file` is not a symlink.
+    fn read_link(&self, file: PathBuf) -> Result<PathBuf>;
+
+    /// Read the contents of a file into a string.
+    fn read_file(&self, file: PathBuf) -> Result<String>;
+
+    /// Write `contents` to a file with the given permission `mode`, creating any missing parent
+    /// directories. If the file already exists, it will be overwritten.
+    fn write_file(&self, file: PathBuf, contents: &str, mode: u32) -> Result<()>;
+
+    /// Remove a file from the filesystem if it exists.
+    fn remove_file(&self, file: PathBuf) -> Result<()>;
 }
 
 /// A struct representing the system with functions for running commands and manipulating
 /// files on the filesystem.
 #[derive(Clone, Debug)]
-pub struct System {}
+pub struct System {
+    deb_dir: Option<PathBuf>,
+    pins: BTreeMap<String, String>,
+    compress_backups: bool,
+    backup_dir: Option<PathBuf>,
+    journal: Option<Journal>,
+    package_manager: OnceLock<PackageManager>,
+    distribution: Option<Distribution>,
+    snap_channel: Option<String>,
+    offline: bool,
+    from_releases: bool,
+    proxy: Option<String>,
+    apt_options: Vec<String>,
+    diversions: bool,
+}
 
 impl System {
     /// Create a new `System` instance.
     pub fn new() -> Result<Self> {
-        Ok(Self {})
+        Ok(Self {
+            deb_dir: None,
+            pins: BTreeMap::new(),
+            compress_backups: false,
+            backup_dir: None,
+            journal: None,
+            package_manager: OnceLock::new(),
+            distribution: None,
+            snap_channel: None,
+            offline: false,
+            from_releases: false,
+            proxy: None,
+            apt_options: Vec::new(),
+            diversions: false,
+        })
+    }
+
+    /// Install packages from `.deb` files in `dir`, rather than from the network archive.
+    pub fn with_local_packages(mut self, dir: PathBuf) -> Self {
+        self.deb_dir = Some(dir);
+        self
+    }
+
+    /// Install packages at the exact versions in `pins`, keyed by package name.
+    pub fn with_pinned_versions(mut self, pins: BTreeMap<String, String>) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Store backups of regular files compressed in the centralized backup store, rather than
+    /// alongside the original.
+    pub fn with_compressed_backups(mut self) -> Self {
+        self.compress_backups = true;
+        self
+    }
+
+    /// Store backups in `dir`, which may be on a different filesystem, rather than alongside the
+    /// originals.
+    pub fn with_backup_dir(mut self, dir: PathBuf) -> Self {
+        self.backup_dir = Some(dir);
+        self
+    }
+
+    /// Record each file and package action in the journal at `file`, so it can be rolled back.
+    pub fn with_journal(mut self, file: PathBuf) -> Self {
+        self.journal = Some(Journal::new(file));
+        self
+    }
+
+    /// Install experiments that are available as snaps from `channel` of the snap store.
+    pub fn with_snaps(mut self, channel: String) -> Self {
+        self.snap_channel = Some(channel);
+        self
+    }
+
+    /// Download experiments that are available as prebuilt releases from GitHub.
+    pub fn with_releases(mut self) -> Self {
+        self.from_releases = true;
+        self
+    }
+
+    /// Move replaced files aside with dpkg diversions, rather than backing them up.
+    pub fn with_diversions(mut self) -> Self {
+        self.diversions = true;
+        self
+    }
+
+    /// Don't use the network, installing packages from the `.deb` files in `dir`.
+    pub fn with_offline(mut self, dir: PathBuf) -> Self {
+        self.offline = true;
+        self.with_local_packages(dir)
+    }
+
+    /// Send the requests of apt and the other commands that download packages through `proxy`.
+    pub fn with_proxy(mut self, proxy: String) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Pass `options` to apt with `-o` for every package operation, e.g. `Acquire::Retries=3`.
+    pub fn with_apt_options(mut self, options: Vec<String>) -> Self {
+        self.apt_options = options;
+        self
+    }
+
+    /// Restore `file` from an uncompressed backup, after checking it against the backup
+    /// manifest. If the original was a hard link to a file that's already been restored, the
+    /// link is recreated and the backup discarded, rather than restoring a separate copy.
+    fn restore_from(&self, file: &Path, backup: &Path) -> Result<()> {
+        trace!("Restoring {} -> {}", backup.display(), file.display());
+        if backup.is_symlink() {
+            return move_file(backup, file);
+        }
+        verify_backup(self, file, backup)?;
+        match restored_hardlink(self, file)? {
+            Some(original) => {
+                trace!("Linking {} to {}", file.display(), original.display());
+                link_file(&original, file)?;
+                fs::remove_file(backup)?;
+            }
+            None => move_file(backup, file)?,
+        }
+        forget_backup(self, file)
+    }
+
+    /// Prepare a command to be run, passing it the apt options and proxy.
+    fn process(&self, cmd: &Command) -> std::process::Command {
+        let mut options = match &self.proxy {
+            Some(proxy) => apt_proxy_options(proxy),
+            None => Vec::new(),
+        };
+        options.extend(self.apt_options.iter().cloned());
+        let cmd = with_apt_options(cmd, &options);
+
+        let mut process = std::process::Command::new(&cmd.command);
+        process.args(&cmd.args);
+        if let Some(proxy) = &self.proxy {
+            process.envs(proxy_environment(proxy));
+        }
+        process
+    }
+
+    /// Report `distribution` as the system's distribution, rather than detecting it.
+    pub fn with_distribution(mut self, distribution: Distribution) -> Self {
+        self.distribution = Some(distribution);
+        self
     }
 }
 
 impl Worker for System {
+    fn distribution(&self) -> Result<Distribution> {
+        match &self.distribution {
+            Some(distribution) => Ok(distribution.clone()),
+            None => detect_distribution(self),
+        }
+    }
+
+    /// Report the package manager, detecting it from the distribution only once.
+    fn package_manager(&self) -> PackageManager {
+        *self.package_manager.get_or_init(|| {
+            self.distribution()
+                .map(|d| PackageManager::for_distribution(&d.id))
+                .unwrap_or(PackageManager::Apt)
+        })
+    }
+
+    /// Report the directory from which packages are installed as local `.deb` files.
+    fn local_package_dir(&self) -> Option<PathBuf> {
+        self.deb_dir.clone()
+    }
+
+    /// Report the exact version a package must be installed at, if versions are pinned.
+    fn pinned_version(&self, package: &str) -> Option<String> {
+        self.pins.get(package).cloned()
+    }
+
+    fn snap_channel(&self) -> Option<String> {
+        self.snap_channel.clone()
+    }
+
+    fn offline(&self) -> bool {
+        self.offline
+    }
+
+    fn use_releases(&self) -> bool {
+        self.from_releases
+    }
+
+    fn diversions(&self) -> bool {
+        self.diversions
+    }
+
+    /// Report whether backups are compressed into the centralized backup store.
+    fn compress_backups(&self) -> bool {
+        self.compress_backups
+    }
+
+    fn backup_dir(&self) -> Option<PathBuf> {
+        self.backup_dir.clone()
+    }
+
+    fn journaling(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    fn journal(&self, entry: JournalEntry) -> Result<()> {
+        match &self.journal {
+            Some(journal) => journal.record(&entry),
+            None => Ok(()),
+        }
+    }
+
     /// Run a command and return the output. If the command fails, an error will be returned.
     fn run(&self, cmd: &Command) -> Result<Output> {
         debug!("Running command: {}", cmd.command());
-        let output = std::process::Command::new(&cmd.command)
-            .args(&cmd.args)
-            .output()?;
+        let output = self.process(cmd).output()?;
 
         if !output.status.success() {
             anyhow::bail!(
@@ -107,6 +649,16 @@ impl Worker for System {
         Ok(output)
     }
 
+    /// Run a command in `dir` with no input, returning its output whether or not it succeeded.
+    fn capture(&self, cmd: &Command, dir: &Path) -> Result<Output> {
+        debug!("Running command in {}: {}", dir.display(), cmd.command());
+        Ok(self
+            .process(cmd)
+            .current_dir(dir)
+            .stdin(std::process::Stdio::null())
+            .output()?)
+    }
+
     /// List files in a directory. If the directory does not exist or is not a directory, an error
     /// will be returned.
     fn list_files(&self, directory: PathBuf) -> Result<Vec<PathBuf>> {
@@ -132,16 +684,74 @@ impl Worker for System {
         Ok(which(binary_name)?)
     }
 
+    /// Report the systemd services with processes that have mapped binaries or libraries which
+    /// have since been deleted or replaced on disk, by inspecting `/proc/<pid>/maps`.
+    fn stale_services(&self) -> Result<Vec<String>> {
+        let mut services = Vec::new();
+
+        for entry in fs::read_dir("/proc")? {
+            let path = entry?.path();
+            let is_pid = path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().chars().all(|c| c.is_ascii_digit()));
+            if !is_pid {
+                continue;
+            }
+
+            // Processes may exit while they're being inspected, so read errors are ignored.
+            let Ok(maps) = fs::read_to_string(path.join("maps")) else {
+                continue;
+            };
+            let stale = maps
+                .lines()
+                .any(|l| l.ends_with("(deleted)") && (l.contains(" /usr/") || l.contains(" /lib")));
+            if !stale {
+                continue;
+            }
+
+            if let Some(service) = fs::read_to_string(path.join("cgroup"))
+                .ok()
+                .and_then(|c| service_from_cgroup(&c))
+            {
+                services.push(service);
+            }
+        }
+
+        services.sort();
+        services.dedup();
+        Ok(services)
+    }
+
     /// Replace a file with a symlink. If the target file already exists, it will be backed up
     /// before being replaced.
     fn replace_file_with_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()> {
-        if fs::exists(&target)? {
-            if target.is_symlink() {
-                trace!("Skipping {}, symlink already exists", target.display());
+        if fs::symlink_metadata(&target).is_ok() {
+            // Existing symlinks, such as busybox applets or links managed by the alternatives
+            // system, are backed up as symlinks and replaced, unless they already point at
+            // `source`.
+            if fs::read_link(&target).is_ok_and(|link| link == source) {
+                trace!(
+                    "Skipping {}, already linked to {}",
+                    target.display(),
+                    source.display()
+                );
                 return Ok(());
             }
-            self.backup_file(target.clone())?;
-            fs::remove_file(&target)?;
+            if self.diversions && !target.is_symlink() {
+                // dpkg renames the file, so its diverted copy takes the place of the backup. It's
+                // not recorded in the backup manifest, as upgrades replace the diverted copy.
+                let backup = self.backup_location(&target);
+                self.journal(JournalEntry::ReplaceFile {
+                    file: target.clone(),
+                })?;
+                divert_file(self, &target, &backup)?;
+            } else {
+                self.backup_file(target.clone())?;
+                self.journal(JournalEntry::ReplaceFile {
+                    file: target.clone(),
+                })?;
+                fs::remove_file(&target)?;
+            }
         }
 
         self.create_symlink(source, target)?;
@@ -149,28 +759,62 @@ impl Worker for System {
     }
 
     /// Backup a file by copying it to a new file with the same name, but with a `.oxidizr.bak`
-    /// extension.
+    /// extension. If a backup directory is configured, the copy is kept there instead.
     fn backup_file(&self, file: PathBuf) -> Result<()> {
-        let backup_file = backup_filename(&file);
+        let backup_file = self.backup_location(&file);
         trace!("Backing up {} -> {}", file.display(), backup_file.display());
+        if let Some(parent) = backup_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Symlinks (such as busybox applets) are backed up as a copy of the link itself, rather
+        // than a copy of the file it points to.
+        if file.is_symlink() {
+            remove_file_if_exists(&backup_file)?;
+            std::os::unix::fs::symlink(fs::read_link(&file)?, &backup_file)?;
+            return Ok(());
+        }
+
+        if self.compress_backups {
+            return compress_backup(self, &file);
+        }
+
+        let metadata = fs::metadata(&file)?;
+        if let Some(parent) = backup_file.parent() {
+            ensure_free_space(self, parent, metadata.len())?;
+        }
         fs::copy(&file, &backup_file)?;
 
         // Ensure the same permissions are set on the backup file as on the original file.
         // This accounts for permissions such as SUID, SGID, and sticky bits which are not
         // preserved by `fs::copy`.
-        let metadata = fs::metadata(&file)?;
         fs::set_permissions(&backup_file, metadata.permissions())?;
-        Ok(())
+        // Hard links to the same original are recorded, so they're restored as links again.
+        let meta = BackupMeta {
+            hardlink: hardlink_id(self, &file)?,
+            ..backup_meta(self, &backup_file)?
+        };
+        record_backup(self, &file, meta)
     }
 
     /// Restore a file from a backup. If the backup file does not exist, the original file will be
     /// left untouched.
     fn restore_file(&self, file: PathBuf) -> Result<()> {
-        let backup_file = backup_filename(&file);
-
-        if fs::exists(&backup_file)? {
-            trace!("Restoring {} -> {}", backup_file.display(), file.display());
-            fs::rename(&backup_file, &file)?;
+        let backup_file = self.backup_location(&file);
+        let sibling = backup_filename(&file);
+
+        if is_diverted(self, &file) {
+            trace!("Removing the diversion of {}", file.display());
+            remove_file_if_exists(&file)?;
+            undivert_file(self, &file, &backup_file)?;
+        } else if fs::symlink_metadata(&backup_file).is_ok() {
+            self.restore_from(&file, &backup_file)?;
+        } else if fs::symlink_metadata(&sibling).is_ok() {
+            // Backups taken before a backup directory was configured are kept alongside.
+            self.restore_from(&file, &sibling)?;
+        } else if has_compressed_backup(self, &file) {
+            trace!("Restoring {} from the backup store", file.display());
+            restore_compressed_backup(self, &file)?;
         } else {
             warn!("No backup found for '{}', skipping restore", file.display());
         }
@@ -179,18 +823,82 @@ impl Worker for System {
     }
 
     /// Create a symlink from `source` to `target`. If `target` already exists, it will be
-    /// removed and overwritten with the symlink.
+    /// removed and overwritten with the symlink. Missing parent directories are created.
     fn create_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()> {
         trace!("Symlinking {} -> {}", source.display(), target.display());
+        if self.journaling() {
+            self.journal(JournalEntry::CreateSymlink {
+                link: target.clone(),
+                previous: fs::read_link(&target).ok(),
+            })?;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
         remove_file_if_exists(&target)?;
         std::os::unix::fs::symlink(source, target)?;
         Ok(())
     }
+
+    /// Copy a file, preserving its permissions and creating any missing parent directories.
+    fn copy_file(&self, source: PathBuf, dest: PathBuf) -> Result<()> {
+        trace!("Copying {} -> {}", source.display(), dest.display());
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&source, &dest)?;
+        fs::set_permissions(&dest, fs::metadata(&source)?.permissions())?;
+        Ok(())
+    }
+
+    /// Check whether a file exists. Broken symlinks are reported as existing.
+    fn file_exists(&self, file: PathBuf) -> bool {
+        fs::symlink_metadata(file).is_ok()
+    }
+
+    /// Read the target of a symlink, returning an error if `file` is not a symlink.
+    fn read_link(&self, file: PathBuf) -> Result<PathBuf> {
+        Ok(fs::read_link(file)?)
+    }
+
+    /// Read the contents of a file into a string.
+    fn read_file(&self, file: PathBuf) -> Result<String> {
+        Ok(fs::read_to_string(file)?)
+    }
+
+    /// Write `contents` to a file with the given permission `mode`, creating any missing parent
+    /// directories. If the file already exists, it will be overwritten.
+    fn write_file(&self, file: PathBuf, contents: &str, mode: u32) -> Result<()> {
+        trace!("Writing {}", file.display());
+        if self.journaling() {
+            let previous = fs::read_to_string(&file).ok().zip(
+                fs::metadata(&file)
+                    .ok()
+                    .map(|m| m.permissions().mode() & 0o7777),
+            );
+            self.journal(JournalEntry::WriteFile {
+                file: file.clone(),
+                previous,
+            })?;
+        }
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&file, contents)?;
+        fs::set_permissions(&file, fs::Permissions::from_mode(mode))?;
+        Ok(())
+    }
+
+    /// Remove a file from the filesystem if it exists.
+    fn remove_file(&self, file: PathBuf) -> Result<()> {
+        trace!("Removing {}", file.display());
+        remove_file_if_exists(&file)
+    }
 }
 
 /// Generate a backup filename. For a given file `/path/to/file`, the backup filename will be
 /// `/path/to/.file.oxidizr.bak`.
-fn backup_filename(file: &Path) -> PathBuf {
+pub fn backup_filename(file: &Path) -> PathBuf {
     let mut backup_file = file.parent().unwrap_or(&PathBuf::from(".")).to_path_buf();
     backup_file.push(format!(
         ".{}.oxidizr.bak",
@@ -199,9 +907,38 @@ fn backup_filename(file: &Path) -> PathBuf {
     backup_file
 }
 
+/// Detect the distribution with `lsb_release`. If `lsb_release` isn't installed, as is common on
+/// Fedora, the information is read from `/etc/os-release` instead. Ubuntu derivatives, which
+/// `lsb_release` reports under their own names, are reported as the Ubuntu release they're
+/// based on.
+pub fn detect_distribution<W: Worker + ?Sized>(system: &W) -> Result<Distribution> {
+    let os_release = || system.read_file(PathBuf::from("/etc/os-release"));
+    let cmd = Command::build("lsb_release", &["-is"]);
+    let Ok(id) = system.run(&cmd) else {
+        return Distribution::from_os_release(&os_release()?);
+    };
+
+    let cmd = Command::build("lsb_release", &["-rs"]);
+    let release = system.run(&cmd)?;
+
+    let distribution = Distribution {
+        id: String::from_utf8(id.stdout)?.trim().to_string(),
+        release: String::from_utf8(release.stdout)?.trim().to_string(),
+    }
+    .normalized();
+    if !distribution.is_supported()
+        && let Some(base) = os_release()
+            .ok()
+            .and_then(|o| Distribution::ubuntu_base(&o))
+    {
+        return Ok(base);
+    }
+    Ok(distribution)
+}
+
 /// Remove a file from the filesystem if it exists.
 fn remove_file_if_exists(file: &PathBuf) -> Result<()> {
-    if fs::exists(file)? {
+    if fs::symlink_metadata(file).is_ok() {
         fs::remove_file(file)?;
     }
     Ok(())
@@ -212,6 +949,29 @@ mod tests {
     use std::path::PathBuf;
 
     use crate::utils::worker::backup_filename;
+    use crate::utils::{Distribution, MockSystem, Worker};
+
+    #[test]
+    fn test_distribution_ubuntu_derivative() {
+        let runner = MockSystem::new(Distribution {
+            id: "Linuxmint".to_string(),
+            release: "22".to_string(),
+        });
+        assert_eq!(runner.distribution().unwrap().id, "Linuxmint");
+
+        runner.mock_files(vec![(
+            "/etc/os-release",
+            "NAME=\"Linux Mint\"\nID=linuxmint\nID_LIKE=\"ubuntu debian\"\nUBUNTU_CODENAME=noble\n",
+            false,
+        )]);
+        assert_eq!(
+            runner.distribution().unwrap(),
+            Distribution {
+                id: "Ubuntu".to_string(),
+                release: "24.04".to_string(),
+            }
+        );
+    }
 
     #[test]
     fn test_backup_filename() {
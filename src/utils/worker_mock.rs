@@ -1,9 +1,15 @@
 #[cfg(test)]
 pub mod tests {
-    use crate::utils::{Command, Distribution, Worker};
+    use crate::utils::{Command, Distribution, JournalEntry, PackageManager, Worker};
 
     use anyhow::Result;
-    use std::{cell::RefCell, collections::HashMap, path::PathBuf, process::Output};
+    use std::os::unix::process::ExitStatusExt;
+    use std::{
+        cell::RefCell,
+        collections::HashMap,
+        path::{Path, PathBuf},
+        process::{ExitStatus, Output},
+    };
 
     #[derive(Debug, Clone)]
     pub struct MockSystem {
@@ -17,12 +23,42 @@ pub mod tests {
         pub installed_packages: RefCell<Vec<String>>,
         /// List of symlinks created by the worker
         pub created_symlinks: RefCell<Vec<(String, String)>>,
+        /// Exact versions packages must be installed at
+        pub pins: RefCell<HashMap<String, String>>,
+        /// Symlinks present on the mock system, mapping the link to its target
+        pub symlinks: RefCell<HashMap<PathBuf, PathBuf>>,
         /// List of files restored by the worker
         pub restored_files: RefCell<Vec<String>>,
         /// List of files backed up by the worker
         pub backed_up_files: RefCell<Vec<String>>,
         /// HashMap of mocked commands and their faked responses
         pub mocked_commands: RefCell<HashMap<String, String>>,
+        /// List of mocked commands that fail when run
+        pub failing_commands: RefCell<Vec<String>>,
+        /// List of files copied by the worker, as (source, destination)
+        pub copied_files: RefCell<Vec<(String, String)>>,
+        /// List of files written by the worker
+        pub written_files: RefCell<Vec<String>>,
+        /// List of files removed by the worker
+        pub removed_files: RefCell<Vec<String>>,
+        /// Directory from which packages are installed as local `.deb` files
+        pub deb_dir: RefCell<Option<PathBuf>>,
+        /// Services that report as running replaced binaries or libraries
+        pub stale_services: RefCell<Vec<String>>,
+        /// Whether backups are compressed into the backup store
+        pub compress_backups: RefCell<bool>,
+        /// Actions recorded in the journal, if journaling is enabled
+        pub journal: RefCell<Option<Vec<JournalEntry>>>,
+        /// Directory in which backups are stored, rather than alongside the originals
+        pub backup_dir: RefCell<Option<PathBuf>>,
+        /// The package manager of the mocked distribution
+        pub package_manager: PackageManager,
+        /// Channel from which snaps are installed, if experiments are installed from snaps
+        pub snap_channel: RefCell<Option<String>>,
+        /// Whether the network must not be used
+        pub offline: RefCell<bool>,
+        /// Whether experiments are installed from prebuilt releases
+        pub from_releases: RefCell<bool>,
     }
 
     impl Default for MockSystem {
@@ -41,9 +77,24 @@ pub mod tests {
                 files: RefCell::new(HashMap::new()),
                 installed_packages: RefCell::new(Vec::new()),
                 created_symlinks: RefCell::new(Vec::new()),
+                symlinks: RefCell::new(HashMap::new()),
+                pins: RefCell::new(HashMap::new()),
                 restored_files: RefCell::new(Vec::new()),
                 backed_up_files: RefCell::new(Vec::new()),
                 mocked_commands: RefCell::new(HashMap::new()),
+                failing_commands: RefCell::new(Vec::new()),
+                copied_files: RefCell::new(Vec::new()),
+                written_files: RefCell::new(Vec::new()),
+                removed_files: RefCell::new(Vec::new()),
+                deb_dir: RefCell::new(None),
+                stale_services: RefCell::new(Vec::new()),
+                compress_backups: RefCell::new(false),
+                backup_dir: RefCell::new(None),
+                journal: RefCell::new(None),
+                package_manager: PackageManager::for_distribution(&distribution.id),
+                snap_channel: RefCell::new(None),
+                offline: RefCell::new(false),
+                from_releases: RefCell::new(false),
             };
 
             s.mock_command("lsb_release -is", distribution.id.as_str());
@@ -59,22 +110,62 @@ pub mod tests {
             }
         }
 
+        pub fn mock_snap_channel(&self, channel: &str) {
+            *self.snap_channel.borrow_mut() = Some(channel.to_string());
+        }
+
+        pub fn mock_from_releases(&self) {
+            self.from_releases.replace(true);
+        }
+
+        pub fn mock_offline(&self, dir: &str) {
+            self.offline.replace(true);
+            self.mock_local_packages(dir);
+        }
+
+        pub fn mock_pinned_versions(&self, pins: Vec<(&str, &str)>) {
+            for (package, version) in pins {
+                self.pins
+                    .borrow_mut()
+                    .insert(package.to_string(), version.to_string());
+            }
+        }
+
+        pub fn mock_symlinks(&self, symlinks: Vec<(&str, &str)>) {
+            for (link, target) in symlinks {
+                self.symlinks
+                    .borrow_mut()
+                    .insert(PathBuf::from(link), PathBuf::from(target));
+            }
+        }
+
         pub fn mock_install_package(&self, package: &str) {
             self.installed_packages
                 .borrow_mut()
                 .push(package.to_string());
         }
 
+        pub fn mock_local_packages(&self, dir: &str) {
+            self.deb_dir.replace(Some(PathBuf::from(dir)));
+        }
+
         pub fn mock_command(&self, command: &str, stdout: &str) {
             self.mocked_commands
                 .borrow_mut()
                 .insert(command.to_string(), stdout.to_string());
         }
+
+        pub fn mock_command_failure(&self, command: &str) {
+            self.failing_commands.borrow_mut().push(command.to_string());
+        }
     }
 
     impl Worker for MockSystem {
         fn run(&self, cmd: &Command) -> Result<Output> {
             self.commands.borrow_mut().push(cmd.command());
+            if self.failing_commands.borrow().contains(&cmd.command()) {
+                anyhow::bail!("Failed to run command '{}'", cmd.command());
+            }
             let mocked = self.mocked_commands.borrow();
             let default_stdout = String::default();
             let stdout = mocked.get(&cmd.command()).unwrap_or(&default_stdout);
@@ -86,6 +177,70 @@ pub mod tests {
             })
         }
 
+        fn capture(&self, cmd: &Command, _dir: &Path) -> Result<Output> {
+            self.commands.borrow_mut().push(cmd.command());
+            // Failing commands exit with status 1 rather than returning an error.
+            let status = match self.failing_commands.borrow().contains(&cmd.command()) {
+                true => ExitStatus::from_raw(1 << 8),
+                false => ExitStatus::default(),
+            };
+            let mocked = self.mocked_commands.borrow();
+            let stdout = mocked.get(&cmd.command()).cloned().unwrap_or_default();
+
+            Ok(Output {
+                stdout: stdout.into_bytes(),
+                stderr: Vec::new(),
+                status,
+            })
+        }
+
+        fn pinned_version(&self, package: &str) -> Option<String> {
+            self.pins.borrow().get(package).cloned()
+        }
+
+        fn snap_channel(&self) -> Option<String> {
+            self.snap_channel.borrow().clone()
+        }
+
+        fn offline(&self) -> bool {
+            *self.offline.borrow()
+        }
+
+        fn use_releases(&self) -> bool {
+            *self.from_releases.borrow()
+        }
+
+        fn compress_backups(&self) -> bool {
+            *self.compress_backups.borrow()
+        }
+
+        fn backup_dir(&self) -> Option<PathBuf> {
+            self.backup_dir.borrow().clone()
+        }
+
+        fn journaling(&self) -> bool {
+            self.journal.borrow().is_some()
+        }
+
+        fn journal(&self, entry: JournalEntry) -> Result<()> {
+            if let Some(journal) = self.journal.borrow_mut().as_mut() {
+                journal.push(entry);
+            }
+            Ok(())
+        }
+
+        fn local_package_dir(&self) -> Option<PathBuf> {
+            self.deb_dir.borrow().clone()
+        }
+
+        fn package_manager(&self) -> PackageManager {
+            self.package_manager
+        }
+
+        fn stale_services(&self) -> Result<Vec<String>> {
+            Ok(self.stale_services.borrow().clone())
+        }
+
         fn check_installed(&self, package: &str) -> Result<bool> {
             Ok(self
                 .installed_packages
@@ -114,14 +269,29 @@ pub mod tests {
         }
 
         fn replace_file_with_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()> {
-            if self.files.borrow().contains_key(&target) {
+            let link = self.symlinks.borrow().get(&target).cloned();
+            if link.as_ref() == Some(&source) {
+                return Ok(());
+            }
+            if link.is_some() || self.files.borrow().contains_key(&target) {
                 self.backup_file(target.clone())?;
+                self.journal(JournalEntry::ReplaceFile {
+                    file: target.clone(),
+                })?;
             }
 
             self.create_symlink(source, target.clone())
         }
 
         fn create_symlink(&self, source: PathBuf, target: PathBuf) -> Result<()> {
+            let previous = self.symlinks.borrow().get(&target).cloned();
+            self.journal(JournalEntry::CreateSymlink {
+                link: target.clone(),
+                previous,
+            })?;
+            self.symlinks
+                .borrow_mut()
+                .insert(target.clone(), source.clone());
             self.created_symlinks.borrow_mut().push((
                 source.into_os_string().into_string().unwrap(),
                 target.into_os_string().into_string().unwrap(),
@@ -137,10 +307,64 @@ pub mod tests {
         }
 
         fn restore_file(&self, file: PathBuf) -> Result<()> {
+            self.symlinks.borrow_mut().remove(&file);
             self.restored_files
                 .borrow_mut()
                 .push(file.into_os_string().into_string().unwrap());
             Ok(())
         }
+
+        fn copy_file(&self, source: PathBuf, dest: PathBuf) -> Result<()> {
+            let contents = self.read_file(source.clone())?;
+            self.files
+                .borrow_mut()
+                .insert(dest.clone(), (contents, false));
+            self.copied_files.borrow_mut().push((
+                source.into_os_string().into_string().unwrap(),
+                dest.into_os_string().into_string().unwrap(),
+            ));
+            Ok(())
+        }
+
+        fn file_exists(&self, file: PathBuf) -> bool {
+            self.files.borrow().contains_key(&file)
+        }
+
+        fn read_link(&self, file: PathBuf) -> Result<PathBuf> {
+            match self.symlinks.borrow().get(&file) {
+                Some(target) => Ok(target.clone()),
+                None => anyhow::bail!("{} is not a symlink in mocked filesystem", file.display()),
+            }
+        }
+
+        fn read_file(&self, file: PathBuf) -> Result<String> {
+            match self.files.borrow().get(&file) {
+                Some((contents, _)) => Ok(contents.clone()),
+                None => anyhow::bail!("{} not found in mocked filesystem", file.display()),
+            }
+        }
+
+        fn write_file(&self, file: PathBuf, contents: &str, _mode: u32) -> Result<()> {
+            let previous = self.files.borrow().get(&file).map(|f| (f.0.clone(), 0o644));
+            self.journal(JournalEntry::WriteFile {
+                file: file.clone(),
+                previous,
+            })?;
+            self.files
+                .borrow_mut()
+                .insert(file.clone(), (contents.to_string(), false));
+            self.written_files
+                .borrow_mut()
+                .push(file.into_os_string().into_string().unwrap());
+            Ok(())
+        }
+
+        fn remove_file(&self, file: PathBuf) -> Result<()> {
+            self.files.borrow_mut().remove(&file);
+            self.removed_files
+                .borrow_mut()
+                .push(file.into_os_string().into_string().unwrap());
+            Ok(())
+        }
     }
 }
@@ -56,3 +56,17 @@ ensure_diffutils_absent() {
     ls -la /usr/bin | NOMATCH ".find.oxidizr.bak"
     find --help | MATCH "GNU"
 }
+
+ensure_util_linux_installed() {
+    if [[ "$(lsb_release -rs)" == "25.04" ]]; then
+        apt list rust-util-linux | MATCH installed
+        ls -la "/usr/bin/rev"| MATCH "/usr/bin/rev -> /usr/lib/cargo/bin/util-linux/rev"
+        ls -la "/usr/bin/.rev.oxidizr.bak" || echo "No backup file for /usr/bin/rev"
+    fi
+}
+
+ensure_util_linux_absent() {
+    apt list rust-util-linux | NOMATCH installed
+    ls -la /usr/bin/rev | NOMATCH "/usr/bin/rev -> /usr/lib/cargo/bin/util-linux/rev"
+    ls -la /usr/bin | NOMATCH ".rev.oxidizr.bak"
+}